@@ -0,0 +1,1270 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Local API Server
+// REST access to customers, products, invoices, payments and returns for other programs
+// =====================================================
+//
+// An HTTP/1.1 server on 127.0.0.1, off until the user enables it, so a storefront or
+// script on the same machine can do what the app does. Every endpoint calls the same
+// database methods and command helpers as the matching Tauri command, and failures come
+// back as the same {error, message} objects with a matching HTTP status.
+//
+// Requests need the header `Authorization: Bearer <token>`; only the OpenAPI
+// description at /api/v1/openapi.json is public. The token is generated on first use
+// and kept in `api_server_settings`. Changes made through the API are recorded in the
+// audit trail as made by "api". Each connection carries one request.
+
+use crate::database::{
+    ApiServerSettings, Customer, Database, Invoice, InvoiceFilter, InvoiceItem, NewPayment,
+    NoteAllocation, PaymentFilter, Product,
+};
+use crate::numbering::{self, NumberFormat};
+use crate::operations::{self, ApiError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// The open database, shared by the app's commands and the server's connections
+pub type SharedDatabase = Arc<Mutex<Option<Database>>>;
+
+pub const BASE_PATH: &str = "/api/v1";
+pub const MIN_PORT: u16 = 1024;
+
+/// Name the audit trail records against changes made through the API
+const API_ACTOR: &str = "api";
+const TOKEN_BYTES: usize = 32;
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn check_port(port: u16) -> Result<u16, ServerError> {
+    if port < MIN_PORT {
+        return Err(ServerError::InvalidPort(port));
+    }
+    Ok(port)
+}
+
+pub fn generate_token() -> Result<String, ServerError> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::getrandom(&mut bytes).map_err(ServerError::Token)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// The stored settings, generating and saving a token if there is none yet
+pub fn load_settings(db: &Database) -> Result<ApiServerSettings, ServerError> {
+    let settings = db.get_api_server_settings()?;
+    if settings.token.is_some() {
+        return Ok(settings);
+    }
+    Ok(db.set_api_token(&generate_token()?)?)
+}
+
+/// Compares every byte, so the time taken does not tell how much of a guess was right
+fn tokens_match(given: &str, expected: &str) -> bool {
+    !expected.is_empty()
+        && given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// =====================================================
+// Server
+// =====================================================
+
+/// A running server; it stops when dropped
+pub struct ApiServer {
+    port: u16,
+    stopping: Arc<AtomicBool>,
+    accept_loop: Option<JoinHandle<()>>,
+}
+
+impl ApiServer {
+    /// Listens on 127.0.0.1:`port` and serves each connection on a thread of its own
+    pub fn start(db: SharedDatabase, port: u16, token: String) -> Result<Self, ServerError> {
+        check_port(port)?;
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .map_err(|err| ServerError::Bind { port, err })?;
+
+        let stopping = Arc::new(AtomicBool::new(false));
+        let token = Arc::new(token);
+        let accept_loop = {
+            let stopping = stopping.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopping.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            log::warn!("API server could not accept a connection: {}", err);
+                            continue;
+                        }
+                    };
+                    let db = db.clone();
+                    let token = token.clone();
+                    std::thread::spawn(move || {
+                        if let Err(err) = serve_connection(stream, &db, &token) {
+                            log::debug!("API connection failed: {}", err);
+                        }
+                    });
+                }
+            })
+        };
+
+        log::info!("Local API listening on http://127.0.0.1:{}{}", port, BASE_PATH);
+        Ok(ApiServer { port, stopping, accept_loop: Some(accept_loop) })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, BASE_PATH)
+    }
+
+    /// Serves until the process ends, for the command line
+    pub fn wait(mut self) {
+        if let Some(accept_loop) = self.accept_loop.take() {
+            let _ = accept_loop.join();
+        }
+    }
+
+    /// Stops accepting connections; requests already being served are finished
+    #[cfg(feature = "gui")]
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(accept_loop) = self.accept_loop.take() {
+            self.stopping.store(true, Ordering::SeqCst);
+            // The accept loop only sees the flag once a connection wakes it
+            let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port));
+            let _ = accept_loop.join();
+            log::info!("Local API on port {} stopped", self.port);
+        }
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn serve_connection(stream: TcpStream, db: &SharedDatabase, token: &str) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_request(&mut reader) {
+        Ok(request) => handle(db, token, &request),
+        Err(RequestError::Closed) => return Ok(()),
+        Err(RequestError::Io(err)) => return Err(err),
+        Err(RequestError::Invalid { status, message }) => Response::error(
+            status,
+            ApiError { error: "InvalidRequest".to_string(), message: message.to_string() },
+        ),
+    };
+    write_response(&mut &stream, &response)
+}
+
+// =====================================================
+// HTTP Requests and Responses
+// =====================================================
+
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+enum RequestError {
+    /// The client closed the connection without sending anything
+    Closed,
+    Invalid { status: u16, message: &'static str },
+    Io(io::Error),
+}
+
+impl From<io::Error> for RequestError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::InvalidData => {
+                RequestError::Invalid { status: 400, message: "Request headers must be UTF-8" }
+            }
+            _ => RequestError::Io(err),
+        }
+    }
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request, RequestError> {
+    let mut budget = MAX_HEADER_BYTES;
+    let request_line = match read_line(reader, &mut budget)? {
+        Some(line) => line,
+        None => return Err(RequestError::Closed),
+    };
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => (method, target),
+        _ => return Err(RequestError::Invalid { status: 400, message: "Malformed request line" }),
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader, &mut budget)?.unwrap_or_default();
+        if line.is_empty() {
+            break;
+        }
+        match line.split_once(':') {
+            Some((name, value)) => headers.push((name.trim().to_string(), value.trim().to_string())),
+            None => return Err(RequestError::Invalid { status: 400, message: "Malformed header line" }),
+        }
+    }
+
+    let mut request = Request {
+        method: method.to_string(),
+        path: String::new(),
+        query: Vec::new(),
+        headers,
+        body: Vec::new(),
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    request.path = decode_component(path);
+    request.query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode_component(key), decode_component(value))
+        })
+        .collect();
+
+    if request.header("transfer-encoding").is_some() {
+        return Err(RequestError::Invalid { status: 411, message: "Send the body with a Content-Length" });
+    }
+    let length = match request.header("content-length") {
+        Some(length) => length
+            .parse::<usize>()
+            .map_err(|_| RequestError::Invalid { status: 400, message: "Invalid Content-Length" })?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(RequestError::Invalid { status: 413, message: "Request body is larger than 1 MB" });
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body)?;
+    Ok(request)
+}
+
+/// One header line without its line ending; None at the end of the stream
+fn read_line(reader: &mut impl BufRead, budget: &mut usize) -> Result<Option<String>, RequestError> {
+    let mut line = String::new();
+    let read = reader.by_ref().take(*budget as u64 + 1).read_line(&mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read > *budget {
+        return Err(RequestError::Invalid { status: 431, message: "Request headers are too large" });
+    }
+    *budget -= read;
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+/// Undoes URL encoding: `%XX` escapes, and `+` for a space as forms send it
+fn decode_component(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' => text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', None) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, None) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+    /// Methods the path does answer to, sent with 405
+    allow: Option<String>,
+}
+
+impl Response {
+    fn new(status: u16, body: Value) -> Self {
+        Response { status, body, allow: None }
+    }
+
+    fn error(status: u16, err: ApiError) -> Self {
+        Response::new(status, json!({ "error": err.error, "message": err.message }))
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+fn write_response(writer: &mut impl Write, response: &Response) -> io::Result<()> {
+    let body = serde_json::to_vec(&response.body)?;
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n",
+        response.status,
+        reason_phrase(response.status),
+        body.len()
+    );
+    if response.status == 401 {
+        head.push_str("WWW-Authenticate: Bearer\r\n");
+    }
+    if let Some(allow) = &response.allow {
+        head.push_str(&format!("Allow: {}\r\n", allow));
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// HTTP status for an error code from the command layer
+fn status_for(code: &str) -> u16 {
+    match code {
+        "Unauthorized" => 401,
+        "NotFound" => 404,
+        "InvoiceLocked" | "InvoiceHasSettlements" | "InvoiceNotAmendable" | "InvoiceCancelled"
+        | "IllegalStatusTransition" | "PaymentAlreadyVoided" | "CompanySettingsMissing" => 409,
+        "DatabaseNotInitialized" => 503,
+        "DatabaseError" | "SerializationError" => 500,
+        _ => 400,
+    }
+}
+
+// =====================================================
+// Routing
+// =====================================================
+
+fn handle(db: &SharedDatabase, token: &str, request: &Request) -> Response {
+    let path = match request.path.strip_prefix(BASE_PATH) {
+        Some(path) => path.trim_end_matches('/'),
+        None => return Response::error(404, no_endpoint(request)),
+    };
+    if path == "/openapi.json" && request.method == "GET" {
+        return Response::new(200, openapi());
+    }
+
+    let authorized = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| tokens_match(given.trim(), token));
+    if !authorized {
+        return Response::error(
+            401,
+            ApiError {
+                error: "Unauthorized".to_string(),
+                message: "Send the API token from Payvlo's settings as Authorization: Bearer <token>"
+                    .to_string(),
+            },
+        );
+    }
+
+    let mut allowed = Vec::new();
+    for route in ROUTES {
+        let id = match route.matches(path) {
+            Some(id) => id,
+            None => continue,
+        };
+        if route.method != request.method {
+            allowed.push(route.method);
+            continue;
+        }
+
+        // One lock covers the request and the actor switch around it, so no change from
+        // the app is recorded against the API
+        let db_mutex = db.lock().unwrap();
+        let call = Call { id, request };
+        let result = match db_mutex.as_ref() {
+            Some(db) if route.changes_data() => as_api_actor(db, || (route.handler)(db, &call)),
+            Some(db) => (route.handler)(db, &call),
+            None => Err(ApiError {
+                error: "DatabaseNotInitialized".to_string(),
+                message: "Database not initialized".to_string(),
+            }),
+        };
+        return match result {
+            Ok(body) => Response::new(if route.created { 201 } else { 200 }, body),
+            Err(err) => Response::error(status_for(&err.error), err),
+        };
+    }
+
+    if allowed.is_empty() {
+        return Response::error(404, no_endpoint(request));
+    }
+    let mut response = Response::error(
+        405,
+        ApiError {
+            error: "MethodNotAllowed".to_string(),
+            message: format!("{} does not accept {}", request.path, request.method),
+        },
+    );
+    response.allow = Some(allowed.join(", "));
+    response
+}
+
+fn no_endpoint(request: &Request) -> ApiError {
+    ApiError {
+        error: "NotFound".to_string(),
+        message: format!("No endpoint at {}; see {}/openapi.json", request.path, BASE_PATH),
+    }
+}
+
+/// Runs a request that changes data with its changes attributed to the API, then
+/// restores the app's user
+fn as_api_actor(db: &Database, run: impl FnOnce() -> CommandResult<Value>) -> CommandResult<Value> {
+    let actor = db.get_audit_actor().map_err(ApiError::from)?;
+    db.set_audit_actor(API_ACTOR).map_err(ApiError::from)?;
+    let result = run();
+    db.set_audit_actor(&actor).map_err(ApiError::from)?;
+    result
+}
+
+type CommandResult<T> = Result<T, ApiError>;
+
+struct Route {
+    method: &'static str,
+    /// Path below BASE_PATH; `{id}` stands for a record id
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    query: &'static [Param],
+    /// Request and response schemas by name; `Name[]` is a list, "" any JSON object
+    body: Option<&'static str>,
+    response: &'static str,
+    /// Answers 201 Created rather than 200
+    created: bool,
+    handler: fn(&Database, &Call) -> CommandResult<Value>,
+}
+
+impl Route {
+    /// Reads leave the audit actor alone
+    fn changes_data(&self) -> bool {
+        self.method != "GET"
+    }
+
+    /// The id in `path` when it is this route's path
+    fn matches(&self, path: &str) -> Option<Option<i64>> {
+        let mut id = None;
+        let mut given = path.split('/');
+        for expected in self.path.split('/') {
+            let segment = given.next()?;
+            if expected == "{id}" {
+                id = Some(segment.parse::<i64>().ok()?);
+            } else if expected != segment {
+                return None;
+            }
+        }
+        given.next().is_none().then_some(id)
+    }
+}
+
+struct Param {
+    name: &'static str,
+    kind: &'static str,
+    required: bool,
+}
+
+const fn query(name: &'static str, kind: &'static str) -> Param {
+    Param { name, kind, required: false }
+}
+
+const fn required(name: &'static str, kind: &'static str) -> Param {
+    Param { name, kind, required: true }
+}
+
+/// What a handler gets from the request: the id in its path, the query and the body
+struct Call<'a> {
+    id: Option<i64>,
+    request: &'a Request,
+}
+
+impl Call<'_> {
+    fn id(&self) -> i64 {
+        self.id.unwrap_or_default()
+    }
+
+    fn param<T: FromStr>(&self, name: &str) -> CommandResult<Option<T>> {
+        match self.request.query.iter().rev().find(|(key, _)| key == name) {
+            Some((_, value)) if !value.is_empty() => value
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid(format!("Query parameter {} has an invalid value {}", name, value))),
+            _ => Ok(None),
+        }
+    }
+
+    fn required_param(&self, name: &str) -> CommandResult<String> {
+        self.param(name)?
+            .ok_or_else(|| invalid(format!("Query parameter {} is required", name)))
+    }
+
+    fn body<T: DeserializeOwned>(&self) -> CommandResult<T> {
+        serde_json::from_slice(&self.request.body)
+            .map_err(|err| invalid(format!("Request body is not valid: {}", err)))
+    }
+
+    /// A body that may be left out altogether
+    fn optional_body<T: DeserializeOwned + Default>(&self) -> CommandResult<T> {
+        if self.request.body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(T::default());
+        }
+        self.body()
+    }
+}
+
+fn invalid(message: String) -> ApiError {
+    ApiError { error: "InvalidRequest".to_string(), message }
+}
+
+fn not_found(what: &str, id: i64) -> ApiError {
+    ApiError { error: "NotFound".to_string(), message: format!("{} {} does not exist", what, id) }
+}
+
+fn to_value<T: Serialize>(value: T) -> CommandResult<Value> {
+    serde_json::to_value(value)
+        .map_err(|err| ApiError { error: "SerializationError".to_string(), message: err.to_string() })
+}
+
+const ROUTES: &[Route] = &[
+    Route { method: "GET", path: "/company", tag: "Company", summary: "Company settings",
+        query: &[], body: None, response: "", created: false, handler: get_company },
+    Route { method: "GET", path: "/customers", tag: "Customers", summary: "List customers, or search them by name, GSTIN or phone",
+        query: &[query("search", "string"), query("limit", "integer"), query("offset", "integer")],
+        body: None, response: "Customer[]", created: false, handler: list_customers },
+    Route { method: "POST", path: "/customers", tag: "Customers", summary: "Create a customer",
+        query: &[], body: Some("Customer"), response: "Customer", created: true, handler: create_customer },
+    Route { method: "GET", path: "/customers/{id}", tag: "Customers", summary: "Get a customer",
+        query: &[], body: None, response: "Customer", created: false, handler: get_customer },
+    Route { method: "PUT", path: "/customers/{id}", tag: "Customers", summary: "Update a customer",
+        query: &[], body: Some("Customer"), response: "Customer", created: false, handler: update_customer },
+    Route { method: "DELETE", path: "/customers/{id}", tag: "Customers", summary: "Delete a customer",
+        query: &[], body: None, response: "Deleted", created: false, handler: delete_customer },
+    Route { method: "GET", path: "/products", tag: "Products", summary: "List active products, or search them by code, name or HSN/SAC",
+        query: &[query("search", "string"), query("limit", "integer"), query("offset", "integer")],
+        body: None, response: "Product[]", created: false, handler: list_products },
+    Route { method: "POST", path: "/products", tag: "Products", summary: "Create a product",
+        query: &[], body: Some("Product"), response: "Product", created: true, handler: create_product },
+    Route { method: "GET", path: "/products/{id}", tag: "Products", summary: "Get a product",
+        query: &[], body: None, response: "Product", created: false, handler: get_product },
+    Route { method: "PUT", path: "/products/{id}", tag: "Products", summary: "Update a product",
+        query: &[], body: Some("Product"), response: "Product", created: false, handler: update_product },
+    Route { method: "DELETE", path: "/products/{id}", tag: "Products", summary: "Deactivate a product",
+        query: &[], body: None, response: "Deleted", created: false, handler: delete_product },
+    Route { method: "GET", path: "/invoices", tag: "Invoices", summary: "List invoices and notes, newest first",
+        query: &[
+            query("customer_id", "integer"), query("invoice_type", "string"), query("status", "string"),
+            query("date_from", "string"), query("date_to", "string"), query("search", "string"),
+            query("limit", "integer"), query("offset", "integer"),
+        ],
+        body: None, response: "Invoice[]", created: false, handler: list_invoices },
+    Route { method: "POST", path: "/invoices", tag: "Invoices",
+        summary: "Create an invoice; notes are created under /notes. Taxes and totals are recomputed; a blank number is drawn from the series when the invoice is issued. Status SENT issues it at once.",
+        query: &[], body: Some("InvoiceInput"), response: "SavedInvoice", created: true, handler: create_invoice },
+    Route { method: "GET", path: "/invoices/{id}", tag: "Invoices", summary: "Get an invoice with its lines",
+        query: &[], body: None, response: "InvoiceWithItems", created: false, handler: get_invoice },
+    Route { method: "PUT", path: "/invoices/{id}", tag: "Invoices", summary: "Replace a draft invoice and its lines; notes cannot be saved here",
+        query: &[], body: Some("InvoiceInput"), response: "SavedInvoice", created: false, handler: update_invoice },
+    Route { method: "DELETE", path: "/invoices/{id}", tag: "Invoices", summary: "Delete a draft; issued invoices are cancelled instead",
+        query: &[], body: None, response: "Deleted", created: false, handler: delete_invoice },
+    Route { method: "POST", path: "/invoices/{id}/status", tag: "Invoices", summary: "Issue (SENT) or cancel (CANCELLED) an invoice or note",
+        query: &[], body: Some("StatusInput"), response: "StatusChange", created: false, handler: update_invoice_status },
+    Route { method: "POST", path: "/invoices/{id}/cancel", tag: "Invoices", summary: "Cancel an invoice or note, keeping it on record",
+        query: &[], body: Some("CancelInput"), response: "StatusChange", created: false, handler: cancel_invoice },
+    Route { method: "POST", path: "/invoices/{id}/amend", tag: "Invoices", summary: "Start a revised draft of an issued invoice; issuing it cancels the original",
+        query: &[], body: Some("AmendInput"), response: "Created", created: true, handler: amend_invoice },
+    Route { method: "GET", path: "/invoices/{id}/status-history", tag: "Invoices", summary: "Status changes, oldest first",
+        query: &[], body: None, response: "StatusChange[]", created: false, handler: get_invoice_status_history },
+    Route { method: "GET", path: "/invoices/{id}/balance", tag: "Invoices", summary: "Amount paid and outstanding",
+        query: &[], body: None, response: "InvoiceBalance", created: false, handler: get_invoice_balance },
+    Route { method: "GET", path: "/invoices/{id}/notes", tag: "Notes", summary: "Credit and debit notes issued against an invoice",
+        query: &[], body: None, response: "NoteReference[]", created: false, handler: list_invoice_notes },
+    Route { method: "POST", path: "/notes", tag: "Notes", summary: "Create a credit or debit note against one or more invoices",
+        query: &[], body: Some("NoteInput"), response: "Created", created: true, handler: create_note },
+    Route { method: "GET", path: "/notes/{id}/references", tag: "Notes", summary: "Invoices a note amends",
+        query: &[], body: None, response: "NoteReference[]", created: false, handler: get_note_references },
+    Route { method: "GET", path: "/payments", tag: "Payments", summary: "List payments",
+        query: &[
+            query("invoice_id", "integer"), query("customer_id", "integer"), query("payment_method", "string"),
+            query("date_from", "string"), query("date_to", "string"), query("include_voided", "boolean"),
+            query("limit", "integer"), query("offset", "integer"),
+        ],
+        body: None, response: "Payment[]", created: false, handler: list_payments },
+    Route { method: "POST", path: "/payments", tag: "Payments", summary: "Record a receipt against one or more invoices",
+        query: &[], body: Some("NewPayment"), response: "RecordedPayment", created: true, handler: record_payment },
+    Route { method: "POST", path: "/payments/{id}/void", tag: "Payments", summary: "Void a payment, keeping it on record",
+        query: &[], body: Some("VoidInput"), response: "InvoiceBalance", created: false, handler: void_payment },
+    Route { method: "GET", path: "/reports/gstr1", tag: "Reports", summary: "GSTR-1 in the offline tool's JSON format",
+        query: &[required("period", "string")], body: None, response: "", created: false, handler: get_gstr1 },
+    Route { method: "GET", path: "/reports/gstr3b", tag: "Reports", summary: "GSTR-3B tables 3.1 and 3.2",
+        query: &[required("period", "string")], body: None, response: "", created: false, handler: get_gstr3b },
+];
+
+// =====================================================
+// Handlers
+// =====================================================
+
+#[derive(Deserialize)]
+struct InvoiceInput {
+    invoice: Invoice,
+    items: Vec<InvoiceItem>,
+    number_format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NoteInput {
+    note: Invoice,
+    items: Vec<InvoiceItem>,
+    references: Vec<NoteAllocation>,
+    number_format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StatusInput {
+    status: String,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CancelInput {
+    reason: String,
+}
+
+#[derive(Deserialize, Default)]
+struct AmendInput {
+    number_format: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct VoidInput {
+    reason: Option<String>,
+}
+
+fn get_company(db: &Database, _: &Call) -> CommandResult<Value> {
+    let company = db.get_company_settings().map_err(ApiError::from)?.ok_or_else(|| ApiError {
+        error: "NotFound".to_string(),
+        message: "Company settings have not been saved yet".to_string(),
+    })?;
+    to_value(company)
+}
+
+fn list_customers(db: &Database, call: &Call) -> CommandResult<Value> {
+    let customers = match call.param::<String>("search")? {
+        Some(search) => db.search_customers(&search),
+        None => db.get_customers(call.param("limit")?, call.param("offset")?),
+    };
+    to_value(customers.map_err(ApiError::from)?)
+}
+
+fn find_customer(db: &Database, id: i64) -> CommandResult<Customer> {
+    db.get_customer_by_id(id).map_err(ApiError::from)?.ok_or_else(|| not_found("Customer", id))
+}
+
+fn get_customer(db: &Database, call: &Call) -> CommandResult<Value> {
+    to_value(find_customer(db, call.id())?)
+}
+
+fn create_customer(db: &Database, call: &Call) -> CommandResult<Value> {
+    let customer = Customer { id: None, ..call.body()? };
+    let id = db.save_customer(&customer).map_err(ApiError::from)?;
+    to_value(find_customer(db, id)?)
+}
+
+fn update_customer(db: &Database, call: &Call) -> CommandResult<Value> {
+    let id = find_customer(db, call.id())?.id;
+    let customer = Customer { id, ..call.body()? };
+    db.save_customer(&customer).map_err(ApiError::from)?;
+    to_value(find_customer(db, call.id())?)
+}
+
+fn delete_customer(db: &Database, call: &Call) -> CommandResult<Value> {
+    if !db.delete_customer(call.id()).map_err(ApiError::from)? {
+        return Err(not_found("Customer", call.id()));
+    }
+    Ok(json!({ "deleted": true }))
+}
+
+fn list_products(db: &Database, call: &Call) -> CommandResult<Value> {
+    let products = match call.param::<String>("search")? {
+        Some(search) => db.search_products(&search),
+        None => db.get_products(call.param("limit")?, call.param("offset")?),
+    };
+    to_value(products.map_err(ApiError::from)?)
+}
+
+fn find_product(db: &Database, id: i64) -> CommandResult<Product> {
+    db.get_product_by_id(id).map_err(ApiError::from)?.ok_or_else(|| not_found("Product", id))
+}
+
+fn get_product(db: &Database, call: &Call) -> CommandResult<Value> {
+    to_value(find_product(db, call.id())?)
+}
+
+fn create_product(db: &Database, call: &Call) -> CommandResult<Value> {
+    let product = Product { id: None, ..call.body()? };
+    let id = db.save_product(&product).map_err(ApiError::from)?;
+    to_value(find_product(db, id)?)
+}
+
+fn update_product(db: &Database, call: &Call) -> CommandResult<Value> {
+    let id = find_product(db, call.id())?.id;
+    let product = Product { id, ..call.body()? };
+    db.save_product(&product).map_err(ApiError::from)?;
+    to_value(find_product(db, call.id())?)
+}
+
+fn delete_product(db: &Database, call: &Call) -> CommandResult<Value> {
+    if !db.delete_product(call.id()).map_err(ApiError::from)? {
+        return Err(not_found("Product", call.id()));
+    }
+    Ok(json!({ "deleted": true }))
+}
+
+fn list_invoices(db: &Database, call: &Call) -> CommandResult<Value> {
+    let filter = InvoiceFilter {
+        customer_id: call.param("customer_id")?,
+        invoice_type: call.param("invoice_type")?,
+        status: call.param("status")?,
+        date_from: call.param("date_from")?,
+        date_to: call.param("date_to")?,
+        search: call.param("search")?,
+        limit: call.param("limit")?,
+        offset: call.param("offset")?,
+    };
+    to_value(db.list_invoices(&filter).map_err(ApiError::from)?)
+}
+
+fn get_invoice(db: &Database, call: &Call) -> CommandResult<Value> {
+    let invoice = db.get_invoice_by_id(call.id()).map_err(ApiError::from)?;
+    to_value(invoice.ok_or_else(|| not_found("Invoice", call.id()))?)
+}
+
+fn create_invoice(db: &Database, call: &Call) -> CommandResult<Value> {
+    let input: InvoiceInput = call.body()?;
+    let invoice = Invoice { id: None, ..input.invoice };
+    to_value(operations::store_invoice(db, &invoice, &input.items, input.number_format.as_deref())?)
+}
+
+fn update_invoice(db: &Database, call: &Call) -> CommandResult<Value> {
+    if db.get_invoice_by_id(call.id()).map_err(ApiError::from)?.is_none() {
+        return Err(not_found("Invoice", call.id()));
+    }
+    let input: InvoiceInput = call.body()?;
+    let invoice = Invoice { id: Some(call.id()), ..input.invoice };
+    to_value(operations::store_invoice(db, &invoice, &input.items, input.number_format.as_deref())?)
+}
+
+fn delete_invoice(db: &Database, call: &Call) -> CommandResult<Value> {
+    if !db.delete_invoice(call.id())? {
+        return Err(not_found("Invoice", call.id()));
+    }
+    Ok(json!({ "deleted": true }))
+}
+
+fn update_invoice_status(db: &Database, call: &Call) -> CommandResult<Value> {
+    let input: StatusInput = call.body()?;
+    let change = db.update_invoice_status(call.id(), &input.status, input.reason.as_deref(), numbering::today_ist())?;
+    to_value(change)
+}
+
+fn cancel_invoice(db: &Database, call: &Call) -> CommandResult<Value> {
+    let input: CancelInput = call.body()?;
+    to_value(db.cancel_invoice(call.id(), &input.reason, numbering::today_ist())?)
+}
+
+fn amend_invoice(db: &Database, call: &Call) -> CommandResult<Value> {
+    let input: AmendInput = call.optional_body()?;
+    let number_format =
+        NumberFormat::parse(input.number_format.as_deref().unwrap_or(numbering::DEFAULT_FORMAT))?;
+    let id = db.amend_invoice(call.id(), &number_format, numbering::today_ist())?;
+    Ok(json!({ "id": id }))
+}
+
+fn get_invoice_status_history(db: &Database, call: &Call) -> CommandResult<Value> {
+    to_value(db.get_invoice_status_history(call.id()).map_err(ApiError::from)?)
+}
+
+fn get_invoice_balance(db: &Database, call: &Call) -> CommandResult<Value> {
+    let balance = db.get_invoice_balance(call.id()).map_err(ApiError::from)?;
+    to_value(balance.ok_or_else(|| not_found("Invoice", call.id()))?)
+}
+
+fn list_invoice_notes(db: &Database, call: &Call) -> CommandResult<Value> {
+    to_value(db.get_invoice_notes(call.id()).map_err(ApiError::from)?)
+}
+
+fn create_note(db: &Database, call: &Call) -> CommandResult<Value> {
+    let input: NoteInput = call.body()?;
+    let note = Invoice { id: None, ..input.note };
+    let id = operations::store_note(db, &note, &input.items, &input.references, input.number_format.as_deref())?;
+    Ok(json!({ "id": id }))
+}
+
+fn get_note_references(db: &Database, call: &Call) -> CommandResult<Value> {
+    to_value(db.get_note_references(call.id()).map_err(ApiError::from)?)
+}
+
+fn list_payments(db: &Database, call: &Call) -> CommandResult<Value> {
+    let filter = PaymentFilter {
+        invoice_id: call.param("invoice_id")?,
+        customer_id: call.param("customer_id")?,
+        payment_method: call.param("payment_method")?,
+        date_from: call.param("date_from")?,
+        date_to: call.param("date_to")?,
+        include_voided: call.param("include_voided")?,
+        limit: call.param("limit")?,
+        offset: call.param("offset")?,
+    };
+    to_value(db.list_payments(&filter).map_err(ApiError::from)?)
+}
+
+fn record_payment(db: &Database, call: &Call) -> CommandResult<Value> {
+    let payment: NewPayment = call.body()?;
+    let ids = db.record_payment(&payment, numbering::today_ist())?;
+    Ok(json!({ "ids": ids }))
+}
+
+fn void_payment(db: &Database, call: &Call) -> CommandResult<Value> {
+    let input: VoidInput = call.optional_body()?;
+    to_value(db.void_payment(call.id(), input.reason.as_deref(), numbering::today_ist())?)
+}
+
+fn get_gstr1(db: &Database, call: &Call) -> CommandResult<Value> {
+    to_value(operations::compute_gstr1(db, &call.required_param("period")?)?)
+}
+
+fn get_gstr3b(db: &Database, call: &Call) -> CommandResult<Value> {
+    to_value(operations::compute_gstr3b(db, &call.required_param("period")?)?)
+}
+
+// =====================================================
+// OpenAPI Description
+// =====================================================
+
+/// OpenAPI 3.0 description of the routes above
+pub fn openapi() -> Value {
+    let mut paths = Map::new();
+    paths.insert(
+        "/openapi.json".to_string(),
+        json!({ "get": {
+            "tags": ["Meta"],
+            "summary": "This description",
+            "security": [],
+            "responses": { "200": { "description": "OpenAPI document", "content": json_content(&json!({ "type": "object" })) } },
+        }}),
+    );
+
+    for route in ROUTES {
+        let mut parameters: Vec<Value> = route
+            .query
+            .iter()
+            .map(|param| {
+                json!({ "name": param.name, "in": "query", "required": param.required, "schema": { "type": param.kind } })
+            })
+            .collect();
+        if route.path.contains("{id}") {
+            parameters.insert(
+                0,
+                json!({ "name": "id", "in": "path", "required": true, "schema": { "type": "integer", "format": "int64" } }),
+            );
+        }
+
+        let status: u16 = if route.created { 201 } else { 200 };
+        let mut responses = Map::new();
+        responses.insert(
+            status.to_string(),
+            json!({ "description": reason_phrase(status), "content": json_content(&schema(route.response)) }),
+        );
+        responses.insert(
+            "default".to_string(),
+            json!({ "description": "Error", "content": json_content(&schema("ApiError")) }),
+        );
+        let mut operation = json!({
+            "tags": [route.tag],
+            "summary": route.summary,
+            "parameters": parameters,
+            "responses": responses,
+        });
+        if let Some(body) = route.body {
+            operation["requestBody"] = json!({ "required": true, "content": json_content(&schema(body)) });
+        }
+
+        let path = paths.entry(route.path.to_string()).or_insert_with(|| json!({}));
+        path[route.method.to_ascii_lowercase()] = operation;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Payvlo local API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Customers, products, invoices, payments and GST returns of the Payvlo database on this machine. Errors are {error, message} objects with the same codes the app reports.",
+        },
+        "servers": [{ "url": BASE_PATH }],
+        "security": [{ "bearerAuth": [] }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": { "bearerAuth": { "type": "http", "scheme": "bearer" } },
+            "schemas": schemas(),
+        },
+    })
+}
+
+fn json_content(schema: &Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// `Name` refers to a component schema, `Name[]` to a list of them and "" to any object
+fn schema(name: &str) -> Value {
+    match name.strip_suffix("[]") {
+        _ if name.is_empty() => json!({ "type": "object" }),
+        Some(item) => json!({ "type": "array", "items": schema(item) }),
+        None => json!({ "$ref": format!("#/components/schemas/{}", name) }),
+    }
+}
+
+/// Field types: string, integer, number, boolean, date, money (rupees), or a schema
+/// name as `schema` takes them; any of these with `[]` for a list
+fn property(kind: &str) -> Value {
+    match kind {
+        "string" | "integer" | "number" | "boolean" => json!({ "type": kind }),
+        "date" => json!({ "type": "string", "format": "date" }),
+        "money" => json!({ "type": "number", "description": "Rupees, to the paisa" }),
+        _ => match kind.strip_suffix("[]") {
+            Some(item) => json!({ "type": "array", "items": property(item) }),
+            None => schema(kind),
+        },
+    }
+}
+
+fn object(required: &[(&str, &str)], optional: &[(&str, &str)]) -> Value {
+    let properties: Map<String, Value> = required
+        .iter()
+        .chain(optional)
+        .map(|(name, kind)| (name.to_string(), property(kind)))
+        .collect();
+    let required: Vec<&str> = required.iter().map(|(name, _)| *name).collect();
+    json!({ "type": "object", "required": required, "properties": properties })
+}
+
+fn schemas() -> Value {
+    let invoice_required = [
+        ("invoice_number", "string"), ("invoice_date", "date"), ("customer_id", "integer"),
+        ("invoice_type", "string"), ("place_of_supply", "string"), ("reverse_charge", "boolean"),
+        ("subtotal", "money"), ("total_discount", "money"), ("taxable_amount", "money"),
+        ("cgst_amount", "money"), ("sgst_amount", "money"), ("igst_amount", "money"),
+        ("cess_amount", "money"), ("total_tax", "money"), ("total_amount", "money"),
+        ("round_off", "money"), ("final_amount", "money"), ("status", "string"),
+    ];
+    let invoice_optional = [
+        ("id", "integer"), ("payment_terms", "string"), ("due_date", "date"), ("notes", "string"),
+        ("terms_conditions", "string"), ("note_reason", "string"), ("irn", "string"),
+        ("cancelled_at", "string"), ("cancellation_reason", "string"), ("amends_invoice_id", "integer"),
+        ("created_at", "string"), ("updated_at", "string"),
+    ];
+    let mut invoice_with_items = object(&invoice_required, &invoice_optional);
+    invoice_with_items["properties"]["items"] = schema("InvoiceItem[]");
+
+    json!({
+        "ApiError": object(&[("error", "string"), ("message", "string")], &[]),
+        "Customer": object(
+            &[
+                ("customer_name", "string"), ("customer_type", "string"), ("address_line1", "string"),
+                ("city", "string"), ("state", "string"), ("pincode", "string"), ("credit_limit", "money"),
+                ("credit_period_days", "integer"), ("is_active", "boolean"),
+            ],
+            &[
+                ("id", "integer"), ("gstin", "string"), ("pan", "string"), ("address_line2", "string"),
+                ("phone", "string"), ("email", "string"), ("invoice_template_id", "integer"),
+                ("created_at", "string"), ("updated_at", "string"),
+            ],
+        ),
+        "Product": object(
+            &[
+                ("product_code", "string"), ("product_name", "string"), ("hsn_sac_code", "string"),
+                ("product_type", "string"), ("unit_of_measurement", "string"), ("rate", "money"),
+                ("gst_rate", "number"), ("cess_rate", "number"), ("is_active", "boolean"),
+            ],
+            &[("id", "integer"), ("description", "string"), ("created_at", "string"), ("updated_at", "string")],
+        ),
+        "Invoice": object(&invoice_required, &invoice_optional),
+        "InvoiceItem": object(
+            &[
+                ("invoice_id", "integer"), ("product_id", "integer"), ("line_number", "integer"),
+                ("product_code", "string"), ("product_name", "string"), ("hsn_sac_code", "string"),
+                ("quantity", "number"), ("unit_price", "money"), ("discount_percent", "number"),
+                ("discount_amount", "money"), ("taxable_amount", "money"), ("gst_rate", "number"),
+                ("cgst_rate", "number"), ("sgst_rate", "number"), ("igst_rate", "number"),
+                ("cess_rate", "number"), ("cgst_amount", "money"), ("sgst_amount", "money"),
+                ("igst_amount", "money"), ("cess_amount", "money"), ("total_tax", "money"),
+                ("line_total", "money"),
+            ],
+            &[("id", "integer"), ("description", "string")],
+        ),
+        "InvoiceWithItems": invoice_with_items,
+        "InvoiceInput": object(
+            &[("invoice", "Invoice"), ("items", "InvoiceItem[]")],
+            &[("number_format", "string")],
+        ),
+        "SavedInvoice": object(&[("id", "integer"), ("rate_mismatches", "RateMismatch[]")], &[]),
+        "RateMismatch": object(
+            &[
+                ("line_number", "integer"), ("hsn_sac_code", "string"), ("gst_rate", "number"),
+                ("cess_rate", "number"), ("master_gst_rate", "number"), ("master_cess_rate", "number"),
+                ("rate_id", "integer"),
+            ],
+            &[],
+        ),
+        "StatusInput": object(&[("status", "string")], &[("reason", "string")]),
+        "CancelInput": object(&[("reason", "string")], &[]),
+        "AmendInput": object(&[], &[("number_format", "string")]),
+        "StatusChange": object(
+            &[("id", "integer"), ("invoice_id", "integer"), ("to_status", "string"), ("changed_at", "string")],
+            &[("from_status", "string"), ("reason", "string")],
+        ),
+        "InvoiceBalance": object(
+            &[
+                ("invoice_id", "integer"), ("invoice_number", "string"), ("final_amount", "money"),
+                ("adjustments", "money"), ("amount_paid", "money"), ("outstanding", "money"),
+                ("status", "string"),
+            ],
+            &[],
+        ),
+        "NoteAllocation": object(&[("invoice_id", "integer"), ("amount", "money")], &[]),
+        "NoteInput": object(
+            &[("note", "Invoice"), ("items", "InvoiceItem[]"), ("references", "NoteAllocation[]")],
+            &[("number_format", "string")],
+        ),
+        "NoteReference": object(
+            &[
+                ("note_id", "integer"), ("note_number", "string"), ("note_type", "string"),
+                ("note_date", "date"), ("note_status", "string"), ("invoice_id", "integer"),
+                ("invoice_number", "string"), ("invoice_date", "date"), ("invoice_value", "money"),
+                ("invoice_place_of_supply", "string"), ("amount", "money"),
+            ],
+            &[],
+        ),
+        "PaymentAllocation": object(&[("invoice_id", "integer"), ("amount", "money")], &[]),
+        "NewPayment": object(
+            &[("payment_date", "date"), ("payment_method", "string"), ("allocations", "PaymentAllocation[]")],
+            &[("reference_number", "string"), ("notes", "string"), ("receipt_number", "string")],
+        ),
+        "RecordedPayment": object(&[("ids", "integer[]")], &[]),
+        "Payment": object(
+            &[("id", "integer"), ("invoice_id", "integer"), ("payment_date", "date"), ("amount", "money"), ("payment_method", "string")],
+            &[
+                ("reference_number", "string"), ("notes", "string"), ("receipt_number", "string"),
+                ("voided_at", "string"), ("void_reason", "string"), ("created_at", "string"),
+            ],
+        ),
+        "VoidInput": object(&[], &[("reason", "string")]),
+        "Created": object(&[("id", "integer")], &[]),
+        "Deleted": object(&[("deleted", "boolean")], &[]),
+    })
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum ServerError {
+    InvalidPort(u16),
+    Bind { port: u16, err: io::Error },
+    Token(getrandom::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl ServerError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ServerError::InvalidPort(_) => "InvalidApiPort",
+            ServerError::Bind { .. } => "ApiPortUnavailable",
+            ServerError::Token(_) => "ApiTokenUnavailable",
+            ServerError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::InvalidPort(port) => write!(
+                f,
+                "Port {} is reserved for the system; choose one from {} to 65535",
+                port, MIN_PORT
+            ),
+            ServerError::Bind { port, err } => write!(f, "Cannot listen on port {}: {}", port, err),
+            ServerError::Token(err) => write!(f, "Cannot generate an API token: {}", err),
+            ServerError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+impl From<rusqlite::Error> for ServerError {
+    fn from(err: rusqlite::Error) -> Self {
+        ServerError::Sqlite(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn request(method: &str, target: &str, token: Option<&str>, body: &str) -> Request {
+        let mut raw = format!("{} {} HTTP/1.1\r\nHost: 127.0.0.1\r\n", method, target);
+        if let Some(token) = token {
+            raw.push_str(&format!("Authorization: Bearer {}\r\n", token));
+        }
+        raw.push_str(&format!("Content-Length: {}\r\n\r\n{}", body.len(), body));
+        match read_request(&mut raw.as_bytes()) {
+            Ok(request) => request,
+            Err(_) => panic!("{} {} did not parse", method, target),
+        }
+    }
+
+    #[test]
+    fn parses_requests() {
+        let parsed = request("GET", "/api/v1/invoices?search=INV%2F24+25&status=SENT&limit=", None, "");
+        assert_eq!(parsed.path, "/api/v1/invoices");
+        assert_eq!(
+            parsed.query,
+            [
+                ("search".to_string(), "INV/24 25".to_string()),
+                ("status".to_string(), "SENT".to_string()),
+                ("limit".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(request("POST", "/api/v1/customers", None, "{\"a\":1}").body, b"{\"a\":1}");
+
+        let too_large = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1);
+        assert!(matches!(read_request(&mut too_large.as_bytes()), Err(RequestError::Invalid { status: 413, .. })));
+        assert!(matches!(read_request(&mut "NOT HTTP\r\n\r\n".as_bytes()), Err(RequestError::Invalid { status: 400, .. })));
+        assert!(matches!(read_request(&mut "".as_bytes()), Err(RequestError::Closed)));
+    }
+
+    #[test]
+    fn routes_authorized_requests() {
+        let db = Database::new(Path::new(":memory:")).unwrap();
+        db.initialize_schema().unwrap();
+        db.set_audit_actor("owner").unwrap();
+        let db: SharedDatabase = Arc::new(Mutex::new(Some(db)));
+        let status = |method: &str, target: &str, token: Option<&str>, body: &str| {
+            handle(&db, TOKEN, &request(method, target, token, body)).status
+        };
+
+        assert_eq!(status("GET", "/api/v1/openapi.json", None, ""), 200);
+        assert_eq!(status("GET", "/api/v1/customers", None, ""), 401);
+        assert_eq!(status("GET", "/api/v1/customers", Some("0123456789abcdeF"), ""), 401);
+        assert_eq!(status("GET", "/api/v1/customers", Some(TOKEN), ""), 200);
+        assert_eq!(status("GET", "/api/v1/customers/7", Some(TOKEN), ""), 404);
+        assert_eq!(status("GET", "/api/v1/customers/seven", Some(TOKEN), ""), 404);
+        assert_eq!(status("DELETE", "/api/v1/reports/gstr1", Some(TOKEN), ""), 405);
+        assert_eq!(status("GET", "/api/v1/reports/gstr1", Some(TOKEN), ""), 400);
+        assert_eq!(status("POST", "/api/v1/customers", Some(TOKEN), "{}"), 400);
+
+        let customer = r#"{"customer_name":"Sharma Traders","customer_type":"B2C","address_line1":"MG Road",
+            "city":"Pune","state":"Maharashtra","pincode":"411001","credit_limit":0,"credit_period_days":30,"is_active":true}"#;
+        let response = handle(&db, TOKEN, &request("POST", "/api/v1/customers", Some(TOKEN), customer));
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body["customer_name"], "Sharma Traders");
+
+        // Reads write nothing, not even the audit actor
+        let changes = || db.lock().unwrap().as_ref().unwrap().connection.total_changes();
+        let before = changes();
+        assert_eq!(status("GET", "/api/v1/customers", Some(TOKEN), ""), 200);
+        assert_eq!(changes(), before);
+
+        // Changes are attributed to the API, and the app's user is restored afterwards
+        let db = db.lock().unwrap();
+        let db = db.as_ref().unwrap();
+        let id = response.body["id"].as_i64().unwrap();
+        let changed_by: String = db
+            .connection
+            .query_row(
+                "SELECT changed_by FROM audit_log WHERE table_name = 'customers' AND record_id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(changed_by, API_ACTOR);
+        assert_eq!(db.get_audit_actor().unwrap(), "owner");
+    }
+
+    #[test]
+    fn describes_every_route() {
+        let document = openapi();
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+        let text = document.to_string();
+        for reference in text.split("\"$ref\":\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(schemas.contains_key(name), "{} is not defined", name);
+        }
+        for route in ROUTES {
+            let method = route.method.to_ascii_lowercase();
+            assert!(document["paths"][route.path][&method].is_object(), "{} {}", route.method, route.path);
+        }
+    }
+
+    #[test]
+    fn generates_and_compares_tokens() {
+        assert!(tokens_match(TOKEN, TOKEN));
+        assert!(!tokens_match(TOKEN, &TOKEN[1..]));
+        assert_eq!(generate_token().unwrap().len(), TOKEN_BYTES * 2);
+    }
+}
@@ -0,0 +1,154 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Audit Trail
+// Who changed which accounting record, and how
+// =====================================================
+//
+// Database triggers write a row to `audit_log` for every insert, update and delete on
+// the tables below, holding the record as JSON before and after the change. The trail
+// cannot be edited or removed, as the Companies Act audit-trail rule requires. Changes
+// are attributed to the actor stored in `audit_actor`: the operating system account
+// when the database is opened, until the user enters their own name.
+
+#[cfg(feature = "gui")]
+use serde_json::{Map, Value};
+use std::fmt;
+
+#[cfg(feature = "gui")]
+pub const AUDITED_TABLES: [&str; 6] = [
+    "company_settings",
+    "customers",
+    "products",
+    "invoices",
+    "invoice_items",
+    "payments",
+];
+
+pub const MAX_ACTOR_LENGTH: usize = 100;
+
+/// Actor recorded until the user names themselves: the operating system account
+pub fn default_actor() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find_map(|name| normalize_actor(&name).ok())
+        .unwrap_or_else(|| "system".to_string())
+}
+
+pub fn normalize_actor(actor: &str) -> Result<String, AuditError> {
+    let actor = actor.trim();
+    if actor.is_empty() || actor.chars().count() > MAX_ACTOR_LENGTH || actor.chars().any(char::is_control) {
+        return Err(AuditError::InvalidActor(actor.to_string()));
+    }
+    Ok(actor.to_string())
+}
+
+#[cfg(feature = "gui")]
+pub fn check_table(table_name: &str) -> Result<(), AuditError> {
+    if AUDITED_TABLES.contains(&table_name) {
+        Ok(())
+    } else {
+        Err(AuditError::UnknownTable(table_name.to_string()))
+    }
+}
+
+/// Columns whose value differs between the record before and after a change. Inserts
+/// and deletes list every column they carry.
+#[cfg(feature = "gui")]
+pub fn changed_fields(old: Option<&Value>, new: Option<&Value>) -> Vec<String> {
+    let empty = Map::new();
+    let old = old.and_then(Value::as_object).unwrap_or(&empty);
+    let new = new.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut fields: Vec<String> = new
+        .iter()
+        .filter(|(field, value)| old.get(*field) != Some(*value))
+        .map(|(field, _)| field.clone())
+        .collect();
+    fields.extend(old.keys().filter(|field| !new.contains_key(*field)).cloned());
+    fields
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum AuditError {
+    #[cfg(feature = "gui")]
+    UnknownTable(String),
+    InvalidActor(String),
+    Sqlite(rusqlite::Error),
+}
+
+impl AuditError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "gui")]
+            AuditError::UnknownTable(_) => "UnknownAuditTable",
+            AuditError::InvalidActor(_) => "InvalidAuditUser",
+            AuditError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "gui")]
+            AuditError::UnknownTable(table_name) => write!(
+                f,
+                "{} is not audited; use one of {}",
+                table_name,
+                AUDITED_TABLES.join(", ")
+            ),
+            AuditError::InvalidActor(actor) => write!(
+                f,
+                "User name '{}' must be 1 to {} printable characters",
+                actor, MAX_ACTOR_LENGTH
+            ),
+            AuditError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+impl From<rusqlite::Error> for AuditError {
+    fn from(err: rusqlite::Error) -> Self {
+        AuditError::Sqlite(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "gui")]
+    use serde_json::json;
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn lists_changed_fields() {
+        let old = json!({ "id": 1, "gstin": null, "city": "Pune" });
+        let new = json!({ "id": 1, "gstin": "27AAPFU0939F1ZV", "city": "Pune" });
+        assert_eq!(changed_fields(Some(&old), Some(&new)), ["gstin"]);
+        assert_eq!(changed_fields(None, Some(&new)).len(), 3);
+        assert_eq!(changed_fields(Some(&old), None).len(), 3);
+        assert!(changed_fields(Some(&old), Some(&old)).is_empty());
+    }
+
+    #[test]
+    fn validates_actor() {
+        assert_eq!(normalize_actor("  Priya Shah ").unwrap(), "Priya Shah");
+        assert!(matches!(normalize_actor("   "), Err(AuditError::InvalidActor(_))));
+        assert!(normalize_actor("a\nb").is_err());
+        assert!(normalize_actor(&"x".repeat(MAX_ACTOR_LENGTH + 1)).is_err());
+        assert!(!default_actor().is_empty());
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn validates_table() {
+        assert!(check_table("invoices").is_ok());
+        assert!(matches!(check_table("audit_log"), Err(AuditError::UnknownTable(_))));
+    }
+}
@@ -0,0 +1,779 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Backups
+// Online backups, optionally encrypted, taken on a schedule with retention, and
+// restores that are checked before they replace the live database
+// =====================================================
+//
+// A backup is copied page by page with SQLite's backup API while the app keeps using
+// the database. Unencrypted it is a plain SQLite file any SQLite tool can open.
+// Encrypted it is the compressed database sealed with ChaCha20-Poly1305, under a key
+// derived from the password with Argon2id:
+//
+//   "PAYVLOBK" | format (1 byte) | Argon2 memory KiB, passes, lanes (u32 LE each)
+//   | salt (16 bytes) | nonce (12 bytes) | ciphertext and tag
+//
+// The header is authenticated along with the ciphertext, so a wrong password and a
+// damaged archive are both refused before anything is written.
+//
+// A restore unpacks the archive into a scratch file, which must pass PRAGMA
+// integrity_check and be no newer than this build's schema. The current database is
+// then copied aside, the restored pages are copied into the live connection, and the
+// result is migrated to the current schema.
+
+#[cfg(feature = "gui")]
+use crate::database::BackupSettings;
+use crate::database::Database;
+use crate::migrations::{self, MigrationError};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+#[cfg(feature = "gui")]
+use chrono::NaiveDateTime;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 8] = b"PAYVLOBK";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 12 + SALT_LEN + NONCE_LEN;
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// Automatic backups are named `payvlo-YYYYMMDD-HHMMSS` with one of these extensions;
+/// retention only ever removes files named this way
+#[cfg(feature = "gui")]
+const AUTO_PREFIX: &str = "payvlo-";
+pub const PLAIN_EXTENSION: &str = "db";
+#[cfg(feature = "gui")]
+pub const ENCRYPTED_EXTENSION: &str = "pvbak";
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+#[cfg(feature = "gui")]
+pub const MAX_INTERVAL_HOURS: u32 = 720;
+#[cfg(feature = "gui")]
+pub const MAX_KEEP_COUNT: u32 = 365;
+
+/// Argon2id cost of new archives; the cost is stored with each archive, so it can be
+/// raised later without breaking older backups
+const KDF_PARAMS: KdfParams = KdfParams { memory_kib: 19 * 1024, passes: 2, lanes: 1 };
+/// Archives asking for more than this are refused rather than exhausting memory
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum BackupError {
+    FileExists(PathBuf),
+    PasswordRequired,
+    PasswordTooShort,
+    #[cfg(feature = "gui")]
+    InvalidInterval(u32),
+    #[cfg(feature = "gui")]
+    InvalidKeepCount(u32),
+    NotABackup(PathBuf),
+    /// Wrong password, or the archive was altered or truncated
+    DecryptionFailed,
+    IntegrityCheckFailed(Vec<String>),
+    Schema(MigrationError),
+    Io(std::io::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl BackupError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            BackupError::FileExists(_) | BackupError::Io(_) => "FileError",
+            BackupError::PasswordRequired => "BackupPasswordRequired",
+            BackupError::PasswordTooShort => "InvalidBackupSettings",
+            #[cfg(feature = "gui")]
+            BackupError::InvalidInterval(_) | BackupError::InvalidKeepCount(_) => "InvalidBackupSettings",
+            BackupError::NotABackup(_) => "InvalidBackupFile",
+            BackupError::DecryptionFailed => "BackupDecryptionFailed",
+            BackupError::IntegrityCheckFailed(_) => "BackupIntegrityFailed",
+            BackupError::Schema(MigrationError::DatabaseTooNew { .. }) => "DatabaseTooNew",
+            BackupError::Schema(MigrationError::ForeignKeyViolation { .. }) => "MigrationFailed",
+            BackupError::Schema(MigrationError::Sqlite(_)) | BackupError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupError::FileExists(path) => write!(f, "{} already exists; choose a new file", path.display()),
+            BackupError::PasswordRequired => write!(f, "This backup is encrypted; enter its password"),
+            BackupError::PasswordTooShort => {
+                write!(f, "Backup passwords must be at least {} characters", MIN_PASSWORD_LENGTH)
+            }
+            #[cfg(feature = "gui")]
+            BackupError::InvalidInterval(hours) => {
+                write!(f, "Backup interval {} must be from 1 to {} hours", hours, MAX_INTERVAL_HOURS)
+            }
+            #[cfg(feature = "gui")]
+            BackupError::InvalidKeepCount(count) => {
+                write!(f, "Backups to keep {} must be from 1 to {}", count, MAX_KEEP_COUNT)
+            }
+            BackupError::NotABackup(path) => write!(f, "{} is not a Payvlo backup", path.display()),
+            BackupError::DecryptionFailed => {
+                write!(f, "The backup could not be decrypted; the password is wrong or the file is damaged")
+            }
+            BackupError::IntegrityCheckFailed(problems) => {
+                write!(f, "The backup failed the integrity check: {}", problems.join("; "))
+            }
+            BackupError::Schema(err) => write!(f, "{}", err),
+            BackupError::Io(err) => write!(f, "{}", err),
+            BackupError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
+impl From<std::io::Error> for BackupError {
+    fn from(err: std::io::Error) -> Self {
+        BackupError::Io(err)
+    }
+}
+
+impl From<rusqlite::Error> for BackupError {
+    fn from(err: rusqlite::Error) -> Self {
+        BackupError::Sqlite(err)
+    }
+}
+
+impl From<MigrationError> for BackupError {
+    fn from(err: MigrationError) -> Self {
+        BackupError::Schema(err)
+    }
+}
+
+// =====================================================
+// Backup Files
+// =====================================================
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    pub encrypted: bool,
+    pub schema_version: i32,
+}
+
+/// A backup found in the backup directory
+#[cfg(feature = "gui")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupFile {
+    pub path: String,
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub encrypted: bool,
+    /// Taken by the schedule, and so removed once more than the kept number are newer
+    pub automatic: bool,
+    pub modified_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RestoreReport {
+    pub restored_from: String,
+    /// Schema version of the backup, and of the database once migrated
+    pub backup_version: i32,
+    pub schema_version: i32,
+    /// Copy of the database as it was before the restore; none if it was empty
+    pub previous_copy: Option<String>,
+}
+
+/// Removes a half-written file unless it was renamed into place
+struct ScratchFile(PathBuf);
+
+impl ScratchFile {
+    fn next_to(path: &Path, suffix: &str) -> Self {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        let scratch = ScratchFile(path.with_file_name(name));
+        let _ = std::fs::remove_file(&scratch.0);
+        scratch
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct KdfParams {
+    memory_kib: u32,
+    passes: u32,
+    lanes: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Plain,
+    Encrypted,
+}
+
+/// Tells a backup by its first bytes, not its extension
+fn kind_of(path: &Path) -> Result<Option<Kind>, std::io::Error> {
+    use std::io::Read;
+    let mut head = [0u8; 16];
+    let mut file = std::fs::File::open(path)?;
+    let read = file.read(&mut head)?;
+    Ok(if read == head.len() && &head == SQLITE_MAGIC {
+        Some(Kind::Plain)
+    } else if read >= MAGIC.len() && head.starts_with(MAGIC) {
+        Some(Kind::Encrypted)
+    } else {
+        None
+    })
+}
+
+// =====================================================
+// Encryption
+// =====================================================
+
+fn derive_key(password: &str, salt: &[u8], params: KdfParams) -> Result<[u8; 32], BackupError> {
+    let params = Params::new(params.memory_kib, params.passes, params.lanes, Some(32))
+        .map_err(|_| BackupError::DecryptionFailed)?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|_| BackupError::DecryptionFailed)?;
+    Ok(key)
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], BackupError> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|err| BackupError::Io(std::io::Error::other(err.to_string())))?;
+    Ok(bytes)
+}
+
+fn seal(database: &[u8], password: &str, params: KdfParams) -> Result<Vec<u8>, BackupError> {
+    let salt = random_bytes::<SALT_LEN>()?;
+    let nonce = random_bytes::<NONCE_LEN>()?;
+
+    let mut archive = Vec::with_capacity(HEADER_LEN + database.len() / 2);
+    archive.extend_from_slice(MAGIC);
+    archive.push(FORMAT_VERSION);
+    for value in [params.memory_kib, params.passes, params.lanes] {
+        archive.extend_from_slice(&value.to_le_bytes());
+    }
+    archive.extend_from_slice(&salt);
+    archive.extend_from_slice(&nonce);
+
+    let key = derive_key(password, &salt, params)?;
+    let compressed = miniz_oxide::deflate::compress_to_vec(database, 6);
+    let sealed = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: &compressed, aad: &archive })
+        .map_err(|_| BackupError::DecryptionFailed)?;
+    archive.extend_from_slice(&sealed);
+    Ok(archive)
+}
+
+fn open_sealed(archive: &[u8], password: &str, path: &Path) -> Result<Vec<u8>, BackupError> {
+    let not_a_backup = || BackupError::NotABackup(path.to_path_buf());
+    if archive.len() < HEADER_LEN || !archive.starts_with(MAGIC) || archive[MAGIC.len()] != FORMAT_VERSION {
+        return Err(not_a_backup());
+    }
+    let (header, sealed) = archive.split_at(HEADER_LEN);
+    let number = |at: usize| u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]]);
+    let params = KdfParams { memory_kib: number(9), passes: number(13), lanes: number(17) };
+    if params.memory_kib > MAX_KDF_MEMORY_KIB || params.passes > 16 || params.lanes > 16 {
+        return Err(not_a_backup());
+    }
+    let salt = &header[21..21 + SALT_LEN];
+    let nonce = &header[21 + SALT_LEN..];
+
+    let key = derive_key(password, salt, params)?;
+    let compressed = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: header })
+        .map_err(|_| BackupError::DecryptionFailed)?;
+    miniz_oxide::inflate::decompress_to_vec(&compressed).map_err(|_| BackupError::DecryptionFailed)
+}
+
+// =====================================================
+// Backup and Restore
+// =====================================================
+
+/// Writes a backup of the live database to `path`, encrypted when a password is given.
+/// The file only appears once it is complete.
+pub fn create_backup(db: &Database, path: &Path, password: Option<&str>) -> Result<BackupInfo, BackupError> {
+    create_with(db, path, password, KDF_PARAMS)
+}
+
+fn create_with(db: &Database, path: &Path, password: Option<&str>, params: KdfParams) -> Result<BackupInfo, BackupError> {
+    if path.exists() {
+        return Err(BackupError::FileExists(path.to_path_buf()));
+    }
+    let copy = ScratchFile::next_to(path, ".partial");
+    db.backup_to(&copy.0)?;
+
+    match password {
+        Some(password) => {
+            let sealed = seal(&std::fs::read(&copy.0)?, password, params)?;
+            let archive = ScratchFile::next_to(path, ".sealing");
+            std::fs::write(&archive.0, sealed)?;
+            std::fs::rename(&archive.0, path)?;
+        }
+        None => std::fs::rename(&copy.0, path)?,
+    }
+
+    Ok(BackupInfo {
+        path: path.display().to_string(),
+        size_bytes: std::fs::metadata(path)?.len(),
+        encrypted: password.is_some(),
+        schema_version: migrations::current_version(&db.connection)?,
+    })
+}
+
+/// Replaces the live database with the backup at `archive`, once the backup has passed
+/// the integrity check and is known to be a schema this build can open. Unless it is
+/// empty, the database being replaced is first copied into `previous_dir`.
+pub fn restore_backup(
+    db: &mut Database,
+    archive: &Path,
+    password: Option<&str>,
+    previous_dir: &Path,
+) -> Result<RestoreReport, BackupError> {
+    let kind = kind_of(archive)?.ok_or_else(|| BackupError::NotABackup(archive.to_path_buf()))?;
+    let unpacked = ScratchFile::next_to(&previous_dir.join("restore.db"), ".partial");
+    std::fs::create_dir_all(previous_dir)?;
+    match kind {
+        Kind::Plain => {
+            std::fs::copy(archive, &unpacked.0)?;
+        }
+        Kind::Encrypted => {
+            let password = password.ok_or(BackupError::PasswordRequired)?;
+            let database = open_sealed(&std::fs::read(archive)?, password, archive)?;
+            std::fs::write(&unpacked.0, database)?;
+        }
+    }
+
+    let source = Connection::open_with_flags(&unpacked.0, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let backup_version = verify(&source, archive)?;
+
+    let previous_copy = if has_tables(&db.connection)? {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut copy = previous_dir.join(format!("before-restore-{}.{}", stamp, PLAIN_EXTENSION));
+        for n in 2.. {
+            if !copy.exists() {
+                break;
+            }
+            copy = previous_dir.join(format!("before-restore-{}-{}.{}", stamp, n, PLAIN_EXTENSION));
+        }
+        db.backup_to(&copy)?;
+        Some(copy.display().to_string())
+    } else {
+        None
+    };
+
+    // The audit actor belongs to whoever is using the app now, not to the backup, and so
+    // does the backup password, which backups are written without
+    let actor = db.get_audit_actor().ok();
+    let backup_password = db.get_backup_password().ok();
+    db.restore_from(&source)?;
+    drop(source);
+    let schema_version = db.initialize_schema()?;
+    if let Some(actor) = actor {
+        if let Err(err) = db.set_audit_actor(&actor) {
+            log::warn!("Could not keep the audit user after the restore: {}", err);
+        }
+    }
+    if let Some(password) = backup_password {
+        if let Err(err) = db.set_backup_password(password.as_deref()) {
+            log::warn!("Could not keep the backup password after the restore: {}", err);
+        }
+    }
+    log::info!("Restored the database from {}", archive.display());
+
+    Ok(RestoreReport {
+        restored_from: archive.display().to_string(),
+        backup_version,
+        schema_version,
+        previous_copy,
+    })
+}
+
+/// Checks an unpacked backup is a sound Payvlo database; returns its schema version
+fn verify(source: &Connection, archive: &Path) -> Result<i32, BackupError> {
+    let check = || -> rusqlite::Result<Vec<String>> {
+        let mut stmt = source.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    };
+    // A page damaged badly enough can stop the check itself
+    let problems = check().map_err(|err| match err {
+            rusqlite::Error::SqliteFailure(failure, _) if failure.code == rusqlite::ErrorCode::NotADatabase => {
+                BackupError::NotABackup(archive.to_path_buf())
+            }
+            err => BackupError::IntegrityCheckFailed(vec![err.to_string()]),
+        })?;
+    if problems != ["ok"] {
+        return Err(BackupError::IntegrityCheckFailed(problems));
+    }
+
+    let payvlo_tables: i64 = source.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('company_settings', 'invoices')",
+        [],
+        |row| row.get(0),
+    )?;
+    if payvlo_tables != 2 {
+        return Err(BackupError::NotABackup(archive.to_path_buf()));
+    }
+    Ok(migrations::check_compatible(source)?)
+}
+
+fn has_tables(connection: &Connection) -> rusqlite::Result<bool> {
+    connection.query_row("SELECT EXISTS (SELECT 1 FROM sqlite_master)", [], |row| row.get(0))
+}
+
+// =====================================================
+// Schedule and Retention
+// =====================================================
+
+/// Checks new settings; `password` replaces the stored one, which is kept otherwise
+#[cfg(feature = "gui")]
+pub fn check_settings(settings: &BackupSettings, password: Option<&str>, has_password: bool) -> Result<(), BackupError> {
+    if !(1..=MAX_INTERVAL_HOURS).contains(&settings.interval_hours) {
+        return Err(BackupError::InvalidInterval(settings.interval_hours));
+    }
+    if !(1..=MAX_KEEP_COUNT).contains(&settings.keep_count) {
+        return Err(BackupError::InvalidKeepCount(settings.keep_count));
+    }
+    check_password(password)?;
+    if settings.encrypted && password.is_none() && !has_password {
+        return Err(BackupError::PasswordRequired);
+    }
+    Ok(())
+}
+
+/// New passwords only; archives are opened with whatever password they were sealed with
+pub fn check_password(password: Option<&str>) -> Result<(), BackupError> {
+    match password {
+        Some(password) if password.chars().count() < MIN_PASSWORD_LENGTH => Err(BackupError::PasswordTooShort),
+        _ => Ok(()),
+    }
+}
+
+/// Where automatic backups go: the configured directory, else `default_dir`
+#[cfg(feature = "gui")]
+pub fn backup_dir(settings: &BackupSettings, default_dir: &Path) -> PathBuf {
+    match settings.directory.as_deref().map(str::trim) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => default_dir.to_path_buf(),
+    }
+}
+
+#[cfg(feature = "gui")]
+fn is_due(settings: &BackupSettings, now: NaiveDateTime) -> bool {
+    let last = settings
+        .last_backup_at
+        .as_deref()
+        .and_then(|at| NaiveDateTime::parse_from_str(at, "%Y-%m-%d %H:%M:%S").ok());
+    match last {
+        Some(last) => now - last >= chrono::Duration::hours(settings.interval_hours.into()),
+        None => true,
+    }
+}
+
+/// Takes an automatic backup if the schedule is on and one is due, then removes the
+/// oldest automatic backups beyond the number kept. `now` is UTC, as SQLite keeps time.
+/// A failure is recorded in the settings for the user to see, and returned.
+#[cfg(feature = "gui")]
+pub fn run_scheduled(db: &Database, default_dir: &Path, now: NaiveDateTime) -> Result<Option<BackupInfo>, BackupError> {
+    let settings = db.get_backup_settings()?;
+    if !settings.enabled || !is_due(&settings, now) {
+        return Ok(None);
+    }
+
+    let result = take_scheduled(db, &settings, default_dir, now);
+    match &result {
+        Ok(info) => {
+            db.record_backup_success(&now.format("%Y-%m-%d %H:%M:%S").to_string())?;
+            log::info!("Automatic backup written to {}", info.path);
+        }
+        Err(err) => db.record_backup_failure(&err.to_string())?,
+    }
+    result.map(Some)
+}
+
+#[cfg(feature = "gui")]
+fn take_scheduled(
+    db: &Database,
+    settings: &BackupSettings,
+    default_dir: &Path,
+    now: NaiveDateTime,
+) -> Result<BackupInfo, BackupError> {
+    let dir = backup_dir(settings, default_dir);
+    std::fs::create_dir_all(&dir)?;
+
+    let password = if settings.encrypted {
+        Some(db.get_backup_password()?.ok_or(BackupError::PasswordRequired)?)
+    } else {
+        None
+    };
+    let extension = if password.is_some() { ENCRYPTED_EXTENSION } else { PLAIN_EXTENSION };
+    let name = format!("{}{}.{}", AUTO_PREFIX, now.format("%Y%m%d-%H%M%S"), extension);
+    let info = create_backup(db, &dir.join(name), password.as_deref())?;
+
+    prune(&dir, settings.keep_count as usize)?;
+    Ok(info)
+}
+
+/// Whether `name` is one of the schedule's own files
+#[cfg(feature = "gui")]
+fn automatic_stamp(name: &str) -> Option<&str> {
+    let (stamp, extension) = name.strip_prefix(AUTO_PREFIX)?.rsplit_once('.')?;
+    let valid = (extension == PLAIN_EXTENSION || extension == ENCRYPTED_EXTENSION)
+        && stamp.len() == 15
+        && stamp.char_indices().all(|(i, c)| if i == 8 { c == '-' } else { c.is_ascii_digit() });
+    valid.then_some(stamp)
+}
+
+/// Removes the oldest automatic backups in `dir` so that `keep` remain
+#[cfg(feature = "gui")]
+fn prune(dir: &Path, keep: usize) -> Result<Vec<PathBuf>, BackupError> {
+    let mut automatic = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if let Some(stamp) = automatic_stamp(&name) {
+            automatic.push((stamp.to_string(), entry.path()));
+        }
+    }
+    automatic.sort();
+
+    let excess = automatic.len().saturating_sub(keep);
+    let mut removed = Vec::new();
+    for (_, path) in automatic.into_iter().take(excess) {
+        std::fs::remove_file(&path)?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// Backups in `dir`, newest first. Files are recognised by their contents, so manual
+/// backups saved there are listed too.
+#[cfg(feature = "gui")]
+pub fn list_backups(dir: &Path) -> Result<Vec<BackupFile>, BackupError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let path = entry.path();
+        if !metadata.is_file() {
+            continue;
+        }
+        let Some(kind) = kind_of(&path)? else { continue };
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let modified = metadata.modified().ok().map(chrono::DateTime::<chrono::Local>::from);
+        backups.push((
+            modified,
+            BackupFile {
+                path: path.display().to_string(),
+                automatic: automatic_stamp(&file_name).is_some(),
+                file_name,
+                size_bytes: metadata.len(),
+                encrypted: kind == Kind::Encrypted,
+                modified_at: modified.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string()),
+            },
+        ));
+    }
+    backups.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.file_name.cmp(&a.1.file_name)));
+    Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap enough for unoptimised test builds
+    const TEST_KDF: KdfParams = KdfParams { memory_kib: 64, passes: 1, lanes: 1 };
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("payvlo-backup-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn company_name(db: &Database) -> String {
+        db.connection
+            .query_row("SELECT company_name FROM company_settings", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    fn set_company_name(db: &Database, name: &str) {
+        db.connection.execute("DELETE FROM company_settings", []).unwrap();
+        db.connection
+            .execute(
+                "INSERT INTO company_settings (company_name, gstin, pan, address_line1, city, state, pincode)
+                 VALUES (?1, '29ABCDE1234F1Z5', 'ABCDE1234F', '1 MG Road', 'Bengaluru', 'Karnataka', '560001')",
+                [name],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn encrypted_backup_restores_only_with_its_password() {
+        let dir = scratch_dir("encrypted");
+        let mut db = Database::new(&dir.join("live.db")).unwrap();
+        db.initialize_schema().unwrap();
+        set_company_name(&db, "Before Backup Traders");
+
+        let archive = dir.join("copy.pvbak");
+        let info = create_with(&db, &archive, Some("correct horse"), TEST_KDF).unwrap();
+        assert!(info.encrypted);
+        assert_eq!(info.schema_version, migrations::latest_version());
+        assert_eq!(kind_of(&archive).unwrap(), Some(Kind::Encrypted));
+        assert!(matches!(create_backup(&db, &archive, None), Err(BackupError::FileExists(_))));
+
+        set_company_name(&db, "After Backup Traders");
+        let previous = dir.join("previous");
+        assert!(matches!(
+            restore_backup(&mut db, &archive, None, &previous),
+            Err(BackupError::PasswordRequired)
+        ));
+        assert!(matches!(
+            restore_backup(&mut db, &archive, Some("wrong horse"), &previous),
+            Err(BackupError::DecryptionFailed)
+        ));
+        assert_eq!(company_name(&db), "After Backup Traders");
+
+        let report = restore_backup(&mut db, &archive, Some("correct horse"), &previous).unwrap();
+        assert_eq!(company_name(&db), "Before Backup Traders");
+        assert_eq!(report.schema_version, migrations::latest_version());
+        let copy = Database::new(Path::new(&report.previous_copy.unwrap())).unwrap();
+        assert_eq!(company_name(&copy), "After Backup Traders");
+
+        // A flipped byte anywhere, header included, is refused
+        let mut damaged = std::fs::read(&archive).unwrap();
+        damaged[10] ^= 1;
+        std::fs::write(&archive, &damaged).unwrap();
+        assert!(matches!(
+            restore_backup(&mut db, &archive, Some("correct horse"), &previous),
+            Err(BackupError::DecryptionFailed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn restore_refuses_damaged_newer_and_foreign_databases() {
+        let dir = scratch_dir("verify");
+        let mut db = Database::new(&dir.join("live.db")).unwrap();
+        db.initialize_schema().unwrap();
+        set_company_name(&db, "Live Traders");
+        let previous = dir.join("previous");
+
+        let newer = dir.join("newer.db");
+        create_backup(&db, &newer, None).unwrap();
+        Connection::open(&newer)
+            .unwrap()
+            .pragma_update(None, "user_version", migrations::latest_version() + 1)
+            .unwrap();
+        assert!(matches!(
+            restore_backup(&mut db, &newer, None, &previous),
+            Err(BackupError::Schema(MigrationError::DatabaseTooNew { .. }))
+        ));
+
+        let foreign = dir.join("foreign.db");
+        Connection::open(&foreign).unwrap().execute_batch("CREATE TABLE notes (body TEXT)").unwrap();
+        assert!(matches!(restore_backup(&mut db, &foreign, None, &previous), Err(BackupError::NotABackup(_))));
+
+        // Scribble over the page holding the company settings
+        let damaged = dir.join("damaged.db");
+        create_backup(&db, &damaged, None).unwrap();
+        let (root, page_size): (usize, usize) = Connection::open(&damaged)
+            .unwrap()
+            .query_row(
+                "SELECT rootpage, (SELECT page_size FROM pragma_page_size) FROM sqlite_master WHERE name = 'company_settings'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        let mut bytes = std::fs::read(&damaged).unwrap();
+        bytes[(root - 1) * page_size..root * page_size].fill(0xA5);
+        std::fs::write(&damaged, &bytes).unwrap();
+        assert!(matches!(
+            restore_backup(&mut db, &damaged, None, &previous),
+            Err(BackupError::IntegrityCheckFailed(_))
+        ));
+
+        assert_eq!(company_name(&db), "Live Traders");
+        assert!(!previous.exists() || std::fs::read_dir(&previous).unwrap().all(|entry| {
+            !entry.unwrap().file_name().to_string_lossy().starts_with("before-restore")
+        }));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn schedule_backs_up_when_due_and_keeps_the_newest() {
+        let dir = scratch_dir("schedule");
+        let db = Database::new(&dir.join("live.db")).unwrap();
+        db.initialize_schema().unwrap();
+        let backups = dir.join("backups");
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+
+        let mut settings = db.get_backup_settings().unwrap();
+        assert!(run_scheduled(&db, &backups, at("2026-10-01 09:00:00")).unwrap().is_none());
+
+        settings.enabled = true;
+        settings.interval_hours = 24;
+        settings.keep_count = 2;
+        db.save_backup_settings(&settings, None).unwrap();
+        std::fs::create_dir_all(&backups).unwrap();
+        std::fs::write(backups.join("payvlo-notes.db"), "kept").unwrap();
+
+        for (now, taken) in [
+            ("2026-10-01 09:00:00", true),
+            ("2026-10-01 20:00:00", false),
+            ("2026-10-02 09:00:00", true),
+            ("2026-10-03 09:30:00", true),
+        ] {
+            assert_eq!(run_scheduled(&db, &backups, at(now)).unwrap().is_some(), taken, "{}", now);
+        }
+        assert_eq!(db.get_backup_settings().unwrap().last_backup_at.as_deref(), Some("2026-10-03 09:30:00"));
+
+        let listed = list_backups(&backups).unwrap();
+        let mut names: Vec<_> = listed.iter().map(|backup| backup.file_name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["payvlo-20261002-090000.db", "payvlo-20261003-093000.db"]);
+        assert!(listed.iter().all(|backup| backup.automatic && !backup.encrypted));
+        assert!(backups.join("payvlo-notes.db").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn backups_leave_out_the_password_and_restores_keep_the_current_one() {
+        let dir = scratch_dir("password");
+        let mut db = Database::new(&dir.join("live.db")).unwrap();
+        db.initialize_schema().unwrap();
+        db.set_backup_password(Some("first secret")).unwrap();
+
+        let copy = dir.join("copy.db");
+        create_backup(&db, &copy, None).unwrap();
+        let bytes = std::fs::read(&copy).unwrap();
+        assert!(!bytes.windows(12).any(|window| window == b"first secret"));
+        assert_eq!(Database::new(&copy).unwrap().get_backup_password().unwrap(), None);
+
+        db.set_backup_password(Some("second secret")).unwrap();
+        let report = restore_backup(&mut db, &copy, None, &dir.join("previous")).unwrap();
+        assert_eq!(db.get_backup_password().unwrap().as_deref(), Some("second secret"));
+        let previous = std::fs::read(report.previous_copy.unwrap()).unwrap();
+        assert!(!previous.windows(13).any(|window| window == b"second secret"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,5 @@
+// Headless command line for scripts and scheduled jobs; see cli.rs for the commands
+
+fn main() -> std::process::ExitCode {
+    app_lib::cli::main()
+}
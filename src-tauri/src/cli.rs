@@ -0,0 +1,479 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Command Line
+// Headless access to the same database for scripts and scheduled jobs
+// =====================================================
+//
+// payvlo-cli [--db PATH] [--json] COMMAND
+//
+//   init                                   create or upgrade the database
+//   import customers|products FILE [--mapping NAME] [--sheet NAME] [--dry-run]
+//                                          bulk import from CSV or Excel
+//   export gstr1|gstr3b --period PERIOD [--out FILE]
+//   export tally --out FILE [--from DATE] [--to DATE] [--all]
+//   invoice list [--status S] [--type T] [--customer ID] [--from DATE] [--to DATE]
+//                [--search TEXT] [--limit N]
+//   backup FILE [--password-env VAR]       copy the database while it is in use
+//   restore FILE [--password-env VAR]      replace the database with a checked backup
+//   serve [--port N]                       run the local HTTP API in the foreground
+//
+// Backups are encrypted with the password held in the environment variable named by
+// --password-env, so it never appears in the process list or shell history.
+//
+// The database defaults to the one the desktop app uses; PAYVLO_DB or --db picks
+// another. With --json every command prints JSON for other tools to read, and errors
+// are printed to stderr as the same {error, message} objects the app receives.
+//
+// Build it without the desktop app, and so without tauri or a webview, with
+// `cargo build --release --no-default-features --bin payvlo-cli`.
+
+use crate::api_server::{self, ApiServer};
+use crate::backup;
+use crate::database::{Database, InvoiceFilter};
+use crate::import::{self, ImportKind, ImportOptions};
+use crate::money::Money;
+use crate::operations::{self, ApiError};
+use crate::reports;
+use crate::tally::{self, TallyExportOptions};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+
+/// Must match `identifier` in tauri.conf.json, which names the app data directory
+const APP_IDENTIFIER: &str = "com.tauri.dev";
+
+const USAGE: &str = "Usage: payvlo-cli [--db PATH] [--json] COMMAND
+
+Commands:
+  init                                   Create or upgrade the database
+  import customers|products FILE         Import customers or products from .csv or .xlsx
+         [--mapping NAME] [--sheet NAME] using a saved column mapping and worksheet;
+         [--dry-run]                     with --dry-run only report what would fail
+  export gstr1|gstr3b --period PERIOD    Build a GST return (PERIOD is 2026-09 or 092026)
+         [--out FILE]                    and write it to FILE instead of stdout
+  export tally --out FILE                Write Tally import XML for vouchers not exported
+         [--from DATE] [--to DATE]       yet, or with --all for every document in range
+         [--all]
+  invoice list [--status S] [--type T] [--customer ID] [--from DATE] [--to DATE]
+               [--search TEXT] [--limit N]
+  backup FILE [--password-env VAR]       Copy the database to FILE while it is in use,
+                                         encrypted with the password in $VAR
+  restore FILE [--password-env VAR]      Replace the database with a backup once it passes
+                                         the integrity check; the old one is kept aside
+  serve [--port N]                       Run the local HTTP API until stopped with Ctrl+C";
+
+/// What a command produced: JSON for --json, text otherwise
+struct Output {
+    json: Value,
+    text: String,
+    /// Exit with failure after printing, e.g. when some rows were not imported
+    failed: bool,
+}
+
+impl Output {
+    fn new(json: Value, text: String) -> Self {
+        Output { json, text, failed: false }
+    }
+}
+
+/// Command line split into positional words and `--name value` options
+struct Args {
+    words: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ApiError> {
+        let mut words = Vec::new();
+        let mut options = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some("json" | "help" | "dry-run" | "all") => options.push((arg[2..].to_string(), None)),
+                Some(name) => match name.split_once('=') {
+                    Some((name, value)) => options.push((name.to_string(), Some(value.to_string()))),
+                    None => {
+                        let value = args.next().ok_or_else(|| usage(&format!("--{} needs a value", name)))?;
+                        options.push((name.to_string(), Some(value)));
+                    }
+                },
+                None => words.push(arg),
+            }
+        }
+        Ok(Args { words, options })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn word(&self, index: usize, what: &str) -> Result<&str, ApiError> {
+        self.words
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| usage(&format!("Missing {}", what)))
+    }
+
+    /// Rejects options the command does not take, so typos are not silently ignored
+    fn only(&self, allowed: &[&str]) -> Result<(), ApiError> {
+        match self
+            .options
+            .iter()
+            .find(|(option, _)| !["db", "json", "help"].contains(&option.as_str()) && !allowed.contains(&option.as_str()))
+        {
+            Some((option, _)) => Err(usage(&format!("Unknown option --{}", option))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn usage(message: &str) -> ApiError {
+    ApiError {
+        error: "InvalidArguments".to_string(),
+        message: message.to_string(),
+    }
+}
+
+// =====================================================
+// Entry Point
+// =====================================================
+
+pub fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => return report_error(&err, false),
+    };
+    let json = args.flag("json");
+    if args.flag("help") || args.words.is_empty() {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&args) {
+        Ok(output) => {
+            if json {
+                println!("{}", serde_json::to_string_pretty(&output.json).unwrap_or_default());
+            } else if !output.text.is_empty() {
+                println!("{}", output.text);
+            }
+            if output.failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+        }
+        Err(err) => report_error(&err, json),
+    }
+}
+
+fn report_error(err: &ApiError, json: bool) -> ExitCode {
+    if json {
+        eprintln!("{}", serde_json::to_string(err).unwrap_or_default());
+    } else {
+        eprintln!("Error: {}", err.message);
+        if err.error == "InvalidArguments" {
+            eprintln!("\n{}", USAGE);
+        }
+    }
+    if err.error == "InvalidArguments" { ExitCode::from(2) } else { ExitCode::FAILURE }
+}
+
+fn run(args: &Args) -> Result<Output, ApiError> {
+    let db_path = match args.value("db") {
+        Some(path) => PathBuf::from(path),
+        None => default_db_path()?,
+    };
+
+    match args.word(0, "command")? {
+        "init" => {
+            args.only(&[])?;
+            init(&db_path)
+        }
+        "import" => {
+            args.only(&["mapping", "sheet", "dry-run"])?;
+            let kind = ImportKind::parse(args.word(1, "what to import (customers or products)")?)?;
+            let file = args.word(2, "CSV or Excel file to import")?;
+            import(&open_existing(&db_path)?, kind, Path::new(file), args)
+        }
+        "export" => {
+            let report = args.word(1, "what to export (gstr1, gstr3b or tally)")?;
+            if report.eq_ignore_ascii_case("tally") {
+                args.only(&["out", "from", "to", "all"])?;
+                let out = args.value("out").ok_or_else(|| usage("export tally needs --out"))?;
+                export_tally(&open_existing(&db_path)?, Path::new(out), args)
+            } else {
+                args.only(&["period", "out"])?;
+                let period = args.value("period").ok_or_else(|| usage("export needs --period"))?;
+                export(&open_existing(&db_path)?, report, period, args.value("out").map(Path::new))
+            }
+        }
+        "invoice" => match args.word(1, "invoice command (list)")? {
+            "list" => {
+                args.only(&["status", "type", "customer", "from", "to", "search", "limit"])?;
+                list_invoices(&open_existing(&db_path)?, args)
+            }
+            other => Err(usage(&format!("Unknown invoice command {}", other))),
+        },
+        "backup" => {
+            args.only(&["password-env"])?;
+            let file = args.word(1, "backup file")?;
+            create_backup(&open_existing(&db_path)?, Path::new(file), args)
+        }
+        "restore" => {
+            args.only(&["password-env"])?;
+            let file = args.word(1, "backup file to restore")?;
+            restore(&db_path, Path::new(file), args)
+        }
+        "serve" => {
+            args.only(&["port"])?;
+            serve(open_existing(&db_path)?, args.value("port"), args.flag("json"))
+        }
+        other => Err(usage(&format!("Unknown command {}", other))),
+    }
+}
+
+/// The desktop app's database: `payvlo.db` in its data directory, unless PAYVLO_DB is set
+fn default_db_path() -> Result<PathBuf, ApiError> {
+    if let Some(path) = std::env::var_os("PAYVLO_DB") {
+        return Ok(PathBuf::from(path));
+    }
+    let env_dir = |key: &str| std::env::var_os(key).map(PathBuf::from);
+    let data_dir = if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env_dir("XDG_DATA_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".local").join("share")))
+    };
+    data_dir
+        .map(|dir| dir.join(APP_IDENTIFIER).join("payvlo.db"))
+        .ok_or_else(|| usage("Cannot find the app data directory; pass --db or set PAYVLO_DB"))
+}
+
+/// Opens a database created earlier, rather than starting an empty one by mistake
+fn open_existing(db_path: &Path) -> Result<Database, ApiError> {
+    if !db_path.is_file() {
+        return Err(ApiError {
+            error: "DatabaseNotInitialized".to_string(),
+            message: format!("No database at {}; run init first or pass --db", db_path.display()),
+        });
+    }
+    operations::open_database(db_path)
+}
+
+// =====================================================
+// Commands
+// =====================================================
+
+fn create_parent_dir(db_path: &Path) -> Result<(), ApiError> {
+    if let Some(dir) = db_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|err| ApiError {
+            error: "FileError".to_string(),
+            message: format!("Cannot create {}: {}", dir.display(), err),
+        })?;
+    }
+    Ok(())
+}
+
+/// The backup password from the variable --password-env names, if given
+fn backup_password(args: &Args) -> Result<Option<String>, ApiError> {
+    match args.value("password-env") {
+        Some(var) => match std::env::var(var) {
+            Ok(password) => Ok(Some(password)),
+            Err(_) => Err(usage(&format!("Environment variable {} is not set", var))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn init(db_path: &Path) -> Result<Output, ApiError> {
+    create_parent_dir(db_path)?;
+    let db = operations::open_database(db_path)?;
+    let version = db.initialize_schema().map_err(ApiError::from)?;
+    Ok(Output::new(
+        json!({ "path": db_path, "schema_version": version }),
+        format!("Database {} is at schema version {}", db_path.display(), version),
+    ))
+}
+
+fn import(db: &Database, kind: ImportKind, file: &Path, args: &Args) -> Result<Output, ApiError> {
+    let mapping_id = match args.value("mapping") {
+        Some(name) => {
+            let mapping = db.find_import_mapping(kind.as_str(), name).map_err(ApiError::from)?;
+            let mapping = mapping.ok_or_else(|| ApiError {
+                error: "NotFound".to_string(),
+                message: format!("There is no {} import mapping named {}", kind.as_str(), name),
+            })?;
+            mapping.id
+        }
+        None => None,
+    };
+    let options = ImportOptions {
+        columns: None,
+        mapping_id,
+        sheet: args.value("sheet").map(str::to_string),
+        dry_run: args.flag("dry-run"),
+    };
+    let report = import::import_file(db, kind, file, &options)?;
+    let verb = if report.dry_run { "Would import" } else { "Imported" };
+    let mut text = format!("{} {} of {} {}", verb, report.imported, report.total_rows, kind.as_str());
+    for error in &report.errors {
+        text.push_str(&format!("\n  line {}: {}", error.row, error.message));
+    }
+    Ok(Output {
+        failed: !report.errors.is_empty(),
+        json: serde_json::to_value(&report).unwrap_or_default(),
+        text,
+    })
+}
+
+fn export(db: &Database, report: &str, period: &str, out: Option<&Path>) -> Result<Output, ApiError> {
+    let (name, json) = match report.to_lowercase().as_str() {
+        "gstr1" => ("GSTR-1", serde_json::to_value(operations::compute_gstr1(db, period)?)),
+        "gstr3b" => ("GSTR-3B", serde_json::to_value(operations::compute_gstr3b(db, period)?)),
+        other => return Err(usage(&format!("Unknown return {}; use gstr1 or gstr3b", other))),
+    };
+    let json = json.map_err(|err| ApiError::from(reports::ReportError::from(err)))?;
+
+    // Without --out the return itself is the output, ready to pipe
+    let Some(out) = out else {
+        let text = serde_json::to_string(&json).unwrap_or_default();
+        return Ok(Output::new(json, text));
+    };
+    reports::write_json(out, &json)?;
+    Ok(Output::new(
+        json!({ "return": name, "period": period, "path": out }),
+        format!("Wrote {} for {} to {}", name, period, out.display()),
+    ))
+}
+
+fn export_tally(db: &Database, out: &Path, args: &Args) -> Result<Output, ApiError> {
+    let options = TallyExportOptions {
+        date_from: args.value("from").map(str::to_string),
+        date_to: args.value("to").map(str::to_string),
+        incremental: !args.flag("all"),
+    };
+    let report = tally::export_to_file(db, out, &options)?;
+    let mut text = format!(
+        "Wrote {} new, {} altered and {} cancelled vouchers with {} ledgers to {}",
+        report.created, report.altered, report.cancelled, report.ledgers, report.path
+    );
+    for (voucher_type, numbers) in &report.vouchers {
+        text.push_str(&format!("\n  {}: {}", voucher_type, numbers.join(", ")));
+    }
+    Ok(Output::new(serde_json::to_value(&report).unwrap_or_default(), text))
+}
+
+fn list_invoices(db: &Database, args: &Args) -> Result<Output, ApiError> {
+    let number = |name: &str| -> Result<Option<i64>, ApiError> {
+        args.value(name)
+            .map(|value| value.parse().map_err(|_| usage(&format!("--{} must be a number", name))))
+            .transpose()
+    };
+    let filter = InvoiceFilter {
+        customer_id: number("customer")?,
+        invoice_type: args.value("type").map(str::to_uppercase),
+        status: args.value("status").map(str::to_uppercase),
+        date_from: args.value("from").map(str::to_string),
+        date_to: args.value("to").map(str::to_string),
+        search: args.value("search").map(str::to_string),
+        limit: number("limit")?.map(|limit| limit as i32),
+        offset: None,
+    };
+    let invoices = db.list_invoices(&filter).map_err(ApiError::from)?;
+
+    let mut text = format!(
+        "{:<20} {:<10} {:<11} {:<9} {:>14}",
+        "NUMBER", "DATE", "TYPE", "STATUS", "AMOUNT"
+    );
+    for invoice in &invoices {
+        text.push_str(&format!(
+            "\n{:<20} {:<10} {:<11} {:<9} {:>14}",
+            invoice.invoice_number, invoice.invoice_date, invoice.invoice_type, invoice.status, invoice.final_amount
+        ));
+    }
+    let total: Money = invoices.iter().map(|invoice| invoice.final_amount).sum();
+    text.push_str(&format!("\n{} invoice(s), {} in all", invoices.len(), total));
+
+    Ok(Output::new(serde_json::to_value(&invoices).unwrap_or_default(), text))
+}
+
+fn create_backup(db: &Database, file: &Path, args: &Args) -> Result<Output, ApiError> {
+    let password = backup_password(args)?;
+    backup::check_password(password.as_deref())?;
+    let info = backup::create_backup(db, file, password.as_deref())?;
+    let text = format!(
+        "Backed up the database to {}{}",
+        file.display(),
+        if info.encrypted { ", encrypted" } else { "" }
+    );
+    Ok(Output::new(serde_json::to_value(&info).unwrap_or_default(), text))
+}
+
+/// Restores into the database even when it no longer opens, or is missing altogether.
+/// The database it replaces is kept in a backups folder beside it.
+fn restore(db_path: &Path, file: &Path, args: &Args) -> Result<Output, ApiError> {
+    let password = backup_password(args)?;
+    create_parent_dir(db_path)?;
+    let mut db = Database::new(db_path).map_err(ApiError::from)?;
+    let report = backup::restore_backup(&mut db, file, password.as_deref(), &db_path.with_file_name("backups"))?;
+
+    let mut text = format!(
+        "Restored {} from {} (schema version {}, now {})",
+        db_path.display(),
+        file.display(),
+        report.backup_version,
+        report.schema_version
+    );
+    if let Some(previous) = &report.previous_copy {
+        text.push_str(&format!("\nThe database it replaced was kept at {}", previous));
+    }
+    Ok(Output::new(serde_json::to_value(&report).unwrap_or_default(), text))
+}
+
+/// Runs the local API with the app's token until the process is stopped. --port
+/// overrides the saved port for this run only.
+fn serve(db: Database, port: Option<&str>, json: bool) -> Result<Output, ApiError> {
+    let settings = api_server::load_settings(&db)?;
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| usage("--port must be a number from 1024 to 65535"))?,
+        None => settings.port,
+    };
+    let token = settings.token.unwrap_or_default();
+    let server = ApiServer::start(Arc::new(Mutex::new(Some(db))), port, token.clone())?;
+
+    // Printed straight away, since serving only ends with the process
+    if json {
+        println!("{}", json!({ "url": server.url(), "port": server.port(), "token": token }));
+    } else {
+        println!("Serving {} with token {}\nPress Ctrl+C to stop", server.url(), token);
+    }
+    server.wait();
+    Ok(Output::new(Value::Null, String::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Args {
+        Args::parse(line.split_whitespace().map(str::to_string)).unwrap()
+    }
+
+    #[test]
+    fn parses_words_and_options() {
+        let parsed = args("--json export gstr1 --period 2026-09 --out=gstr1.json");
+        assert_eq!(parsed.words, ["export", "gstr1"]);
+        assert!(parsed.flag("json"));
+        assert_eq!(parsed.value("period"), Some("2026-09"));
+        assert_eq!(parsed.value("out"), Some("gstr1.json"));
+        assert!(parsed.only(&["period", "out"]).is_ok());
+        assert_eq!(parsed.only(&["period"]).unwrap_err().error, "InvalidArguments");
+
+        assert!(Args::parse(["invoice".to_string(), "--status".to_string()]).is_err());
+    }
+}
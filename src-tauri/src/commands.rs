@@ -3,57 +3,57 @@
 // Frontend-Backend Bridge for Database Operations
 // =====================================================
 
-use crate::database::{Database, CompanySettings, Customer, Product, IndianState};
+use crate::database::{
+    Database, CompanySettings, Customer, Product, IndianState, Invoice, InvoiceItem,
+    InvoiceWithItems, InvoiceFilter, InvoiceSeries, Payment, NewPayment, PaymentFilter,
+    InvoiceBalance, TransportDetails, InvoiceTemplate, NoteAllocation, NoteReference, StatusChange,
+    AuditEntry, GstRate, ApiServerSettings, ImportMapping, TallySettings, BackupSettings,
+};
+use crate::api_server::{self, ApiServer, ServerError, SharedDatabase};
+use crate::backup::{self, BackupFile, BackupInfo, RestoreReport};
+use crate::einvoice::{self, EInvoice, EInvoiceError, EInvoiceRegistration};
+use crate::ewaybill::{self, EWayBillBulk, EWayBillError};
+use crate::formatting::{self, Language, WordsStyle};
+use crate::gst;
+use crate::import::{self, ImportKind, ImportOptions, ImportPreview, ImportReport};
+use crate::money::Money;
+use crate::numbering::{self, NumberFormat, ResetRule};
+use crate::operations::{
+    compute_gstr1, compute_gstr3b, open_database, store_invoice, store_note, ApiError,
+    CommandResult, SavedInvoice,
+};
+use crate::gstr1::Gstr1Return;
+use crate::gstr3b::Gstr3bSummary;
+use crate::pdf::{self, PdfError};
+use crate::rates;
+use crate::reports;
+use crate::tally::{self, TallyExportOptions, TallyExportReport};
+use crate::templates::{self, TemplateDefinition, TemplateError};
+use crate::validation::{self, GstinError};
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
-use tauri::{AppHandle, State};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
 
 // =====================================================
 // Application State Management
 // =====================================================
 
 pub struct AppState {
-    pub db: Mutex<Option<Database>>,
+    /// Shared with the local API server, which serves requests from its own threads
+    pub db: SharedDatabase,
+    pub api_server: Mutex<Option<ApiServer>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
-            db: Mutex::new(None),
+            db: Arc::new(Mutex::new(None)),
+            api_server: Mutex::new(None),
         }
     }
 }
 
-// =====================================================
-// Error Handling
-// =====================================================
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ApiError {
-    pub error: String,
-    pub message: String,
-}
-
-impl From<rusqlite::Error> for ApiError {
-    fn from(err: rusqlite::Error) -> Self {
-        ApiError {
-            error: "DatabaseError".to_string(),
-            message: err.to_string(),
-        }
-    }
-}
-
-impl From<Box<dyn std::error::Error>> for ApiError {
-    fn from(err: Box<dyn std::error::Error>) -> Self {
-        ApiError {
-            error: "GeneralError".to_string(),
-            message: err.to_string(),
-        }
-    }
-}
-
-type CommandResult<T> = Result<T, ApiError>;
-
 // =====================================================
 // Database Initialization Commands
 // =====================================================
@@ -66,15 +66,20 @@ pub async fn initialize_database(
     // Get database path
     let db_path = Database::get_db_path(&app_handle)?;
     
-    // Create database connection
-    let db = Database::new(&db_path).map_err(ApiError::from)?;
-    
-    // Initialize schema
-    db.initialize_schema().map_err(ApiError::from)?;
+    let db = open_database(&db_path)?;
+    let api_settings = api_server::load_settings(&db)?;
     
     // Store database in app state
     let mut db_mutex = state.db.lock().unwrap();
     *db_mutex = Some(db);
+    drop(db_mutex);
+    
+    // A port taken by another program must not keep the app from opening
+    if api_settings.enabled {
+        if let Err(err) = restart_api_server(state.inner(), &api_settings) {
+            log::warn!("Local API server did not start: {}", err);
+        }
+    }
     
     Ok(true)
 }
@@ -92,6 +97,56 @@ pub async fn check_database_health(state: State<'_, AppState>) -> CommandResult<
     }
 }
 
+// =====================================================
+// Audit Trail Commands
+// =====================================================
+
+/// Sets the name recorded against changes made from now on; returns it as stored
+#[tauri::command]
+pub async fn set_audit_user(
+    name: String,
+    state: State<'_, AppState>,
+) -> CommandResult<String> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let actor = db.set_audit_actor(&name)?;
+    Ok(actor)
+}
+
+#[tauri::command]
+pub async fn get_audit_user(state: State<'_, AppState>) -> CommandResult<String> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let actor = db.get_audit_actor().map_err(ApiError::from)?;
+    Ok(actor)
+}
+
+/// Every recorded change to one customer, product, invoice, invoice line, payment or
+/// the company settings, oldest first
+#[tauri::command]
+pub async fn get_audit_history(
+    table_name: String,
+    record_id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<AuditEntry>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let history = db.get_audit_history(&table_name, record_id)?;
+    Ok(history)
+}
+
 // =====================================================
 // Company Settings Commands
 // =====================================================
@@ -284,186 +339,1291 @@ pub async fn search_products(
 }
 
 // =====================================================
-// Indian States Commands
+// GST Rate Master Commands
 // =====================================================
 
 #[tauri::command]
-pub async fn get_indian_states(state: State<'_, AppState>) -> CommandResult<Vec<IndianState>> {
+pub async fn get_gst_rates(
+    hsn_sac_code: Option<String>,
+    include_inactive: Option<bool>,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<GstRate>> {
     let db_mutex = state.db.lock().unwrap();
     let db = db_mutex.as_ref().ok_or_else(|| ApiError {
         error: "DatabaseNotInitialized".to_string(),
         message: "Database not initialized".to_string(),
     })?;
     
-    let states = db.get_indian_states().map_err(ApiError::from)?;
-    Ok(states)
+    let rates = db
+        .get_gst_rates(hsn_sac_code.as_deref(), include_inactive.unwrap_or(false))
+        .map_err(ApiError::from)?;
+    Ok(rates)
 }
 
 #[tauri::command]
-pub async fn get_state_by_code(
-    state_code: String,
+pub async fn save_gst_rate(
+    rate: GstRate,
     state: State<'_, AppState>,
-) -> CommandResult<Option<IndianState>> {
+) -> CommandResult<i64> {
     let db_mutex = state.db.lock().unwrap();
     let db = db_mutex.as_ref().ok_or_else(|| ApiError {
         error: "DatabaseNotInitialized".to_string(),
         message: "Database not initialized".to_string(),
     })?;
     
-    let state_info = db.get_state_by_code(&state_code).map_err(ApiError::from)?;
-    Ok(state_info)
+    let id = db.save_gst_rate(&rate)?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_gst_rate(
+    id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<bool> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let deleted = db.delete_gst_rate(id).map_err(ApiError::from)?;
+    Ok(deleted)
+}
+
+/// Rate master entry for a code on a date (YYYY-MM-DD), today when no date is given
+#[tauri::command]
+pub async fn lookup_gst_rate(
+    hsn_sac_code: String,
+    date: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<Option<GstRate>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let date = match date {
+        Some(date) => rates::parse_date(&date)?,
+        None => numbering::today_ist(),
+    };
+    let rate = db.lookup_gst_rate(&hsn_sac_code, date).map_err(ApiError::from)?;
+    Ok(rate)
 }
 
 // =====================================================
-// Utility Commands
+// Invoice Management Commands
 // =====================================================
 
 #[tauri::command]
-pub async fn get_record_counts(state: State<'_, AppState>) -> CommandResult<serde_json::Value> {
+pub async fn list_invoices(
+    filter: Option<InvoiceFilter>,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<Invoice>> {
     let db_mutex = state.db.lock().unwrap();
     let db = db_mutex.as_ref().ok_or_else(|| ApiError {
         error: "DatabaseNotInitialized".to_string(),
         message: "Database not initialized".to_string(),
     })?;
     
-    let customers_count = db.count_records("customers").map_err(ApiError::from)?;
-    let products_count = db.count_records("products").map_err(ApiError::from)?;
-    let invoices_count = db.count_records("invoices").map_err(ApiError::from)?;
+    let invoices = db.list_invoices(&filter.unwrap_or_default()).map_err(ApiError::from)?;
+    Ok(invoices)
+}
+
+#[tauri::command]
+pub async fn get_invoice_by_id(
+    id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<Option<InvoiceWithItems>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
     
-    Ok(serde_json::json!({
-        "customers": customers_count,
-        "products": products_count,
-        "invoices": invoices_count
-    }))
+    let invoice = db.get_invoice_by_id(id).map_err(ApiError::from)?;
+    Ok(invoice)
 }
 
 #[tauri::command]
-pub async fn get_next_invoice_number(
-    format: Option<String>,
+pub async fn save_invoice(
+    invoice: Invoice,
+    items: Vec<InvoiceItem>,
+    number_format: Option<String>,
     state: State<'_, AppState>,
-) -> CommandResult<String> {
+) -> CommandResult<SavedInvoice> {
     let db_mutex = state.db.lock().unwrap();
     let db = db_mutex.as_ref().ok_or_else(|| ApiError {
         error: "DatabaseNotInitialized".to_string(),
         message: "Database not initialized".to_string(),
     })?;
     
-    let format_str = format.as_deref().unwrap_or("INV-{YYYY}-{MM}-{####}");
-    let next_number = db.get_next_invoice_number(format_str).map_err(ApiError::from)?;
-    Ok(next_number)
+    store_invoice(db, &invoice, &items, number_format.as_deref())
+}
+
+/// Deletes a draft invoice; issued invoices must be cancelled instead
+#[tauri::command]
+pub async fn delete_invoice(
+    id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<bool> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let deleted = db.delete_invoice(id).map_err(ApiError::from)?;
+    Ok(deleted)
 }
 
 // =====================================================
-// GST Validation Commands (calling TypeScript functions)
+// Invoice Status Commands
 // =====================================================
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GstinValidationResult {
-    pub is_valid: bool,
-    pub state_code: Option<String>,
-    pub pan_number: Option<String>,
-    pub entity_number: Option<String>,
-    pub check_digit: Option<String>,
-    pub error: Option<String>,
-}
+const OVERDUE_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HsnSacValidationResult {
-    pub is_valid: bool,
-    pub validation_type: Option<String>, // "HSN" or "SAC"
-    pub description: Option<String>,
-    pub suggested_gst_rate: Option<f64>,
-    pub error: Option<String>,
+/// Issues (SENT) or cancels (CANCELLED) an invoice or note; PAID and OVERDUE are derived
+#[tauri::command]
+pub async fn update_invoice_status(
+    invoice_id: i64,
+    status: String,
+    reason: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<StatusChange> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let change = db.update_invoice_status(invoice_id, &status, reason.as_deref(), numbering::today_ist())?;
+    Ok(change)
 }
 
-// Note: These validation functions would typically call the TypeScript GST calculator
-// For now, we'll implement basic validation in Rust
-
+/// Cancels an invoice or note, keeping it and its number on record
 #[tauri::command]
-pub async fn validate_gstin(gstin: String) -> CommandResult<GstinValidationResult> {
-    // Basic GSTIN validation (15 characters, proper format)
-    if gstin.len() != 15 {
-        return Ok(GstinValidationResult {
-            is_valid: false,
-            state_code: None,
-            pan_number: None,
-            entity_number: None,
-            check_digit: None,
-            error: Some("GSTIN must be exactly 15 characters".to_string()),
-        });
-    }
+pub async fn cancel_invoice(
+    invoice_id: i64,
+    reason: String,
+    state: State<'_, AppState>,
+) -> CommandResult<StatusChange> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
     
-    let gstin_upper = gstin.to_uppercase();
+    let change = db.cancel_invoice(invoice_id, &reason, numbering::today_ist())?;
+    Ok(change)
+}
+
+/// Creates a revised draft of an issued invoice and returns its id; issuing the
+/// revision cancels the original
+#[tauri::command]
+pub async fn amend_invoice(
+    invoice_id: i64,
+    number_format: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<i64> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
     
-    // Extract components
-    let state_code = &gstin_upper[0..2];
-    let pan_number = &gstin_upper[2..12];
-    let entity_number = &gstin_upper[12..13];
-    let check_digit = &gstin_upper[13..15];
+    let number_format =
+        NumberFormat::parse(number_format.as_deref().unwrap_or(numbering::DEFAULT_FORMAT))?;
     
-    // Basic format validation
-    if !state_code.chars().all(|c| c.is_ascii_digit()) {
-        return Ok(GstinValidationResult {
-            is_valid: false,
-            state_code: None,
-            pan_number: None,
-            entity_number: None,
-            check_digit: None,
-            error: Some("Invalid state code format".to_string()),
-        });
-    }
+    let id = db.amend_invoice(invoice_id, &number_format, numbering::today_ist())?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn get_invoice_status_history(
+    invoice_id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<StatusChange>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
     
-    Ok(GstinValidationResult {
-        is_valid: true,
-        state_code: Some(state_code.to_string()),
-        pan_number: Some(pan_number.to_string()),
-        entity_number: Some(entity_number.to_string()),
-        check_digit: Some(check_digit.to_string()),
-        error: None,
-    })
+    let history = db.get_invoice_status_history(invoice_id).map_err(ApiError::from)?;
+    Ok(history)
 }
 
+/// Marks invoices past their due date OVERDUE now; returns the ids that changed
 #[tauri::command]
-pub async fn validate_hsn_sac(code: String) -> CommandResult<HsnSacValidationResult> {
-    if code.is_empty() {
-        return Ok(HsnSacValidationResult {
-            is_valid: false,
-            validation_type: None,
-            description: None,
-            suggested_gst_rate: None,
-            error: Some("HSN/SAC code is required".to_string()),
-        });
-    }
+pub async fn mark_overdue_invoices(state: State<'_, AppState>) -> CommandResult<Vec<i64>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
     
-    let clean_code = code.trim();
+    let overdue = db.mark_overdue_invoices(numbering::today_ist()).map_err(ApiError::from)?;
+    Ok(overdue)
+}
+
+/// Repeats the overdue sweep hourly so invoices fall due while the app stays open
+pub fn spawn_overdue_sweep(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(OVERDUE_SWEEP_INTERVAL);
+        let state = app_handle.state::<AppState>();
+        let db_mutex = state.db.lock().unwrap();
+        if let Some(db) = db_mutex.as_ref() {
+            if let Err(err) = db.mark_overdue_invoices(numbering::today_ist()) {
+                log::warn!("Overdue sweep failed: {}", err);
+            }
+        }
+    });
+}
+
+// =====================================================
+// Payment Commands
+// =====================================================
+
+#[tauri::command]
+pub async fn record_payment(
+    payment: NewPayment,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<i64>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
     
-    // Check if it's a SAC code (services - starts with 99)
-    if clean_code.starts_with("99") && clean_code.len() == 6 && clean_code.chars().all(|c| c.is_ascii_digit()) {
-        return Ok(HsnSacValidationResult {
-            is_valid: true,
-            validation_type: Some("SAC".to_string()),
-            description: Some("SAC code for services".to_string()),
-            suggested_gst_rate: Some(18.0), // Default service rate
-            error: None,
-        });
-    }
+    let ids = db.record_payment(&payment, numbering::today_ist())?;
+    Ok(ids)
+}
+
+#[tauri::command]
+pub async fn list_payments(
+    filter: Option<PaymentFilter>,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<Payment>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
     
-    // Check if it's an HSN code (goods - 2-8 digits)
-    if clean_code.len() >= 2 && clean_code.len() <= 8 && clean_code.chars().all(|c| c.is_ascii_digit()) {
-        return Ok(HsnSacValidationResult {
-            is_valid: true,
-            validation_type: Some("HSN".to_string()),
-            description: Some("HSN code for goods".to_string()),
-            suggested_gst_rate: Some(18.0), // Default goods rate
-            error: None,
-        });
-    }
+    let payments = db.list_payments(&filter.unwrap_or_default()).map_err(ApiError::from)?;
+    Ok(payments)
+}
+
+#[tauri::command]
+pub async fn void_payment(
+    id: i64,
+    reason: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<InvoiceBalance> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
     
-    Ok(HsnSacValidationResult {
-        is_valid: false,
-        validation_type: None,
-        description: None,
+    let balance = db.void_payment(id, reason.as_deref(), numbering::today_ist())?;
+    Ok(balance)
+}
+
+#[tauri::command]
+pub async fn get_invoice_balance(
+    invoice_id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<Option<InvoiceBalance>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let balance = db.get_invoice_balance(invoice_id).map_err(ApiError::from)?;
+    Ok(balance)
+}
+
+// =====================================================
+// Credit and Debit Note Commands
+// =====================================================
+
+/// Saves a credit or debit note against one or more invoices. Without a number_format
+/// notes are numbered from the CN- or DN- series for their type when issued.
+#[tauri::command]
+pub async fn save_note(
+    note: Invoice,
+    items: Vec<InvoiceItem>,
+    references: Vec<NoteAllocation>,
+    number_format: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<i64> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    store_note(db, &note, &items, &references, number_format.as_deref())
+}
+
+#[tauri::command]
+pub async fn get_note_references(
+    note_id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<NoteReference>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let references = db.get_note_references(note_id).map_err(ApiError::from)?;
+    Ok(references)
+}
+
+#[tauri::command]
+pub async fn list_invoice_notes(
+    invoice_id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<NoteReference>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let notes = db.get_invoice_notes(invoice_id).map_err(ApiError::from)?;
+    Ok(notes)
+}
+
+// =====================================================
+// GST Return Commands
+// =====================================================
+
+/// Builds GSTR-1 for a return period (`MMYYYY` or `YYYY-MM`) and writes the offline-tool
+/// JSON to `path`
+#[tauri::command]
+pub async fn export_gstr1(
+    return_period: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> CommandResult<Gstr1Return> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let gstr1 = compute_gstr1(db, &return_period)?;
+    reports::write_json(std::path::Path::new(&path), &gstr1)?;
+    Ok(gstr1)
+}
+
+/// GSTR-3B tables 3.1 and 3.2 for a return period
+#[tauri::command]
+pub async fn get_gstr3b(
+    return_period: String,
+    state: State<'_, AppState>,
+) -> CommandResult<Gstr3bSummary> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    compute_gstr3b(db, &return_period)
+}
+
+#[tauri::command]
+pub async fn export_gstr3b(
+    return_period: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> CommandResult<Gstr3bSummary> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let gstr3b = compute_gstr3b(db, &return_period)?;
+    reports::write_json(std::path::Path::new(&path), &gstr3b)?;
+    Ok(gstr3b)
+}
+
+// =====================================================
+// Tally Export Commands
+// =====================================================
+
+#[tauri::command]
+pub async fn get_tally_settings(state: State<'_, AppState>) -> CommandResult<TallySettings> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let settings = db.get_tally_settings()?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn save_tally_settings(
+    settings: TallySettings,
+    state: State<'_, AppState>,
+) -> CommandResult<TallySettings> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let settings = tally::check_settings(&settings)?;
+    let saved = db.save_tally_settings(&settings)?;
+    Ok(saved)
+}
+
+/// Writes the Tally import file for issued documents and receipts to `path`. With
+/// `incremental` only vouchers not yet exported, or changed since, are included.
+#[tauri::command]
+pub async fn export_tally(
+    path: String,
+    options: Option<TallyExportOptions>,
+    state: State<'_, AppState>,
+) -> CommandResult<TallyExportReport> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let report = tally::export_to_file(db, std::path::Path::new(&path), &options.unwrap_or_default())?;
+    Ok(report)
+}
+
+// =====================================================
+// Backup Commands
+// =====================================================
+
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Copies the database to `path` while it stays in use, encrypted when a password is given
+#[tauri::command]
+pub async fn create_backup(
+    path: String,
+    password: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<BackupInfo> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    backup::check_password(password.as_deref())?;
+    let info = backup::create_backup(db, std::path::Path::new(&path), password.as_deref())?;
+    Ok(info)
+}
+
+/// Replaces the database with a backup once it passes the integrity and schema checks.
+/// Works before the database is initialized too, so a database that no longer opens
+/// can be recovered.
+#[tauri::command]
+pub async fn restore_backup(
+    path: String,
+    password: Option<String>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> CommandResult<RestoreReport> {
+    let db_path = Database::get_db_path(&app_handle)?;
+    let previous_dir = Database::get_backup_dir(&app_handle)?;
+    let archive = std::path::Path::new(&path);
+
+    let mut db_mutex = state.db.lock().unwrap();
+    let report = match db_mutex.as_mut() {
+        Some(db) => backup::restore_backup(db, archive, password.as_deref(), &previous_dir)?,
+        None => {
+            let mut db = Database::new(&db_path).map_err(ApiError::from)?;
+            let report = backup::restore_backup(&mut db, archive, password.as_deref(), &previous_dir)?;
+            drop(db);
+            *db_mutex = Some(open_database(&db_path)?);
+            report
+        }
+    };
+    let db = db_mutex.as_ref().expect("the database is open after a restore");
+    let api_settings = api_server::load_settings(db)?;
+    drop(db_mutex);
+
+    // The restored database brings its own API settings and token
+    if let Err(err) = restart_api_server(state.inner(), &api_settings) {
+        log::warn!("Local API server did not restart after the restore: {}", err);
+    }
+    Ok(report)
+}
+
+/// Backups in the automatic backup directory, newest first
+#[tauri::command]
+pub async fn list_backups(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<BackupFile>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let settings = db.get_backup_settings().map_err(ApiError::from)?;
+    let dir = backup::backup_dir(&settings, &Database::get_backup_dir(&app_handle)?);
+    let backups = backup::list_backups(&dir)?;
+    Ok(backups)
+}
+
+#[tauri::command]
+pub async fn get_backup_settings(state: State<'_, AppState>) -> CommandResult<BackupSettings> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let settings = db.get_backup_settings()?;
+    Ok(settings)
+}
+
+/// Saves the backup schedule. `password` replaces the one automatic backups are
+/// encrypted with; leave it out to keep the current one.
+#[tauri::command]
+pub async fn save_backup_settings(
+    settings: BackupSettings,
+    password: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<BackupSettings> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let has_password = db.get_backup_password().map_err(ApiError::from)?.is_some();
+    backup::check_settings(&settings, password.as_deref(), has_password)?;
+    let saved = db.save_backup_settings(&settings, password.as_deref())?;
+    Ok(saved)
+}
+
+/// Takes automatic backups as they fall due while the app stays open
+pub fn spawn_backup_schedule(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(BACKUP_CHECK_INTERVAL);
+        let default_dir = match Database::get_backup_dir(&app_handle) {
+            Ok(dir) => dir,
+            Err(err) => {
+                log::warn!("Automatic backup skipped: {}", err);
+                continue;
+            }
+        };
+        let state = app_handle.state::<AppState>();
+        let db_mutex = state.db.lock().unwrap();
+        if let Some(db) = db_mutex.as_ref() {
+            if let Err(err) = backup::run_scheduled(db, &default_dir, chrono::Utc::now().naive_utc()) {
+                log::warn!("Automatic backup failed: {}", err);
+            }
+        }
+    });
+}
+
+// =====================================================
+// E-invoice Commands
+// =====================================================
+
+/// Builds the INV-01 payload for an invoice, writing it to `path` when one is given
+#[tauri::command]
+pub async fn generate_e_invoice(
+    invoice_id: i64,
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<EInvoice> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let company = db
+        .get_company_settings()
+        .map_err(ApiError::from)?
+        .ok_or(EInvoiceError::CompanySettingsMissing)?;
+    let document = db
+        .get_return_invoice(invoice_id)
+        .map_err(ApiError::from)?
+        .ok_or(EInvoiceError::InvoiceNotFound(invoice_id))?;
+    
+    let payload = einvoice::build_e_invoice(&company, &document)?;
+    if let Some(path) = path {
+        reports::write_json(std::path::Path::new(&path), &payload)?;
+    }
+    Ok(payload)
+}
+
+/// Stores the IRN, acknowledgement and signed QR code returned by the IRP
+#[tauri::command]
+pub async fn save_e_invoice_registration(
+    invoice_id: i64,
+    registration: EInvoiceRegistration,
+    state: State<'_, AppState>,
+) -> CommandResult<Invoice> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let invoice = db.save_e_invoice_registration(invoice_id, &registration)?;
+    Ok(invoice)
+}
+
+// =====================================================
+// E-way Bill Commands
+// =====================================================
+
+#[tauri::command]
+pub async fn get_transport_details(
+    invoice_id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<Option<TransportDetails>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let details = db.get_transport_details(invoice_id).map_err(ApiError::from)?;
+    Ok(details)
+}
+
+#[tauri::command]
+pub async fn save_transport_details(
+    details: TransportDetails,
+    state: State<'_, AppState>,
+) -> CommandResult<TransportDetails> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let details = db.save_transport_details(&details)?;
+    Ok(details)
+}
+
+/// Whether the goods on an invoice exceed the e-way bill threshold
+#[tauri::command]
+pub async fn is_e_way_bill_required(
+    invoice_id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<bool> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let document = db
+        .get_return_invoice(invoice_id)
+        .map_err(ApiError::from)?
+        .ok_or(EWayBillError::InvoiceNotFound(invoice_id))?;
+    Ok(ewaybill::is_required(&document))
+}
+
+/// Builds the bulk-upload file for one or more invoices, writing it to `path` when one is given
+#[tauri::command]
+pub async fn generate_e_way_bills(
+    invoice_ids: Vec<i64>,
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<EWayBillBulk> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let company = db
+        .get_company_settings()
+        .map_err(ApiError::from)?
+        .ok_or(EWayBillError::CompanySettingsMissing)?;
+    let mut documents = Vec::with_capacity(invoice_ids.len());
+    for invoice_id in invoice_ids {
+        let document = db
+            .get_return_invoice(invoice_id)
+            .map_err(ApiError::from)?
+            .ok_or(EWayBillError::InvoiceNotFound(invoice_id))?;
+        let transport = db.get_transport_details(invoice_id).map_err(ApiError::from)?;
+        documents.push((document, transport));
+    }
+    
+    let bulk = ewaybill::build_bulk(&company, &documents)?;
+    if let Some(path) = path {
+        reports::write_json(std::path::Path::new(&path), &bulk)?;
+    }
+    Ok(bulk)
+}
+
+// =====================================================
+// Invoice PDF Commands
+// =====================================================
+
+/// Renders the invoice as a PDF in the app data directory with the customer's (or else the
+/// company's) layout template, records the path on the invoice and returns it.
+/// Regenerating overwrites the previous file.
+#[tauri::command]
+pub async fn generate_invoice_pdf(
+    invoice_id: i64,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> CommandResult<String> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let company = db
+        .get_company_settings()
+        .map_err(ApiError::from)?
+        .ok_or(PdfError::CompanySettingsMissing)?;
+    let document = db
+        .get_return_invoice(invoice_id)
+        .map_err(ApiError::from)?
+        .ok_or(PdfError::InvoiceNotFound(invoice_id))?;
+    
+    let template = db
+        .invoice_template_for(&document.customer, &company)
+        .map_err(ApiError::from)?;
+    
+    let pdf_dir = Database::get_pdf_dir(&app_handle)?;
+    let path = pdf::write_invoice(&pdf_dir, &company, &document, &template)?;
+    let path = path.to_string_lossy().to_string();
+    db.set_invoice_pdf_path(invoice_id, &path).map_err(ApiError::from)?;
+    Ok(path)
+}
+
+// =====================================================
+// Invoice Template Commands
+// =====================================================
+
+#[tauri::command]
+pub async fn list_invoice_templates(state: State<'_, AppState>) -> CommandResult<Vec<InvoiceTemplate>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let templates = db.list_invoice_templates().map_err(ApiError::from)?;
+    Ok(templates)
+}
+
+#[tauri::command]
+pub async fn get_invoice_template(
+    id: i64,
+    state: State<'_, AppState>,
+) -> CommandResult<Option<InvoiceTemplate>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let template = db.get_invoice_template(id).map_err(ApiError::from)?;
+    Ok(template)
+}
+
+#[tauri::command]
+pub async fn save_invoice_template(
+    template: InvoiceTemplate,
+    state: State<'_, AppState>,
+) -> CommandResult<InvoiceTemplate> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let saved = db.save_invoice_template(&template)?;
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_invoice_template(id: i64, state: State<'_, AppState>) -> CommandResult<bool> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let deleted = db.delete_invoice_template(id)?;
+    Ok(deleted)
+}
+
+/// Renders a template, saved or not, against an invoice or else a built-in sample and
+/// returns the path of the preview PDF. Nothing is recorded on the invoice.
+#[tauri::command]
+pub async fn preview_invoice_template(
+    definition: TemplateDefinition,
+    invoice_id: Option<i64>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> CommandResult<String> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let problems = definition.validate();
+    if !problems.is_empty() {
+        return Err(TemplateError::Invalid(problems).into());
+    }
+    
+    let company = db
+        .get_company_settings()
+        .map_err(ApiError::from)?
+        .unwrap_or_else(templates::sample_company);
+    let document = match invoice_id {
+        Some(invoice_id) => db
+            .get_return_invoice(invoice_id)
+            .map_err(ApiError::from)?
+            .ok_or(PdfError::InvoiceNotFound(invoice_id))?,
+        None => templates::sample_invoice(&company),
+    };
+    
+    let preview_dir = Database::get_pdf_dir(&app_handle)?.join("previews");
+    let path = pdf::write_invoice(&preview_dir, &company, &document, &definition)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// =====================================================
+// Bulk Import Commands
+// =====================================================
+
+/// Imports customers or products from a .csv or .xlsx file. Rows that fail are listed
+/// in the report; with `dry_run` nothing is saved.
+#[tauri::command]
+pub async fn import_records(
+    kind: String,
+    path: String,
+    options: Option<ImportOptions>,
+    state: State<'_, AppState>,
+) -> CommandResult<ImportReport> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let kind = ImportKind::parse(&kind)?;
+    let report = import::import_file(db, kind, std::path::Path::new(&path), &options.unwrap_or_default())?;
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn preview_import(
+    kind: String,
+    path: String,
+    options: Option<ImportOptions>,
+    state: State<'_, AppState>,
+) -> CommandResult<ImportPreview> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let kind = ImportKind::parse(&kind)?;
+    let preview = import::preview_import(db, kind, std::path::Path::new(&path), &options.unwrap_or_default())?;
+    Ok(preview)
+}
+
+#[tauri::command]
+pub async fn list_import_mappings(kind: String, state: State<'_, AppState>) -> CommandResult<Vec<ImportMapping>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let kind = ImportKind::parse(&kind)?;
+    let mappings = db.list_import_mappings(kind.as_str())?;
+    Ok(mappings)
+}
+
+/// Saving under a name already used for the kind replaces that mapping
+#[tauri::command]
+pub async fn save_import_mapping(
+    mapping: ImportMapping,
+    state: State<'_, AppState>,
+) -> CommandResult<ImportMapping> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    let mapping = import::check_mapping(&mapping)?;
+    let id = db.save_import_mapping(&mapping)?;
+    let saved = db.get_import_mapping(id)?.ok_or_else(|| ApiError {
+        error: "NotFound".to_string(),
+        message: format!("Import mapping {} does not exist", id),
+    })?;
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_import_mapping(id: i64, state: State<'_, AppState>) -> CommandResult<()> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+
+    db.delete_import_mapping(id)?;
+    Ok(())
+}
+
+// =====================================================
+// Indian States Commands
+// =====================================================
+
+#[tauri::command]
+pub async fn get_indian_states(state: State<'_, AppState>) -> CommandResult<Vec<IndianState>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let states = db.get_indian_states().map_err(ApiError::from)?;
+    Ok(states)
+}
+
+#[tauri::command]
+pub async fn get_state_by_code(
+    state_code: String,
+    state: State<'_, AppState>,
+) -> CommandResult<Option<IndianState>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let state_info = db.get_state_by_code(&state_code).map_err(ApiError::from)?;
+    Ok(state_info)
+}
+
+// =====================================================
+// Utility Commands
+// =====================================================
+
+#[tauri::command]
+pub async fn get_record_counts(state: State<'_, AppState>) -> CommandResult<serde_json::Value> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let customers_count = db.count_records("customers").map_err(ApiError::from)?;
+    let products_count = db.count_records("products").map_err(ApiError::from)?;
+    let invoices_count = db.count_records("invoices").map_err(ApiError::from)?;
+    
+    Ok(serde_json::json!({
+        "customers": customers_count,
+        "products": products_count,
+        "invoices": invoices_count
+    }))
+}
+
+#[tauri::command]
+pub async fn get_next_invoice_number(
+    format: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<String> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let format = NumberFormat::parse(format.as_deref().unwrap_or(numbering::DEFAULT_FORMAT))?;
+    let next_number = db.get_next_invoice_number(&format, numbering::today_ist()).map_err(ApiError::from)?;
+    Ok(next_number)
+}
+
+#[tauri::command]
+pub async fn get_invoice_series(state: State<'_, AppState>) -> CommandResult<Vec<InvoiceSeries>> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let series = db.get_invoice_series().map_err(ApiError::from)?;
+    Ok(series)
+}
+
+#[tauri::command]
+pub async fn save_invoice_series(
+    format: String,
+    reset_rule: Option<String>,
+    state: State<'_, AppState>,
+) -> CommandResult<i64> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let format = NumberFormat::parse(&format)?;
+    let reset_rule = match reset_rule {
+        Some(rule) => ResetRule::parse(&rule)?,
+        None => format.default_reset_rule(),
+    };
+    format.check_reset_rule(reset_rule)?;
+    
+    let id = db.save_invoice_series(&format, reset_rule).map_err(ApiError::from)?;
+    Ok(id)
+}
+
+/// Writes an amount in words; English in the invoice style unless asked otherwise
+#[tauri::command]
+pub async fn amount_in_words(
+    amount: Money,
+    language: Option<Language>,
+    style: Option<WordsStyle>,
+) -> CommandResult<String> {
+    Ok(formatting::amount_in_words_with(
+        amount,
+        language.unwrap_or_default(),
+        style.unwrap_or_default(),
+    ))
+}
+
+// =====================================================
+// GST Validation Commands (calling TypeScript functions)
+// =====================================================
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GstinValidationResult {
+    pub is_valid: bool,
+    pub state_code: Option<String>,
+    pub pan_number: Option<String>,
+    pub entity_number: Option<String>,
+    pub check_digit: Option<String>,
+    pub error: Option<String>,
+    pub error_code: Option<String>, // e.g. GSTIN_INVALID_CHECKSUM
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HsnSacValidationResult {
+    pub is_valid: bool,
+    pub validation_type: Option<String>, // "HSN" or "SAC"
+    pub description: Option<String>,
+    pub suggested_gst_rate: Option<f64>,
+    pub error: Option<String>,
+}
+
+// GSTIN checks live in validation.rs; HSN/SAC rates come from the gst_rates master
+
+#[tauri::command]
+pub async fn validate_gstin(
+    gstin: String,
+    state: State<'_, AppState>,
+) -> CommandResult<GstinValidationResult> {
+    let invalid = |err: GstinError| GstinValidationResult {
+        is_valid: false,
+        state_code: None,
+        pan_number: None,
+        entity_number: None,
+        check_digit: None,
+        error: Some(err.to_string()),
+        error_code: Some(err.code().to_string()),
+    };
+    
+    let parts = match validation::validate_gstin_structure(&gstin) {
+        Ok(parts) => parts,
+        Err(err) => return Ok(invalid(err)),
+    };
+    
+    // Prefer the indian_states master; fall back to the built-in list before initialization
+    let db_mutex = state.db.lock().unwrap();
+    let state_exists = match db_mutex.as_ref() {
+        Some(db) => db
+            .get_state_by_code(&parts.state_code)
+            .map_err(ApiError::from)?
+            .is_some_and(|s| s.is_active),
+        None => gst::STATE_CODES.iter().any(|(code, _)| *code == parts.state_code),
+    };
+    if !state_exists {
+        return Ok(invalid(GstinError::UnknownStateCode(parts.state_code)));
+    }
+    
+    Ok(GstinValidationResult {
+        is_valid: true,
+        state_code: Some(parts.state_code),
+        pan_number: Some(parts.pan_number),
+        entity_number: Some(parts.entity_number),
+        check_digit: Some(parts.check_digit),
+        error: None,
+        error_code: None,
+    })
+}
+
+#[tauri::command]
+pub async fn validate_hsn_sac(
+    code: String,
+    state: State<'_, AppState>,
+) -> CommandResult<HsnSacValidationResult> {
+    if code.is_empty() {
+        return Ok(HsnSacValidationResult {
+            is_valid: false,
+            validation_type: None,
+            description: None,
+            suggested_gst_rate: None,
+            error: Some("HSN/SAC code is required".to_string()),
+        });
+    }
+    
+    let clean_code = code.trim();
+    
+    // Suggest the rate in force today; codes the master does not cover get no suggestion
+    let db_mutex = state.db.lock().unwrap();
+    let master = match db_mutex.as_ref() {
+        Some(db) => db.lookup_gst_rate(clean_code, numbering::today_ist()).map_err(ApiError::from)?,
+        None => None,
+    };
+    let suggested_gst_rate = master.as_ref().map(|rate| rate.gst_rate);
+    let master_description = master.and_then(|rate| rate.description);
+    
+    // Check if it's a SAC code (services - starts with 99)
+    if clean_code.starts_with("99") && clean_code.len() == 6 && clean_code.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(HsnSacValidationResult {
+            is_valid: true,
+            validation_type: Some("SAC".to_string()),
+            description: master_description.or_else(|| Some("SAC code for services".to_string())),
+            suggested_gst_rate,
+            error: None,
+        });
+    }
+    
+    // Check if it's an HSN code (goods - 2-8 digits)
+    if clean_code.len() >= 2 && clean_code.len() <= 8 && clean_code.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(HsnSacValidationResult {
+            is_valid: true,
+            validation_type: Some("HSN".to_string()),
+            description: master_description.or_else(|| Some("HSN code for goods".to_string())),
+            suggested_gst_rate,
+            error: None,
+        });
+    }
+    
+    Ok(HsnSacValidationResult {
+        is_valid: false,
+        validation_type: None,
+        description: None,
         suggested_gst_rate: None,
         error: Some("Invalid HSN/SAC format".to_string()),
     })
-} 
\ No newline at end of file
+} 
+
+// =====================================================
+// Local API Server Commands
+// =====================================================
+
+/// Whether the local API is switched on and serving, where, and the token it expects
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiServerStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    pub url: String,
+    pub token: String,
+}
+
+fn api_server_status(state: &AppState, settings: ApiServerSettings) -> ApiServerStatus {
+    ApiServerStatus {
+        enabled: settings.enabled,
+        running: state.api_server.lock().unwrap().is_some(),
+        port: settings.port,
+        url: format!("http://127.0.0.1:{}{}", settings.port, api_server::BASE_PATH),
+        token: settings.token.unwrap_or_default(),
+    }
+}
+
+/// Stops the running server, if any, and starts it again as `settings` say
+fn restart_api_server(state: &AppState, settings: &ApiServerSettings) -> Result<(), ServerError> {
+    let mut server = state.api_server.lock().unwrap();
+    if let Some(running) = server.take() {
+        running.stop();
+    }
+    if settings.enabled {
+        let token = settings.token.clone().unwrap_or_default();
+        *server = Some(ApiServer::start(state.db.clone(), settings.port, token)?);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_api_server_status(state: State<'_, AppState>) -> CommandResult<ApiServerStatus> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let settings = api_server::load_settings(db)?;
+    Ok(api_server_status(state.inner(), settings))
+}
+
+/// Switches the local API on or off and sets its port, at once and for later starts.
+/// The settings are kept only if the server could start on that port.
+#[tauri::command]
+pub async fn configure_api_server(
+    enabled: bool,
+    port: u16,
+    state: State<'_, AppState>,
+) -> CommandResult<ApiServerStatus> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let port = api_server::check_port(port)?;
+    let settings = ApiServerSettings { enabled, port, ..api_server::load_settings(db)? };
+    restart_api_server(state.inner(), &settings)?;
+    
+    let settings = db.save_api_server_settings(enabled, port).map_err(ApiError::from)?;
+    Ok(api_server_status(state.inner(), settings))
+}
+
+/// Replaces the API token; a running server refuses the old one from then on
+#[tauri::command]
+pub async fn regenerate_api_token(state: State<'_, AppState>) -> CommandResult<ApiServerStatus> {
+    let db_mutex = state.db.lock().unwrap();
+    let db = db_mutex.as_ref().ok_or_else(|| ApiError {
+        error: "DatabaseNotInitialized".to_string(),
+        message: "Database not initialized".to_string(),
+    })?;
+    
+    let settings = db.set_api_token(&api_server::generate_token()?).map_err(ApiError::from)?;
+    let running = state.api_server.lock().unwrap().is_some();
+    if running {
+        restart_api_server(state.inner(), &settings)?;
+    }
+    Ok(api_server_status(state.inner(), settings))
+}
@@ -3,10 +3,34 @@
 // Rust SQLite Data Access Layer
 // =====================================================
 
-use rusqlite::{Connection, Result as SqliteResult, Row, params};
+use chrono::NaiveDate;
+use rusqlite::{
+    backup::Backup, Connection, Result as SqliteResult, Row, ToSql, Transaction, TransactionBehavior, params,
+    params_from_iter, types::Type,
+};
 use serde::{Deserialize, Serialize};
+use crate::audit::{self, AuditError};
+#[cfg(feature = "gui")]
+use crate::einvoice::{self, EInvoiceError, EInvoiceRegistration};
+#[cfg(feature = "gui")]
+use crate::ewaybill::{self, EWayBillError};
+use crate::lifecycle::{self, StatusError};
+use crate::migrations::{self, MigrationError};
+use crate::money::Money;
+use crate::notes::{self, NoteError};
+use crate::numbering::{self, NumberFormat, ResetRule};
+use crate::payments::{self, PaymentError};
+use crate::rates::{self, RateMismatch};
+#[cfg(feature = "gui")]
+use crate::rates::RateError;
+use crate::tally::ExportedVoucher;
+#[cfg(feature = "gui")]
+use crate::templates::{self, TemplateDefinition, TemplateError};
+use std::collections::BTreeMap;
 use std::path::Path;
-use tauri::AppHandle;
+use std::time::Duration;
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Manager};
 
 // =====================================================
 // Database Models (matching TypeScript types)
@@ -32,6 +56,9 @@ pub struct CompanySettings {
     pub logo_path: Option<String>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
+    /// Layout used for customers without one of their own; the standard layout when unset
+    #[serde(default)]
+    pub invoice_template_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -48,11 +75,13 @@ pub struct Customer {
     pub pincode: String,
     pub phone: Option<String>,
     pub email: Option<String>,
-    pub credit_limit: f64,
+    pub credit_limit: Money,
     pub credit_period_days: i32,
     pub is_active: bool,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
+    #[serde(default)]
+    pub invoice_template_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -64,7 +93,7 @@ pub struct Product {
     pub hsn_sac_code: String,
     pub product_type: String, // GOODS, SERVICES
     pub unit_of_measurement: String,
-    pub rate: f64,
+    pub rate: Money,
     pub gst_rate: f64,
     pub cess_rate: f64,
     pub is_active: bool,
@@ -81,17 +110,17 @@ pub struct Invoice {
     pub invoice_type: String, // REGULAR, EXPORT, DEBIT_NOTE, CREDIT_NOTE
     pub place_of_supply: String,
     pub reverse_charge: bool,
-    pub subtotal: f64,
-    pub total_discount: f64,
-    pub taxable_amount: f64,
-    pub cgst_amount: f64,
-    pub sgst_amount: f64,
-    pub igst_amount: f64,
-    pub cess_amount: f64,
-    pub total_tax: f64,
-    pub total_amount: f64,
-    pub round_off: f64,
-    pub final_amount: f64,
+    pub subtotal: Money,
+    pub total_discount: Money,
+    pub taxable_amount: Money,
+    pub cgst_amount: Money,
+    pub sgst_amount: Money,
+    pub igst_amount: Money,
+    pub cess_amount: Money,
+    pub total_tax: Money,
+    pub total_amount: Money,
+    pub round_off: Money,
+    pub final_amount: Money,
     pub payment_terms: Option<String>,
     pub due_date: Option<String>,
     pub status: String, // DRAFT, SENT, PAID, OVERDUE, CANCELLED
@@ -100,6 +129,26 @@ pub struct Invoice {
     pub pdf_path: Option<String>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
+    // E-invoice registration, set only from the IRP response
+    #[serde(default)]
+    pub irn: Option<String>,
+    #[serde(default)]
+    pub ack_number: Option<String>,
+    #[serde(default)]
+    pub ack_date: Option<String>,
+    #[serde(default)]
+    pub signed_qr_code: Option<String>,
+    /// Why a credit or debit note was issued, one of `notes::NOTE_REASONS`
+    #[serde(default)]
+    pub note_reason: Option<String>,
+    // Cancellation keeps the document and its number; both fields are set only by cancelling
+    #[serde(default)]
+    pub cancelled_at: Option<String>,
+    #[serde(default)]
+    pub cancellation_reason: Option<String>,
+    /// The issued invoice this revised draft replaces, set only by amending
+    #[serde(default)]
+    pub amends_invoice_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -113,24 +162,301 @@ pub struct InvoiceItem {
     pub description: Option<String>,
     pub hsn_sac_code: String,
     pub quantity: f64,
-    pub unit_price: f64,
+    pub unit_price: Money,
     pub discount_percent: f64,
-    pub discount_amount: f64,
-    pub taxable_amount: f64,
+    pub discount_amount: Money,
+    pub taxable_amount: Money,
     pub gst_rate: f64,
     pub cgst_rate: f64,
     pub sgst_rate: f64,
     pub igst_rate: f64,
     pub cess_rate: f64,
-    pub cgst_amount: f64,
-    pub sgst_amount: f64,
-    pub igst_amount: f64,
-    pub cess_amount: f64,
-    pub total_tax: f64,
-    pub line_total: f64,
+    pub cgst_amount: Money,
+    pub sgst_amount: Money,
+    pub igst_amount: Money,
+    pub cess_amount: Money,
+    pub total_tax: Money,
+    pub line_total: Money,
     pub created_at: Option<String>,
 }
 
+/// Invoice header together with its line items, as stored and returned to the frontend
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InvoiceWithItems {
+    #[serde(flatten)]
+    pub invoice: Invoice,
+    pub items: Vec<InvoiceItem>,
+}
+
+/// Optional filters for listing invoices; unset fields are not applied
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct InvoiceFilter {
+    pub customer_id: Option<i64>,
+    pub invoice_type: Option<String>,
+    pub status: Option<String>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub search: Option<String>, // Invoice number or customer name
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+}
+
+/// Invoice line with the unit and type of its product, as reported in GST returns
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReturnLine {
+    #[serde(flatten)]
+    pub item: InvoiceItem,
+    pub unit_of_measurement: String,
+    pub product_type: String, // GOODS, SERVICES
+}
+
+/// An issued document with its customer and lines, the input to return generation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReturnInvoice {
+    pub invoice: Invoice,
+    pub customer: Customer,
+    pub lines: Vec<ReturnLine>,
+    /// Invoices amended by a credit or debit note, earliest first; empty for invoices
+    #[serde(default)]
+    pub references: Vec<NoteReference>,
+}
+
+/// How the goods of an invoice are moved, the Part-B details of its e-way bill
+#[cfg(feature = "gui")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransportDetails {
+    pub id: Option<i64>,
+    pub invoice_id: i64,
+    pub transporter_id: Option<String>,
+    pub transporter_name: Option<String>,
+    pub transport_mode: String, // ROAD, RAIL, AIR, SHIP
+    pub distance_km: i32,
+    pub vehicle_number: Option<String>,
+    pub vehicle_type: String, // REGULAR, OVER_DIMENSIONAL
+    pub transport_doc_number: Option<String>,
+    pub transport_doc_date: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// A named invoice layout; the definition is stored as JSON
+#[cfg(feature = "gui")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InvoiceTemplate {
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub definition: TemplateDefinition,
+    /// Shipped with the app; copied rather than edited
+    #[serde(default)]
+    pub is_builtin: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Payment {
+    pub id: Option<i64>,
+    pub invoice_id: i64,
+    pub payment_date: String,
+    pub amount: Money,
+    pub payment_method: String, // CASH, CHEQUE, BANK_TRANSFER, UPI, CARD, OTHER
+    pub reference_number: Option<String>,
+    pub notes: Option<String>,
+    pub receipt_number: Option<String>,
+    pub voided_at: Option<String>,
+    pub void_reason: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// Portion of a receipt applied to one invoice
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PaymentAllocation {
+    pub invoice_id: i64,
+    pub amount: Money,
+}
+
+/// A receipt from a customer, settling one invoice or split across several
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewPayment {
+    pub payment_date: String,
+    pub payment_method: String,
+    pub reference_number: Option<String>,
+    pub notes: Option<String>,
+    pub receipt_number: Option<String>,
+    pub allocations: Vec<PaymentAllocation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PaymentFilter {
+    pub invoice_id: Option<i64>,
+    pub customer_id: Option<i64>,
+    pub payment_method: Option<String>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub include_voided: Option<bool>,
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+}
+
+/// Share of a credit or debit note's value applied to one invoice
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteAllocation {
+    pub invoice_id: i64,
+    pub amount: Money,
+}
+
+/// A note together with one invoice it amends
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteReference {
+    pub note_id: i64,
+    pub note_number: String,
+    pub note_type: String, // CREDIT_NOTE, DEBIT_NOTE
+    pub note_date: String,
+    pub note_status: String,
+    pub invoice_id: i64,
+    pub invoice_number: String,
+    pub invoice_date: String,
+    pub invoice_value: Money,
+    pub invoice_place_of_supply: String,
+    pub amount: Money,
+}
+
+/// One status change of an invoice or note
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusChange {
+    pub id: i64,
+    pub invoice_id: i64,
+    pub from_status: Option<String>, // None when the document was created
+    pub to_status: String,
+    pub reason: Option<String>,
+    pub changed_at: String,
+}
+
+/// Rate master entry for an HSN/SAC code or a prefix of one, in force between two dates
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GstRate {
+    pub id: Option<i64>,
+    pub hsn_sac_code: String,
+    pub description: Option<String>,
+    pub gst_rate: f64,
+    #[serde(default)]
+    pub cess_rate: f64,
+    pub effective_from: String,
+    pub effective_to: Option<String>, // None while still in force
+    pub is_active: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// One insert, update or delete of an audited record, with the record before and after
+#[cfg(feature = "gui")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub table_name: String,
+    pub record_id: i64,
+    pub action: String, // INSERT, UPDATE, DELETE
+    pub old_values: Option<serde_json::Value>,
+    pub new_values: Option<serde_json::Value>,
+    pub changed_fields: Vec<String>,
+    pub changed_by: String,
+    pub changed_at: String,
+}
+
+/// How the local API server is set up; there is a single row
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// How automatic backups are taken; there is a single row. The password for encrypted
+/// backups is never read out.
+#[cfg(feature = "gui")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    /// A backups folder beside the database when unset
+    pub directory: Option<String>,
+    pub interval_hours: u32,
+    pub keep_count: u32,
+    /// Whether a password is set, which automatic backups are encrypted with
+    pub encrypted: bool,
+    pub last_backup_at: Option<String>,
+    pub last_error: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// Ledger names used when exporting to Tally. Sales and tax ledger names may contain
+/// `{rate}`, so lines at different rates post to ledgers such as "Sales @ 18%".
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TallySettings {
+    pub company_name: Option<String>,
+    pub party_group: String,
+    pub sales_ledger: String,
+    pub cgst_ledger: String,
+    pub sgst_ledger: String,
+    pub igst_ledger: String,
+    pub cess_ledger: String,
+    pub round_off_ledger: String,
+    pub cash_ledger: String,
+    pub bank_ledger: String,
+    /// Customers whose Tally ledger is not named after them
+    #[serde(default)]
+    pub party_ledgers: Vec<TallyPartyLedger>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TallyPartyLedger {
+    pub customer_id: i64,
+    pub ledger_name: String,
+}
+
+/// Which column of an import file fills which field, saved under a name per kind.
+/// Columns are keyed by their header; an empty field leaves the column out.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportMapping {
+    pub id: Option<i64>,
+    pub name: String,
+    pub kind: String, // customers, products
+    pub columns: BTreeMap<String, String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// Amount received against an invoice and what is still owed. Issued debit notes add
+/// to the amount due and credit notes reduce it; `adjustments` is their net.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InvoiceBalance {
+    pub invoice_id: i64,
+    pub invoice_number: String,
+    pub final_amount: Money,
+    pub adjustments: Money,
+    pub amount_paid: Money,
+    pub outstanding: Money,
+    pub status: String,
+    /// Notes are settled through the invoices they amend, never paid directly
+    #[serde(skip)]
+    pub is_note: bool,
+}
+
+/// One invoice number format with its reset rule; counters are kept per reset period
+#[cfg(feature = "gui")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InvoiceSeries {
+    pub id: Option<i64>,
+    pub format: String,
+    pub reset_rule: String, // NEVER, FINANCIAL_YEAR, CALENDAR_YEAR, MONTHLY
+    pub is_active: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[cfg(feature = "gui")]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IndianState {
     pub id: Option<i64>,
@@ -156,27 +482,54 @@ impl Database {
         // Enable foreign key constraints
         connection.execute("PRAGMA foreign_keys = ON", [])?;
         
+        // Wait for other writers (e.g. a second app instance) instead of failing at once
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        
         Ok(Database { connection })
     }
 
-    /// Initialize database with schema
-    pub fn initialize_schema(&self) -> SqliteResult<()> {
-        // Read and execute schema.sql
-        let schema_sql = include_str!("../../../src/lib/database/schema.sql");
-        self.connection.execute_batch(schema_sql)?;
-        Ok(())
+    /// Initialize database schema, applying any pending migrations
+    pub fn initialize_schema(&self) -> Result<i32, MigrationError> {
+        migrations::run_migrations(&self.connection)
     }
 
     /// Get database file path for the app
+    #[cfg(feature = "gui")]
     pub fn get_db_path(app_handle: &AppHandle) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
         let app_data_dir = app_handle
-            .path_resolver()
+            .path()
             .app_data_dir()
-            .ok_or("Failed to get app data directory")?;
+            .map_err(|_| "Failed to get app data directory")?;
             
         std::fs::create_dir_all(&app_data_dir)?;
         Ok(app_data_dir.join("payvlo.db"))
     }
+
+    /// Directory generated invoice PDFs are written to
+    #[cfg(feature = "gui")]
+    pub fn get_pdf_dir(app_handle: &AppHandle) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+        let app_data_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|_| "Failed to get app data directory")?;
+
+        let pdf_dir = app_data_dir.join("invoices");
+        std::fs::create_dir_all(&pdf_dir)?;
+        Ok(pdf_dir)
+    }
+
+    /// Directory automatic backups are written to unless another is configured
+    #[cfg(feature = "gui")]
+    pub fn get_backup_dir(app_handle: &AppHandle) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+        let app_data_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|_| "Failed to get app data directory")?;
+
+        let backup_dir = app_data_dir.join("backups");
+        std::fs::create_dir_all(&backup_dir)?;
+        Ok(backup_dir)
+    }
 }
 
 // =====================================================
@@ -204,6 +557,7 @@ impl CompanySettings {
             logo_path: row.get(15)?,
             created_at: row.get(16)?,
             updated_at: row.get(17)?,
+            invoice_template_id: row.get(18)?,
         })
     }
 }
@@ -228,6 +582,7 @@ impl Customer {
             is_active: row.get(14)?,
             created_at: row.get(15)?,
             updated_at: row.get(16)?,
+            invoice_template_id: row.get(17)?,
         })
     }
 }
@@ -281,6 +636,14 @@ impl Invoice {
             pdf_path: row.get(23)?,
             created_at: row.get(24)?,
             updated_at: row.get(25)?,
+            irn: row.get(26)?,
+            ack_number: row.get(27)?,
+            ack_date: row.get(28)?,
+            signed_qr_code: row.get(29)?,
+            note_reason: row.get(30)?,
+            cancelled_at: row.get(31)?,
+            cancellation_reason: row.get(32)?,
+            amends_invoice_id: row.get(33)?,
         })
     }
 }
@@ -317,6 +680,211 @@ impl InvoiceItem {
     }
 }
 
+#[cfg(feature = "gui")]
+impl InvoiceSeries {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(InvoiceSeries {
+            id: Some(row.get(0)?),
+            format: row.get(1)?,
+            reset_rule: row.get(2)?,
+            is_active: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
+    }
+}
+
+impl Payment {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(Payment {
+            id: Some(row.get(0)?),
+            invoice_id: row.get(1)?,
+            payment_date: row.get(2)?,
+            amount: row.get(3)?,
+            payment_method: row.get(4)?,
+            reference_number: row.get(5)?,
+            notes: row.get(6)?,
+            created_at: row.get(7)?,
+            receipt_number: row.get(8)?,
+            voided_at: row.get(9)?,
+            void_reason: row.get(10)?,
+        })
+    }
+}
+
+impl StatusChange {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(StatusChange {
+            id: row.get(0)?,
+            invoice_id: row.get(1)?,
+            from_status: row.get(2)?,
+            to_status: row.get(3)?,
+            reason: row.get(4)?,
+            changed_at: row.get(5)?,
+        })
+    }
+}
+
+impl GstRate {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(GstRate {
+            id: Some(row.get(0)?),
+            hsn_sac_code: row.get(1)?,
+            description: row.get(2)?,
+            gst_rate: row.get(3)?,
+            cess_rate: row.get::<_, Option<f64>>(4)?.unwrap_or(0.0),
+            effective_from: row.get(5)?,
+            effective_to: row.get(6)?,
+            is_active: row.get(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+        })
+    }
+}
+
+#[cfg(feature = "gui")]
+impl AuditEntry {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let values = |index: usize| -> SqliteResult<Option<serde_json::Value>> {
+            row.get::<_, Option<String>>(index)?
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .map_err(|err| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(err)))
+        };
+        let old_values = values(4)?;
+        let new_values = values(5)?;
+        Ok(AuditEntry {
+            id: row.get(0)?,
+            table_name: row.get(1)?,
+            record_id: row.get(2)?,
+            action: row.get(3)?,
+            changed_fields: audit::changed_fields(old_values.as_ref(), new_values.as_ref()),
+            old_values,
+            new_values,
+            changed_by: row.get(6)?,
+            changed_at: row.get(7)?,
+        })
+    }
+}
+
+impl ApiServerSettings {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(ApiServerSettings {
+            enabled: row.get(1)?,
+            port: row.get(2)?,
+            token: row.get(3)?,
+            updated_at: row.get(5)?,
+        })
+    }
+}
+
+#[cfg(feature = "gui")]
+impl BackupSettings {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(BackupSettings {
+            enabled: row.get(1)?,
+            directory: row.get(2)?,
+            interval_hours: row.get(3)?,
+            keep_count: row.get(4)?,
+            encrypted: row.get::<_, Option<String>>(5)?.is_some(),
+            last_backup_at: row.get(6)?,
+            last_error: row.get(7)?,
+            updated_at: row.get(9)?,
+        })
+    }
+}
+
+impl TallySettings {
+    /// Settings without the party ledgers, which are read separately
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(TallySettings {
+            company_name: row.get(1)?,
+            party_group: row.get(2)?,
+            sales_ledger: row.get(3)?,
+            cgst_ledger: row.get(4)?,
+            sgst_ledger: row.get(5)?,
+            igst_ledger: row.get(6)?,
+            cess_ledger: row.get(7)?,
+            round_off_ledger: row.get(8)?,
+            cash_ledger: row.get(9)?,
+            bank_ledger: row.get(10)?,
+            party_ledgers: Vec::new(),
+            updated_at: row.get(12)?,
+        })
+    }
+}
+
+impl ImportMapping {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let columns: String = row.get(3)?;
+        Ok(ImportMapping {
+            id: Some(row.get(0)?),
+            name: row.get(1)?,
+            kind: row.get(2)?,
+            columns: serde_json::from_str(&columns)
+                .map_err(|err| rusqlite::Error::FromSqlConversionFailure(3, Type::Text, Box::new(err)))?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
+    }
+}
+
+impl NoteReference {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(NoteReference {
+            note_id: row.get(0)?,
+            note_number: row.get(1)?,
+            note_type: row.get(2)?,
+            note_date: row.get(3)?,
+            note_status: row.get(4)?,
+            invoice_id: row.get(5)?,
+            invoice_number: row.get(6)?,
+            invoice_date: row.get(7)?,
+            invoice_value: row.get(8)?,
+            invoice_place_of_supply: row.get(9)?,
+            amount: row.get(10)?,
+        })
+    }
+}
+
+#[cfg(feature = "gui")]
+impl TransportDetails {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        Ok(TransportDetails {
+            id: Some(row.get(0)?),
+            invoice_id: row.get(1)?,
+            transporter_id: row.get(2)?,
+            transporter_name: row.get(3)?,
+            transport_mode: row.get(4)?,
+            distance_km: row.get(5)?,
+            vehicle_number: row.get(6)?,
+            vehicle_type: row.get(7)?,
+            transport_doc_number: row.get(8)?,
+            transport_doc_date: row.get(9)?,
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
+        })
+    }
+}
+
+#[cfg(feature = "gui")]
+impl InvoiceTemplate {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let definition: String = row.get(3)?;
+        Ok(InvoiceTemplate {
+            id: Some(row.get(0)?),
+            name: row.get(1)?,
+            description: row.get(2)?,
+            definition: serde_json::from_str(&definition)
+                .map_err(|err| rusqlite::Error::FromSqlConversionFailure(3, Type::Text, Box::new(err)))?,
+            is_builtin: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    }
+}
+
+#[cfg(feature = "gui")]
 impl IndianState {
     pub fn from_row(row: &Row) -> SqliteResult<Self> {
         Ok(IndianState {
@@ -339,7 +907,7 @@ impl Database {
             "SELECT * FROM company_settings ORDER BY id DESC LIMIT 1"
         )?;
         
-        let result = stmt.query_row([], |row| CompanySettings::from_row(row));
+        let result = stmt.query_row([], CompanySettings::from_row);
         
         match result {
             Ok(settings) => Ok(Some(settings)),
@@ -348,6 +916,7 @@ impl Database {
         }
     }
 
+    #[cfg(feature = "gui")]
     pub fn save_company_settings(&self, settings: &CompanySettings) -> SqliteResult<i64> {
         if let Some(id) = settings.id {
             // Update existing
@@ -357,13 +926,14 @@ impl Database {
                  address_line2 = ?5, city = ?6, state = ?7, pincode = ?8,
                  phone = ?9, email = ?10, website = ?11, bank_name = ?12,
                  account_number = ?13, ifsc_code = ?14, logo_path = ?15,
-                 updated_at = CURRENT_TIMESTAMP
-                 WHERE id = ?16",
+                 invoice_template_id = ?16, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?17",
                 params![
                     settings.company_name, settings.gstin, settings.pan, settings.address_line1,
                     settings.address_line2, settings.city, settings.state, settings.pincode,
                     settings.phone, settings.email, settings.website, settings.bank_name,
-                    settings.account_number, settings.ifsc_code, settings.logo_path, id
+                    settings.account_number, settings.ifsc_code, settings.logo_path,
+                    settings.invoice_template_id, id
                 ],
             )?;
             Ok(id)
@@ -372,13 +942,15 @@ impl Database {
             self.connection.execute(
                 "INSERT INTO company_settings 
                  (company_name, gstin, pan, address_line1, address_line2, city, state, pincode,
-                  phone, email, website, bank_name, account_number, ifsc_code, logo_path)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                  phone, email, website, bank_name, account_number, ifsc_code, logo_path,
+                  invoice_template_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
                 params![
                     settings.company_name, settings.gstin, settings.pan, settings.address_line1,
                     settings.address_line2, settings.city, settings.state, settings.pincode,
                     settings.phone, settings.email, settings.website, settings.bank_name,
-                    settings.account_number, settings.ifsc_code, settings.logo_path
+                    settings.account_number, settings.ifsc_code, settings.logo_path,
+                    settings.invoice_template_id
                 ],
             )?;
             Ok(self.connection.last_insert_rowid())
@@ -399,7 +971,7 @@ impl Database {
             "SELECT * FROM customers ORDER BY customer_name ASC LIMIT ?1 OFFSET ?2"
         )?;
         
-        let rows = stmt.query_map(params![limit, offset], |row| Customer::from_row(row))?;
+        let rows = stmt.query_map(params![limit, offset], Customer::from_row)?;
         let mut customers = Vec::new();
         
         for row in rows {
@@ -412,7 +984,7 @@ impl Database {
     pub fn get_customer_by_id(&self, id: i64) -> SqliteResult<Option<Customer>> {
         let mut stmt = self.connection.prepare("SELECT * FROM customers WHERE id = ?1")?;
         
-        let result = stmt.query_row(params![id], |row| Customer::from_row(row));
+        let result = stmt.query_row(params![id], Customer::from_row);
         
         match result {
             Ok(customer) => Ok(Some(customer)),
@@ -429,13 +1001,13 @@ impl Database {
                  customer_name = ?1, gstin = ?2, pan = ?3, customer_type = ?4,
                  address_line1 = ?5, address_line2 = ?6, city = ?7, state = ?8, pincode = ?9,
                  phone = ?10, email = ?11, credit_limit = ?12, credit_period_days = ?13,
-                 is_active = ?14, updated_at = CURRENT_TIMESTAMP
-                 WHERE id = ?15",
+                 is_active = ?14, invoice_template_id = ?15, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?16",
                 params![
                     customer.customer_name, customer.gstin, customer.pan, customer.customer_type,
                     customer.address_line1, customer.address_line2, customer.city, customer.state,
                     customer.pincode, customer.phone, customer.email, customer.credit_limit,
-                    customer.credit_period_days, customer.is_active, id
+                    customer.credit_period_days, customer.is_active, customer.invoice_template_id, id
                 ],
             )?;
             Ok(id)
@@ -444,13 +1016,14 @@ impl Database {
             self.connection.execute(
                 "INSERT INTO customers 
                  (customer_name, gstin, pan, customer_type, address_line1, address_line2,
-                  city, state, pincode, phone, email, credit_limit, credit_period_days, is_active)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                  city, state, pincode, phone, email, credit_limit, credit_period_days, is_active,
+                  invoice_template_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
                 params![
                     customer.customer_name, customer.gstin, customer.pan, customer.customer_type,
                     customer.address_line1, customer.address_line2, customer.city, customer.state,
                     customer.pincode, customer.phone, customer.email, customer.credit_limit,
-                    customer.credit_period_days, customer.is_active
+                    customer.credit_period_days, customer.is_active, customer.invoice_template_id
                 ],
             )?;
             Ok(self.connection.last_insert_rowid())
@@ -470,7 +1043,7 @@ impl Database {
              ORDER BY customer_name ASC"
         )?;
         
-        let rows = stmt.query_map(params![search_pattern], |row| Customer::from_row(row))?;
+        let rows = stmt.query_map(params![search_pattern], Customer::from_row)?;
         let mut customers = Vec::new();
         
         for row in rows {
@@ -494,7 +1067,7 @@ impl Database {
             "SELECT * FROM products WHERE is_active = 1 ORDER BY product_name ASC LIMIT ?1 OFFSET ?2"
         )?;
         
-        let rows = stmt.query_map(params![limit, offset], |row| Product::from_row(row))?;
+        let rows = stmt.query_map(params![limit, offset], Product::from_row)?;
         let mut products = Vec::new();
         
         for row in rows {
@@ -507,7 +1080,7 @@ impl Database {
     pub fn get_product_by_id(&self, id: i64) -> SqliteResult<Option<Product>> {
         let mut stmt = self.connection.prepare("SELECT * FROM products WHERE id = ?1")?;
         
-        let result = stmt.query_row(params![id], |row| Product::from_row(row));
+        let result = stmt.query_row(params![id], Product::from_row);
         
         match result {
             Ok(product) => Ok(Some(product)),
@@ -565,7 +1138,7 @@ impl Database {
              ORDER BY product_name ASC"
         )?;
         
-        let rows = stmt.query_map(params![search_pattern], |row| Product::from_row(row))?;
+        let rows = stmt.query_map(params![search_pattern], Product::from_row)?;
         let mut products = Vec::new();
         
         for row in rows {
@@ -577,82 +1150,2069 @@ impl Database {
 }
 
 // =====================================================
-// CRUD Operations - Indian States
+// CRUD Operations - Invoices
 // =====================================================
 
 impl Database {
-    pub fn get_indian_states(&self) -> SqliteResult<Vec<IndianState>> {
-        let mut stmt = self.connection.prepare(
-            "SELECT * FROM indian_states WHERE is_active = 1 ORDER BY state_name ASC"
-        )?;
-        
-        let rows = stmt.query_map([], |row| IndianState::from_row(row))?;
-        let mut states = Vec::new();
-        
-        for row in rows {
-            states.push(row?);
+    pub fn list_invoices(&self, filter: &InvoiceFilter) -> SqliteResult<Vec<Invoice>> {
+        let mut sql = String::from(
+            "SELECT i.* FROM invoices i JOIN customers c ON c.id = i.customer_id WHERE 1 = 1"
+        );
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if let Some(customer_id) = filter.customer_id {
+            values.push(Box::new(customer_id));
+            sql.push_str(&format!(" AND i.customer_id = ?{}", values.len()));
+        }
+        if let Some(invoice_type) = &filter.invoice_type {
+            values.push(Box::new(invoice_type.clone()));
+            sql.push_str(&format!(" AND i.invoice_type = ?{}", values.len()));
+        }
+        if let Some(status) = &filter.status {
+            values.push(Box::new(status.clone()));
+            sql.push_str(&format!(" AND i.status = ?{}", values.len()));
+        }
+        if let Some(date_from) = &filter.date_from {
+            values.push(Box::new(date_from.clone()));
+            sql.push_str(&format!(" AND i.invoice_date >= ?{}", values.len()));
+        }
+        if let Some(date_to) = &filter.date_to {
+            values.push(Box::new(date_to.clone()));
+            sql.push_str(&format!(" AND i.invoice_date <= ?{}", values.len()));
+        }
+        if let Some(search) = &filter.search {
+            values.push(Box::new(format!("%{}%", search)));
+            sql.push_str(&format!(
+                " AND (i.invoice_number LIKE ?{0} OR c.customer_name LIKE ?{0})",
+                values.len()
+            ));
         }
-        
-        Ok(states)
-    }
 
-    pub fn get_state_by_code(&self, state_code: &str) -> SqliteResult<Option<IndianState>> {
-        let mut stmt = self.connection.prepare("SELECT * FROM indian_states WHERE state_code = ?1")?;
-        
-        let result = stmt.query_row(params![state_code], |row| IndianState::from_row(row));
-        
-        match result {
-            Ok(state) => Ok(Some(state)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
+        values.push(Box::new(filter.limit.unwrap_or(100)));
+        values.push(Box::new(filter.offset.unwrap_or(0)));
+        sql.push_str(&format!(
+            " ORDER BY i.invoice_date DESC, i.id DESC LIMIT ?{} OFFSET ?{}",
+            values.len() - 1,
+            values.len()
+        ));
+
+        let mut stmt = self.connection.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), Invoice::from_row)?;
+        let mut invoices = Vec::new();
+
+        for row in rows {
+            invoices.push(row?);
         }
+
+        Ok(invoices)
     }
-}
 
-// =====================================================
-// Utility Functions
-// =====================================================
+    pub fn get_invoice_by_id(&self, id: i64) -> SqliteResult<Option<InvoiceWithItems>> {
+        let mut stmt = self.connection.prepare("SELECT * FROM invoices WHERE id = ?1")?;
 
-impl Database {
-    pub fn count_records(&self, table: &str) -> SqliteResult<i64> {
-        let query = format!("SELECT COUNT(*) FROM {}", table);
-        let count: i64 = self.connection.query_row(&query, [], |row| row.get(0))?;
-        Ok(count)
+        let invoice = match stmt.query_row(params![id], Invoice::from_row) {
+            Ok(invoice) => invoice,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let items = self.get_invoice_items(id)?;
+        Ok(Some(InvoiceWithItems { invoice, items }))
     }
 
-    pub fn get_next_invoice_number(&self, format: &str) -> SqliteResult<String> {
-        // Get the last invoice number
+    pub fn get_invoice_items(&self, invoice_id: i64) -> SqliteResult<Vec<InvoiceItem>> {
         let mut stmt = self.connection.prepare(
-            "SELECT invoice_number FROM invoices ORDER BY id DESC LIMIT 1"
+            "SELECT * FROM invoice_items WHERE invoice_id = ?1 ORDER BY line_number ASC"
         )?;
-        
-        let last_number = stmt.query_row([], |row| {
-            let number: String = row.get(0)?;
-            Ok(number)
-        });
-        
-        let last_number_str = match last_number {
-            Ok(num) => Some(num),
-            Err(rusqlite::Error::QueryReturnedNoRows) => None,
-            Err(e) => return Err(e),
+
+        let rows = stmt.query_map(params![invoice_id], InvoiceItem::from_row)?;
+        let mut items = Vec::new();
+
+        for row in rows {
+            items.push(row?);
+        }
+
+        Ok(items)
+    }
+
+    /// Saves the invoice header and replaces its line items in a single transaction.
+    /// Drafts without a number of their own are numbered from the series for
+    /// `number_format` when issued. Only drafts may be changed; a draft saved as SENT is issued.
+    pub fn save_invoice(
+        &self,
+        invoice: &Invoice,
+        items: &[InvoiceItem],
+        number_format: &NumberFormat,
+        today: NaiveDate,
+    ) -> Result<i64, StatusError> {
+        // IMMEDIATE takes the write lock up front so concurrent saves cannot draw the same number
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+
+        // Notes are saved with save_note, which checks them against the invoices they amend
+        let stored_type = match invoice.id {
+            Some(id) => Self::invoice_type(&tx, id)?,
+            None => None,
         };
-        
-        // Generate next number (this would use the same logic as the TypeScript version)
-        // For now, return a simple incremented format
-        let current_year = chrono::Utc::now().year();
-        let current_month = chrono::Utc::now().month();
-        
-        if let Some(last) = last_number_str {
-            // Extract number from last invoice and increment
-            if let Some(captures) = regex::Regex::new(r"(\d+)$").unwrap().captures(&last) {
-                if let Some(num_str) = captures.get(1) {
-                    let num: u32 = num_str.as_str().parse().unwrap_or(0);
-                    return Ok(format!("INV-{}-{:02}-{:04}", current_year, current_month, num + 1));
-                }
-            }
+        if let Some(kind) = std::iter::once(&invoice.invoice_type).chain(&stored_type).find(|kind| notes::is_note(kind)) {
+            return Err(NoteError::SavedAsInvoice(kind.clone()).into());
         }
-        
-        // First invoice
-        Ok(format!("INV-{}-{:02}-0001", current_year, current_month))
+
+        let invoice_id = Self::write_invoice(&tx, invoice, items, number_format, today)?;
+        tx.commit()?;
+        Ok(invoice_id)
     }
-} 
\ No newline at end of file
+
+    /// Writes the header and lines of an invoice and records any change of status; the
+    /// caller owns the transaction
+    fn write_invoice(
+        tx: &Connection,
+        invoice: &Invoice,
+        items: &[InvoiceItem],
+        number_format: &NumberFormat,
+        today: NaiveDate,
+    ) -> Result<i64, StatusError> {
+        if invoice.status != "DRAFT" {
+            lifecycle::check_initial(&invoice.status)?;
+        }
+
+        // The document is written as a draft and only then issued or cancelled, so the
+        // lock on issued invoices never stops it writing its own lines
+        let invoice_id = if let Some(id) = invoice.id {
+            let (invoice_number, stored_status) = Self::invoice_status(tx, id)?;
+            if stored_status != "DRAFT" {
+                return Err(StatusError::Locked { invoice_number, status: stored_status });
+            }
+
+            // Update existing header and drop the old lines
+            let (invoice_number, series) = Self::draft_numbering(id, invoice, number_format);
+            tx.execute(
+                "UPDATE invoices SET 
+                 invoice_number = ?1, invoice_date = ?2, customer_id = ?3, invoice_type = ?4,
+                 place_of_supply = ?5, reverse_charge = ?6, subtotal = ?7, total_discount = ?8,
+                 taxable_amount = ?9, cgst_amount = ?10, sgst_amount = ?11, igst_amount = ?12,
+                 cess_amount = ?13, total_tax = ?14, total_amount = ?15, round_off = ?16,
+                 final_amount = ?17, payment_terms = ?18, due_date = ?19,
+                 notes = ?20, terms_conditions = ?21, pdf_path = ?22, note_reason = ?23,
+                 number_format = ?24
+                 WHERE id = ?25",
+                params![
+                    invoice_number, invoice.invoice_date, invoice.customer_id, invoice.invoice_type,
+                    invoice.place_of_supply, invoice.reverse_charge, invoice.subtotal, invoice.total_discount,
+                    invoice.taxable_amount, invoice.cgst_amount, invoice.sgst_amount, invoice.igst_amount,
+                    invoice.cess_amount, invoice.total_tax, invoice.total_amount, invoice.round_off,
+                    invoice.final_amount, invoice.payment_terms, invoice.due_date,
+                    invoice.notes, invoice.terms_conditions, invoice.pdf_path, invoice.note_reason, series, id
+                ],
+            )?;
+            tx.execute("DELETE FROM invoice_items WHERE invoice_id = ?1", params![id])?;
+            id
+        } else {
+            // The id is taken up front so an unnumbered draft can hold DRAFT-<id>; the
+            // IMMEDIATE transaction keeps it from being claimed in the meantime
+            let id: i64 = tx.query_row(
+                "SELECT MAX(COALESCE((SELECT seq FROM sqlite_sequence WHERE name = 'invoices'), 0),
+                            COALESCE((SELECT MAX(id) FROM invoices), 0)) + 1",
+                [],
+                |row| row.get(0),
+            )?;
+            let (invoice_number, series) = Self::draft_numbering(id, invoice, number_format);
+
+            // Insert new
+            tx.execute(
+                "INSERT INTO invoices 
+                 (id, invoice_number, invoice_date, customer_id, invoice_type, place_of_supply,
+                  reverse_charge, subtotal, total_discount, taxable_amount, cgst_amount,
+                  sgst_amount, igst_amount, cess_amount, total_tax, total_amount, round_off,
+                  final_amount, payment_terms, due_date, status, notes, terms_conditions, pdf_path,
+                  note_reason, number_format)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                         ?17, ?18, ?19, ?20, 'DRAFT', ?21, ?22, ?23, ?24, ?25)",
+                params![
+                    id, invoice_number, invoice.invoice_date, invoice.customer_id, invoice.invoice_type,
+                    invoice.place_of_supply, invoice.reverse_charge, invoice.subtotal, invoice.total_discount,
+                    invoice.taxable_amount, invoice.cgst_amount, invoice.sgst_amount, invoice.igst_amount,
+                    invoice.cess_amount, invoice.total_tax, invoice.total_amount, invoice.round_off,
+                    invoice.final_amount, invoice.payment_terms, invoice.due_date,
+                    invoice.notes, invoice.terms_conditions, invoice.pdf_path, invoice.note_reason, series
+                ],
+            )?;
+            Self::record_status_change(tx, id, None, "DRAFT", "Created")?;
+            id
+        };
+
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO invoice_items 
+                 (invoice_id, product_id, line_number, product_code, product_name, description,
+                  hsn_sac_code, quantity, unit_price, discount_percent, discount_amount,
+                  taxable_amount, gst_rate, cgst_rate, sgst_rate, igst_rate, cess_rate,
+                  cgst_amount, sgst_amount, igst_amount, cess_amount, total_tax, line_total)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                         ?17, ?18, ?19, ?20, ?21, ?22, ?23)"
+            )?;
+
+            for item in items {
+                stmt.execute(params![
+                    invoice_id, item.product_id, item.line_number, item.product_code,
+                    item.product_name, item.description, item.hsn_sac_code, item.quantity,
+                    item.unit_price, item.discount_percent, item.discount_amount,
+                    item.taxable_amount, item.gst_rate, item.cgst_rate, item.sgst_rate,
+                    item.igst_rate, item.cess_rate, item.cgst_amount, item.sgst_amount,
+                    item.igst_amount, item.cess_amount, item.total_tax, item.line_total
+                ])?;
+            }
+        }
+
+        if invoice.status != "DRAFT" {
+            Self::request_status(tx, invoice_id, "DRAFT", &invoice.status, None, today)?;
+        }
+        Ok(invoice_id)
+    }
+
+    /// Type of a saved invoice or note, if it exists
+    fn invoice_type(connection: &Connection, id: i64) -> SqliteResult<Option<String>> {
+        match connection.query_row("SELECT invoice_type FROM invoices WHERE id = ?1", params![id], |row| row.get(0)) {
+            Ok(invoice_type) => Ok(Some(invoice_type)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Number to save a draft under and the series to number it from when issued. A number
+    /// typed by the user is kept as it is; otherwise the draft holds DRAFT-<id> until issue.
+    fn draft_numbering(id: i64, invoice: &Invoice, number_format: &NumberFormat) -> (String, Option<String>) {
+        let placeholder = numbering::draft_number(id);
+        let number = invoice.invoice_number.trim();
+        if number.is_empty() || number == placeholder {
+            (placeholder, Some(number_format.template().to_string()))
+        } else {
+            (number.to_string(), None)
+        }
+    }
+
+    /// Deletes a draft; issued invoices are cancelled instead
+    pub fn delete_invoice(&self, id: i64) -> Result<bool, StatusError> {
+        match Self::invoice_status(&self.connection, id) {
+            Ok((_, status)) if status == "DRAFT" => {}
+            Ok((invoice_number, status)) => return Err(StatusError::NotDeletable { invoice_number, status }),
+            Err(StatusError::InvoiceNotFound(_)) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+
+        // Line items are removed by the ON DELETE CASCADE foreign key
+        let rows_affected = self.connection.execute("DELETE FROM invoices WHERE id = ?1", params![id])?;
+        Ok(rows_affected > 0)
+    }
+}
+
+// =====================================================
+// CRUD Operations - Payments
+// =====================================================
+
+/// Signed amount a note reference carries against its invoice; credit notes reduce it
+const NOTE_ADJUSTMENT: &str = "CASE n.invoice_type WHEN 'CREDIT_NOTE' THEN -r.amount ELSE r.amount END";
+
+/// SQL totalling a money expression in whole paise. Amounts are stored as REAL rupees, so
+/// each one is rounded to paise before adding to keep float error out of the total.
+fn sum_paise(expression: &str) -> String {
+    format!("COALESCE(SUM(CAST(ROUND(({}) * 100) AS INTEGER)), 0)", expression)
+}
+
+impl Database {
+    /// Records a receipt against one or more invoices in a single transaction and moves
+    /// every invoice it settles to PAID. Returns the ids of the payment rows created.
+    pub fn record_payment(&self, payment: &NewPayment, today: NaiveDate) -> Result<Vec<i64>, PaymentError> {
+        let amounts: Vec<Money> = payment.allocations.iter().map(|a| a.amount).collect();
+        payments::validate_payment(&payment.payment_date, &payment.payment_method, &amounts)?;
+
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+        let mut ids = Vec::with_capacity(payment.allocations.len());
+
+        for allocation in &payment.allocations {
+            let balance = Self::invoice_balance(&tx, allocation.invoice_id)?
+                .ok_or(PaymentError::InvoiceNotFound(allocation.invoice_id))?;
+            if balance.status == "CANCELLED" {
+                return Err(PaymentError::InvoiceCancelled(balance.invoice_number));
+            }
+            if balance.status == "DRAFT" {
+                return Err(PaymentError::NotIssued(balance.invoice_number));
+            }
+            if balance.is_note {
+                return Err(PaymentError::NotReceivable(balance.invoice_number));
+            }
+            if allocation.amount > balance.outstanding {
+                return Err(PaymentError::Overpayment {
+                    invoice_number: balance.invoice_number,
+                    outstanding: balance.outstanding,
+                    amount: allocation.amount,
+                });
+            }
+
+            tx.execute(
+                "INSERT INTO payments 
+                 (invoice_id, payment_date, amount, payment_method, reference_number, notes, receipt_number)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    allocation.invoice_id, payment.payment_date, allocation.amount,
+                    payment.payment_method, payment.reference_number, payment.notes,
+                    payment.receipt_number
+                ],
+            )?;
+            ids.push(tx.last_insert_rowid());
+
+            Self::refresh_payment_status(&tx, allocation.invoice_id, today, "Payment recorded")?;
+        }
+
+        // Rows of one receipt share a number so split payments can be traced back together
+        if payment.receipt_number.is_none() {
+            let receipt_number = format!("RCT-{:06}", ids[0]);
+            let mut stmt = tx.prepare("UPDATE payments SET receipt_number = ?1 WHERE id = ?2")?;
+            for id in &ids {
+                stmt.execute(params![receipt_number, id])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    pub fn list_payments(&self, filter: &PaymentFilter) -> SqliteResult<Vec<Payment>> {
+        let mut sql = String::from(
+            "SELECT p.* FROM payments p JOIN invoices i ON i.id = p.invoice_id WHERE 1 = 1"
+        );
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if !filter.include_voided.unwrap_or(false) {
+            sql.push_str(" AND p.voided_at IS NULL");
+        }
+        if let Some(invoice_id) = filter.invoice_id {
+            values.push(Box::new(invoice_id));
+            sql.push_str(&format!(" AND p.invoice_id = ?{}", values.len()));
+        }
+        if let Some(customer_id) = filter.customer_id {
+            values.push(Box::new(customer_id));
+            sql.push_str(&format!(" AND i.customer_id = ?{}", values.len()));
+        }
+        if let Some(payment_method) = &filter.payment_method {
+            values.push(Box::new(payment_method.clone()));
+            sql.push_str(&format!(" AND p.payment_method = ?{}", values.len()));
+        }
+        if let Some(date_from) = &filter.date_from {
+            values.push(Box::new(date_from.clone()));
+            sql.push_str(&format!(" AND p.payment_date >= ?{}", values.len()));
+        }
+        if let Some(date_to) = &filter.date_to {
+            values.push(Box::new(date_to.clone()));
+            sql.push_str(&format!(" AND p.payment_date <= ?{}", values.len()));
+        }
+
+        values.push(Box::new(filter.limit.unwrap_or(100)));
+        values.push(Box::new(filter.offset.unwrap_or(0)));
+        sql.push_str(&format!(
+            " ORDER BY p.payment_date DESC, p.id DESC LIMIT ?{} OFFSET ?{}",
+            values.len() - 1,
+            values.len()
+        ));
+
+        let mut stmt = self.connection.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), Payment::from_row)?;
+        let mut payments = Vec::new();
+
+        for row in rows {
+            payments.push(row?);
+        }
+
+        Ok(payments)
+    }
+
+    /// Voids a payment, keeping it on record, and reopens its invoice if it is no longer settled
+    pub fn void_payment(&self, id: i64, reason: Option<&str>, today: NaiveDate) -> Result<InvoiceBalance, PaymentError> {
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+
+        let (invoice_id, voided_at) = match tx.query_row(
+            "SELECT invoice_id, voided_at FROM payments WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?)),
+        ) {
+            Ok(payment) => payment,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Err(PaymentError::PaymentNotFound(id)),
+            Err(e) => return Err(e.into()),
+        };
+        if voided_at.is_some() {
+            return Err(PaymentError::AlreadyVoided(id));
+        }
+
+        tx.execute(
+            "UPDATE payments SET voided_at = CURRENT_TIMESTAMP, void_reason = ?1 WHERE id = ?2",
+            params![reason, id],
+        )?;
+        Self::refresh_payment_status(&tx, invoice_id, today, "Payment voided")?;
+
+        let balance = Self::invoice_balance(&tx, invoice_id)?.ok_or(PaymentError::InvoiceNotFound(invoice_id))?;
+        tx.commit()?;
+        Ok(balance)
+    }
+
+    pub fn get_invoice_balance(&self, invoice_id: i64) -> SqliteResult<Option<InvoiceBalance>> {
+        Self::invoice_balance(&self.connection, invoice_id)
+    }
+
+    fn invoice_balance(connection: &Connection, invoice_id: i64) -> SqliteResult<Option<InvoiceBalance>> {
+        let sql = format!(
+            "SELECT i.invoice_number, i.final_amount, i.status,
+                    (SELECT {} FROM payments
+                     WHERE invoice_id = i.id AND voided_at IS NULL),
+                    (SELECT {}
+                     FROM invoice_note_references r JOIN invoices n ON n.id = r.note_id
+                     WHERE r.invoice_id = i.id AND n.status NOT IN ('DRAFT', 'CANCELLED')),
+                    i.invoice_type
+             FROM invoices i WHERE i.id = ?1",
+            sum_paise("amount"),
+            sum_paise(NOTE_ADJUSTMENT),
+        );
+        let balance = connection.query_row(
+            &sql,
+            params![invoice_id],
+            |row| {
+                let final_amount: Money = row.get(1)?;
+                let amount_paid = Money::from_paise(row.get(3)?);
+                let adjustments = Money::from_paise(row.get(4)?);
+                Ok(InvoiceBalance {
+                    invoice_id,
+                    invoice_number: row.get(0)?,
+                    final_amount,
+                    adjustments,
+                    amount_paid,
+                    outstanding: final_amount + adjustments - amount_paid,
+                    status: row.get(2)?,
+                    is_note: notes::is_note(&row.get::<_, String>(5)?),
+                })
+            },
+        );
+
+        match balance {
+            Ok(balance) => Ok(Some(balance)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Moves an invoice to PAID when fully settled, or back out of PAID when it no longer is
+    fn refresh_payment_status(
+        connection: &Connection,
+        invoice_id: i64,
+        today: NaiveDate,
+        reason: &str,
+    ) -> SqliteResult<()> {
+        let Some(balance) = Self::invoice_balance(connection, invoice_id)? else {
+            return Ok(());
+        };
+        let due_date: Option<String> = connection.query_row(
+            "SELECT due_date FROM invoices WHERE id = ?1",
+            params![invoice_id],
+            |row| row.get(0),
+        )?;
+
+        let status = payments::settled_status(
+            &balance.status,
+            balance.final_amount + balance.adjustments,
+            balance.amount_paid,
+            due_date.as_deref(),
+            today,
+        );
+        if status != balance.status {
+            Self::change_status(connection, invoice_id, &balance.status, &status, reason)?;
+        }
+        Ok(())
+    }
+}
+
+// =====================================================
+// Invoice Status Lifecycle
+// =====================================================
+
+impl Database {
+    /// Issues or cancels an invoice or note at the user's request
+    pub fn update_invoice_status(
+        &self,
+        invoice_id: i64,
+        status: &str,
+        reason: Option<&str>,
+        today: NaiveDate,
+    ) -> Result<StatusChange, StatusError> {
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+
+        let (_, current) = Self::invoice_status(&tx, invoice_id)?;
+        Self::request_status(&tx, invoice_id, &current, status, reason, today)?;
+
+        let change = tx.query_row(
+            "SELECT * FROM invoice_status_history WHERE invoice_id = ?1 ORDER BY id DESC LIMIT 1",
+            params![invoice_id],
+            StatusChange::from_row,
+        )?;
+        tx.commit()?;
+        Ok(change)
+    }
+
+    /// Cancels an invoice or note. The document and its number are kept, with the reason
+    /// and time of cancellation; a reason is required once the document has been issued.
+    pub fn cancel_invoice(&self, invoice_id: i64, reason: &str, today: NaiveDate) -> Result<StatusChange, StatusError> {
+        self.update_invoice_status(invoice_id, "CANCELLED", Some(reason), today)
+    }
+
+    /// Starts a revision of an issued invoice: a new draft with the same customer and lines,
+    /// dated today and numbered from `number_format` once issued, that points back at the original.
+    /// The original stays as it is until the revision is issued, which cancels it.
+    pub fn amend_invoice(&self, invoice_id: i64, number_format: &NumberFormat, today: NaiveDate) -> Result<i64, StatusError> {
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+
+        let original = match tx.query_row(
+            "SELECT * FROM invoices WHERE id = ?1",
+            params![invoice_id],
+            Invoice::from_row,
+        ) {
+            Ok(invoice) => invoice,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Err(StatusError::InvoiceNotFound(invoice_id)),
+            Err(e) => return Err(e.into()),
+        };
+        let open_revision: Option<String> = match tx.query_row(
+            "SELECT invoice_number FROM invoices WHERE amends_invoice_id = ?1 AND status = 'DRAFT'",
+            params![invoice_id],
+            |row| row.get(0),
+        ) {
+            Ok(number) => Some(number),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let problem = if notes::is_note(&original.invoice_type) {
+            Some("credit and debit notes are corrected by issuing a further note".to_string())
+        } else if original.status == "DRAFT" {
+            Some("it is still a draft and can be edited directly".to_string())
+        } else if original.status == "CANCELLED" {
+            Some("it is cancelled".to_string())
+        } else {
+            open_revision.map(|number| format!("revision {} is already in progress", number))
+        };
+        if let Some(problem) = problem {
+            return Err(StatusError::NotAmendable { invoice_number: original.invoice_number, problem });
+        }
+        Self::check_unsettled(&tx, invoice_id, &original.invoice_number)?;
+
+        let items = tx
+            .prepare("SELECT * FROM invoice_items WHERE invoice_id = ?1 ORDER BY line_number ASC")?
+            .query_map(params![invoice_id], InvoiceItem::from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        let revision = Invoice {
+            id: None,
+            invoice_number: String::new(),
+            invoice_date: today.to_string(),
+            status: "DRAFT".to_string(),
+            pdf_path: None,
+            ..original
+        };
+        let revision_id = Self::write_invoice(&tx, &revision, &items, number_format, today)?;
+        tx.execute(
+            "UPDATE invoices SET amends_invoice_id = ?1 WHERE id = ?2",
+            params![invoice_id, revision_id],
+        )?;
+
+        tx.commit()?;
+        Ok(revision_id)
+    }
+
+    /// Moves issued invoices past their due date to OVERDUE and returns their ids. Runs
+    /// when the database is opened and periodically while the app stays open.
+    pub fn mark_overdue_invoices(&self, today: NaiveDate) -> SqliteResult<Vec<i64>> {
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+
+        let due: Vec<(i64, Option<String>)> = tx
+            .prepare(
+                "SELECT id, due_date FROM invoices
+                 WHERE status = 'SENT' AND due_date IS NOT NULL
+                   AND invoice_type NOT IN ('CREDIT_NOTE', 'DEBIT_NOTE')",
+            )?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqliteResult<_>>()?;
+
+        let mut overdue = Vec::new();
+        for (invoice_id, due_date) in due {
+            if lifecycle::is_overdue(due_date.as_deref(), today) {
+                Self::change_status(&tx, invoice_id, "SENT", "OVERDUE", "Past due date")?;
+                overdue.push(invoice_id);
+            }
+        }
+
+        tx.commit()?;
+        if !overdue.is_empty() {
+            log::info!("Marked {} invoice(s) overdue", overdue.len());
+        }
+        Ok(overdue)
+    }
+
+    /// Status changes of an invoice or note, oldest first
+    pub fn get_invoice_status_history(&self, invoice_id: i64) -> SqliteResult<Vec<StatusChange>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT * FROM invoice_status_history WHERE invoice_id = ?1 ORDER BY id ASC"
+        )?;
+        let rows = stmt.query_map(params![invoice_id], StatusChange::from_row)?;
+        rows.collect()
+    }
+
+    /// Number and current status of an invoice
+    fn invoice_status(connection: &Connection, invoice_id: i64) -> Result<(String, String), StatusError> {
+        match connection.query_row(
+            "SELECT invoice_number, status FROM invoices WHERE id = ?1",
+            params![invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(status) => Ok(status),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Err(StatusError::InvoiceNotFound(invoice_id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Applies a status chosen by the user. Cancelling stamps the reason and time on the
+    /// document and is refused while payments or notes still rest on it; issuing a revision
+    /// cancels the invoice it replaces.
+    fn request_status(
+        connection: &Connection,
+        invoice_id: i64,
+        from: &str,
+        to: &str,
+        reason: Option<&str>,
+        today: NaiveDate,
+    ) -> Result<(), StatusError> {
+        lifecycle::check_requested(from, to)?;
+        let reason = reason.map(str::trim).filter(|reason| !reason.is_empty());
+
+        let (mut invoice_number, invoice_type, amends_invoice_id, invoice_date, series): (
+            String,
+            String,
+            Option<i64>,
+            String,
+            Option<String>,
+        ) = connection.query_row(
+            "SELECT invoice_number, invoice_type, amends_invoice_id, invoice_date, number_format
+             FROM invoices WHERE id = ?1",
+            params![invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )?;
+
+        if to == "CANCELLED" {
+            if reason.is_none() && from != "DRAFT" {
+                return Err(StatusError::MissingReason);
+            }
+            Self::check_unsettled(connection, invoice_id, &invoice_number)?;
+
+            connection.execute(
+                "UPDATE invoices SET cancelled_at = CURRENT_TIMESTAMP, cancellation_reason = ?1 WHERE id = ?2",
+                params![reason, invoice_id],
+            )?;
+            Self::change_status(connection, invoice_id, from, to, reason.unwrap_or("Cancelled"))?;
+
+            // A cancelled note no longer counts against the invoices it amended
+            if notes::is_note(&invoice_type) {
+                let amended = connection
+                    .prepare("SELECT invoice_id FROM invoice_note_references WHERE note_id = ?1")?
+                    .query_map(params![invoice_id], |row| row.get::<_, i64>(0))?
+                    .collect::<SqliteResult<Vec<_>>>()?;
+                for amended_id in amended {
+                    Self::refresh_payment_status(connection, amended_id, today, "Credit or debit note cancelled")?;
+                }
+            }
+            return Ok(());
+        }
+
+        // Other notes may have been issued against the same invoices since this one was drafted
+        let amended = if from == "DRAFT" && notes::is_note(&invoice_type) {
+            Self::check_note_references(connection, invoice_id)?
+        } else {
+            Vec::new()
+        };
+
+        // Issuing draws the number, while the document is still a draft and may take it
+        if let Some(template) = series {
+            let format = NumberFormat::parse(&template)?;
+            let date = NaiveDate::parse_from_str(&invoice_date, "%Y-%m-%d")
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            invoice_number = Self::allocate_invoice_number(connection, &format, date)?;
+            connection.execute(
+                "UPDATE invoices SET invoice_number = ?1, number_format = NULL WHERE id = ?2",
+                params![invoice_number, invoice_id],
+            )?;
+        }
+
+        Self::change_status(connection, invoice_id, from, to, reason.unwrap_or("Issued"))?;
+        for amended_id in amended {
+            Self::refresh_payment_status(connection, amended_id, today, "Credit or debit note issued")?;
+        }
+        if let Some(original_id) = amends_invoice_id {
+            let (_, original_status) = Self::invoice_status(connection, original_id)?;
+            if original_status != "CANCELLED" {
+                let replaced_by = format!("Replaced by {}", invoice_number);
+                Self::request_status(connection, original_id, &original_status, "CANCELLED", Some(&replaced_by), today)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuses to cancel or amend an invoice while unvoided payments or issued notes rest on it
+    fn check_unsettled(connection: &Connection, invoice_id: i64, invoice_number: &str) -> Result<(), StatusError> {
+        let (has_payments, has_notes): (bool, bool) = connection.query_row(
+            "SELECT EXISTS(SELECT 1 FROM payments WHERE invoice_id = ?1 AND voided_at IS NULL),
+                    EXISTS(SELECT 1 FROM invoice_note_references r JOIN invoices n ON n.id = r.note_id
+                           WHERE r.invoice_id = ?1 AND n.status NOT IN ('DRAFT', 'CANCELLED'))",
+            params![invoice_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let settled_by = if has_payments {
+            "payments"
+        } else if has_notes {
+            "credit or debit notes"
+        } else {
+            return Ok(());
+        };
+        Err(StatusError::HasSettlements { invoice_number: invoice_number.to_string(), settled_by })
+    }
+
+    /// Sets a new status and records the move; callers check that the move is allowed
+    fn change_status(connection: &Connection, invoice_id: i64, from: &str, to: &str, reason: &str) -> SqliteResult<()> {
+        connection.execute("UPDATE invoices SET status = ?1 WHERE id = ?2", params![to, invoice_id])?;
+        Self::record_status_change(connection, invoice_id, Some(from), to, reason)
+    }
+
+    fn record_status_change(
+        connection: &Connection,
+        invoice_id: i64,
+        from: Option<&str>,
+        to: &str,
+        reason: &str,
+    ) -> SqliteResult<()> {
+        connection.execute(
+            "INSERT INTO invoice_status_history (invoice_id, from_status, to_status, reason)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![invoice_id, from, to, reason],
+        )?;
+        Ok(())
+    }
+}
+
+// =====================================================
+// Credit and Debit Notes
+// =====================================================
+
+impl Database {
+    /// Saves a credit or debit note with its lines and the invoices it amends in one
+    /// transaction, then settles or reopens each amended invoice for its new value.
+    /// Notes without a number of their own are numbered from the series for
+    /// `number_format` when issued.
+    pub fn save_note(
+        &self,
+        note: &Invoice,
+        items: &[InvoiceItem],
+        allocations: &[NoteAllocation],
+        number_format: &NumberFormat,
+        today: NaiveDate,
+    ) -> Result<i64, NoteError> {
+        notes::validate_note(note, allocations)?;
+        lifecycle::check_initial(&note.status)?;
+
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+
+        if let Some(stored_type) = note.id.map(|id| Self::invoice_type(&tx, id)).transpose()?.flatten() {
+            if !notes::is_note(&stored_type) {
+                return Err(NoteError::NotANote(stored_type));
+            }
+        }
+
+        for allocation in allocations {
+            Self::check_note_allocation(&tx, note, allocation.invoice_id, allocation.amount)?;
+        }
+
+        let previous = match note.id {
+            Some(id) => tx
+                .prepare("SELECT invoice_id FROM invoice_note_references WHERE note_id = ?1")?
+                .query_map(params![id], |row| row.get::<_, i64>(0))?
+                .collect::<SqliteResult<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        // Written as a draft with its references first, so issuing checks the new references
+        let draft = Invoice { status: "DRAFT".to_string(), ..note.clone() };
+        let note_id = Self::write_invoice(&tx, &draft, items, number_format, today)?;
+        tx.execute("DELETE FROM invoice_note_references WHERE note_id = ?1", params![note_id])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO invoice_note_references (note_id, invoice_id, amount) VALUES (?1, ?2, ?3)"
+            )?;
+            for allocation in allocations {
+                stmt.execute(params![note_id, allocation.invoice_id, allocation.amount])?;
+            }
+        }
+        if note.status != "DRAFT" {
+            Self::request_status(&tx, note_id, "DRAFT", &note.status, None, today)?;
+        }
+
+        for invoice_id in previous.into_iter().chain(allocations.iter().map(|a| a.invoice_id)) {
+            Self::refresh_payment_status(&tx, invoice_id, today, "Credit or debit note saved")?;
+        }
+
+        tx.commit()?;
+        Ok(note_id)
+    }
+
+    /// Checks a saved note against each invoice it references, as it stands now, and
+    /// returns the ids of those invoices
+    fn check_note_references(connection: &Connection, note_id: i64) -> Result<Vec<i64>, NoteError> {
+        let note = connection.query_row("SELECT * FROM invoices WHERE id = ?1", params![note_id], Invoice::from_row)?;
+        let references = connection
+            .prepare("SELECT invoice_id, amount FROM invoice_note_references WHERE note_id = ?1")?
+            .query_map(params![note_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Money>(1)?)))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        for &(invoice_id, amount) in &references {
+            Self::check_note_allocation(connection, &note, invoice_id, amount)?;
+        }
+        Ok(references.into_iter().map(|(invoice_id, _)| invoice_id).collect())
+    }
+
+    /// Checks that `amount` of `note` may be put against an invoice, given the other notes
+    /// already issued against it
+    fn check_note_allocation(connection: &Connection, note: &Invoice, invoice_id: i64, amount: Money) -> Result<(), NoteError> {
+        let original = match connection.query_row(
+            "SELECT * FROM invoices WHERE id = ?1",
+            params![invoice_id],
+            Invoice::from_row,
+        ) {
+            Ok(invoice) => invoice,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Err(NoteError::InvoiceNotFound(invoice_id)),
+            Err(e) => return Err(e.into()),
+        };
+        // Other issued notes only; what this note carried before is being replaced
+        let adjustments = Money::from_paise(connection.query_row(
+            &format!(
+                "SELECT {} FROM invoice_note_references r JOIN invoices n ON n.id = r.note_id
+                 WHERE r.invoice_id = ?1 AND n.id IS NOT ?2 AND n.status NOT IN ('DRAFT', 'CANCELLED')",
+                sum_paise(NOTE_ADJUSTMENT)
+            ),
+            params![invoice_id, note.id],
+            |row| row.get(0),
+        )?);
+        notes::check_original(note, &original, adjustments, amount)
+    }
+
+    /// Invoices amended by a note, earliest first
+    pub fn get_note_references(&self, note_id: i64) -> SqliteResult<Vec<NoteReference>> {
+        self.note_references("r.note_id = ?1", note_id)
+    }
+
+    /// Notes issued against an invoice, including drafts and cancelled ones
+    pub fn get_invoice_notes(&self, invoice_id: i64) -> SqliteResult<Vec<NoteReference>> {
+        self.note_references("r.invoice_id = ?1", invoice_id)
+    }
+
+    fn note_references(&self, condition: &str, id: i64) -> SqliteResult<Vec<NoteReference>> {
+        let sql = format!(
+            "SELECT r.note_id, n.invoice_number, n.invoice_type, n.invoice_date, n.status,
+                    r.invoice_id, i.invoice_number, i.invoice_date, i.final_amount, i.place_of_supply, r.amount
+             FROM invoice_note_references r
+             JOIN invoices n ON n.id = r.note_id
+             JOIN invoices i ON i.id = r.invoice_id
+             WHERE {}
+             ORDER BY i.invoice_date ASC, i.id ASC, n.invoice_date ASC, n.id ASC",
+            condition
+        );
+        let mut stmt = self.connection.prepare_cached(&sql)?;
+        let rows = stmt.query_map(params![id], NoteReference::from_row)?;
+        rows.collect()
+    }
+}
+
+// =====================================================
+// Reporting Queries
+// =====================================================
+
+impl Database {
+    /// Issued invoices, credit and debit notes dated within the range, including cancelled
+    /// ones; drafts are never reported
+    pub fn get_return_invoices(&self, date_from: NaiveDate, date_to: NaiveDate) -> SqliteResult<Vec<ReturnInvoice>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT * FROM invoices
+             WHERE invoice_date BETWEEN ?1 AND ?2 AND status != 'DRAFT'
+               AND number_format IS NULL -- drafts cancelled before issue never used a number
+             ORDER BY invoice_date ASC, id ASC"
+        )?;
+        let invoices = stmt
+            .query_map(params![date_from.to_string(), date_to.to_string()], Invoice::from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        invoices.into_iter().map(|invoice| self.return_document(invoice)).collect()
+    }
+
+    /// One invoice with its customer and lines, as used for e-invoice and e-way bill export
+    #[cfg(feature = "gui")]
+    pub fn get_return_invoice(&self, id: i64) -> SqliteResult<Option<ReturnInvoice>> {
+        let invoice = match self.connection.query_row(
+            "SELECT * FROM invoices WHERE id = ?1",
+            params![id],
+            Invoice::from_row,
+        ) {
+            Ok(invoice) => invoice,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        self.return_document(invoice).map(Some)
+    }
+
+    fn return_document(&self, invoice: Invoice) -> SqliteResult<ReturnInvoice> {
+        let customer = self
+            .connection
+            .prepare_cached("SELECT * FROM customers WHERE id = ?1")?
+            .query_row(params![invoice.customer_id], Customer::from_row)?;
+
+        let mut line_stmt = self.connection.prepare_cached(
+            "SELECT ii.*, p.unit_of_measurement AS product_uom, p.product_type AS product_type
+             FROM invoice_items ii
+             JOIN products p ON p.id = ii.product_id
+             WHERE ii.invoice_id = ?1 ORDER BY ii.line_number ASC"
+        )?;
+        // By name, as invoice_items may gain columns ahead of these
+        let lines = line_stmt
+            .query_map(params![invoice.id], |row| {
+                Ok(ReturnLine {
+                    item: InvoiceItem::from_row(row)?,
+                    unit_of_measurement: row.get("product_uom")?,
+                    product_type: row.get("product_type")?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        let references = match invoice.id {
+            Some(id) if notes::is_note(&invoice.invoice_type) => self.get_note_references(id)?,
+            _ => Vec::new(),
+        };
+
+        Ok(ReturnInvoice { invoice, customer, lines, references })
+    }
+}
+
+// =====================================================
+// E-invoice Registration
+// =====================================================
+
+#[cfg(feature = "gui")]
+impl Database {
+    /// Stores the IRP response on an invoice. Saving the same IRN again is a no-op; an
+    /// invoice that already carries a different IRN is rejected.
+    pub fn save_e_invoice_registration(
+        &self,
+        invoice_id: i64,
+        registration: &EInvoiceRegistration,
+    ) -> Result<Invoice, EInvoiceError> {
+        einvoice::validate_registration(registration)?;
+        let irn = registration.irn.trim().to_lowercase();
+
+        let existing: Option<String> = match self.connection.query_row(
+            "SELECT irn FROM invoices WHERE id = ?1",
+            params![invoice_id],
+            |row| row.get(0),
+        ) {
+            Ok(existing) => existing,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Err(EInvoiceError::InvoiceNotFound(invoice_id)),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(existing) = existing.filter(|existing| *existing != irn) {
+            return Err(EInvoiceError::AlreadyRegistered(existing));
+        }
+
+        self.connection.execute(
+            "UPDATE invoices SET irn = ?1, ack_number = ?2, ack_date = ?3, signed_qr_code = ?4,
+                    updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?5",
+            params![
+                irn,
+                registration.ack_number.trim(),
+                registration.ack_date.trim(),
+                registration.signed_qr_code.trim(),
+                invoice_id
+            ],
+        )?;
+
+        Ok(self.connection.query_row(
+            "SELECT * FROM invoices WHERE id = ?1",
+            params![invoice_id],
+            Invoice::from_row,
+        )?)
+    }
+}
+
+// =====================================================
+// E-way Bill Transport Details
+// =====================================================
+
+#[cfg(feature = "gui")]
+impl Database {
+    pub fn get_transport_details(&self, invoice_id: i64) -> SqliteResult<Option<TransportDetails>> {
+        match self.connection.query_row(
+            "SELECT * FROM invoice_transport WHERE invoice_id = ?1",
+            params![invoice_id],
+            TransportDetails::from_row,
+        ) {
+            Ok(details) => Ok(Some(details)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Validates and stores the transport details of an invoice, replacing any saved before
+    pub fn save_transport_details(&self, details: &TransportDetails) -> Result<TransportDetails, EWayBillError> {
+        let details = ewaybill::normalize_transport(details);
+        let problems = ewaybill::validate_transport(&details);
+        if !problems.is_empty() {
+            return Err(EWayBillError::Invalid(problems));
+        }
+
+        let exists: bool = self.connection.query_row(
+            "SELECT EXISTS(SELECT 1 FROM invoices WHERE id = ?1)",
+            params![details.invoice_id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(EWayBillError::InvoiceNotFound(details.invoice_id));
+        }
+
+        self.connection.execute(
+            "INSERT INTO invoice_transport (
+                invoice_id, transporter_id, transporter_name, transport_mode, distance_km,
+                vehicle_number, vehicle_type, transport_doc_number, transport_doc_date
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT(invoice_id) DO UPDATE SET
+                transporter_id = excluded.transporter_id,
+                transporter_name = excluded.transporter_name,
+                transport_mode = excluded.transport_mode,
+                distance_km = excluded.distance_km,
+                vehicle_number = excluded.vehicle_number,
+                vehicle_type = excluded.vehicle_type,
+                transport_doc_number = excluded.transport_doc_number,
+                transport_doc_date = excluded.transport_doc_date,
+                updated_at = CURRENT_TIMESTAMP",
+            params![
+                details.invoice_id,
+                details.transporter_id,
+                details.transporter_name,
+                details.transport_mode,
+                details.distance_km,
+                details.vehicle_number,
+                details.vehicle_type,
+                details.transport_doc_number,
+                details.transport_doc_date
+            ],
+        )?;
+
+        Ok(self.connection.query_row(
+            "SELECT * FROM invoice_transport WHERE invoice_id = ?1",
+            params![details.invoice_id],
+            TransportDetails::from_row,
+        )?)
+    }
+}
+
+// =====================================================
+// Invoice PDFs
+// =====================================================
+
+#[cfg(feature = "gui")]
+impl Database {
+    /// Records where an invoice's PDF was written; false when the invoice does not exist
+    pub fn set_invoice_pdf_path(&self, invoice_id: i64, pdf_path: &str) -> SqliteResult<bool> {
+        let updated = self.connection.execute(
+            "UPDATE invoices SET pdf_path = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
+            params![pdf_path, invoice_id],
+        )?;
+        Ok(updated > 0)
+    }
+}
+
+// =====================================================
+// Invoice Layout Templates
+// =====================================================
+
+#[cfg(feature = "gui")]
+impl Database {
+    pub fn list_invoice_templates(&self) -> SqliteResult<Vec<InvoiceTemplate>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT * FROM invoice_templates ORDER BY is_builtin DESC, name ASC"
+        )?;
+        let rows = stmt.query_map([], InvoiceTemplate::from_row)?;
+        rows.collect()
+    }
+
+    pub fn get_invoice_template(&self, id: i64) -> SqliteResult<Option<InvoiceTemplate>> {
+        let result = self.connection.query_row(
+            "SELECT * FROM invoice_templates WHERE id = ?1",
+            params![id],
+            InvoiceTemplate::from_row,
+        );
+
+        match result {
+            Ok(template) => Ok(Some(template)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates or updates a user template. Built-in templates are read-only.
+    pub fn save_invoice_template(&self, template: &InvoiceTemplate) -> Result<InvoiceTemplate, TemplateError> {
+        templates::validate_template(template)?;
+        let name = template.name.trim();
+
+        if let Some(id) = template.id {
+            let existing = self.get_invoice_template(id)?.ok_or(TemplateError::NotFound(id))?;
+            if existing.is_builtin {
+                return Err(TemplateError::BuiltIn(existing.name));
+            }
+        }
+        let taken: bool = self.connection.query_row(
+            "SELECT EXISTS(SELECT 1 FROM invoice_templates WHERE name = ?1 COLLATE NOCASE AND id IS NOT ?2)",
+            params![name, template.id],
+            |row| row.get(0),
+        )?;
+        if taken {
+            return Err(TemplateError::Invalid(vec![format!("A template named \"{}\" already exists", name)]));
+        }
+
+        let definition = serde_json::to_string(&template.definition)
+            .map_err(|err| TemplateError::Invalid(vec![err.to_string()]))?;
+        let id = match template.id {
+            Some(id) => {
+                self.connection.execute(
+                    "UPDATE invoice_templates SET name = ?1, description = ?2, definition = ?3,
+                            updated_at = CURRENT_TIMESTAMP
+                     WHERE id = ?4",
+                    params![name, template.description, definition, id],
+                )?;
+                id
+            }
+            None => {
+                self.connection.execute(
+                    "INSERT INTO invoice_templates (name, description, definition) VALUES (?1, ?2, ?3)",
+                    params![name, template.description, definition],
+                )?;
+                self.connection.last_insert_rowid()
+            }
+        };
+
+        Ok(self.connection.query_row(
+            "SELECT * FROM invoice_templates WHERE id = ?1",
+            params![id],
+            InvoiceTemplate::from_row,
+        )?)
+    }
+
+    /// Deletes a user template; companies and customers using it fall back to the default
+    pub fn delete_invoice_template(&self, id: i64) -> Result<bool, TemplateError> {
+        match self.get_invoice_template(id)? {
+            Some(template) if template.is_builtin => Err(TemplateError::BuiltIn(template.name)),
+            Some(_) => {
+                let rows_affected = self
+                    .connection
+                    .execute("DELETE FROM invoice_templates WHERE id = ?1", params![id])?;
+                Ok(rows_affected > 0)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// The layout for a document: the customer's template, else the company's, else the
+    /// standard layout
+    pub fn invoice_template_for(
+        &self,
+        customer: &Customer,
+        company: &CompanySettings,
+    ) -> SqliteResult<TemplateDefinition> {
+        let chosen = customer.invoice_template_id.or(company.invoice_template_id);
+        match chosen {
+            Some(id) => Ok(self
+                .get_invoice_template(id)?
+                .map(|template| template.definition)
+                .unwrap_or_default()),
+            None => Ok(TemplateDefinition::default()),
+        }
+    }
+}
+
+// =====================================================
+// CRUD Operations - Indian States
+// =====================================================
+
+#[cfg(feature = "gui")]
+impl Database {
+    pub fn get_indian_states(&self) -> SqliteResult<Vec<IndianState>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT * FROM indian_states WHERE is_active = 1 ORDER BY state_name ASC"
+        )?;
+        
+        let rows = stmt.query_map([], IndianState::from_row)?;
+        let mut states = Vec::new();
+        
+        for row in rows {
+            states.push(row?);
+        }
+        
+        Ok(states)
+    }
+
+    pub fn get_state_by_code(&self, state_code: &str) -> SqliteResult<Option<IndianState>> {
+        let mut stmt = self.connection.prepare("SELECT * FROM indian_states WHERE state_code = ?1")?;
+        
+        let result = stmt.query_row(params![state_code], IndianState::from_row);
+        
+        match result {
+            Ok(state) => Ok(Some(state)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// =====================================================
+// GST Rate Master
+// =====================================================
+
+impl Database {
+    /// Rate entries by code and start date; `code` narrows the list to entries that apply
+    /// to it, and inactive entries are left out unless asked for
+    pub fn get_gst_rates(&self, code: Option<&str>, include_inactive: bool) -> SqliteResult<Vec<GstRate>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT * FROM gst_rates
+             WHERE (?1 IS NULL OR ?1 LIKE hsn_sac_code || '%') AND (?2 OR is_active = 1)
+             ORDER BY hsn_sac_code ASC, effective_from ASC"
+        )?;
+        let code = code.map(rates::normalize_code);
+        let rows = stmt.query_map(params![code, include_inactive], GstRate::from_row)?;
+        rows.collect()
+    }
+
+    /// Adds or updates a rate entry; entries for the same code may not overlap in time
+    #[cfg(feature = "gui")]
+    pub fn save_gst_rate(&self, rate: &GstRate) -> Result<i64, RateError> {
+        let rate = rates::validate_rate(rate)?;
+
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+        let same_code = tx
+            .prepare("SELECT * FROM gst_rates WHERE hsn_sac_code = ?1 AND is_active = 1 AND id IS NOT ?2")?
+            .query_map(params![rate.hsn_sac_code, rate.id], GstRate::from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        if rate.is_active {
+            if let Some(existing) = same_code.iter().find(|existing| rates::overlaps(&rate, existing)) {
+                return Err(RateError::Overlap {
+                    hsn_sac_code: rate.hsn_sac_code,
+                    effective_from: existing.effective_from.clone(),
+                });
+            }
+        }
+
+        let id = if let Some(id) = rate.id {
+            let rows_affected = tx.execute(
+                "UPDATE gst_rates SET
+                 hsn_sac_code = ?1, description = ?2, gst_rate = ?3, cess_rate = ?4,
+                 effective_from = ?5, effective_to = ?6, is_active = ?7
+                 WHERE id = ?8",
+                params![
+                    rate.hsn_sac_code, rate.description, rate.gst_rate, rate.cess_rate,
+                    rate.effective_from, rate.effective_to, rate.is_active, id
+                ],
+            )?;
+            if rows_affected == 0 {
+                return Err(RateError::RateNotFound(id));
+            }
+            id
+        } else {
+            tx.execute(
+                "INSERT INTO gst_rates
+                 (hsn_sac_code, description, gst_rate, cess_rate, effective_from, effective_to, is_active,
+                  updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, CURRENT_TIMESTAMP)",
+                params![
+                    rate.hsn_sac_code, rate.description, rate.gst_rate, rate.cess_rate,
+                    rate.effective_from, rate.effective_to, rate.is_active
+                ],
+            )?;
+            tx.last_insert_rowid()
+        };
+
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Soft delete: the entry stops applying but stays on record
+    #[cfg(feature = "gui")]
+    pub fn delete_gst_rate(&self, id: i64) -> SqliteResult<bool> {
+        let rows_affected = self.connection.execute(
+            "UPDATE gst_rates SET is_active = 0 WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(rows_affected > 0)
+    }
+
+    /// The rate master entry that applies to an HSN/SAC code on a date
+    pub fn lookup_gst_rate(&self, code: &str, date: NaiveDate) -> SqliteResult<Option<GstRate>> {
+        let candidates = self.get_gst_rates(Some(code), false)?;
+        Ok(rates::resolve(&candidates, code, date).cloned())
+    }
+
+    /// Lines whose GST or cess rate differs from the rate master on the invoice date.
+    /// Codes the master does not cover are not flagged.
+    pub fn check_item_rates(&self, invoice_date: NaiveDate, items: &[InvoiceItem]) -> SqliteResult<Vec<RateMismatch>> {
+        let mut mismatches = Vec::new();
+        for item in items {
+            if let Some(master) = self.lookup_gst_rate(&item.hsn_sac_code, invoice_date)? {
+                mismatches.extend(rates::check_line(item, &master));
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+// =====================================================
+// Bulk Import
+// =====================================================
+
+impl Database {
+    /// Saves customers in one transaction; returns the index and error of each one that
+    /// could not be saved, the others are kept. A dry run saves nothing but reports
+    /// the same failures.
+    pub fn import_customers(&self, customers: &[Customer], dry_run: bool) -> SqliteResult<Vec<(usize, String)>> {
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+        let mut failures = Vec::new();
+        for (index, customer) in customers.iter().enumerate() {
+            if let Err(err) = self.save_customer(customer) {
+                failures.push((index, err.to_string()));
+            }
+        }
+        if dry_run {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+        Ok(failures)
+    }
+
+    /// Saves products in one transaction, as `import_customers`. A product code that is
+    /// already taken is reported rather than left to the unique constraint.
+    pub fn import_products(&self, products: &[Product], dry_run: bool) -> SqliteResult<Vec<(usize, String)>> {
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+        let mut failures = Vec::new();
+        for (index, product) in products.iter().enumerate() {
+            let taken: bool = self.connection.query_row(
+                "SELECT EXISTS (SELECT 1 FROM products WHERE product_code = ?1 AND id IS NOT ?2)",
+                params![product.product_code, product.id],
+                |row| row.get(0),
+            )?;
+            if taken {
+                failures.push((index, format!("product_code {} already exists", product.product_code)));
+            } else if let Err(err) = self.save_product(product) {
+                failures.push((index, err.to_string()));
+            }
+        }
+        if dry_run {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+        Ok(failures)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn list_import_mappings(&self, kind: &str) -> SqliteResult<Vec<ImportMapping>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT * FROM import_mappings WHERE kind = ?1 ORDER BY name ASC"
+        )?;
+        let rows = stmt.query_map(params![kind], ImportMapping::from_row)?;
+        rows.collect()
+    }
+
+    pub fn get_import_mapping(&self, id: i64) -> SqliteResult<Option<ImportMapping>> {
+        match self.connection.query_row(
+            "SELECT * FROM import_mappings WHERE id = ?1",
+            params![id],
+            ImportMapping::from_row,
+        ) {
+            Ok(mapping) => Ok(Some(mapping)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn find_import_mapping(&self, kind: &str, name: &str) -> SqliteResult<Option<ImportMapping>> {
+        match self.connection.query_row(
+            "SELECT * FROM import_mappings WHERE kind = ?1 AND name = ?2",
+            params![kind, name],
+            ImportMapping::from_row,
+        ) {
+            Ok(mapping) => Ok(Some(mapping)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Saving under a name already used for the kind replaces that mapping
+    #[cfg(feature = "gui")]
+    pub fn save_import_mapping(&self, mapping: &ImportMapping) -> SqliteResult<i64> {
+        let columns = serde_json::to_string(&mapping.columns)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+        if let Some(id) = mapping.id {
+            self.connection.execute(
+                "UPDATE import_mappings SET name = ?1, kind = ?2, columns = ?3 WHERE id = ?4",
+                params![mapping.name, mapping.kind, columns, id],
+            )?;
+            Ok(id)
+        } else {
+            self.connection.execute(
+                "INSERT INTO import_mappings (name, kind, columns) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(kind, name) DO UPDATE SET columns = excluded.columns",
+                params![mapping.name, mapping.kind, columns],
+            )?;
+            self.connection.query_row(
+                "SELECT id FROM import_mappings WHERE kind = ?1 AND name = ?2",
+                params![mapping.kind, mapping.name],
+                |row| row.get(0),
+            )
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn delete_import_mapping(&self, id: i64) -> SqliteResult<()> {
+        self.connection.execute("DELETE FROM import_mappings WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+}
+
+// =====================================================
+// Audit Trail
+// =====================================================
+
+impl Database {
+    /// Name recorded against every change made from now on
+    pub fn set_audit_actor(&self, actor: &str) -> Result<String, AuditError> {
+        let actor = audit::normalize_actor(actor)?;
+        self.connection.execute("UPDATE audit_actor SET actor = ?1 WHERE id = 1", params![actor])?;
+        Ok(actor)
+    }
+
+    pub fn get_audit_actor(&self) -> SqliteResult<String> {
+        self.connection.query_row("SELECT actor FROM audit_actor WHERE id = 1", [], |row| row.get(0))
+    }
+
+    /// Every change to one record, oldest first. Deleted records keep their history.
+    #[cfg(feature = "gui")]
+    pub fn get_audit_history(&self, table_name: &str, record_id: i64) -> Result<Vec<AuditEntry>, AuditError> {
+        audit::check_table(table_name)?;
+        let mut stmt = self.connection.prepare(
+            "SELECT * FROM audit_log WHERE table_name = ?1 AND record_id = ?2 ORDER BY id ASC"
+        )?;
+        let rows = stmt.query_map(params![table_name, record_id], AuditEntry::from_row)?;
+        Ok(rows.collect::<SqliteResult<Vec<_>>>()?)
+    }
+}
+
+// =====================================================
+// Local API Server
+// =====================================================
+
+impl Database {
+    pub fn get_api_server_settings(&self) -> SqliteResult<ApiServerSettings> {
+        self.connection.query_row(
+            "SELECT * FROM api_server_settings WHERE id = 1",
+            [],
+            ApiServerSettings::from_row,
+        )
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn save_api_server_settings(&self, enabled: bool, port: u16) -> SqliteResult<ApiServerSettings> {
+        self.connection.execute(
+            "UPDATE api_server_settings SET enabled = ?1, port = ?2 WHERE id = 1",
+            params![enabled, port],
+        )?;
+        self.get_api_server_settings()
+    }
+
+    /// Replaces the token; programs holding the old one are refused from then on
+    pub fn set_api_token(&self, token: &str) -> SqliteResult<ApiServerSettings> {
+        self.connection.execute(
+            "UPDATE api_server_settings SET token = ?1 WHERE id = 1",
+            params![token],
+        )?;
+        self.get_api_server_settings()
+    }
+}
+
+// =====================================================
+// Tally Export
+// =====================================================
+
+impl Database {
+    pub fn get_tally_settings(&self) -> SqliteResult<TallySettings> {
+        let mut settings = self.connection.query_row(
+            "SELECT * FROM tally_settings WHERE id = 1",
+            [],
+            TallySettings::from_row,
+        )?;
+        let mut stmt = self.connection.prepare(
+            "SELECT customer_id, ledger_name FROM tally_party_ledgers ORDER BY customer_id ASC"
+        )?;
+        settings.party_ledgers = stmt
+            .query_map([], |row| Ok(TallyPartyLedger { customer_id: row.get(0)?, ledger_name: row.get(1)? }))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(settings)
+    }
+
+    /// Replaces the ledger names, including every customer's ledger name
+    #[cfg(feature = "gui")]
+    pub fn save_tally_settings(&self, settings: &TallySettings) -> SqliteResult<TallySettings> {
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+        self.connection.execute(
+            "UPDATE tally_settings SET company_name = ?1, party_group = ?2, sales_ledger = ?3,
+             cgst_ledger = ?4, sgst_ledger = ?5, igst_ledger = ?6, cess_ledger = ?7,
+             round_off_ledger = ?8, cash_ledger = ?9, bank_ledger = ?10
+             WHERE id = 1",
+            params![
+                settings.company_name, settings.party_group, settings.sales_ledger,
+                settings.cgst_ledger, settings.sgst_ledger, settings.igst_ledger, settings.cess_ledger,
+                settings.round_off_ledger, settings.cash_ledger, settings.bank_ledger
+            ],
+        )?;
+        self.connection.execute("DELETE FROM tally_party_ledgers", [])?;
+        for party in &settings.party_ledgers {
+            self.connection.execute(
+                "INSERT INTO tally_party_ledgers (customer_id, ledger_name) VALUES (?1, ?2)",
+                params![party.customer_id, party.ledger_name],
+            )?;
+        }
+        tx.commit()?;
+        self.get_tally_settings()
+    }
+
+    /// Tally ledger name of every customer: the mapped name, else the customer name.
+    /// Tally names are unique regardless of case, so a customer sharing a name with an
+    /// earlier one gets its id appended; earlier customers keep their names.
+    pub fn get_tally_party_names(&self) -> SqliteResult<BTreeMap<i64, String>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT c.id,
+                    COALESCE(t.ledger_name,
+                        CASE WHEN EXISTS (SELECT 1 FROM customers e
+                                          WHERE e.customer_name = c.customer_name COLLATE NOCASE AND e.id < c.id)
+                             THEN c.customer_name || ' (' || c.id || ')'
+                             ELSE c.customer_name END)
+             FROM customers c LEFT JOIN tally_party_ledgers t ON t.customer_id = c.id"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Payments dated within the range, voided ones included, each with the number and
+    /// customer of the invoice it pays
+    pub fn get_tally_payments(&self, date_from: NaiveDate, date_to: NaiveDate) -> SqliteResult<Vec<(Payment, String, i64)>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT p.*, i.invoice_number, i.customer_id FROM payments p
+             JOIN invoices i ON i.id = p.invoice_id
+             WHERE p.payment_date BETWEEN ?1 AND ?2
+             ORDER BY p.payment_date ASC, p.id ASC"
+        )?;
+        let rows = stmt.query_map(params![date_from.to_string(), date_to.to_string()], |row| {
+            Ok((Payment::from_row(row)?, row.get(11)?, row.get(12)?))
+        })?;
+        rows.collect()
+    }
+
+    pub fn get_tally_exports(&self) -> SqliteResult<BTreeMap<String, ExportedVoucher>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT remote_id, voucher_type, voucher_number, amount, is_cancelled FROM tally_exports"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ExportedVoucher {
+                remote_id: row.get(0)?,
+                voucher_type: row.get(1)?,
+                voucher_number: row.get(2)?,
+                amount: row.get(3)?,
+                is_cancelled: row.get(4)?,
+            })
+        })?;
+        rows.map(|row| row.map(|voucher| (voucher.remote_id.clone(), voucher))).collect()
+    }
+
+    /// Logs vouchers as sent, replacing what was logged for them before
+    pub fn record_tally_exports(&self, vouchers: &[ExportedVoucher]) -> SqliteResult<()> {
+        let tx = Transaction::new_unchecked(&self.connection, TransactionBehavior::Immediate)?;
+        for voucher in vouchers {
+            self.connection.execute(
+                "INSERT INTO tally_exports (remote_id, voucher_type, voucher_number, amount, is_cancelled)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(remote_id) DO UPDATE SET
+                     voucher_type = excluded.voucher_type, voucher_number = excluded.voucher_number,
+                     amount = excluded.amount, is_cancelled = excluded.is_cancelled,
+                     exported_at = CURRENT_TIMESTAMP",
+                params![voucher.remote_id, voucher.voucher_type, voucher.voucher_number, voucher.amount, voucher.is_cancelled],
+            )?;
+        }
+        tx.commit()
+    }
+}
+
+// =====================================================
+// Backups
+// =====================================================
+
+/// Pages copied per step of a backup, between which other connections may write
+const BACKUP_PAGES_PER_STEP: i32 = 1024;
+const BACKUP_STEP_PAUSE: Duration = Duration::from_millis(10);
+
+impl Database {
+    /// Writes a consistent copy of the database to a new file while it stays in use. The
+    /// copy leaves out the backup password, since unencrypted copies may leave the machine.
+    pub fn backup_to(&self, path: &Path) -> SqliteResult<()> {
+        let mut target = Connection::open(path)?;
+        Backup::new(&self.connection, &mut target)?.run_to_completion(
+            BACKUP_PAGES_PER_STEP,
+            BACKUP_STEP_PAUSE,
+            None,
+        )?;
+
+        // Zero the old value in place, with no journal file to hold a copy of the page
+        target.pragma_update(None, "journal_mode", "MEMORY")?;
+        target.pragma_update(None, "secure_delete", true)?;
+        target.execute("UPDATE backup_settings SET password = NULL WHERE id = 1", [])?;
+        Ok(())
+    }
+
+    /// Replaces the whole database with the contents of `source`, schema version included
+    pub fn restore_from(&mut self, source: &Connection) -> SqliteResult<()> {
+        let backup = Backup::new(source, &mut self.connection)?;
+        backup.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn get_backup_settings(&self) -> SqliteResult<BackupSettings> {
+        self.connection.query_row(
+            "SELECT * FROM backup_settings WHERE id = 1",
+            [],
+            BackupSettings::from_row,
+        )
+    }
+
+    pub fn get_backup_password(&self) -> SqliteResult<Option<String>> {
+        self.connection.query_row("SELECT password FROM backup_settings WHERE id = 1", [], |row| row.get(0))
+    }
+
+    /// Puts back the password a restore would otherwise take from the backup
+    pub fn set_backup_password(&self, password: Option<&str>) -> SqliteResult<()> {
+        self.connection.execute("UPDATE backup_settings SET password = ?1 WHERE id = 1", params![password])?;
+        Ok(())
+    }
+
+    /// Saves the schedule. A new `password` replaces the stored one, which is otherwise
+    /// kept while backups stay encrypted and forgotten when they no longer are.
+    #[cfg(feature = "gui")]
+    pub fn save_backup_settings(&self, settings: &BackupSettings, password: Option<&str>) -> SqliteResult<BackupSettings> {
+        self.connection.execute(
+            "UPDATE backup_settings SET enabled = ?1, directory = ?2, interval_hours = ?3, keep_count = ?4,
+             password = CASE WHEN ?5 THEN COALESCE(?6, password) END
+             WHERE id = 1",
+            params![
+                settings.enabled,
+                settings.directory.as_deref().map(str::trim).filter(|dir| !dir.is_empty()),
+                settings.interval_hours,
+                settings.keep_count,
+                settings.encrypted,
+                password
+            ],
+        )?;
+        self.get_backup_settings()
+    }
+
+    /// `at` is UTC in SQLite's format
+    #[cfg(feature = "gui")]
+    pub fn record_backup_success(&self, at: &str) -> SqliteResult<()> {
+        self.connection.execute(
+            "UPDATE backup_settings SET last_backup_at = ?1, last_error = NULL WHERE id = 1",
+            params![at],
+        )?;
+        Ok(())
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn record_backup_failure(&self, error: &str) -> SqliteResult<()> {
+        self.connection.execute("UPDATE backup_settings SET last_error = ?1 WHERE id = 1", params![error])?;
+        Ok(())
+    }
+}
+
+// =====================================================
+// Utility Functions
+// =====================================================
+
+#[cfg(any(feature = "gui", test))]
+impl Database {
+    pub fn count_records(&self, table: &str) -> SqliteResult<i64> {
+        let query = format!("SELECT COUNT(*) FROM {}", table);
+        let count: i64 = self.connection.query_row(&query, [], |row| row.get(0))?;
+        Ok(count)
+    }
+}
+
+// =====================================================
+// Invoice Numbering Series
+// =====================================================
+
+impl Database {
+    #[cfg(feature = "gui")]
+    pub fn get_invoice_series(&self) -> SqliteResult<Vec<InvoiceSeries>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT id, format, reset_rule, is_active, created_at, updated_at
+             FROM invoice_series ORDER BY id ASC",
+        )?;
+
+        let rows = stmt.query_map([], InvoiceSeries::from_row)?;
+        let mut series = Vec::new();
+
+        for row in rows {
+            series.push(row?);
+        }
+
+        Ok(series)
+    }
+
+    /// Creates a series or changes its reset rule. Counters already issued are kept, so
+    /// switching a series to a reset rule continues its numbering until the next period.
+    #[cfg(feature = "gui")]
+    pub fn save_invoice_series(&self, format: &NumberFormat, reset_rule: ResetRule) -> SqliteResult<i64> {
+        self.connection.execute(
+            "INSERT INTO invoice_series (format, reset_rule) VALUES (?1, ?2)
+             ON CONFLICT(format) DO UPDATE SET reset_rule = excluded.reset_rule",
+            params![format.template(), reset_rule.as_str()],
+        )?;
+
+        self.connection.query_row(
+            "SELECT id FROM invoice_series WHERE format = ?1",
+            params![format.template()],
+            |row| row.get(0),
+        )
+    }
+
+    /// Previews the number the next saved invoice will receive; nothing is consumed
+    #[cfg(any(feature = "gui", test))]
+    pub fn get_next_invoice_number(&self, format: &NumberFormat, date: NaiveDate) -> SqliteResult<String> {
+        let rule = Self::series_reset_rule(&self.connection, format)?;
+        let counter = Self::next_counter(&self.connection, format, rule, date)?;
+        Ok(format.render(date, counter))
+    }
+
+    /// Reset rule of the series for `format`; series not yet saved use the format's default
+    fn series_reset_rule(connection: &Connection, format: &NumberFormat) -> SqliteResult<ResetRule> {
+        let stored = connection.query_row(
+            "SELECT reset_rule FROM invoice_series WHERE format = ?1",
+            params![format.template()],
+            |row| row.get::<_, String>(0),
+        );
+
+        match stored {
+            Ok(rule) => ResetRule::parse(&rule).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+            }),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(format.default_reset_rule()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads the counter for the reset period containing `date`, seeding new periods past
+    /// any existing invoice numbers from the same template and period so legacy databases
+    /// never collide
+    fn next_counter(
+        connection: &Connection,
+        format: &NumberFormat,
+        rule: ResetRule,
+        date: NaiveDate,
+    ) -> SqliteResult<i64> {
+        let existing = connection.query_row(
+            "SELECT c.next_number FROM invoice_series_counters c
+             JOIN invoice_series s ON s.id = c.series_id
+             WHERE s.format = ?1 AND c.period = ?2",
+            params![format.template(), rule.period(date)],
+            |row| row.get(0),
+        );
+
+        match existing {
+            Ok(next_number) => Ok(next_number),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                let pattern = format.counter_pattern(rule, date);
+                let mut stmt = connection.prepare("SELECT invoice_number FROM invoices")?;
+                let numbers = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+                let mut highest = 0;
+                for number in numbers {
+                    let number = number?;
+                    if let Some(counter) = pattern
+                        .captures(&number)
+                        .and_then(|captures| captures[1].parse::<i64>().ok())
+                    {
+                        highest = highest.max(counter);
+                    }
+                }
+                Ok(highest + 1)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Consumes the next number of a series. Must run inside the transaction that
+    /// issues the document so a rolled-back issue leaves no gap.
+    fn allocate_invoice_number(
+        connection: &Connection,
+        format: &NumberFormat,
+        date: NaiveDate,
+    ) -> Result<String, StatusError> {
+        let rule = Self::series_reset_rule(connection, format)?;
+        let counter = Self::next_counter(connection, format, rule, date)?;
+        let number = format.render_checked(date, counter)?;
+
+        connection.execute(
+            "INSERT INTO invoice_series (format, reset_rule) VALUES (?1, ?2)
+             ON CONFLICT(format) DO NOTHING",
+            params![format.template(), rule.as_str()],
+        )?;
+        connection.execute(
+            "INSERT INTO invoice_series_counters (series_id, period, next_number)
+             SELECT id, ?2, ?3 FROM invoice_series WHERE format = ?1
+             ON CONFLICT(series_id, period) DO UPDATE SET next_number = excluded.next_number",
+            params![format.template(), rule.period(date), counter + 1],
+        )?;
+
+        Ok(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbering::NumberingError;
+    use crate::reports::fixtures::{customer, document};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// An empty database with customers 1 (Acme Traders) and 2 (Bharat Steel) and product 1
+    fn database() -> Database {
+        let db = Database::new(Path::new(":memory:")).unwrap();
+        db.initialize_schema().unwrap();
+        for name in ["Acme Traders", "Bharat Steel"] {
+            let buyer = Customer { id: None, customer_name: name.to_string(), ..customer(None, "B2C") };
+            db.save_customer(&buyer).unwrap();
+        }
+        db.save_product(&Product {
+            id: None,
+            product_code: "P1".to_string(),
+            product_name: "Steel rod".to_string(),
+            description: None,
+            hsn_sac_code: "7214".to_string(),
+            product_type: "GOODS".to_string(),
+            unit_of_measurement: "PCS".to_string(),
+            rate: Money::from_rupees(1000.0),
+            gst_rate: 18.0,
+            cess_rate: 0.0,
+            is_active: true,
+            created_at: None,
+            updated_at: None,
+        })
+        .unwrap();
+        db
+    }
+
+    fn try_save(
+        db: &Database,
+        number: &str,
+        kind: &str,
+        customer_id: i64,
+        status: &str,
+        invoice_date: &str,
+    ) -> Result<i64, StatusError> {
+        let mut document = document(number, kind, "Maharashtra", 1000.0, customer(None, "B2C"));
+        document.invoice.id = None;
+        document.invoice.customer_id = customer_id;
+        document.invoice.status = status.to_string();
+        document.invoice.invoice_date = invoice_date.to_string();
+        let items: Vec<InvoiceItem> = document.lines.into_iter().map(|line| line.item).collect();
+        let format = NumberFormat::parse(crate::numbering::DEFAULT_FORMAT).unwrap();
+        db.save_invoice(&document.invoice, &items, &format, date(2026, 10, 16))
+    }
+
+    fn save(db: &Database, number: &str, kind: &str, customer_id: i64, status: &str, invoice_date: &str) -> i64 {
+        try_save(db, number, kind, customer_id, status, invoice_date).unwrap()
+    }
+
+    /// A draft credit note for ₹1,180 to customer 1, dated after the invoices it may amend
+    fn draft_credit_note() -> (Invoice, Vec<InvoiceItem>) {
+        let mut note = document("", "CREDIT_NOTE", "Maharashtra", 1000.0, customer(None, "B2C"));
+        note.invoice.id = None;
+        note.invoice.status = "DRAFT".to_string();
+        note.invoice.invoice_date = "2026-09-20".to_string();
+        note.invoice.note_reason = Some("SALES_RETURN".to_string());
+        (note.invoice, note.lines.into_iter().map(|line| line.item).collect())
+    }
+
+    fn numbers(db: &Database, filter: InvoiceFilter) -> Vec<String> {
+        db.list_invoices(&filter).unwrap().into_iter().map(|invoice| invoice.invoice_number).collect()
+    }
+
+    fn count(db: &Database, table: &str) -> i64 {
+        db.count_records(table).unwrap()
+    }
+
+    #[test]
+    fn failed_line_rolls_back_whole_invoice() {
+        let db = database();
+        let format = NumberFormat::parse(crate::numbering::DEFAULT_FORMAT).unwrap();
+        let mut document = document("", "REGULAR", "Maharashtra", 1000.0, customer(None, "B2C"));
+        document.invoice.id = None;
+        let mut item = document.lines[0].item.clone();
+        item.product_id = 99; // no such product, so the line breaks its foreign key
+
+        let result = db.save_invoice(&document.invoice, &[item], &format, date(2026, 10, 16));
+        assert!(matches!(result, Err(StatusError::Sqlite(_))));
+        assert_eq!(count(&db, "invoices"), 0);
+        assert_eq!(count(&db, "invoice_items"), 0);
+        assert_eq!(count(&db, "invoice_status_history"), 0);
+        assert_eq!(db.get_next_invoice_number(&format, date(2026, 9, 10)).unwrap(), "INV-2026-09-0001");
+    }
+
+    #[test]
+    fn numbers_drafts_only_when_issued() {
+        let db = database();
+        let today = date(2026, 10, 16);
+        let deleted = save(&db, "", "REGULAR", 1, "DRAFT", "2026-09-10");
+        let issued = save(&db, "", "REGULAR", 1, "DRAFT", "2026-09-10");
+        assert_eq!(numbers(&db, InvoiceFilter::default()), ["DRAFT-2", "DRAFT-1"]);
+
+        assert!(db.delete_invoice(deleted).unwrap());
+        db.update_invoice_status(issued, "SENT", None, today).unwrap();
+        save(&db, "", "REGULAR", 1, "SENT", "2026-09-10");
+        let abandoned = save(&db, "", "REGULAR", 1, "DRAFT", "2026-09-10");
+        db.cancel_invoice(abandoned, "", today).unwrap();
+        assert_eq!(numbers(&db, InvoiceFilter::default()), ["DRAFT-4", "INV-2026-09-0002", "INV-2026-09-0001"]);
+
+        // The cancelled draft never used a number, so returns do not report it
+        let returned = db.get_return_invoices(date(2026, 9, 1), date(2026, 9, 30)).unwrap();
+        assert_eq!(returned.len(), 2);
+        let line = &returned[0].lines[0];
+        assert_eq!((line.unit_of_measurement.as_str(), line.product_type.as_str()), ("PCS", "GOODS"));
+    }
+
+    #[test]
+    fn refuses_number_longer_than_rule_46_allows() {
+        let db = database();
+        save(&db, "INV-2026-09-9999", "REGULAR", 1, "DRAFT", "2026-09-10");
+
+        let result = try_save(&db, "", "REGULAR", 1, "SENT", "2026-09-10");
+        assert!(matches!(
+            result,
+            Err(StatusError::Numbering(NumberingError::NumberTooLong(number))) if number == "INV-2026-09-10000"
+        ));
+        assert_eq!(count(&db, "invoices"), 1);
+    }
+
+    #[test]
+    fn totals_payments_in_whole_paise() {
+        let db = database();
+        let invoice_id = save(&db, "INV-1", "REGULAR", 1, "SENT", "2026-09-10");
+        for amount in [393.33, 393.33, 393.34] {
+            let payment = NewPayment {
+                payment_date: "2026-09-20".to_string(),
+                payment_method: "UPI".to_string(),
+                reference_number: None,
+                notes: None,
+                receipt_number: None,
+                allocations: vec![PaymentAllocation { invoice_id, amount: Money::from_rupees(amount) }],
+            };
+            db.record_payment(&payment, date(2026, 9, 20)).unwrap();
+        }
+
+        let balance = db.get_invoice_balance(invoice_id).unwrap().unwrap();
+        assert_eq!(balance.amount_paid, Money::from_paise(118_000));
+        assert_eq!(balance.outstanding, Money::ZERO);
+        assert_eq!(balance.status, "PAID");
+    }
+
+    #[test]
+    fn rechecks_notes_against_their_invoices_when_issued() {
+        let db = database();
+        let today = date(2026, 10, 16);
+        let invoice_id = save(&db, "INV-1", "REGULAR", 1, "SENT", "2026-09-10");
+        let (note, items) = draft_credit_note();
+        let format = NumberFormat::parse(notes::CREDIT_NOTE_FORMAT).unwrap();
+        let full_credit = [NoteAllocation { invoice_id, amount: Money::from_rupees(1180.0) }];
+
+        // Both drafts fit while neither is issued
+        let first = db.save_note(&note, &items, &full_credit, &format, today).unwrap();
+        let second = db.save_note(&note, &items, &full_credit, &format, today).unwrap();
+
+        db.update_invoice_status(first, "SENT", None, today).unwrap();
+        let balance = db.get_invoice_balance(invoice_id).unwrap().unwrap();
+        assert_eq!((balance.outstanding, balance.status.as_str()), (Money::ZERO, "PAID"));
+
+        let result = db.update_invoice_status(second, "SENT", None, today);
+        assert!(matches!(result, Err(StatusError::Note(ref err)) if err.code() == "CreditExceedsInvoice"), "{:?}", result);
+        assert_eq!(db.get_invoice_by_id(second).unwrap().unwrap().invoice.status, "DRAFT");
+    }
+
+    #[test]
+    fn refuses_notes_saved_as_invoices() {
+        let db = database();
+        let result = try_save(&db, "", "CREDIT_NOTE", 1, "SENT", "2026-09-10");
+        assert!(matches!(result, Err(StatusError::Note(ref err)) if err.code() == "InvalidNote"), "{:?}", result);
+
+        // Nor may an invoice update turn a saved note into an invoice
+        let invoice_id = save(&db, "INV-1", "REGULAR", 1, "SENT", "2026-09-10");
+        let (note, items) = draft_credit_note();
+        let format = NumberFormat::parse(notes::CREDIT_NOTE_FORMAT).unwrap();
+        let allocation = [NoteAllocation { invoice_id, amount: Money::from_rupees(1180.0) }];
+        let note_id = db.save_note(&note, &items, &allocation, &format, date(2026, 10, 16)).unwrap();
+
+        let as_invoice = Invoice { id: Some(note_id), invoice_type: "REGULAR".to_string(), ..note };
+        let result = db.save_invoice(&as_invoice, &items, &format, date(2026, 10, 16));
+        assert!(matches!(result, Err(StatusError::Note(_))), "{:?}", result);
+        assert_eq!(db.get_invoice_by_id(note_id).unwrap().unwrap().invoice.invoice_type, "CREDIT_NOTE");
+    }
+
+    #[test]
+    fn filters_invoice_list() {
+        let db = database();
+        save(&db, "INV-1", "REGULAR", 1, "DRAFT", "2026-09-01");
+        save(&db, "INV-2", "REGULAR", 2, "SENT", "2026-09-15");
+        save(&db, "EXP-3", "EXPORT", 1, "DRAFT", "2026-10-01");
+
+        assert_eq!(numbers(&db, InvoiceFilter::default()), ["EXP-3", "INV-2", "INV-1"]);
+        assert_eq!(numbers(&db, InvoiceFilter { customer_id: Some(1), ..Default::default() }), ["EXP-3", "INV-1"]);
+        assert_eq!(
+            numbers(&db, InvoiceFilter { invoice_type: Some("EXPORT".to_string()), ..Default::default() }),
+            ["EXP-3"]
+        );
+        assert_eq!(numbers(&db, InvoiceFilter { status: Some("SENT".to_string()), ..Default::default() }), ["INV-2"]);
+        assert_eq!(
+            numbers(&db, InvoiceFilter { date_from: Some("2026-09-15".to_string()), ..Default::default() }),
+            ["EXP-3", "INV-2"]
+        );
+        assert_eq!(
+            numbers(&db, InvoiceFilter { date_to: Some("2026-09-15".to_string()), ..Default::default() }),
+            ["INV-2", "INV-1"]
+        );
+        assert_eq!(numbers(&db, InvoiceFilter { search: Some("bharat".to_string()), ..Default::default() }), ["INV-2"]);
+        assert_eq!(numbers(&db, InvoiceFilter { search: Some("EXP".to_string()), ..Default::default() }), ["EXP-3"]);
+        assert_eq!(
+            numbers(&db, InvoiceFilter { limit: Some(1), offset: Some(1), ..Default::default() }),
+            ["INV-2"]
+        );
+    }
+}
@@ -0,0 +1,460 @@
+// =====================================================
+// Payvlo GST Invoice Generator - E-invoice
+// INV-01 JSON payload per NIC e-invoice schema 1.1
+// =====================================================
+//
+// The payload is built from a stored invoice, its customer and the company settings
+// and validated against the mandatory fields and lengths of the schema before export.
+// The IRP's response (IRN, acknowledgement and signed QR) is stored back on the invoice.
+
+use crate::database::{CompanySettings, ReturnInvoice, ReturnLine};
+use crate::money::Money;
+use crate::reports;
+use crate::validation;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const SCHEMA_VERSION: &str = "1.1";
+pub const MAX_ITEMS: usize = 1000;
+
+// =====================================================
+// Payload Structure
+// =====================================================
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EInvoice {
+    pub version: String,
+    pub tran_dtls: TransactionDetails,
+    pub doc_dtls: DocumentDetails,
+    pub seller_dtls: PartyDetails,
+    pub buyer_dtls: PartyDetails,
+    pub item_list: Vec<ItemDetails>,
+    pub val_dtls: ValueDetails,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct TransactionDetails {
+    pub tax_sch: String,
+    pub sup_typ: String,
+    pub reg_rev: String,
+    pub igst_on_intra: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct DocumentDetails {
+    pub typ: String,
+    pub no: String,
+    pub dt: String,
+}
+
+/// Seller or buyer; `pos` is only sent for the buyer
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct PartyDetails {
+    pub gstin: String,
+    pub lgl_nm: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pos: Option<String>,
+    pub addr1: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addr2: Option<String>,
+    pub loc: String,
+    pub pin: u32,
+    pub stcd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ph: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub em: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ItemDetails {
+    pub sl_no: String,
+    pub prd_desc: String,
+    pub is_servc: String,
+    pub hsn_cd: String,
+    pub qty: f64,
+    pub unit: String,
+    pub unit_price: Money,
+    pub tot_amt: Money,
+    pub discount: Money,
+    pub ass_amt: Money,
+    pub gst_rt: f64,
+    pub igst_amt: Money,
+    pub cgst_amt: Money,
+    pub sgst_amt: Money,
+    pub ces_rt: f64,
+    pub ces_amt: Money,
+    pub tot_item_val: Money,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ValueDetails {
+    pub ass_val: Money,
+    pub cgst_val: Money,
+    pub sgst_val: Money,
+    pub igst_val: Money,
+    pub ces_val: Money,
+    pub discount: Money,
+    pub oth_chrg: Money,
+    pub rnd_off_amt: Money,
+    pub tot_inv_val: Money,
+}
+
+/// What the IRP returns for a registered invoice
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EInvoiceRegistration {
+    pub irn: String,
+    pub ack_number: String,
+    pub ack_date: String,
+    pub signed_qr_code: String,
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum EInvoiceError {
+    /// Every mandatory field that is missing or malformed, one message each
+    Invalid(Vec<String>),
+    InvoiceNotFound(i64),
+    AlreadyRegistered(String),
+    InvalidRegistration(String),
+    CompanySettingsMissing,
+    Sqlite(rusqlite::Error),
+}
+
+impl EInvoiceError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EInvoiceError::Invalid(_) => "EInvoiceValidationError",
+            EInvoiceError::InvoiceNotFound(_) => "NotFound",
+            EInvoiceError::AlreadyRegistered(_) => "EInvoiceAlreadyRegistered",
+            EInvoiceError::InvalidRegistration(_) => "InvalidEInvoiceRegistration",
+            EInvoiceError::CompanySettingsMissing => "CompanySettingsMissing",
+            EInvoiceError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for EInvoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EInvoiceError::Invalid(problems) => {
+                write!(f, "Invoice cannot be e-invoiced: {}", problems.join("; "))
+            }
+            EInvoiceError::InvoiceNotFound(id) => write!(f, "Invoice {} does not exist", id),
+            EInvoiceError::AlreadyRegistered(irn) => {
+                write!(f, "Invoice is already registered with IRN {}", irn)
+            }
+            EInvoiceError::InvalidRegistration(message) => f.write_str(message),
+            EInvoiceError::CompanySettingsMissing => {
+                f.write_str("Company settings must be saved before generating e-invoices")
+            }
+            EInvoiceError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for EInvoiceError {}
+
+impl From<rusqlite::Error> for EInvoiceError {
+    fn from(err: rusqlite::Error) -> Self {
+        EInvoiceError::Sqlite(err)
+    }
+}
+
+// =====================================================
+// Field Helpers
+// =====================================================
+
+/// Dates in NIC e-invoice and e-way bill files are written `dd/mm/yyyy`
+pub fn nic_date(date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| date.format("%d/%m/%Y").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+fn optional(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|value| !value.is_empty()).map(str::to_string)
+}
+
+/// Phone numbers are sent as 6 to 12 digits; anything else is left out
+fn phone(value: Option<&str>) -> Option<String> {
+    let digits: String = value?.chars().filter(|c| c.is_ascii_digit()).collect();
+    let digits = digits.strip_prefix("91").filter(|d| d.len() == 10).unwrap_or(&digits).to_string();
+    (6..=12).contains(&digits.len()).then_some(digits)
+}
+
+fn check_length(problems: &mut Vec<String>, field: &str, value: &str, min: usize, max: usize) {
+    let length = value.trim().chars().count();
+    if length < min || length > max {
+        problems.push(format!("{} must be {} to {} characters", field, min, max));
+    }
+}
+
+/// Document numbers: up to 16 letters, digits, '/' and '-', not starting with 0, '/' or '-'
+pub fn is_valid_document_number(number: &str) -> bool {
+    let valid_chars = number.chars().all(|c| c.is_ascii_alphanumeric() || c == '/' || c == '-');
+    let valid_start = !number.starts_with(['0', '/', '-']);
+    (1..=16).contains(&number.len()) && valid_chars && valid_start
+}
+
+fn item_details(index: usize, line: &ReturnLine) -> ItemDetails {
+    let item = &line.item;
+    ItemDetails {
+        sl_no: (index + 1).to_string(),
+        prd_desc: item.product_name.clone(),
+        is_servc: if line.product_type == "SERVICES" { "Y" } else { "N" }.to_string(),
+        hsn_cd: item.hsn_sac_code.trim().to_string(),
+        qty: item.quantity,
+        unit: reports::uqc(&line.unit_of_measurement).to_string(),
+        unit_price: item.unit_price,
+        tot_amt: item.unit_price.times(item.quantity),
+        discount: item.discount_amount,
+        ass_amt: item.taxable_amount,
+        gst_rt: item.gst_rate,
+        igst_amt: item.igst_amount,
+        cgst_amt: item.cgst_amount,
+        sgst_amt: item.sgst_amount,
+        ces_rt: item.cess_rate,
+        ces_amt: item.cess_amount,
+        tot_item_val: item.line_total,
+    }
+}
+
+// =====================================================
+// Payload Builder
+// =====================================================
+
+/// Builds and validates the INV-01 payload for an invoice, credit note or debit note.
+/// All validation problems are reported together.
+pub fn build_e_invoice(company: &CompanySettings, document: &ReturnInvoice) -> Result<EInvoice, EInvoiceError> {
+    let invoice = &document.invoice;
+    let customer = &document.customer;
+    let mut problems = Vec::new();
+
+    let is_export = invoice.invoice_type == "EXPORT" || customer.customer_type == "EXPORT";
+    let with_igst = document.lines.iter().any(|line| line.item.igst_amount > Money::ZERO);
+
+    if invoice.status == "DRAFT" || invoice.status == "CANCELLED" {
+        problems.push(format!("{} invoices cannot be e-invoiced", invoice.status.to_lowercase()));
+    }
+    if !is_valid_document_number(&invoice.invoice_number) {
+        problems.push(
+            "Document number must be 1-16 letters, digits, '/' or '-' and not start with 0, '/' or '-'"
+                .to_string(),
+        );
+    }
+
+    // Seller
+    if let Err(err) = validation::validate_gstin_structure(&company.gstin) {
+        problems.push(format!("Seller GSTIN: {}", err));
+    }
+    check_length(&mut problems, "Seller legal name", &company.company_name, 3, 100);
+    check_length(&mut problems, "Seller address line 1", &company.address_line1, 1, 100);
+    check_length(&mut problems, "Seller location", &company.city, 3, 50);
+    if !validation::is_valid_pincode(&company.pincode) {
+        problems.push("Seller pincode must be 6 digits".to_string());
+    }
+
+    // Buyer; exports go to an unregistered person outside India
+    let (buyer_gstin, buyer_state, buyer_pin, pos) = if is_export {
+        let foreign = reports::FOREIGN_POS_CODE.to_string();
+        ("URP".to_string(), foreign.clone(), 999_999, foreign)
+    } else {
+        let gstin = customer.gstin.as_deref().unwrap_or_default().trim().to_uppercase();
+        match validation::validate_gstin_structure(&gstin) {
+            Ok(_) => {}
+            Err(validation::GstinError::Required) => {
+                problems.push("Buyer GSTIN is required for B2B e-invoices".to_string())
+            }
+            Err(err) => problems.push(format!("Buyer GSTIN: {}", err)),
+        }
+        if !validation::is_valid_pincode(&customer.pincode) {
+            problems.push("Buyer pincode must be 6 digits".to_string());
+        }
+        let pos = reports::pos_code(&invoice.place_of_supply, &invoice.invoice_type);
+        if pos == reports::FOREIGN_POS_CODE {
+            problems.push(format!("Place of supply {} is not an Indian state", invoice.place_of_supply));
+        }
+        let state = gstin.get(0..2).unwrap_or_default().to_string();
+        (gstin, state, customer.pincode.trim().parse().unwrap_or(0), pos)
+    };
+    check_length(&mut problems, "Buyer legal name", &customer.customer_name, 3, 100);
+    check_length(&mut problems, "Buyer address line 1", &customer.address_line1, 1, 100);
+    check_length(&mut problems, "Buyer location", &customer.city, 3, 50);
+
+    // Items
+    if document.lines.is_empty() {
+        problems.push("Invoice must have at least one item".to_string());
+    }
+    if document.lines.len() > MAX_ITEMS {
+        problems.push(format!("E-invoices may have at most {} items", MAX_ITEMS));
+    }
+    for line in &document.lines {
+        if !validation::is_valid_hsn_sac(&line.item.hsn_sac_code) {
+            problems.push(format!(
+                "Line {}: HSN/SAC {} must be 4, 6 or 8 digits",
+                line.item.line_number, line.item.hsn_sac_code
+            ));
+        }
+        if line.item.quantity <= 0.0 {
+            problems.push(format!("Line {}: quantity must be greater than 0", line.item.line_number));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(EInvoiceError::Invalid(problems));
+    }
+
+    let sup_typ = match (is_export, with_igst) {
+        (true, true) => "EXPWP",
+        (true, false) => "EXPWOP",
+        (false, _) => "B2B",
+    };
+    let typ = match invoice.invoice_type.as_str() {
+        "CREDIT_NOTE" => "CRN",
+        "DEBIT_NOTE" => "DBN",
+        _ => "INV",
+    };
+
+    Ok(EInvoice {
+        version: SCHEMA_VERSION.to_string(),
+        tran_dtls: TransactionDetails {
+            tax_sch: "GST".to_string(),
+            sup_typ: sup_typ.to_string(),
+            reg_rev: if invoice.reverse_charge { "Y" } else { "N" }.to_string(),
+            igst_on_intra: "N".to_string(),
+        },
+        doc_dtls: DocumentDetails {
+            typ: typ.to_string(),
+            no: invoice.invoice_number.clone(),
+            dt: nic_date(&invoice.invoice_date),
+        },
+        seller_dtls: PartyDetails {
+            gstin: company.gstin.trim().to_uppercase(),
+            lgl_nm: company.company_name.trim().to_string(),
+            pos: None,
+            addr1: company.address_line1.trim().to_string(),
+            addr2: optional(company.address_line2.as_deref()),
+            loc: company.city.trim().to_string(),
+            pin: company.pincode.trim().parse().unwrap_or(0),
+            stcd: company.gstin.trim().get(0..2).unwrap_or_default().to_string(),
+            ph: phone(company.phone.as_deref()),
+            em: optional(company.email.as_deref()),
+        },
+        buyer_dtls: PartyDetails {
+            gstin: buyer_gstin,
+            lgl_nm: customer.customer_name.trim().to_string(),
+            pos: Some(pos),
+            addr1: customer.address_line1.trim().to_string(),
+            addr2: optional(customer.address_line2.as_deref()),
+            loc: customer.city.trim().to_string(),
+            pin: buyer_pin,
+            stcd: buyer_state,
+            ph: phone(customer.phone.as_deref()),
+            em: optional(customer.email.as_deref()),
+        },
+        item_list: document.lines.iter().enumerate().map(|(i, line)| item_details(i, line)).collect(),
+        val_dtls: ValueDetails {
+            ass_val: invoice.taxable_amount,
+            cgst_val: invoice.cgst_amount,
+            sgst_val: invoice.sgst_amount,
+            igst_val: invoice.igst_amount,
+            ces_val: invoice.cess_amount,
+            discount: Money::ZERO,
+            oth_chrg: Money::ZERO,
+            rnd_off_amt: invoice.round_off,
+            tot_inv_val: invoice.final_amount,
+        },
+    })
+}
+
+/// Checks the IRP response before it is stored on the invoice
+pub fn validate_registration(registration: &EInvoiceRegistration) -> Result<(), EInvoiceError> {
+    let irn = registration.irn.trim();
+    if irn.len() != 64 || !irn.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(EInvoiceError::InvalidRegistration(
+            "IRN must be a 64-character hexadecimal hash".to_string(),
+        ));
+    }
+    if registration.ack_number.trim().is_empty() {
+        return Err(EInvoiceError::InvalidRegistration("Acknowledgement number is required".to_string()));
+    }
+    if registration.signed_qr_code.trim().is_empty() {
+        return Err(EInvoiceError::InvalidRegistration("Signed QR code is required".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reports::fixtures::{customer, document};
+    use crate::templates::sample_company;
+
+    #[test]
+    fn builds_inv01_payload() {
+        let buyer = customer(Some("29AAGCB7383J1Z4"), "B2B");
+        let company = CompanySettings { phone: Some("+91 98765 43210".to_string()), ..sample_company() };
+        let payload = build_e_invoice(&company, &document("INV/001", "REGULAR", "Karnataka", 1000.0, buyer)).unwrap();
+        let json = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(json["Version"], "1.1");
+        assert_eq!(json["TranDtls"]["SupTyp"], "B2B");
+        assert_eq!(json["DocDtls"]["Dt"], "10/09/2026");
+        assert_eq!(json["SellerDtls"]["Stcd"], "27");
+        assert_eq!(json["SellerDtls"]["Ph"], "9876543210");
+        assert_eq!(json["BuyerDtls"]["Pos"], "29");
+        assert_eq!(json["BuyerDtls"]["Pin"], 411001);
+        assert_eq!(json["ItemList"][0]["HsnCd"], "7214");
+        assert_eq!(json["ItemList"][0]["IgstAmt"], 180.0);
+        assert_eq!(json["ValDtls"]["TotInvVal"], 1180.0);
+        assert!(json["SellerDtls"].get("Pos").is_none());
+    }
+
+    #[test]
+    fn reports_every_missing_field() {
+        let mut document = document("0INV", "REGULAR", "Karnataka", 1000.0, customer(None, "B2C"));
+        document.lines[0].item.hsn_sac_code = "72".to_string();
+        document.customer.pincode = "4110".to_string();
+
+        match build_e_invoice(&sample_company(), &document) {
+            Err(EInvoiceError::Invalid(problems)) => {
+                assert_eq!(problems.len(), 4, "{:?}", problems);
+                assert!(problems.iter().any(|p| p.contains("Buyer GSTIN is required")));
+            }
+            other => panic!("expected validation errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn uses_unregistered_buyer_for_exports() {
+        let document = document("EXP/1", "EXPORT", "United States", 1000.0, customer(None, "EXPORT"));
+        let payload = build_e_invoice(&sample_company(), &document).unwrap();
+        assert_eq!(payload.tran_dtls.sup_typ, "EXPWP");
+        assert_eq!(payload.buyer_dtls.gstin, "URP");
+        assert_eq!(payload.buyer_dtls.pos.as_deref(), Some("96"));
+    }
+
+    #[test]
+    fn validates_irp_registration() {
+        let mut registration = EInvoiceRegistration {
+            irn: "a".repeat(64),
+            ack_number: "112010036563310".to_string(),
+            ack_date: "2026-09-10 12:00:00".to_string(),
+            signed_qr_code: "eyJhbGciOi".to_string(),
+        };
+        assert!(validate_registration(&registration).is_ok());
+        registration.irn = "xyz".to_string();
+        assert!(validate_registration(&registration).is_err());
+    }
+}
@@ -0,0 +1,622 @@
+// =====================================================
+// Payvlo GST Invoice Generator - E-way Bill
+// Bulk-upload JSON for goods moved under an invoice
+// =====================================================
+//
+// Only goods lines travel under an e-way bill: service lines are left out of the item
+// list and their value is reported as other charges. Part-B comes from the transport
+// details stored against the invoice.
+
+use crate::database::{CompanySettings, ReturnInvoice, ReturnLine, TransportDetails};
+use crate::einvoice;
+use crate::money::Money;
+use crate::reports;
+use crate::validation;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::fmt;
+
+pub const SCHEMA_VERSION: &str = "1.0.0621";
+
+/// Consignment value above which goods may not move without an e-way bill
+pub const THRESHOLD_RUPEES: f64 = 50_000.0;
+
+/// Longest distance the portal accepts, in kilometres
+pub const MAX_DISTANCE_KM: i32 = 4000;
+
+/// State code the portal uses for consignees outside India
+const OTHER_COUNTRY_STATE_CODE: u32 = 99;
+
+pub const TRANSPORT_MODES: [&str; 4] = ["ROAD", "RAIL", "AIR", "SHIP"];
+pub const VEHICLE_TYPES: [&str; 2] = ["REGULAR", "OVER_DIMENSIONAL"];
+
+// =====================================================
+// Payload Structure
+// =====================================================
+
+/// The file imported by the e-way bill portal's bulk generation tool
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EWayBillBulk {
+    pub version: String,
+    pub bill_lists: Vec<EWayBill>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EWayBill {
+    pub user_gstin: String,
+    pub supply_type: String,
+    pub sub_supply_type: u32,
+    pub sub_supply_desc: String,
+    pub doc_type: String,
+    pub doc_no: String,
+    pub doc_date: String,
+    pub from_gstin: String,
+    pub from_trd_name: String,
+    pub from_addr1: String,
+    pub from_addr2: String,
+    pub from_place: String,
+    pub from_pincode: u32,
+    pub from_state_code: u32,
+    pub act_from_state_code: u32,
+    pub to_gstin: String,
+    pub to_trd_name: String,
+    pub to_addr1: String,
+    pub to_addr2: String,
+    pub to_place: String,
+    pub to_pincode: u32,
+    pub to_state_code: u32,
+    pub act_to_state_code: u32,
+    pub transaction_type: u32,
+    pub total_value: Money,
+    pub cgst_value: Money,
+    pub sgst_value: Money,
+    pub igst_value: Money,
+    pub cess_value: Money,
+    pub cess_non_advol_value: Money,
+    pub other_value: Money,
+    pub tot_inv_value: Money,
+    pub trans_mode: String,
+    pub trans_distance: String,
+    pub transporter_name: String,
+    pub transporter_id: String,
+    pub trans_doc_no: String,
+    pub trans_doc_date: String,
+    pub vehicle_no: String,
+    pub vehicle_type: String,
+    pub main_hsn_code: u32,
+    pub item_list: Vec<EWayBillItem>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EWayBillItem {
+    pub item_no: u32,
+    pub product_name: String,
+    pub product_desc: String,
+    pub hsn_code: u32,
+    pub quantity: f64,
+    pub qty_unit: String,
+    pub taxable_amount: Money,
+    pub sgst_rate: f64,
+    pub cgst_rate: f64,
+    pub igst_rate: f64,
+    pub cess_rate: f64,
+    pub cess_non_advol: f64,
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum EWayBillError {
+    /// Every problem found, one message each
+    Invalid(Vec<String>),
+    InvoiceNotFound(i64),
+    CompanySettingsMissing,
+    Sqlite(rusqlite::Error),
+}
+
+impl EWayBillError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EWayBillError::Invalid(_) => "EWayBillValidationError",
+            EWayBillError::InvoiceNotFound(_) => "NotFound",
+            EWayBillError::CompanySettingsMissing => "CompanySettingsMissing",
+            EWayBillError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for EWayBillError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EWayBillError::Invalid(problems) => {
+                write!(f, "E-way bill cannot be generated: {}", problems.join("; "))
+            }
+            EWayBillError::InvoiceNotFound(id) => write!(f, "Invoice {} does not exist", id),
+            EWayBillError::CompanySettingsMissing => {
+                f.write_str("Company settings must be saved before generating e-way bills")
+            }
+            EWayBillError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for EWayBillError {}
+
+impl From<rusqlite::Error> for EWayBillError {
+    fn from(err: rusqlite::Error) -> Self {
+        EWayBillError::Sqlite(err)
+    }
+}
+
+// =====================================================
+// Transport Details
+// =====================================================
+
+fn optional(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|value| !value.is_empty()).map(str::to_string)
+}
+
+/// Registration numbers and IDs are stored upper case without spaces or hyphens
+fn compact(value: Option<&str>) -> Option<String> {
+    let value: String = value?
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Tidies user input before it is validated and stored
+pub fn normalize_transport(details: &TransportDetails) -> TransportDetails {
+    TransportDetails {
+        transporter_id: compact(details.transporter_id.as_deref()),
+        transporter_name: optional(details.transporter_name.as_deref()),
+        transport_mode: details.transport_mode.trim().to_uppercase(),
+        vehicle_number: compact(details.vehicle_number.as_deref()),
+        vehicle_type: details.vehicle_type.trim().to_uppercase(),
+        transport_doc_number: optional(details.transport_doc_number.as_deref()),
+        transport_doc_date: optional(details.transport_doc_date.as_deref()),
+        ..details.clone()
+    }
+}
+
+/// Regular (MH12AB1234), Bharat series (22BH1234AA) or temporary (TR...) registrations
+pub fn is_valid_vehicle_number(number: &str) -> bool {
+    let chars: Vec<char> = number.chars().collect();
+    if !chars.iter().all(char::is_ascii_alphanumeric) {
+        return false;
+    }
+    if number.starts_with("TR") {
+        return (8..=15).contains(&chars.len());
+    }
+    if chars.len() == 10 && number.get(2..4) == Some("BH") {
+        return chars[0..2].iter().all(char::is_ascii_digit)
+            && chars[4..8].iter().all(char::is_ascii_digit)
+            && chars[8..].iter().all(char::is_ascii_uppercase);
+    }
+
+    // Two-letter state, one or two digit district, up to three series letters, four digits
+    let state_len = chars.iter().take_while(|c| c.is_ascii_uppercase()).count();
+    let district_len = chars[state_len..].iter().take_while(|c| c.is_ascii_digit()).count();
+    let series_len = chars[state_len + district_len..]
+        .iter()
+        .take_while(|c| c.is_ascii_uppercase())
+        .count();
+    let number_len = chars.len() - state_len - district_len - series_len;
+    state_len == 2
+        && (1..=2).contains(&district_len)
+        && series_len <= 3
+        && number_len == 4
+        && chars[chars.len() - 4..].iter().all(char::is_ascii_digit)
+}
+
+/// GSTINs and TRANSINs are both 15 characters starting with a state code
+fn is_valid_transporter_id(id: &str) -> bool {
+    id.len() == 15
+        && id.chars().all(|c| c.is_ascii_alphanumeric())
+        && id[0..2].chars().all(|c| c.is_ascii_digit())
+}
+
+/// Problems with normalized transport details, one message each
+pub fn validate_transport(details: &TransportDetails) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if !TRANSPORT_MODES.contains(&details.transport_mode.as_str()) {
+        problems.push(format!("Transport mode must be one of {}", TRANSPORT_MODES.join(", ")));
+    }
+    if !VEHICLE_TYPES.contains(&details.vehicle_type.as_str()) {
+        problems.push(format!("Vehicle type must be one of {}", VEHICLE_TYPES.join(", ")));
+    }
+    if !(0..=MAX_DISTANCE_KM).contains(&details.distance_km) {
+        problems.push(format!("Distance must be 0 to {} km", MAX_DISTANCE_KM));
+    }
+    if let Some(id) = details.transporter_id.as_deref() {
+        if !is_valid_transporter_id(id) {
+            problems.push("Transporter ID must be a 15-character GSTIN or TRANSIN".to_string());
+        }
+    }
+    if details.transporter_name.as_deref().is_some_and(|name| name.chars().count() > 100) {
+        problems.push("Transporter name must be at most 100 characters".to_string());
+    }
+    if let Some(number) = details.vehicle_number.as_deref() {
+        if !is_valid_vehicle_number(number) {
+            problems.push(format!("Vehicle number {} is not a valid registration number", number));
+        }
+    }
+    if let Some(date) = details.transport_doc_date.as_deref() {
+        if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            problems.push("Transport document date must be YYYY-MM-DD".to_string());
+        }
+    }
+    if details.transport_doc_number.as_deref().is_some_and(|number| number.chars().count() > 15) {
+        problems.push("Transport document number must be at most 15 characters".to_string());
+    }
+
+    if details.transport_mode == "ROAD" {
+        if details.vehicle_number.is_none() && details.transporter_id.is_none() {
+            problems.push("Road transport needs a vehicle number or a transporter ID".to_string());
+        }
+    } else if details.transport_doc_number.is_none() || details.transport_doc_date.is_none() {
+        problems.push(format!(
+            "{} transport needs the transport document number and date",
+            details.transport_mode.to_lowercase()
+        ));
+    }
+
+    problems
+}
+
+// =====================================================
+// Payload Builder
+// =====================================================
+
+fn is_goods(line: &ReturnLine) -> bool {
+    line.product_type == "GOODS"
+}
+
+/// Goods value including tax, compared against the e-way bill threshold
+pub fn consignment_value(document: &ReturnInvoice) -> Money {
+    document
+        .lines
+        .iter()
+        .filter(|line| is_goods(line))
+        .map(|line| line.item.line_total)
+        .sum()
+}
+
+/// Whether goods on this invoice may not move without an e-way bill
+pub fn is_required(document: &ReturnInvoice) -> bool {
+    consignment_value(document) > Money::from_rupees(THRESHOLD_RUPEES)
+}
+
+fn state_number(code: &str) -> u32 {
+    code.parse().unwrap_or(0)
+}
+
+fn item(index: usize, line: &ReturnLine) -> EWayBillItem {
+    let item = &line.item;
+    EWayBillItem {
+        item_no: index as u32 + 1,
+        product_name: item.product_name.clone(),
+        product_desc: item.description.clone().unwrap_or_else(|| item.product_name.clone()),
+        hsn_code: item.hsn_sac_code.trim().parse().unwrap_or(0),
+        quantity: item.quantity,
+        qty_unit: reports::uqc(&line.unit_of_measurement).to_string(),
+        taxable_amount: item.taxable_amount,
+        sgst_rate: item.sgst_rate,
+        cgst_rate: item.cgst_rate,
+        igst_rate: item.igst_rate,
+        cess_rate: item.cess_rate,
+        cess_non_advol: 0.0,
+    }
+}
+
+/// Builds the e-way bill for one invoice. All validation problems are reported together.
+pub fn build_e_way_bill(
+    company: &CompanySettings,
+    document: &ReturnInvoice,
+    transport: Option<&TransportDetails>,
+) -> Result<EWayBill, EWayBillError> {
+    let invoice = &document.invoice;
+    let customer = &document.customer;
+    let mut problems = Vec::new();
+
+    let is_export = invoice.invoice_type == "EXPORT" || customer.customer_type == "EXPORT";
+    let goods: Vec<&ReturnLine> = document.lines.iter().filter(|line| is_goods(line)).collect();
+
+    if invoice.status == "DRAFT" || invoice.status == "CANCELLED" {
+        problems.push(format!("{} invoices cannot be moved under an e-way bill", invoice.status.to_lowercase()));
+    }
+    if invoice.invoice_type != "REGULAR" && invoice.invoice_type != "EXPORT" {
+        problems.push("Only regular and export invoices can be moved under an e-way bill".to_string());
+    }
+    if !einvoice::is_valid_document_number(&invoice.invoice_number) {
+        problems.push(
+            "Document number must be 1-16 letters, digits, '/' or '-' and not start with 0, '/' or '-'"
+                .to_string(),
+        );
+    }
+
+    // Part B
+    let transport = transport.map(normalize_transport);
+    match &transport {
+        Some(transport) => {
+            problems.extend(validate_transport(transport));
+            let doc_date = transport.transport_doc_date.as_deref().unwrap_or_default();
+            if !doc_date.is_empty() && doc_date < invoice.invoice_date.as_str() {
+                problems.push("Transport document date cannot be before the invoice date".to_string());
+            }
+        }
+        None => problems.push("Transport details are required".to_string()),
+    }
+
+    // Consignor; goods are dispatched from the company address
+    let from_state = match validation::validate_gstin_structure(&company.gstin) {
+        Ok(parts) => parts.state_code,
+        Err(err) => {
+            problems.push(format!("Seller GSTIN: {}", err));
+            String::new()
+        }
+    };
+    if !validation::is_valid_pincode(&company.pincode) {
+        problems.push("Seller pincode must be 6 digits".to_string());
+    } else if !from_state.is_empty() && !validation::pincode_matches_state(&company.pincode, &from_state) {
+        problems.push(format!("Seller pincode {} is not in state {}", company.pincode.trim(), from_state));
+    }
+
+    // Consignee; exports are billed to an unregistered person outside India
+    let (to_gstin, to_state, act_to_state, to_pincode) = if is_export {
+        // Goods leave through a port in the supplier's state
+        ("URP".to_string(), OTHER_COUNTRY_STATE_CODE, state_number(&from_state), 999_999)
+    } else {
+        let pos = reports::pos_code(&invoice.place_of_supply, &invoice.invoice_type);
+        if pos == reports::FOREIGN_POS_CODE {
+            problems.push(format!("Place of supply {} is not an Indian state", invoice.place_of_supply));
+        }
+        let gstin = customer.gstin.as_deref().unwrap_or_default().trim().to_uppercase();
+        let (to_gstin, to_state) = if reports::is_registered(Some(&gstin)) {
+            match validation::validate_gstin_structure(&gstin) {
+                Ok(parts) => (gstin, parts.state_code),
+                Err(err) => {
+                    problems.push(format!("Buyer GSTIN: {}", err));
+                    (gstin, pos.clone())
+                }
+            }
+        } else {
+            ("URP".to_string(), pos.clone())
+        };
+        if !validation::is_valid_pincode(&customer.pincode) {
+            problems.push("Buyer pincode must be 6 digits".to_string());
+        } else if !validation::pincode_matches_state(&customer.pincode, &to_state) {
+            problems.push(format!("Buyer pincode {} is not in state {}", customer.pincode.trim(), to_state));
+        }
+        let pincode = customer.pincode.trim().parse().unwrap_or(0);
+        (to_gstin, state_number(&to_state), state_number(&pos), pincode)
+    };
+
+    // Items
+    if goods.is_empty() {
+        problems.push("Invoice has no goods lines; services do not need an e-way bill".to_string());
+    }
+    for line in &goods {
+        let hsn = line.item.hsn_sac_code.trim();
+        if !validation::is_valid_hsn_sac(hsn) || hsn.starts_with("99") {
+            problems.push(format!(
+                "Line {}: HSN {} must be a 4, 6 or 8 digit goods code",
+                line.item.line_number, hsn
+            ));
+        }
+        if line.item.quantity <= 0.0 {
+            problems.push(format!("Line {}: quantity must be greater than 0", line.item.line_number));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(EWayBillError::Invalid(problems));
+    }
+    let transport = transport.expect("validated above");
+
+    let total_value: Money = goods.iter().map(|line| line.item.taxable_amount).sum();
+    let cgst_value: Money = goods.iter().map(|line| line.item.cgst_amount).sum();
+    let sgst_value: Money = goods.iter().map(|line| line.item.sgst_amount).sum();
+    let igst_value: Money = goods.iter().map(|line| line.item.igst_amount).sum();
+    let cess_value: Money = goods.iter().map(|line| line.item.cess_amount).sum();
+    // Services, round-off and anything else on the invoice that is not goods
+    let other_value = invoice.final_amount - (total_value + cgst_value + sgst_value + igst_value + cess_value);
+
+    let main_line = goods
+        .iter()
+        .max_by_key(|line| line.item.taxable_amount)
+        .expect("at least one goods line");
+    let trans_mode = match transport.transport_mode.as_str() {
+        "RAIL" => "2",
+        "AIR" => "3",
+        "SHIP" => "4",
+        _ => "1",
+    };
+
+    Ok(EWayBill {
+        user_gstin: company.gstin.trim().to_uppercase(),
+        supply_type: "O".to_string(),
+        sub_supply_type: if is_export { 3 } else { 1 },
+        sub_supply_desc: String::new(),
+        doc_type: "INV".to_string(),
+        doc_no: invoice.invoice_number.clone(),
+        doc_date: einvoice::nic_date(&invoice.invoice_date),
+        from_gstin: company.gstin.trim().to_uppercase(),
+        from_trd_name: company.company_name.trim().to_string(),
+        from_addr1: company.address_line1.trim().to_string(),
+        from_addr2: optional(company.address_line2.as_deref()).unwrap_or_default(),
+        from_place: company.city.trim().to_string(),
+        from_pincode: company.pincode.trim().parse().unwrap_or(0),
+        from_state_code: state_number(&from_state),
+        act_from_state_code: state_number(&from_state),
+        to_gstin,
+        to_trd_name: customer.customer_name.trim().to_string(),
+        to_addr1: customer.address_line1.trim().to_string(),
+        to_addr2: optional(customer.address_line2.as_deref()).unwrap_or_default(),
+        to_place: customer.city.trim().to_string(),
+        to_pincode,
+        to_state_code: to_state,
+        act_to_state_code: act_to_state,
+        transaction_type: 1,
+        total_value,
+        cgst_value,
+        sgst_value,
+        igst_value,
+        cess_value,
+        cess_non_advol_value: Money::ZERO,
+        other_value,
+        tot_inv_value: invoice.final_amount,
+        trans_mode: trans_mode.to_string(),
+        trans_distance: transport.distance_km.to_string(),
+        transporter_name: transport.transporter_name.clone().unwrap_or_default(),
+        transporter_id: transport.transporter_id.clone().unwrap_or_default(),
+        trans_doc_no: transport.transport_doc_number.clone().unwrap_or_default(),
+        trans_doc_date: transport.transport_doc_date.as_deref().map(einvoice::nic_date).unwrap_or_default(),
+        vehicle_no: transport.vehicle_number.clone().unwrap_or_default(),
+        vehicle_type: if transport.vehicle_type == "OVER_DIMENSIONAL" { "O" } else { "R" }.to_string(),
+        main_hsn_code: main_line.item.hsn_sac_code.trim().parse().unwrap_or(0),
+        item_list: goods.iter().enumerate().map(|(i, line)| item(i, line)).collect(),
+    })
+}
+
+/// Builds one bulk file for several invoices; problems are prefixed with the invoice number
+pub fn build_bulk(
+    company: &CompanySettings,
+    documents: &[(ReturnInvoice, Option<TransportDetails>)],
+) -> Result<EWayBillBulk, EWayBillError> {
+    let mut bills = Vec::new();
+    let mut problems = Vec::new();
+
+    for (document, transport) in documents {
+        match build_e_way_bill(company, document, transport.as_ref()) {
+            Ok(bill) => bills.push(bill),
+            Err(EWayBillError::Invalid(found)) => problems.extend(
+                found
+                    .into_iter()
+                    .map(|problem| format!("{}: {}", document.invoice.invoice_number, problem)),
+            ),
+            Err(err) => return Err(err),
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(EWayBillError::Invalid(problems));
+    }
+    Ok(EWayBillBulk {
+        version: SCHEMA_VERSION.to_string(),
+        bill_lists: bills,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reports::fixtures::{customer, document};
+    use crate::templates::sample_company;
+
+    fn road(vehicle: &str) -> TransportDetails {
+        TransportDetails {
+            id: None,
+            invoice_id: 1,
+            transporter_id: None,
+            transporter_name: Some("Fast Movers".to_string()),
+            transport_mode: "road".to_string(),
+            distance_km: 840,
+            vehicle_number: Some(vehicle.to_string()),
+            vehicle_type: "REGULAR".to_string(),
+            transport_doc_number: None,
+            transport_doc_date: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn karnataka_buyer() -> crate::database::Customer {
+        let mut buyer = customer(Some("29AAGCB7383J1Z4"), "B2B");
+        buyer.pincode = "560001".to_string();
+        buyer
+    }
+
+    #[test]
+    fn builds_bulk_upload_json() {
+        let mut document = document("INV/001", "REGULAR", "Karnataka", 60_000.0, karnataka_buyer());
+        let mut service = document.lines[0].clone();
+        service.product_type = "SERVICES".to_string();
+        service.item.line_number = 2;
+        service.item.hsn_sac_code = "998314".to_string();
+        document.lines.push(service);
+        document.invoice.final_amount = Money::from_rupees(141_600.0);
+
+        let bulk = build_bulk(&sample_company(), &[(document, Some(road("MH 12-AB 1234")))]).unwrap();
+        let json = serde_json::to_value(&bulk).unwrap();
+        let bill = &json["billLists"][0];
+
+        assert_eq!(json["version"], SCHEMA_VERSION);
+        assert_eq!(bill["docDate"], "10/09/2026");
+        assert_eq!(bill["fromStateCode"], 27);
+        assert_eq!(bill["toStateCode"], 29);
+        assert_eq!(bill["toPincode"], 560001);
+        assert_eq!(bill["transMode"], "1");
+        assert_eq!(bill["transDistance"], "840");
+        assert_eq!(bill["vehicleNo"], "MH12AB1234");
+        assert_eq!(bill["itemList"].as_array().unwrap().len(), 1);
+        assert_eq!(bill["itemList"][0]["hsnCode"], 7214);
+        assert_eq!(bill["totalValue"], 60000.0);
+        assert_eq!(bill["igstValue"], 10800.0);
+        assert_eq!(bill["otherValue"], 70800.0);
+        assert_eq!(bill["totInvValue"], 141600.0);
+    }
+
+    #[test]
+    fn reports_inconsistent_pincodes_and_missing_goods() {
+        let mut document = document("INV/002", "REGULAR", "Karnataka", 60_000.0, karnataka_buyer());
+        document.customer.pincode = "411001".to_string();
+        document.lines[0].product_type = "SERVICES".to_string();
+        let company = CompanySettings { pincode: "560001".to_string(), ..sample_company() };
+
+        match build_e_way_bill(&company, &document, Some(&road("MH12AB1234"))) {
+            Err(EWayBillError::Invalid(problems)) => {
+                assert_eq!(problems.len(), 3, "{:?}", problems);
+                assert!(problems.iter().any(|p| p.contains("Seller pincode 560001")));
+                assert!(problems.iter().any(|p| p.contains("Buyer pincode 411001")));
+                assert!(problems.iter().any(|p| p.contains("no goods lines")));
+            }
+            other => panic!("expected validation errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validates_transport_details() {
+        assert!(validate_transport(&normalize_transport(&road("mh12ab1234"))).is_empty());
+        assert!(!validate_transport(&normalize_transport(&road("MH1234"))).is_empty());
+
+        let mut rail = normalize_transport(&road(""));
+        rail.transport_mode = "RAIL".to_string();
+        assert_eq!(rail.vehicle_number, None);
+        assert_eq!(validate_transport(&rail).len(), 1);
+
+        for number in ["MH12AB1234", "DL1C1234", "22BH1234AA", "TR12345678"] {
+            assert!(is_valid_vehicle_number(number), "{}", number);
+        }
+        for number in ["MH12ABCD1234", "M12AB1234", "MH12AB12345", "22BH12345A"] {
+            assert!(!is_valid_vehicle_number(number), "{}", number);
+        }
+    }
+
+    #[test]
+    fn applies_threshold_to_goods_value() {
+        let below = document("INV/003", "REGULAR", "Karnataka", 40_000.0, karnataka_buyer());
+        assert_eq!(consignment_value(&below), Money::from_rupees(47_200.0));
+        assert!(!is_required(&below));
+
+        let above = document("INV/004", "REGULAR", "Karnataka", 45_000.0, karnataka_buyer());
+        assert!(is_required(&above));
+    }
+}
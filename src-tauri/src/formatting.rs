@@ -0,0 +1,488 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Formatting
+// Indian digit grouping and amounts in words
+// =====================================================
+//
+// Mirrors `formatIndianCurrency` and `amountToWords` in src/lib/gst/calculator.ts so
+// Rust output (PDFs, exports) reads the same as the screens. Words can also be written
+// in Hindi or Marathi. Hindi numbers up to 99 are irregular, as are Marathi ones, so
+// both languages spell them out from a table rather than composing tens and units.
+
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+
+const ONES: [&str; 20] = [
+    "", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Nine", "Ten",
+    "Eleven", "Twelve", "Thirteen", "Fourteen", "Fifteen", "Sixteen", "Seventeen",
+    "Eighteen", "Nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "Twenty", "Thirty", "Forty", "Fifty", "Sixty", "Seventy", "Eighty", "Ninety",
+];
+
+const HINDI: [&str; 100] = [
+    "", "एक", "दो", "तीन", "चार", "पाँच", "छह", "सात", "आठ", "नौ",
+    "दस", "ग्यारह", "बारह", "तेरह", "चौदह", "पंद्रह", "सोलह", "सत्रह", "अठारह", "उन्नीस",
+    "बीस", "इक्कीस", "बाईस", "तेईस", "चौबीस", "पच्चीस", "छब्बीस", "सत्ताईस", "अट्ठाईस", "उनतीस",
+    "तीस", "इकतीस", "बत्तीस", "तैंतीस", "चौंतीस", "पैंतीस", "छत्तीस", "सैंतीस", "अड़तीस", "उनतालीस",
+    "चालीस", "इकतालीस", "बयालीस", "तैंतालीस", "चवालीस", "पैंतालीस", "छियालीस", "सैंतालीस", "अड़तालीस", "उनचास",
+    "पचास", "इक्यावन", "बावन", "तिरपन", "चौवन", "पचपन", "छप्पन", "सत्तावन", "अट्ठावन", "उनसठ",
+    "साठ", "इकसठ", "बासठ", "तिरसठ", "चौंसठ", "पैंसठ", "छियासठ", "सड़सठ", "अड़सठ", "उनहत्तर",
+    "सत्तर", "इकहत्तर", "बहत्तर", "तिहत्तर", "चौहत्तर", "पचहत्तर", "छिहत्तर", "सतहत्तर", "अठहत्तर", "उन्यासी",
+    "अस्सी", "इक्यासी", "बयासी", "तिरासी", "चौरासी", "पचासी", "छियासी", "सत्तासी", "अट्ठासी", "नवासी",
+    "नब्बे", "इक्यानबे", "बानबे", "तिरानबे", "चौरानबे", "पंचानबे", "छियानबे", "सत्तानबे", "अट्ठानबे", "निन्यानबे",
+];
+
+const MARATHI: [&str; 100] = [
+    "", "एक", "दोन", "तीन", "चार", "पाच", "सहा", "सात", "आठ", "नऊ",
+    "दहा", "अकरा", "बारा", "तेरा", "चौदा", "पंधरा", "सोळा", "सतरा", "अठरा", "एकोणीस",
+    "वीस", "एकवीस", "बावीस", "तेवीस", "चोवीस", "पंचवीस", "सव्वीस", "सत्तावीस", "अठ्ठावीस", "एकोणतीस",
+    "तीस", "एकतीस", "बत्तीस", "तेहेतीस", "चौतीस", "पस्तीस", "छत्तीस", "सदतीस", "अडतीस", "एकोणचाळीस",
+    "चाळीस", "एक्केचाळीस", "बेचाळीस", "त्रेचाळीस", "चव्वेचाळीस", "पंचेचाळीस", "सेहेचाळीस", "सत्तेचाळीस", "अठ्ठेचाळीस", "एकोणपन्नास",
+    "पन्नास", "एक्कावन्न", "बावन्न", "त्रेपन्न", "चोपन्न", "पंचावन्न", "छप्पन्न", "सत्तावन्न", "अठ्ठावन्न", "एकोणसाठ",
+    "साठ", "एकसष्ट", "बासष्ट", "त्रेसष्ट", "चौसष्ट", "पासष्ट", "सहासष्ट", "सदुसष्ट", "अडुसष्ट", "एकोणसत्तर",
+    "सत्तर", "एकाहत्तर", "बाहत्तर", "त्र्याहत्तर", "चौऱ्याहत्तर", "पंच्याहत्तर", "शहात्तर", "सत्याहत्तर", "अठ्ठ्याहत्तर", "एकोणऐंशी",
+    "ऐंशी", "एक्याऐंशी", "ब्याऐंशी", "त्र्याऐंशी", "चौऱ्याऐंशी", "पंच्याऐंशी", "शहाऐंशी", "सत्त्याऐंशी", "अठ्ठ्याऐंशी", "एकोणनव्वद",
+    "नव्वद", "एक्याण्णव", "ब्याण्णव", "त्र्याण्णव", "चौऱ्याण्णव", "पंच्याण्णव", "शहाण्णव", "सत्त्याण्णव", "अठ्ठ्याण्णव", "नव्व्याण्णव",
+];
+
+/// Marathi joins the hundreds into one word ("दोनशे"); a bare hundred is "शंभर"
+const MARATHI_HUNDREDS: [&str; 10] = [
+    "", "एकशे", "दोनशे", "तीनशे", "चारशे", "पाचशे", "सहाशे", "सातशे", "आठशे", "नऊशे",
+];
+
+// =====================================================
+// Digit Grouping
+// =====================================================
+
+/// Groups whole rupees the Indian way: the last three digits, then pairs (1,20,00,000)
+pub fn group_indian(whole: u64) -> String {
+    let digits = whole.to_string();
+    if digits.len() <= 3 {
+        return digits;
+    }
+
+    let (head, last_three) = digits.split_at(digits.len() - 3);
+    let mut groups: Vec<&str> = Vec::new();
+    let mut end = head.len();
+    while end > 0 {
+        let start = end.saturating_sub(2);
+        groups.push(&head[start..end]);
+        end = start;
+    }
+    groups.reverse();
+    format!("{},{}", groups.join(","), last_three)
+}
+
+/// `1,20,000.50`; negative amounts carry a leading minus
+pub fn format_indian(amount: Money) -> String {
+    let sign = if amount < Money::ZERO { "-" } else { "" };
+    let paise = amount.paise().unsigned_abs();
+    format!("{}{}.{:02}", sign, group_indian(paise / 100), paise % 100)
+}
+
+/// `₹1,20,000.50`, with the sign ahead of the symbol as on the frontend
+pub fn format_indian_currency(amount: Money) -> String {
+    let formatted = format_indian(amount);
+    match formatted.strip_prefix('-') {
+        Some(positive) => format!("-₹{}", positive),
+        None => format!("₹{}", formatted),
+    }
+}
+
+// =====================================================
+// Amounts in Words
+// =====================================================
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    English,
+    Hindi,
+    Marathi,
+}
+
+/// Where the currency goes in the words
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WordsStyle {
+    /// "Rupees One Lakh Twenty Thousand and Fifty Paise Only", as printed on invoices
+    #[default]
+    Invoice,
+    /// "one lakh twenty thousand rupees and fifty paise only", as `amountToWords` writes it
+    Sentence,
+}
+
+/// The fixed words of a language
+struct Vocabulary {
+    zero: &'static str,
+    thousand: &'static str,
+    lakh: &'static str,
+    crore: &'static str,
+    rupee: &'static str,
+    rupees: &'static str,
+    paise: &'static str,
+    and: &'static str,
+    only: &'static str,
+    minus: &'static str,
+}
+
+impl Language {
+    fn vocabulary(self) -> Vocabulary {
+        match self {
+            Language::English => Vocabulary {
+                zero: "Zero",
+                thousand: "Thousand",
+                lakh: "Lakh",
+                crore: "Crore",
+                rupee: "Rupee",
+                rupees: "Rupees",
+                paise: "Paise",
+                and: "and",
+                only: "Only",
+                minus: "Minus",
+            },
+            Language::Hindi => Vocabulary {
+                zero: "शून्य",
+                thousand: "हज़ार",
+                lakh: "लाख",
+                crore: "करोड़",
+                rupee: "रुपया",
+                rupees: "रुपये",
+                paise: "पैसे",
+                and: "और",
+                only: "मात्र",
+                minus: "ऋण",
+            },
+            Language::Marathi => Vocabulary {
+                zero: "शून्य",
+                thousand: "हजार",
+                lakh: "लाख",
+                crore: "कोटी",
+                rupee: "रुपया",
+                rupees: "रुपये",
+                paise: "पैसे",
+                and: "आणि",
+                only: "फक्त",
+                minus: "उणे",
+            },
+        }
+    }
+
+    fn below_hundred(self, number: usize) -> String {
+        match self {
+            Language::English => match number {
+                0..=19 => ONES[number].to_string(),
+                _ if number % 10 == 0 => TENS[number / 10].to_string(),
+                _ => format!("{} {}", TENS[number / 10], ONES[number % 10]),
+            },
+            Language::Hindi => HINDI[number].to_string(),
+            Language::Marathi => MARATHI[number].to_string(),
+        }
+    }
+
+    fn hundreds(self, count: usize, rest: usize) -> String {
+        match self {
+            Language::English => format!("{} Hundred", ONES[count]),
+            Language::Hindi => format!("{} सौ", HINDI[count]),
+            Language::Marathi if count == 1 && rest == 0 => "शंभर".to_string(),
+            Language::Marathi => MARATHI_HUNDREDS[count].to_string(),
+        }
+    }
+}
+
+fn below_thousand(number: u64, language: Language) -> String {
+    let number = number as usize;
+    let (hundreds, rest) = (number / 100, number % 100);
+    match (hundreds, rest) {
+        (0, _) => language.below_hundred(rest),
+        (_, 0) => language.hundreds(hundreds, rest),
+        _ => format!("{} {}", language.hundreds(hundreds, rest), language.below_hundred(rest)),
+    }
+}
+
+/// A whole number in words with crore, lakh and thousand; counts above 99 crore are
+/// themselves written out ("One Thousand Crore")
+pub fn number_in_words(number: u64, language: Language) -> String {
+    let words = language.vocabulary();
+    if number == 0 {
+        return words.zero.to_string();
+    }
+
+    let crore = number / 10_000_000;
+    let lakh = number % 10_000_000 / 100_000;
+    let thousand = number % 100_000 / 1_000;
+    let rest = number % 1_000;
+
+    let mut parts = Vec::new();
+    if crore > 0 {
+        parts.push(format!("{} {}", number_in_words(crore, language), words.crore));
+    }
+    for (count, unit) in [(lakh, words.lakh), (thousand, words.thousand)] {
+        if count > 0 {
+            parts.push(format!("{} {}", below_thousand(count, language), unit));
+        }
+    }
+    if rest > 0 {
+        parts.push(below_thousand(rest, language));
+    }
+    parts.join(" ")
+}
+
+/// The amount in words in the given language and style
+pub fn amount_in_words_with(amount: Money, language: Language, style: WordsStyle) -> String {
+    let words = language.vocabulary();
+    let paise = amount.paise().unsigned_abs();
+    let (rupees, paise) = (paise / 100, paise % 100);
+
+    let mut parts = Vec::new();
+    if amount < Money::ZERO {
+        parts.push(words.minus.to_string());
+    }
+    let paise_words = format!("{} {}", number_in_words(paise, language), words.paise);
+    match style {
+        WordsStyle::Invoice => match (rupees, paise) {
+            (_, 0) => parts.push(format!("{} {}", words.rupees, number_in_words(rupees, language))),
+            (0, _) => parts.push(paise_words),
+            _ => parts.push(format!(
+                "{} {} {} {}",
+                words.rupees,
+                number_in_words(rupees, language),
+                words.and,
+                paise_words
+            )),
+        },
+        WordsStyle::Sentence => {
+            let unit = if rupees == 1 { words.rupee } else { words.rupees };
+            match (rupees, paise) {
+                (0, 0) => parts.push(format!("{} {}", words.zero, words.rupees)),
+                (_, 0) => parts.push(format!("{} {}", number_in_words(rupees, language), unit)),
+                (0, _) => parts.push(paise_words),
+                _ => parts.push(format!(
+                    "{} {} {} {}",
+                    number_in_words(rupees, language),
+                    unit,
+                    words.and,
+                    paise_words
+                )),
+            }
+        }
+    }
+    parts.push(words.only.to_string());
+
+    let sentence = parts.join(" ");
+    match style {
+        WordsStyle::Sentence => sentence.to_lowercase(),
+        WordsStyle::Invoice => sentence,
+    }
+}
+
+/// The amount as printed on invoices: "Rupees One Lakh Twenty Thousand and Fifty Paise Only"
+pub fn amount_in_words(amount: Money) -> String {
+    amount_in_words_with(amount, Language::English, WordsStyle::Invoice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rupees(amount: f64) -> Money {
+        Money::from_rupees(amount)
+    }
+
+    /// Output of `formatIndianCurrency(x)`, `formatIndianCurrency(x, false)` and
+    /// `amountToWords(x)` from calculator.ts, captured with node
+    const FRONTEND: &[(f64, &str, &str, &str)] = &[
+        (0.0, "₹0.00", "0.00", "zero rupees only"),
+        (0.01, "₹0.01", "0.01", "one paise only"),
+        (0.5, "₹0.50", "0.50", "fifty paise only"),
+        (0.75, "₹0.75", "0.75", "seventy five paise only"),
+        (1.0, "₹1.00", "1.00", "one rupee only"),
+        (1.01, "₹1.01", "1.01", "one rupee and one paise only"),
+        (2.0, "₹2.00", "2.00", "two rupees only"),
+        (9.0, "₹9.00", "9.00", "nine rupees only"),
+        (10.0, "₹10.00", "10.00", "ten rupees only"),
+        (11.0, "₹11.00", "11.00", "eleven rupees only"),
+        (15.0, "₹15.00", "15.00", "fifteen rupees only"),
+        (19.0, "₹19.00", "19.00", "nineteen rupees only"),
+        (20.0, "₹20.00", "20.00", "twenty rupees only"),
+        (21.0, "₹21.00", "21.00", "twenty one rupees only"),
+        (45.5, "₹45.50", "45.50", "forty five rupees and fifty paise only"),
+        (99.0, "₹99.00", "99.00", "ninety nine rupees only"),
+        (99.99, "₹99.99", "99.99", "ninety nine rupees and ninety nine paise only"),
+        (100.0, "₹100.00", "100.00", "one hundred rupees only"),
+        (101.0, "₹101.00", "101.00", "one hundred one rupees only"),
+        (110.0, "₹110.00", "110.00", "one hundred ten rupees only"),
+        (115.0, "₹115.00", "115.00", "one hundred fifteen rupees only"),
+        (120.0, "₹120.00", "120.00", "one hundred twenty rupees only"),
+        (999.0, "₹999.00", "999.00", "nine hundred ninety nine rupees only"),
+        (1_000.0, "₹1,000.00", "1,000.00", "one thousand rupees only"),
+        (1_001.0, "₹1,001.00", "1,001.00", "one thousand one rupees only"),
+        (1_010.0, "₹1,010.00", "1,010.00", "one thousand ten rupees only"),
+        (1_180.0, "₹1,180.00", "1,180.00", "one thousand one hundred eighty rupees only"),
+        (
+            1_234.56,
+            "₹1,234.56",
+            "1,234.56",
+            "one thousand two hundred thirty four rupees and fifty six paise only",
+        ),
+        (
+            9_999.99,
+            "₹9,999.99",
+            "9,999.99",
+            "nine thousand nine hundred ninety nine rupees and ninety nine paise only",
+        ),
+        (10_000.0, "₹10,000.00", "10,000.00", "ten thousand rupees only"),
+        (10_001.0, "₹10,001.00", "10,001.00", "ten thousand one rupees only"),
+        (12_345.0, "₹12,345.00", "12,345.00", "twelve thousand three hundred forty five rupees only"),
+        (
+            99_999.0,
+            "₹99,999.00",
+            "99,999.00",
+            "ninety nine thousand nine hundred ninety nine rupees only",
+        ),
+        (100_000.0, "₹1,00,000.00", "1,00,000.00", "one lakh rupees only"),
+        (100_001.0, "₹1,00,001.00", "1,00,001.00", "one lakh one rupees only"),
+        (
+            120_000.5,
+            "₹1,20,000.50",
+            "1,20,000.50",
+            "one lakh twenty thousand rupees and fifty paise only",
+        ),
+        (150_000.0, "₹1,50,000.00", "1,50,000.00", "one lakh fifty thousand rupees only"),
+        (
+            999_999.0,
+            "₹9,99,999.00",
+            "9,99,999.00",
+            "nine lakh ninety nine thousand nine hundred ninety nine rupees only",
+        ),
+        (1_000_000.0, "₹10,00,000.00", "10,00,000.00", "ten lakh rupees only"),
+        (
+            1_234_567.89,
+            "₹12,34,567.89",
+            "12,34,567.89",
+            "twelve lakh thirty four thousand five hundred sixty seven rupees and eighty nine paise only",
+        ),
+        (
+            9_999_999.0,
+            "₹99,99,999.00",
+            "99,99,999.00",
+            "ninety nine lakh ninety nine thousand nine hundred ninety nine rupees only",
+        ),
+        (10_000_000.0, "₹1,00,00,000.00", "1,00,00,000.00", "one crore rupees only"),
+        (10_000_001.0, "₹1,00,00,001.00", "1,00,00,001.00", "one crore one rupees only"),
+        (
+            12_345_678.9,
+            "₹1,23,45,678.90",
+            "1,23,45,678.90",
+            "one crore twenty three lakh forty five thousand six hundred seventy eight rupees and ninety paise only",
+        ),
+        (20_500_019.0, "₹2,05,00,019.00", "2,05,00,019.00", "two crore five lakh nineteen rupees only"),
+        (
+            99_999_999.99,
+            "₹9,99,99,999.99",
+            "9,99,99,999.99",
+            "nine crore ninety nine lakh ninety nine thousand nine hundred ninety nine rupees and ninety nine paise only",
+        ),
+        (100_000_000.0, "₹10,00,00,000.00", "10,00,00,000.00", "ten crore rupees only"),
+        (
+            123_456_789.12,
+            "₹12,34,56,789.12",
+            "12,34,56,789.12",
+            "twelve crore thirty four lakh fifty six thousand seven hundred eighty nine rupees and twelve paise only",
+        ),
+        (
+            999_999_999.0,
+            "₹99,99,99,999.00",
+            "99,99,99,999.00",
+            "ninety nine crore ninety nine lakh ninety nine thousand nine hundred ninety nine rupees only",
+        ),
+        (-1.0, "-₹1.00", "-1.00", "minus one rupee only"),
+        (-15.0, "-₹15.00", "-15.00", "minus fifteen rupees only"),
+        (-1_500.0, "-₹1,500.00", "-1,500.00", "minus one thousand five hundred rupees only"),
+        (-0.5, "-₹0.50", "-0.50", "minus fifty paise only"),
+        (
+            -120_000.5,
+            "-₹1,20,000.50",
+            "-1,20,000.50",
+            "minus one lakh twenty thousand rupees and fifty paise only",
+        ),
+    ];
+
+    #[test]
+    fn matches_the_frontend() {
+        for &(amount, currency, plain, words) in FRONTEND {
+            let amount = rupees(amount);
+            assert_eq!(format_indian_currency(amount), currency);
+            assert_eq!(format_indian(amount), plain);
+            assert_eq!(amount_in_words_with(amount, Language::English, WordsStyle::Sentence), words);
+        }
+    }
+
+    #[test]
+    fn groups_every_digit_count() {
+        let mut number = 0u64;
+        let expected = [
+            "0", "1", "12", "123", "1,234", "12,345", "1,23,456", "12,34,567", "1,23,45,678",
+            "12,34,56,789", "1,23,45,67,890",
+        ];
+        for (digits, grouped) in expected.iter().enumerate() {
+            if digits > 0 {
+                number = number * 10 + (digits as u64 % 10);
+            }
+            assert_eq!(group_indian(number), *grouped);
+        }
+        assert_eq!(group_indian(u64::MAX), "1,84,46,74,40,73,70,95,51,615");
+    }
+
+    #[test]
+    fn writes_amounts_in_words() {
+        assert_eq!(
+            amount_in_words(rupees(120_000.5)),
+            "Rupees One Lakh Twenty Thousand and Fifty Paise Only"
+        );
+        assert_eq!(amount_in_words(rupees(0.0)), "Rupees Zero Only");
+        assert_eq!(amount_in_words(rupees(0.75)), "Seventy Five Paise Only");
+        assert_eq!(amount_in_words(rupees(1.0)), "Rupees One Only");
+        assert_eq!(amount_in_words(rupees(1_180.0)), "Rupees One Thousand One Hundred Eighty Only");
+        assert_eq!(amount_in_words(rupees(-15.0)), "Minus Rupees Fifteen Only");
+        assert_eq!(number_in_words(10_000_000_000, Language::English), "One Thousand Crore");
+        assert_eq!(number_in_words(20_500_019, Language::English), "Two Crore Five Lakh Nineteen");
+    }
+
+    #[test]
+    fn writes_hindi_and_marathi() {
+        assert_eq!(
+            amount_in_words_with(rupees(120_000.5), Language::Hindi, WordsStyle::Invoice),
+            "रुपये एक लाख बीस हज़ार और पचास पैसे मात्र"
+        );
+        assert_eq!(
+            amount_in_words_with(rupees(1.0), Language::Hindi, WordsStyle::Sentence),
+            "एक रुपया मात्र"
+        );
+        assert_eq!(number_in_words(2_345, Language::Hindi), "दो हज़ार तीन सौ पैंतालीस");
+        assert_eq!(number_in_words(12_500_000, Language::Hindi), "एक करोड़ पच्चीस लाख");
+
+        assert_eq!(
+            amount_in_words_with(rupees(120_000.5), Language::Marathi, WordsStyle::Invoice),
+            "रुपये एक लाख वीस हजार आणि पन्नास पैसे फक्त"
+        );
+        assert_eq!(number_in_words(100, Language::Marathi), "शंभर");
+        assert_eq!(number_in_words(120, Language::Marathi), "एकशे वीस");
+        assert_eq!(number_in_words(999, Language::Marathi), "नऊशे नव्व्याण्णव");
+        assert_eq!(
+            amount_in_words_with(rupees(-0.5), Language::Marathi, WordsStyle::Sentence),
+            "उणे पन्नास पैसे फक्त"
+        );
+
+        // Every number below a hundred has its own word
+        for table in [&HINDI, &MARATHI] {
+            let mut words: Vec<&str> = table[1..].to_vec();
+            words.sort_unstable();
+            words.dedup();
+            assert_eq!(words.len(), 99);
+        }
+    }
+}
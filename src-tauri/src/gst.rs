@@ -0,0 +1,455 @@
+// =====================================================
+// Payvlo GST Invoice Generator - GST Tax Engine
+// Authoritative line and invoice tax computation
+// =====================================================
+
+use crate::database::{Invoice, InvoiceItem};
+use crate::money::Money;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// =====================================================
+// GST Rate Configuration
+// =====================================================
+
+/// GST rates are percentages from 0 to 100 with at most two decimals: the usual slabs,
+/// and special rates such as 0.25 on rough diamonds, 1.5 on cut diamonds or 3 on gold
+pub fn is_valid_gst_rate(rate: f64) -> bool {
+    (0.0..=100.0).contains(&rate) && ((rate * 100.0).round() - rate * 100.0).abs() < 1e-6
+}
+
+// State codes for GSTIN validation and IGST determination
+pub const STATE_CODES: [(&str, &str); 38] = [
+    ("01", "Jammu and Kashmir"),
+    ("02", "Himachal Pradesh"),
+    ("03", "Punjab"),
+    ("04", "Chandigarh"),
+    ("05", "Uttarakhand"),
+    ("06", "Haryana"),
+    ("07", "Delhi"),
+    ("08", "Rajasthan"),
+    ("09", "Uttar Pradesh"),
+    ("10", "Bihar"),
+    ("11", "Sikkim"),
+    ("12", "Arunachal Pradesh"),
+    ("13", "Nagaland"),
+    ("14", "Manipur"),
+    ("15", "Mizoram"),
+    ("16", "Tripura"),
+    ("17", "Meghalaya"),
+    ("18", "Assam"),
+    ("19", "West Bengal"),
+    ("20", "Jharkhand"),
+    ("21", "Odisha"),
+    ("22", "Chhattisgarh"),
+    ("23", "Madhya Pradesh"),
+    ("24", "Gujarat"),
+    ("25", "Daman and Diu"),
+    ("26", "Dadra and Nagar Haveli"),
+    ("27", "Maharashtra"),
+    ("28", "Andhra Pradesh"),
+    ("29", "Karnataka"),
+    ("30", "Goa"),
+    ("31", "Lakshadweep"),
+    ("32", "Kerala"),
+    ("33", "Tamil Nadu"),
+    ("34", "Puducherry"),
+    ("35", "Andaman and Nicobar Islands"),
+    ("36", "Telangana"),
+    ("37", "Andhra Pradesh (New)"),
+    ("38", "Ladakh"),
+];
+
+// =====================================================
+// Calculation Results
+// =====================================================
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GstCalculation {
+    pub taxable_amount: Money,
+    pub gst_rate: f64,
+    pub cgst_rate: f64,
+    pub sgst_rate: f64,
+    pub igst_rate: f64,
+    pub cess_rate: f64,
+    pub cgst_amount: Money,
+    pub sgst_amount: Money,
+    pub igst_amount: Money,
+    pub cess_amount: Money,
+    pub total_tax: Money,
+    pub total_amount: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LineItemCalculation {
+    pub quantity: f64,
+    pub unit_price: Money,
+    pub discount_percent: f64,
+    pub discount_amount: Money,
+    pub taxable_amount: Money,
+    pub gst_calculation: GstCalculation,
+    pub line_total: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InvoiceTotals {
+    pub subtotal: Money,
+    pub total_discount: Money,
+    pub taxable_amount: Money,
+    pub cgst_total: Money,
+    pub sgst_total: Money,
+    pub igst_total: Money,
+    pub cess_total: Money,
+    pub total_tax: Money,
+    pub total_amount: Money,
+    pub round_off: Money,
+    pub final_amount: Money,
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GstError {
+    InvalidGstRate(f64),
+    InvalidLineItem(String),
+    EmptyInvoice,
+    TotalsMismatch {
+        field: String,
+        expected: Money,
+        actual: Money,
+    },
+}
+
+impl fmt::Display for GstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GstError::InvalidGstRate(rate) => write!(
+                f,
+                "Invalid GST rate: {}. Must be from 0 to 100 with at most two decimals",
+                rate
+            ),
+            GstError::InvalidLineItem(message) => f.write_str(message),
+            GstError::EmptyInvoice => f.write_str("Invoice must have at least one line item"),
+            GstError::TotalsMismatch { field, expected, actual } => write!(
+                f,
+                "{} is {} but the computed value is {}",
+                field, actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GstError {}
+
+// =====================================================
+// Core GST Calculation Functions
+// =====================================================
+
+/// Resolves a state name or 2-digit state code to its GST state code
+pub fn state_code(state: &str) -> Option<&'static str> {
+    let state = state.trim();
+    STATE_CODES
+        .iter()
+        .find(|(code, name)| *code == state || name.eq_ignore_ascii_case(state))
+        .map(|(code, _)| *code)
+}
+
+/// Determines whether a supply is inter-state (IGST) or intra-state (CGST + SGST).
+/// Exports and places of supply outside India are always inter-state.
+pub fn is_inter_state_transaction(company_state: &str, place_of_supply: &str, invoice_type: &str) -> bool {
+    if invoice_type == "EXPORT" {
+        return true;
+    }
+
+    match (state_code(company_state), state_code(place_of_supply)) {
+        (Some(supplier), Some(supply)) => supplier != supply,
+        // Place of supply outside the state list is a foreign country
+        (Some(_), None) => true,
+        _ => !company_state.trim().eq_ignore_ascii_case(place_of_supply.trim()),
+    }
+}
+
+/// Calculates GST amounts for a given taxable amount and rate
+pub fn calculate_gst(
+    taxable_amount: Money,
+    gst_rate: f64,
+    cess_rate: f64,
+    is_inter_state: bool,
+) -> Result<GstCalculation, GstError> {
+    if !is_valid_gst_rate(gst_rate) {
+        return Err(GstError::InvalidGstRate(gst_rate));
+    }
+
+    let cess_amount = taxable_amount.percent(cess_rate);
+
+    let (cgst_amount, sgst_amount, igst_amount) = if is_inter_state {
+        (Money::ZERO, Money::ZERO, taxable_amount.percent(gst_rate))
+    } else {
+        // Each half is rounded on its own so CGST and SGST are always equal
+        let half = taxable_amount.percent(gst_rate / 2.0);
+        (half, half, Money::ZERO)
+    };
+
+    let total_tax = cgst_amount + sgst_amount + igst_amount + cess_amount;
+
+    Ok(GstCalculation {
+        taxable_amount,
+        gst_rate,
+        cgst_rate: if is_inter_state { 0.0 } else { gst_rate / 2.0 },
+        sgst_rate: if is_inter_state { 0.0 } else { gst_rate / 2.0 },
+        igst_rate: if is_inter_state { gst_rate } else { 0.0 },
+        cess_rate,
+        cgst_amount,
+        sgst_amount,
+        igst_amount,
+        cess_amount,
+        total_tax,
+        total_amount: taxable_amount + total_tax,
+    })
+}
+
+/// Calculates line item totals including discounts and GST
+pub fn calculate_line_item(
+    quantity: f64,
+    unit_price: Money,
+    discount_percent: f64,
+    gst_rate: f64,
+    cess_rate: f64,
+    is_inter_state: bool,
+) -> Result<LineItemCalculation, GstError> {
+    if quantity <= 0.0 {
+        return Err(GstError::InvalidLineItem("Quantity must be greater than 0".to_string()));
+    }
+    if unit_price < Money::ZERO {
+        return Err(GstError::InvalidLineItem("Unit price cannot be negative".to_string()));
+    }
+    if !(0.0..=100.0).contains(&discount_percent) {
+        return Err(GstError::InvalidLineItem(
+            "Discount percent must be between 0 and 100".to_string(),
+        ));
+    }
+
+    let gross_amount = unit_price.times(quantity);
+    let discount_amount = gross_amount.percent(discount_percent);
+    let taxable_amount = gross_amount - discount_amount;
+
+    let gst_calculation = calculate_gst(taxable_amount, gst_rate, cess_rate, is_inter_state)?;
+    let line_total = gst_calculation.total_amount;
+
+    Ok(LineItemCalculation {
+        quantity,
+        unit_price,
+        discount_percent,
+        discount_amount,
+        taxable_amount,
+        gst_calculation,
+        line_total,
+    })
+}
+
+/// Calculates invoice totals from calculated line items, rounding off to the nearest rupee
+pub fn calculate_invoice_totals(line_items: &[LineItemCalculation]) -> Result<InvoiceTotals, GstError> {
+    if line_items.is_empty() {
+        return Err(GstError::EmptyInvoice);
+    }
+
+    let sum = |f: &dyn Fn(&LineItemCalculation) -> Money| line_items.iter().map(f).sum::<Money>();
+
+    let subtotal = sum(&|item| item.unit_price.times(item.quantity));
+    let total_discount = sum(&|item| item.discount_amount);
+    let taxable_amount = sum(&|item| item.taxable_amount);
+    let cgst_total = sum(&|item| item.gst_calculation.cgst_amount);
+    let sgst_total = sum(&|item| item.gst_calculation.sgst_amount);
+    let igst_total = sum(&|item| item.gst_calculation.igst_amount);
+    let cess_total = sum(&|item| item.gst_calculation.cess_amount);
+
+    let total_tax = cgst_total + sgst_total + igst_total + cess_total;
+    let total_amount = taxable_amount + total_tax;
+    let final_amount = total_amount.round_to_rupee();
+
+    Ok(InvoiceTotals {
+        subtotal,
+        total_discount,
+        taxable_amount,
+        cgst_total,
+        sgst_total,
+        igst_total,
+        cess_total,
+        total_tax,
+        total_amount,
+        round_off: final_amount - total_amount,
+        final_amount,
+    })
+}
+
+// =====================================================
+// Invoice Recomputation
+// =====================================================
+
+fn check(field: &str, actual: Money, expected: Money) -> Result<(), GstError> {
+    if actual != expected {
+        return Err(GstError::TotalsMismatch {
+            field: field.to_string(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Recomputes every tax figure on an invoice from its quantities, prices and rates.
+/// Fails if any submitted amount differs from the computed one, otherwise returns
+/// the invoice and items carrying the authoritative figures.
+pub fn recompute_invoice(
+    company_state: &str,
+    invoice: &Invoice,
+    items: &[InvoiceItem],
+) -> Result<(Invoice, Vec<InvoiceItem>), GstError> {
+    let inter_state =
+        is_inter_state_transaction(company_state, &invoice.place_of_supply, &invoice.invoice_type);
+
+    let mut calculations = Vec::with_capacity(items.len());
+    let mut recomputed_items = Vec::with_capacity(items.len());
+
+    for item in items {
+        let line = calculate_line_item(
+            item.quantity,
+            item.unit_price,
+            item.discount_percent,
+            item.gst_rate,
+            item.cess_rate,
+            inter_state,
+        )?;
+        let gst = &line.gst_calculation;
+        let prefix = format!("Line {} ", item.line_number);
+
+        check(&format!("{}discount_amount", prefix), item.discount_amount, line.discount_amount)?;
+        check(&format!("{}taxable_amount", prefix), item.taxable_amount, line.taxable_amount)?;
+        check(&format!("{}cgst_amount", prefix), item.cgst_amount, gst.cgst_amount)?;
+        check(&format!("{}sgst_amount", prefix), item.sgst_amount, gst.sgst_amount)?;
+        check(&format!("{}igst_amount", prefix), item.igst_amount, gst.igst_amount)?;
+        check(&format!("{}cess_amount", prefix), item.cess_amount, gst.cess_amount)?;
+        check(&format!("{}total_tax", prefix), item.total_tax, gst.total_tax)?;
+        check(&format!("{}line_total", prefix), item.line_total, line.line_total)?;
+
+        recomputed_items.push(InvoiceItem {
+            discount_amount: line.discount_amount,
+            taxable_amount: line.taxable_amount,
+            cgst_rate: gst.cgst_rate,
+            sgst_rate: gst.sgst_rate,
+            igst_rate: gst.igst_rate,
+            cgst_amount: gst.cgst_amount,
+            sgst_amount: gst.sgst_amount,
+            igst_amount: gst.igst_amount,
+            cess_amount: gst.cess_amount,
+            total_tax: gst.total_tax,
+            line_total: line.line_total,
+            ..item.clone()
+        });
+        calculations.push(line);
+    }
+
+    let totals = calculate_invoice_totals(&calculations)?;
+
+    check("subtotal", invoice.subtotal, totals.subtotal)?;
+    check("total_discount", invoice.total_discount, totals.total_discount)?;
+    check("taxable_amount", invoice.taxable_amount, totals.taxable_amount)?;
+    check("cgst_amount", invoice.cgst_amount, totals.cgst_total)?;
+    check("sgst_amount", invoice.sgst_amount, totals.sgst_total)?;
+    check("igst_amount", invoice.igst_amount, totals.igst_total)?;
+    check("cess_amount", invoice.cess_amount, totals.cess_total)?;
+    check("total_tax", invoice.total_tax, totals.total_tax)?;
+    check("total_amount", invoice.total_amount, totals.total_amount)?;
+    check("round_off", invoice.round_off, totals.round_off)?;
+    check("final_amount", invoice.final_amount, totals.final_amount)?;
+
+    let recomputed_invoice = Invoice {
+        subtotal: totals.subtotal,
+        total_discount: totals.total_discount,
+        taxable_amount: totals.taxable_amount,
+        cgst_amount: totals.cgst_total,
+        sgst_amount: totals.sgst_total,
+        igst_amount: totals.igst_total,
+        cess_amount: totals.cess_total,
+        total_tax: totals.total_tax,
+        total_amount: totals.total_amount,
+        round_off: totals.round_off,
+        final_amount: totals.final_amount,
+        ..invoice.clone()
+    };
+
+    Ok((recomputed_invoice, recomputed_items))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rupees(amount: f64) -> Money {
+        Money::from_rupees(amount)
+    }
+
+    #[test]
+    fn splits_intra_state_tax_equally() {
+        let gst = calculate_gst(rupees(1000.0), 18.0, 0.0, false).unwrap();
+        assert_eq!(gst.cgst_amount, rupees(90.0));
+        assert_eq!(gst.sgst_amount, rupees(90.0));
+        assert_eq!(gst.igst_amount, Money::ZERO);
+        assert_eq!(gst.total_amount, rupees(1180.0));
+    }
+
+    #[test]
+    fn charges_igst_for_inter_state_supply() {
+        let gst = calculate_gst(rupees(1000.0), 12.0, 1.0, true).unwrap();
+        assert_eq!(gst.igst_amount, rupees(120.0));
+        assert_eq!(gst.cess_amount, rupees(10.0));
+        assert_eq!(gst.total_tax, rupees(130.0));
+    }
+
+    #[test]
+    fn rejects_unknown_gst_rate() {
+        assert_eq!(calculate_gst(rupees(100.0), 12.345, 0.0, false), Err(GstError::InvalidGstRate(12.345)));
+        assert_eq!(calculate_gst(rupees(100.0), 101.0, 0.0, false), Err(GstError::InvalidGstRate(101.0)));
+        assert_eq!(calculate_gst(rupees(100.0), -5.0, 0.0, false), Err(GstError::InvalidGstRate(-5.0)));
+    }
+
+    #[test]
+    fn accepts_special_rates() {
+        let gold = calculate_gst(rupees(100_000.0), 3.0, 0.0, false).unwrap();
+        assert_eq!((gold.cgst_amount, gold.sgst_amount), (rupees(1500.0), rupees(1500.0)));
+
+        let rough_diamonds = calculate_gst(rupees(10_001.0), 0.25, 0.0, false).unwrap();
+        assert_eq!(rough_diamonds.cgst_rate, 0.125);
+        assert_eq!(rough_diamonds.cgst_amount, rupees(12.50));
+
+        assert_eq!(calculate_gst(rupees(1000.0), 1.5, 0.0, true).unwrap().igst_amount, rupees(15.0));
+        assert_eq!(calculate_gst(rupees(1000.0), 40.0, 0.0, true).unwrap().igst_amount, rupees(400.0));
+    }
+
+    #[test]
+    fn determines_inter_state_from_place_of_supply() {
+        assert!(!is_inter_state_transaction("Maharashtra", "27", "REGULAR"));
+        assert!(!is_inter_state_transaction("maharashtra", "Maharashtra", "REGULAR"));
+        assert!(is_inter_state_transaction("Maharashtra", "Karnataka", "REGULAR"));
+        assert!(is_inter_state_transaction("Maharashtra", "United States", "REGULAR"));
+        assert!(is_inter_state_transaction("Maharashtra", "Maharashtra", "EXPORT"));
+    }
+
+    #[test]
+    fn rounds_off_invoice_to_nearest_rupee() {
+        let lines = vec![
+            calculate_line_item(3.0, rupees(33.33), 0.0, 18.0, 0.0, false).unwrap(),
+            calculate_line_item(1.0, rupees(250.0), 10.0, 5.0, 0.0, false).unwrap(),
+        ];
+        let totals = calculate_invoice_totals(&lines).unwrap();
+        assert_eq!(totals.subtotal, rupees(349.99));
+        assert_eq!(totals.total_discount, rupees(25.0));
+        assert_eq!(totals.taxable_amount, rupees(324.99));
+        assert_eq!(totals.total_tax, rupees(29.26));
+        assert_eq!(totals.total_amount, rupees(354.25));
+        assert_eq!(totals.round_off, rupees(-0.25));
+        assert_eq!(totals.final_amount, rupees(354.0));
+    }
+}
@@ -0,0 +1,714 @@
+// =====================================================
+// Payvlo GST Invoice Generator - GSTR-1 Export
+// Outward supply return in the GSTN offline tool JSON format
+// =====================================================
+//
+// Documents are sorted into the return sections as follows:
+//   b2b    invoices to registered recipients
+//   b2cl   inter-state invoices to unregistered recipients above the B2CL threshold
+//   b2cs   all other invoices to unregistered recipients, summarised by place and rate
+//   exp    export invoices, with or without payment of IGST
+//   cdnr   credit/debit notes to registered recipients, quoting the invoice amended
+//   cdnur  credit/debit notes on B2CL and export invoices; notes on B2CS supplies
+//          adjust the b2cs summary instead
+// The HSN summary is split into B2B and B2C tables and the document summary counts
+// every issued number, cancelled ones included.
+
+use crate::database::{ReturnInvoice, ReturnLine};
+use crate::gst;
+use crate::money::Money;
+use crate::reports::{self, ReturnPeriod};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// =====================================================
+// Return Structure
+// =====================================================
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Gstr1Return {
+    pub gstin: String,
+    pub fp: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub b2b: Vec<B2bRecipient>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub b2cl: Vec<B2clPlace>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub b2cs: Vec<B2csSupply>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cdnr: Vec<CdnrRecipient>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cdnur: Vec<CdnurNote>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exp: Vec<ExportGroup>,
+    pub hsn: HsnSummary,
+    pub doc_issue: DocumentSummary,
+}
+
+/// Tax on one rate within a document
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ItemDetail {
+    pub txval: Money,
+    pub rt: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iamt: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camt: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub samt: Option<Money>,
+    pub csamt: Money,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Item {
+    pub num: u32,
+    pub itm_det: ItemDetail,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct B2bRecipient {
+    pub ctin: String,
+    pub inv: Vec<B2bInvoice>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct B2bInvoice {
+    pub inum: String,
+    pub idt: String,
+    pub val: Money,
+    pub pos: String,
+    pub rchrg: String,
+    pub inv_typ: String,
+    pub itms: Vec<Item>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct B2clPlace {
+    pub pos: String,
+    pub inv: Vec<B2clInvoice>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct B2clInvoice {
+    pub inum: String,
+    pub idt: String,
+    pub val: Money,
+    pub itms: Vec<Item>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct B2csSupply {
+    pub sply_ty: String,
+    pub pos: String,
+    pub typ: String,
+    pub rt: f64,
+    pub txval: Money,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iamt: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camt: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub samt: Option<Money>,
+    pub csamt: Money,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CdnrRecipient {
+    pub ctin: String,
+    pub nt: Vec<CdnrNote>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CdnrNote {
+    pub ntty: String,
+    pub nt_num: String,
+    pub nt_dt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idt: Option<String>,
+    pub val: Money,
+    pub pos: String,
+    pub rchrg: String,
+    pub inv_typ: String,
+    pub itms: Vec<Item>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CdnurNote {
+    pub typ: String,
+    pub ntty: String,
+    pub nt_num: String,
+    pub nt_dt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idt: Option<String>,
+    pub val: Money,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pos: Option<String>,
+    pub itms: Vec<Item>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ExportGroup {
+    pub exp_typ: String,
+    pub inv: Vec<ExportInvoice>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ExportInvoice {
+    pub inum: String,
+    pub idt: String,
+    pub val: Money,
+    pub itms: Vec<ExportItem>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ExportItem {
+    pub txval: Money,
+    pub rt: f64,
+    pub iamt: Money,
+    pub csamt: Money,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct HsnSummary {
+    pub hsn_b2b: Vec<HsnEntry>,
+    pub hsn_b2c: Vec<HsnEntry>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct HsnEntry {
+    pub num: u32,
+    pub hsn_sc: String,
+    pub desc: String,
+    pub uqc: String,
+    pub qty: f64,
+    pub rt: f64,
+    pub txval: Money,
+    pub iamt: Money,
+    pub camt: Money,
+    pub samt: Money,
+    pub csamt: Money,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DocumentSummary {
+    pub doc_det: Vec<DocumentType>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DocumentType {
+    pub doc_num: u32,
+    pub doc_typ: String,
+    pub docs: Vec<DocumentRange>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DocumentRange {
+    pub num: u32,
+    pub from: String,
+    pub to: String,
+    pub totnum: u32,
+    pub cancel: u32,
+    pub net_issue: u32,
+}
+
+// =====================================================
+// Aggregation Helpers
+// =====================================================
+
+#[derive(Debug, Default, Clone, Copy)]
+struct TaxTotals {
+    txval: Money,
+    iamt: Money,
+    camt: Money,
+    samt: Money,
+    csamt: Money,
+}
+
+impl TaxTotals {
+    fn add(&mut self, line: &ReturnLine, sign: i64) {
+        let signed = |amount: Money| if sign < 0 { -amount } else { amount };
+        self.txval += signed(line.item.taxable_amount);
+        self.iamt += signed(line.item.igst_amount);
+        self.camt += signed(line.item.cgst_amount);
+        self.samt += signed(line.item.sgst_amount);
+        self.csamt += signed(line.item.cess_amount);
+    }
+}
+
+/// Rates are keyed in hundredths of a percent so they can be ordered and compared exactly
+fn rate_key(rate: f64) -> i64 {
+    (rate * 100.0).round() as i64
+}
+
+fn rate_of(key: i64) -> f64 {
+    key as f64 / 100.0
+}
+
+/// Line totals of a document grouped by GST rate
+fn totals_by_rate(lines: &[ReturnLine]) -> BTreeMap<i64, TaxTotals> {
+    let mut totals: BTreeMap<i64, TaxTotals> = BTreeMap::new();
+    for line in lines {
+        totals.entry(rate_key(line.item.gst_rate)).or_default().add(line, 1);
+    }
+    totals
+}
+
+fn items(lines: &[ReturnLine], inter_state: bool) -> Vec<Item> {
+    totals_by_rate(lines)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (rate, totals))| Item {
+            num: index as u32 + 1,
+            itm_det: ItemDetail {
+                txval: totals.txval,
+                rt: rate_of(rate),
+                iamt: inter_state.then_some(totals.iamt),
+                camt: (!inter_state).then_some(totals.camt),
+                samt: (!inter_state).then_some(totals.samt),
+                csamt: totals.csamt,
+            },
+        })
+        .collect()
+}
+
+fn yes_no(flag: bool) -> String {
+    if flag { "Y" } else { "N" }.to_string()
+}
+
+fn note_type(invoice_type: &str) -> String {
+    if invoice_type == "CREDIT_NOTE" { "C" } else { "D" }.to_string()
+}
+
+/// Number and date of the invoice a note amends; the earliest when it amends several
+fn original_invoice(document: &ReturnInvoice) -> (Option<String>, Option<String>) {
+    document
+        .references
+        .first()
+        .map(|original| (original.invoice_number.clone(), reports::gstn_date(&original.invoice_date)))
+        .unzip()
+}
+
+// =====================================================
+// Return Builder
+// =====================================================
+
+/// Builds the GSTR-1 return for `period` from the documents dated within it
+pub fn build_gstr1(
+    company_gstin: &str,
+    company_state: &str,
+    period: &ReturnPeriod,
+    documents: &[ReturnInvoice],
+) -> Gstr1Return {
+    let mut b2b: BTreeMap<String, Vec<B2bInvoice>> = BTreeMap::new();
+    let mut b2cl: BTreeMap<String, Vec<B2clInvoice>> = BTreeMap::new();
+    let mut b2cs: BTreeMap<(bool, String, i64), TaxTotals> = BTreeMap::new();
+    let mut cdnr: BTreeMap<String, Vec<CdnrNote>> = BTreeMap::new();
+    let mut cdnur = Vec::new();
+    let mut exp: BTreeMap<&'static str, Vec<ExportInvoice>> = BTreeMap::new();
+    let mut hsn_b2b: BTreeMap<(String, String, i64), HsnAccumulator> = BTreeMap::new();
+    let mut hsn_b2c: BTreeMap<(String, String, i64), HsnAccumulator> = BTreeMap::new();
+
+    for document in documents.iter().filter(|d| d.invoice.status != "CANCELLED") {
+        let invoice = &document.invoice;
+        let customer = &document.customer;
+        let kind = invoice.invoice_type.as_str();
+        let is_note = kind == "CREDIT_NOTE" || kind == "DEBIT_NOTE";
+        let registered = kind != "EXPORT" && reports::is_registered(customer.gstin.as_deref());
+        let pos = reports::pos_code(&invoice.place_of_supply, kind);
+        let is_export =
+            kind == "EXPORT" || customer.customer_type == "EXPORT" || pos == reports::FOREIGN_POS_CODE;
+        let inter_state =
+            is_export || gst::is_inter_state_transaction(company_state, &invoice.place_of_supply, kind);
+        // A note is B2CL when the invoice it amends was, however small the note itself
+        let large = match document.references.first() {
+            Some(original) if is_note => {
+                gst::is_inter_state_transaction(company_state, &original.invoice_place_of_supply, kind)
+                    && original.invoice_value > reports::b2cl_threshold(&original.invoice_date)
+            }
+            _ => inter_state && invoice.final_amount > reports::b2cl_threshold(&invoice.invoice_date),
+        };
+        let ctin = customer.gstin.as_deref().unwrap_or_default().trim().to_uppercase();
+        let with_igst = document.lines.iter().any(|line| line.item.igst_amount > Money::ZERO);
+        let (inum, idt) = original_invoice(document);
+
+        let sign = if kind == "CREDIT_NOTE" { -1 } else { 1 };
+        let hsn = if registered { &mut hsn_b2b } else { &mut hsn_b2c };
+        for line in &document.lines {
+            let key = (
+                line.item.hsn_sac_code.trim().to_string(),
+                reports::uqc(&line.unit_of_measurement).to_string(),
+                rate_key(line.item.gst_rate),
+            );
+            hsn.entry(key)
+                .or_default()
+                .add(line, sign);
+        }
+
+        if registered && is_note {
+            cdnr.entry(ctin).or_default().push(CdnrNote {
+                ntty: note_type(kind),
+                nt_num: invoice.invoice_number.clone(),
+                nt_dt: reports::gstn_date(&invoice.invoice_date),
+                inum,
+                idt,
+                val: invoice.final_amount,
+                pos,
+                rchrg: yes_no(invoice.reverse_charge),
+                inv_typ: "R".to_string(),
+                itms: items(&document.lines, inter_state),
+            });
+        } else if registered {
+            b2b.entry(ctin).or_default().push(B2bInvoice {
+                inum: invoice.invoice_number.clone(),
+                idt: reports::gstn_date(&invoice.invoice_date),
+                val: invoice.final_amount,
+                pos,
+                rchrg: yes_no(invoice.reverse_charge),
+                inv_typ: "R".to_string(),
+                itms: items(&document.lines, inter_state),
+            });
+        } else if is_export && is_note {
+            cdnur.push(CdnurNote {
+                typ: if with_igst { "EXPWP" } else { "EXPWOP" }.to_string(),
+                ntty: note_type(kind),
+                nt_num: invoice.invoice_number.clone(),
+                nt_dt: reports::gstn_date(&invoice.invoice_date),
+                inum,
+                idt,
+                val: invoice.final_amount,
+                pos: None,
+                itms: items(&document.lines, true),
+            });
+        } else if is_export {
+            let exp_typ = if with_igst { "WPAY" } else { "WOPAY" };
+            exp.entry(exp_typ).or_default().push(ExportInvoice {
+                inum: invoice.invoice_number.clone(),
+                idt: reports::gstn_date(&invoice.invoice_date),
+                val: invoice.final_amount,
+                itms: totals_by_rate(&document.lines)
+                    .into_iter()
+                    .map(|(rate, totals)| ExportItem {
+                        txval: totals.txval,
+                        rt: rate_of(rate),
+                        iamt: totals.iamt,
+                        csamt: totals.csamt,
+                    })
+                    .collect(),
+            });
+        } else if large && is_note {
+            cdnur.push(CdnurNote {
+                typ: "B2CL".to_string(),
+                ntty: note_type(kind),
+                nt_num: invoice.invoice_number.clone(),
+                nt_dt: reports::gstn_date(&invoice.invoice_date),
+                inum,
+                idt,
+                val: invoice.final_amount,
+                pos: Some(pos),
+                itms: items(&document.lines, true),
+            });
+        } else if large {
+            b2cl.entry(pos).or_default().push(B2clInvoice {
+                inum: invoice.invoice_number.clone(),
+                idt: reports::gstn_date(&invoice.invoice_date),
+                val: invoice.final_amount,
+                itms: items(&document.lines, true),
+            });
+        } else {
+            // Notes on small B2C supplies are netted into the B2CS summary
+            for line in &document.lines {
+                b2cs.entry((inter_state, pos.clone(), rate_key(line.item.gst_rate)))
+                    .or_default()
+                    .add(line, sign);
+            }
+        }
+    }
+
+    Gstr1Return {
+        gstin: company_gstin.trim().to_uppercase(),
+        fp: period.gstn(),
+        b2b: b2b.into_iter().map(|(ctin, inv)| B2bRecipient { ctin, inv }).collect(),
+        b2cl: b2cl.into_iter().map(|(pos, inv)| B2clPlace { pos, inv }).collect(),
+        b2cs: b2cs
+            .into_iter()
+            .map(|((inter_state, pos, rate), totals)| B2csSupply {
+                sply_ty: if inter_state { "INTER" } else { "INTRA" }.to_string(),
+                pos,
+                typ: "OE".to_string(),
+                rt: rate_of(rate),
+                txval: totals.txval,
+                iamt: inter_state.then_some(totals.iamt),
+                camt: (!inter_state).then_some(totals.camt),
+                samt: (!inter_state).then_some(totals.samt),
+                csamt: totals.csamt,
+            })
+            .collect(),
+        cdnr: cdnr.into_iter().map(|(ctin, nt)| CdnrRecipient { ctin, nt }).collect(),
+        cdnur,
+        exp: exp
+            .into_iter()
+            .map(|(exp_typ, inv)| ExportGroup { exp_typ: exp_typ.to_string(), inv })
+            .collect(),
+        hsn: HsnSummary {
+            hsn_b2b: hsn_entries(hsn_b2b),
+            hsn_b2c: hsn_entries(hsn_b2c),
+        },
+        doc_issue: document_summary(documents),
+    }
+}
+
+#[derive(Debug, Default)]
+struct HsnAccumulator {
+    desc: String,
+    qty: f64,
+    totals: TaxTotals,
+}
+
+impl HsnAccumulator {
+    fn add(&mut self, line: &ReturnLine, sign: i64) {
+        if self.desc.is_empty() {
+            self.desc = line.item.product_name.clone();
+        }
+        self.qty += sign as f64 * line.item.quantity;
+        self.totals.add(line, sign);
+    }
+}
+
+fn hsn_entries(summary: BTreeMap<(String, String, i64), HsnAccumulator>) -> Vec<HsnEntry> {
+    summary
+        .into_iter()
+        .enumerate()
+        .map(|(index, ((hsn_sc, uqc, rate), entry))| HsnEntry {
+            num: index as u32 + 1,
+            hsn_sc,
+            desc: entry.desc,
+            uqc,
+            qty: (entry.qty * 1_000.0).round() / 1_000.0,
+            rt: rate_of(rate),
+            txval: entry.totals.txval,
+            iamt: entry.totals.iamt,
+            camt: entry.totals.camt,
+            samt: entry.totals.samt,
+            csamt: entry.totals.csamt,
+        })
+        .collect()
+}
+
+/// Table 13: number ranges issued per document type, cancelled documents included. Each
+/// series (the number up to its closing run of digits) is reported as its own range.
+fn document_summary(documents: &[ReturnInvoice]) -> DocumentSummary {
+    let kinds = [
+        (1, "Invoices for outward supply", &["REGULAR", "EXPORT"][..]),
+        (4, "Debit Note", &["DEBIT_NOTE"][..]),
+        (5, "Credit Note", &["CREDIT_NOTE"][..]),
+    ];
+
+    let doc_det = kinds
+        .iter()
+        .filter_map(|(doc_num, doc_typ, types)| {
+            let mut series: BTreeMap<&str, Vec<(u64, &str, bool)>> = BTreeMap::new();
+            for document in documents.iter().filter(|d| types.contains(&d.invoice.invoice_type.as_str())) {
+                let number = document.invoice.invoice_number.as_str();
+                let (prefix, counter) = split_counter(number);
+                series
+                    .entry(prefix)
+                    .or_default()
+                    .push((counter, number, document.invoice.status == "CANCELLED"));
+            }
+            if series.is_empty() {
+                return None;
+            }
+
+            let docs = series
+                .into_values()
+                .enumerate()
+                .map(|(index, mut numbers)| {
+                    numbers.sort();
+                    let totnum = numbers.len() as u32;
+                    let cancel = numbers.iter().filter(|(_, _, cancelled)| *cancelled).count() as u32;
+                    DocumentRange {
+                        num: index as u32 + 1,
+                        from: numbers[0].1.to_string(),
+                        to: numbers[numbers.len() - 1].1.to_string(),
+                        totnum,
+                        cancel,
+                        net_issue: totnum - cancel,
+                    }
+                })
+                .collect();
+            Some(DocumentType { doc_num: *doc_num, doc_typ: doc_typ.to_string(), docs })
+        })
+        .collect();
+
+    DocumentSummary { doc_det }
+}
+
+/// Splits a document number into its series prefix and the counter it ends with, so
+/// `INV-2026-09-0010` sorts after `INV-2026-09-0009` and apart from `CN-2026-09-0001`
+fn split_counter(number: &str) -> (&str, u64) {
+    let prefix = number.trim_end_matches(|c: char| c.is_ascii_digit());
+    (prefix, number[prefix.len()..].parse().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::NoteReference;
+    use crate::reports::fixtures::{customer, document};
+
+    fn rupees(amount: f64) -> Money {
+        Money::from_rupees(amount)
+    }
+
+    fn build(documents: &[ReturnInvoice]) -> Gstr1Return {
+        let period = ReturnPeriod::parse("092026").unwrap();
+        build_gstr1("27AAPFU0939F1ZV", "Maharashtra", &period, documents)
+    }
+
+    #[test]
+    fn sorts_documents_into_sections() {
+        let registered = customer(Some("29AAGCB7383J1Z4"), "B2B");
+        let unregistered = customer(None, "B2C");
+        let overseas = customer(None, "EXPORT");
+        let mut credit_note = document("CN-1", "CREDIT_NOTE", "Karnataka", 100.0, registered.clone());
+        credit_note.references.push(NoteReference {
+            note_id: 6,
+            note_number: "CN-1".to_string(),
+            note_type: "CREDIT_NOTE".to_string(),
+            note_date: "2026-09-10".to_string(),
+            note_status: "SENT".to_string(),
+            invoice_id: 1,
+            invoice_number: "INV-1".to_string(),
+            invoice_date: "2026-08-28".to_string(),
+            invoice_value: rupees(1180.0),
+            invoice_place_of_supply: "Karnataka".to_string(),
+            amount: rupees(118.0),
+        });
+        let documents = vec![
+            document("INV-1", "REGULAR", "Karnataka", 1000.0, registered.clone()),
+            document("INV-2", "REGULAR", "Karnataka", 200_000.0, unregistered.clone()),
+            document("INV-3", "REGULAR", "Maharashtra", 500.0, unregistered.clone()),
+            document("INV-4", "EXPORT", "United States", 5000.0, overseas),
+            credit_note,
+            document("CN-2", "CREDIT_NOTE", "Maharashtra", 100.0, unregistered),
+        ];
+        let gstr1 = build(&documents);
+
+        assert_eq!(gstr1.fp, "092026");
+        assert_eq!(gstr1.b2b[0].ctin, "29AAGCB7383J1Z4");
+        assert_eq!(gstr1.b2b[0].inv[0].idt, "10-09-2026");
+        assert_eq!(gstr1.b2b[0].inv[0].itms[0].itm_det.iamt, Some(rupees(180.0)));
+        assert_eq!(gstr1.b2cl[0].pos, "29");
+        assert_eq!(gstr1.b2cl[0].inv[0].inum, "INV-2");
+        assert_eq!(gstr1.exp[0].exp_typ, "WPAY");
+        assert_eq!(gstr1.cdnr[0].nt[0].ntty, "C");
+        assert_eq!(gstr1.cdnr[0].nt[0].inum.as_deref(), Some("INV-1"));
+        assert_eq!(gstr1.cdnr[0].nt[0].idt.as_deref(), Some("28-08-2026"));
+        assert!(gstr1.cdnur.is_empty());
+
+        // The small credit note is netted into the intra-state B2CS row
+        assert_eq!(gstr1.b2cs.len(), 1);
+        assert_eq!(gstr1.b2cs[0].sply_ty, "INTRA");
+        assert_eq!(gstr1.b2cs[0].txval, rupees(400.0));
+        assert_eq!(gstr1.b2cs[0].camt, Some(rupees(36.0)));
+    }
+
+    #[test]
+    fn classifies_notes_by_the_invoice_they_amend() {
+        let unregistered = customer(None, "B2C");
+        let large = document("INV-2", "REGULAR", "Karnataka", 200_000.0, unregistered.clone());
+        let mut credit_note = document("CN-1", "CREDIT_NOTE", "Karnataka", 1000.0, unregistered);
+        credit_note.references.push(NoteReference {
+            note_id: 6,
+            note_number: "CN-1".to_string(),
+            note_type: "CREDIT_NOTE".to_string(),
+            note_date: "2026-09-10".to_string(),
+            note_status: "SENT".to_string(),
+            invoice_id: 2,
+            invoice_number: "INV-2".to_string(),
+            invoice_date: "2026-08-28".to_string(),
+            invoice_value: large.invoice.final_amount,
+            invoice_place_of_supply: "Karnataka".to_string(),
+            amount: rupees(1180.0),
+        });
+        let gstr1 = build(&[credit_note]);
+
+        assert!(gstr1.b2cs.is_empty());
+        assert_eq!(gstr1.cdnur[0].typ, "B2CL");
+        assert_eq!(gstr1.cdnur[0].inum.as_deref(), Some("INV-2"));
+        assert_eq!(gstr1.cdnur[0].val, rupees(1180.0));
+    }
+
+    #[test]
+    fn summarises_hsn_and_documents() {
+        let registered = customer(Some("29AAGCB7383J1Z4"), "B2B");
+        let unregistered = customer(None, "B2C");
+        let mut cancelled = document("INV-3", "REGULAR", "Maharashtra", 300.0, unregistered.clone());
+        cancelled.invoice.status = "CANCELLED".to_string();
+        let documents = vec![
+            document("INV-1", "REGULAR", "Karnataka", 1000.0, registered.clone()),
+            document("INV-2", "REGULAR", "Maharashtra", 500.0, unregistered),
+            cancelled,
+            document("CN-1", "CREDIT_NOTE", "Karnataka", 100.0, registered),
+        ];
+        let gstr1 = build(&documents);
+
+        let b2b = &gstr1.hsn.hsn_b2b[0];
+        assert_eq!((b2b.hsn_sc.as_str(), b2b.uqc.as_str(), b2b.qty), ("7214", "PCS", 0.0));
+        assert_eq!(b2b.txval, rupees(900.0));
+        assert_eq!(gstr1.hsn.hsn_b2c[0].txval, rupees(500.0));
+
+        let invoices = &gstr1.doc_issue.doc_det[0];
+        assert_eq!(invoices.doc_num, 1);
+        assert_eq!(
+            invoices.docs[0],
+            DocumentRange {
+                num: 1,
+                from: "INV-1".to_string(),
+                to: "INV-3".to_string(),
+                totnum: 3,
+                cancel: 1,
+                net_issue: 2,
+            }
+        );
+        assert_eq!(gstr1.doc_issue.doc_det[1].doc_typ, "Credit Note");
+    }
+
+    #[test]
+    fn reports_each_series_as_its_own_range() {
+        let buyer = customer(None, "B2C");
+        let mut cancelled = document("INV-26-10", "REGULAR", "Maharashtra", 100.0, buyer.clone());
+        cancelled.invoice.status = "CANCELLED".to_string();
+        let documents = vec![
+            document("INV-26-9", "REGULAR", "Maharashtra", 100.0, buyer.clone()),
+            cancelled,
+            document("INV-26-11", "REGULAR", "Maharashtra", 100.0, buyer.clone()),
+            document("EXP-26-1", "EXPORT", "United States", 100.0, customer(None, "EXPORT")),
+        ];
+        let invoices = &build(&documents).doc_issue.doc_det[0];
+
+        let ranges: Vec<_> = invoices
+            .docs
+            .iter()
+            .map(|range| (range.num, range.from.as_str(), range.to.as_str(), range.totnum, range.cancel))
+            .collect();
+        assert_eq!(ranges, [(1, "EXP-26-1", "EXP-26-1", 1, 0), (2, "INV-26-9", "INV-26-11", 3, 1)]);
+    }
+
+    #[test]
+    fn serializes_in_offline_tool_shape() {
+        let registered = customer(Some("27AAPFU0939F1ZV"), "B2B");
+        let documents = vec![document("INV-1", "REGULAR", "Maharashtra", 1000.0, registered)];
+        let json = serde_json::to_value(build(&documents)).unwrap();
+
+        let item = &json["b2b"][0]["inv"][0]["itms"][0];
+        assert_eq!(item["num"], 1);
+        assert_eq!(item["itm_det"]["camt"], 90.0);
+        assert!(item["itm_det"].get("iamt").is_none());
+        assert!(json.get("b2cl").is_none());
+    }
+}
@@ -0,0 +1,208 @@
+// =====================================================
+// Payvlo GST Invoice Generator - GSTR-3B Summary
+// Tables 3.1 and 3.2 computed from issued documents
+// =====================================================
+//
+// Table 3.1 rows:
+//   (a) osup_det       taxable outward supplies other than zero-rated, nil and exempt
+//   (b) osup_zero      zero-rated supplies (exports)
+//   (c) osup_nil_exmp  nil-rated and exempt supplies, and outward supplies on which the
+//                      recipient pays tax under reverse charge
+//   (d) isup_rev       inward supplies liable to reverse charge
+//   (e) osup_nongst    non-GST outward supplies
+// Payvlo records outward documents only, so (d) and (e) are always zero.
+// Table 3.2 breaks the inter-state part of (a) made to unregistered persons down by
+// place of supply. Credit notes reduce and debit notes add to the rows they belong to.
+
+use crate::database::{ReturnInvoice, ReturnLine};
+use crate::gst;
+use crate::money::Money;
+use crate::reports::{self, ReturnPeriod};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// =====================================================
+// Summary Structure
+// =====================================================
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Gstr3bSummary {
+    pub gstin: String,
+    pub ret_period: String,
+    pub sup_details: SupplyDetails,
+    pub inter_sup: InterStateSupplies,
+}
+
+/// Table 3.1
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct SupplyDetails {
+    pub osup_det: TaxValues,
+    pub osup_zero: TaxValues,
+    pub osup_nil_exmp: TaxValues,
+    pub isup_rev: TaxValues,
+    pub osup_nongst: TaxValues,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq)]
+pub struct TaxValues {
+    pub txval: Money,
+    pub iamt: Money,
+    pub camt: Money,
+    pub samt: Money,
+    pub csamt: Money,
+}
+
+impl TaxValues {
+    fn add(&mut self, line: &ReturnLine, with_tax: bool, sign: i64) {
+        let signed = |amount: Money| if sign < 0 { -amount } else { amount };
+        self.txval += signed(line.item.taxable_amount);
+        if with_tax {
+            self.iamt += signed(line.item.igst_amount);
+            self.camt += signed(line.item.cgst_amount);
+            self.samt += signed(line.item.sgst_amount);
+            self.csamt += signed(line.item.cess_amount);
+        }
+    }
+}
+
+/// Table 3.2
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct InterStateSupplies {
+    pub unreg_details: Vec<PlaceOfSupplyValues>,
+    pub comp_details: Vec<PlaceOfSupplyValues>,
+    pub uin_details: Vec<PlaceOfSupplyValues>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PlaceOfSupplyValues {
+    pub pos: String,
+    pub txval: Money,
+    pub iamt: Money,
+}
+
+// =====================================================
+// Summary Builder
+// =====================================================
+
+/// Computes GSTR-3B tables 3.1 and 3.2 for `period` from the documents dated within it
+pub fn build_gstr3b(
+    company_gstin: &str,
+    company_state: &str,
+    period: &ReturnPeriod,
+    documents: &[ReturnInvoice],
+) -> Gstr3bSummary {
+    let mut details = SupplyDetails::default();
+    let mut unregistered: BTreeMap<String, (Money, Money)> = BTreeMap::new();
+
+    for document in documents.iter().filter(|d| d.invoice.status != "CANCELLED") {
+        let invoice = &document.invoice;
+        let kind = invoice.invoice_type.as_str();
+        let pos = reports::pos_code(&invoice.place_of_supply, kind);
+        let is_export = kind == "EXPORT"
+            || document.customer.customer_type == "EXPORT"
+            || pos == reports::FOREIGN_POS_CODE;
+        let inter_state =
+            is_export || gst::is_inter_state_transaction(company_state, &invoice.place_of_supply, kind);
+        let registered = reports::is_registered(document.customer.gstin.as_deref());
+        let sign = if kind == "CREDIT_NOTE" { -1 } else { 1 };
+
+        for line in &document.lines {
+            if is_export {
+                details.osup_zero.add(line, true, sign);
+            } else if invoice.reverse_charge || line.item.gst_rate == 0.0 {
+                // Under reverse charge the recipient pays the tax, so only the value is reported
+                details.osup_nil_exmp.add(line, false, sign);
+            } else {
+                details.osup_det.add(line, true, sign);
+
+                if inter_state && !registered {
+                    let entry = unregistered.entry(pos.clone()).or_default();
+                    let signed = |amount: Money| if sign < 0 { -amount } else { amount };
+                    entry.0 += signed(line.item.taxable_amount);
+                    entry.1 += signed(line.item.igst_amount);
+                }
+            }
+        }
+    }
+
+    Gstr3bSummary {
+        gstin: company_gstin.trim().to_uppercase(),
+        ret_period: period.gstn(),
+        sup_details: details,
+        inter_sup: InterStateSupplies {
+            unreg_details: unregistered
+                .into_iter()
+                .map(|(pos, (txval, iamt))| PlaceOfSupplyValues { pos, txval, iamt })
+                .collect(),
+            ..InterStateSupplies::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reports::fixtures::{customer, document, document_at_rate};
+
+    fn rupees(amount: f64) -> Money {
+        Money::from_rupees(amount)
+    }
+
+    fn build(documents: &[ReturnInvoice]) -> Gstr3bSummary {
+        let period = ReturnPeriod::parse("092026").unwrap();
+        build_gstr3b("27AAPFU0939F1ZV", "Maharashtra", &period, documents)
+    }
+
+    #[test]
+    fn computes_table_3_1() {
+        let registered = customer(Some("29AAGCB7383J1Z4"), "B2B");
+        let unregistered = customer(None, "B2C");
+        let mut reverse_charge = document("INV-4", "REGULAR", "Karnataka", 700.0, registered.clone());
+        reverse_charge.invoice.reverse_charge = true;
+        let mut cancelled = document("INV-6", "REGULAR", "Maharashtra", 900.0, unregistered.clone());
+        cancelled.invoice.status = "CANCELLED".to_string();
+        let documents = vec![
+            document("INV-1", "REGULAR", "Maharashtra", 1000.0, unregistered.clone()),
+            document("INV-2", "REGULAR", "Karnataka", 2000.0, registered.clone()),
+            document("INV-3", "EXPORT", "United States", 5000.0, customer(None, "EXPORT")),
+            reverse_charge,
+            document_at_rate("INV-5", "REGULAR", "Maharashtra", 300.0, 0.0, unregistered),
+            cancelled,
+            document("CN-1", "CREDIT_NOTE", "Karnataka", 500.0, registered),
+        ];
+        let details = build(&documents).sup_details;
+
+        assert_eq!(details.osup_det.txval, rupees(2500.0));
+        assert_eq!(details.osup_det.camt, rupees(90.0));
+        assert_eq!(details.osup_det.samt, rupees(90.0));
+        assert_eq!(details.osup_det.iamt, rupees(270.0));
+        assert_eq!(details.osup_zero.txval, rupees(5000.0));
+        assert_eq!(details.osup_zero.iamt, rupees(900.0));
+        assert_eq!(details.osup_nil_exmp.txval, rupees(1000.0));
+        assert_eq!(details.osup_nil_exmp.iamt, Money::ZERO);
+        assert_eq!(details.isup_rev, TaxValues::default());
+    }
+
+    #[test]
+    fn breaks_down_inter_state_supplies_to_unregistered_persons() {
+        let unregistered = customer(None, "B2C");
+        let documents = vec![
+            document("INV-1", "REGULAR", "Karnataka", 1000.0, unregistered.clone()),
+            document("INV-2", "REGULAR", "Gujarat", 400.0, unregistered.clone()),
+            document("INV-3", "REGULAR", "Karnataka", 500.0, unregistered.clone()),
+            document("INV-4", "REGULAR", "Maharashtra", 800.0, unregistered),
+            document("INV-5", "REGULAR", "Karnataka", 600.0, customer(Some("29AAGCB7383J1Z4"), "B2B")),
+        ];
+        let summary = build(&documents);
+
+        assert_eq!(
+            summary.inter_sup.unreg_details,
+            vec![
+                PlaceOfSupplyValues { pos: "24".to_string(), txval: rupees(400.0), iamt: rupees(72.0) },
+                PlaceOfSupplyValues { pos: "29".to_string(), txval: rupees(1500.0), iamt: rupees(270.0) },
+            ]
+        );
+        assert_eq!(summary.ret_period, "092026");
+        assert!(summary.inter_sup.comp_details.is_empty());
+    }
+}
@@ -0,0 +1,791 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Bulk Import
+// Customers and products from CSV files and Excel workbooks
+// =====================================================
+//
+// The first row of a file names its columns, matched to record fields without regard
+// to case, spaces or underscores ("Customer Name" fills `customer_name`). A mapping
+// can send a differently named column to a field instead, or leave it out. Rows that
+// cannot be read or fail validation are reported by their line in the file; the rest
+// are saved together in one transaction, or only checked on a dry run.
+
+#[cfg(feature = "gui")]
+use crate::database::ImportMapping;
+use crate::database::{Customer, Database, Product};
+use crate::gst;
+use crate::money::Money;
+use crate::validation::{is_valid_hsn_sac, is_valid_pincode, validate_gstin_structure};
+use crate::xlsx;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Rows shown when previewing a file before it is mapped
+#[cfg(feature = "gui")]
+const PREVIEW_ROWS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportKind {
+    Customers,
+    Products,
+}
+
+impl ImportKind {
+    pub fn parse(kind: &str) -> Result<Self, ImportError> {
+        match kind.trim().to_lowercase().as_str() {
+            "customers" => Ok(ImportKind::Customers),
+            "products" => Ok(ImportKind::Products),
+            _ => Err(ImportError::UnknownKind(kind.to_string())),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportKind::Customers => "customers",
+            ImportKind::Products => "products",
+        }
+    }
+
+    fn required_columns(self) -> &'static [&'static str] {
+        match self {
+            ImportKind::Customers => &["customer_name", "address_line1", "city", "state", "pincode"],
+            ImportKind::Products => &["product_code", "product_name", "hsn_sac_code", "rate", "gst_rate"],
+        }
+    }
+
+    /// Every field a column can be mapped to
+    pub fn fields(self) -> &'static [&'static str] {
+        match self {
+            ImportKind::Customers => &[
+                "customer_name", "gstin", "pan", "customer_type", "address_line1", "address_line2", "city",
+                "state", "pincode", "phone", "email", "credit_limit", "credit_period_days", "is_active",
+            ],
+            ImportKind::Products => &[
+                "product_code", "product_name", "description", "hsn_sac_code", "product_type",
+                "unit_of_measurement", "rate", "gst_rate", "cess_rate", "is_active",
+            ],
+        }
+    }
+}
+
+/// How to read a file. `columns` maps file headers to fields and takes precedence over
+/// the saved mapping `mapping_id`; `sheet` picks the worksheet of a workbook.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ImportOptions {
+    pub columns: Option<BTreeMap<String, String>>,
+    pub mapping_id: Option<i64>,
+    pub sheet: Option<String>,
+    pub dry_run: bool,
+}
+
+/// A row that was not imported, by its line number in the file
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportReport {
+    pub kind: ImportKind,
+    pub dry_run: bool,
+    pub total_rows: usize,
+    /// Rows saved, or on a dry run the rows that would have been
+    pub imported: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// The headers of a file, the field each one fills under the chosen mapping and the
+/// first rows, so columns can be mapped before importing
+#[cfg(feature = "gui")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportPreview {
+    pub kind: ImportKind,
+    pub sheets: Vec<String>,
+    pub headers: Vec<String>,
+    pub fields: Vec<Option<String>>,
+    pub missing: Vec<String>,
+    pub available_fields: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub total_rows: usize,
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum ImportError {
+    UnknownKind(String),
+    UnterminatedQuote { line: usize },
+    EmptyFile,
+    MissingColumns(Vec<String>),
+    UnsupportedFormat(String),
+    InvalidWorkbook(String),
+    /// A mapping sends a column to a field the kind does not have
+    UnknownField(String),
+    /// Two columns would fill the same field
+    DuplicateField(String),
+    MappingNotFound(i64),
+    #[cfg(feature = "gui")]
+    MappingNameRequired,
+    Io(std::io::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl ImportError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ImportError::UnknownKind(_) => "UnknownImportKind",
+            ImportError::UnterminatedQuote { .. }
+            | ImportError::EmptyFile
+            | ImportError::MissingColumns(_)
+            | ImportError::UnsupportedFormat(_)
+            | ImportError::InvalidWorkbook(_) => "InvalidImportFile",
+            ImportError::UnknownField(_) | ImportError::DuplicateField(_) => "InvalidImportMapping",
+            #[cfg(feature = "gui")]
+            ImportError::MappingNameRequired => "InvalidImportMapping",
+            ImportError::MappingNotFound(_) => "NotFound",
+            ImportError::Io(_) => "FileError",
+            ImportError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::UnknownKind(kind) => {
+                write!(f, "Cannot import {}; use customers or products", kind)
+            }
+            ImportError::UnterminatedQuote { line } => {
+                write!(f, "Quoted value starting on line {} is never closed", line)
+            }
+            ImportError::EmptyFile => f.write_str("The file has no header row"),
+            ImportError::MissingColumns(columns) => {
+                write!(f, "The file is missing the columns {}", columns.join(", "))
+            }
+            ImportError::UnsupportedFormat(extension) => {
+                write!(f, "Cannot import .{} files; use a .csv or .xlsx file", extension)
+            }
+            ImportError::InvalidWorkbook(problem) => write!(f, "Cannot read the workbook: {}", problem),
+            ImportError::UnknownField(field) => write!(f, "There is no field named {} to import into", field),
+            ImportError::DuplicateField(field) => write!(f, "More than one column fills {}", field),
+            ImportError::MappingNotFound(id) => write!(f, "Import mapping {} does not exist", id),
+            #[cfg(feature = "gui")]
+            ImportError::MappingNameRequired => f.write_str("Give the import mapping a name"),
+            ImportError::Io(err) => write!(f, "{}", err),
+            ImportError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<std::io::Error> for ImportError {
+    fn from(err: std::io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
+
+impl From<rusqlite::Error> for ImportError {
+    fn from(err: rusqlite::Error) -> Self {
+        ImportError::Sqlite(err)
+    }
+}
+
+// =====================================================
+// CSV Reading
+// =====================================================
+
+/// Splits CSV text into records of fields, each with the line it starts on. Fields may
+/// be quoted to hold commas, line breaks or doubled quotes; blank lines are skipped.
+pub fn parse_csv(text: &str) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut quote_line = 0;
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => {
+                in_quotes = true;
+                quote_line = line;
+            }
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|value| !value.trim().is_empty()) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                record_line = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if in_quotes {
+        return Err(ImportError::UnterminatedQuote { line: quote_line });
+    }
+    record.push(field);
+    if record.iter().any(|value| !value.trim().is_empty()) {
+        records.push((record_line, record));
+    }
+    Ok(records)
+}
+
+/// Column names reduced to field form: "Customer Name" and "customer_name" both match
+fn field_name(header: &str) -> String {
+    header
+        .trim()
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+struct Columns {
+    names: Vec<String>,
+}
+
+impl Columns {
+    fn new(headers: &[String], kind: ImportKind, mapping: Option<&BTreeMap<String, String>>) -> Result<Self, ImportError> {
+        let names = Self::names(headers, kind, mapping)?;
+        let missing = Self::missing(&names, kind);
+        if !missing.is_empty() {
+            return Err(ImportError::MissingColumns(missing));
+        }
+        Ok(Columns { names })
+    }
+
+    /// The field each header fills, through `mapping` where it names the header.
+    /// Headers that match no field are ignored.
+    fn names(headers: &[String], kind: ImportKind, mapping: Option<&BTreeMap<String, String>>) -> Result<Vec<String>, ImportError> {
+        let mut mapped = BTreeMap::new();
+        for (header, field) in mapping.into_iter().flatten() {
+            let field = field_name(field);
+            if !field.is_empty() && !kind.fields().contains(&field.as_str()) {
+                return Err(ImportError::UnknownField(field));
+            }
+            mapped.insert(field_name(header), field);
+        }
+
+        let names: Vec<String> = headers
+            .iter()
+            .map(|header| {
+                let header = field_name(header);
+                mapped.get(&header).cloned().unwrap_or(header)
+            })
+            .collect();
+        for (index, name) in names.iter().enumerate() {
+            if kind.fields().contains(&name.as_str()) && names[..index].contains(name) {
+                return Err(ImportError::DuplicateField(name.clone()));
+            }
+        }
+        Ok(names)
+    }
+
+    fn missing(names: &[String], kind: ImportKind) -> Vec<String> {
+        kind.required_columns()
+            .iter()
+            .filter(|column| !names.iter().any(|name| name == *column))
+            .map(|column| column.to_string())
+            .collect()
+    }
+
+    /// Trimmed value of a column, None when absent or blank
+    fn get<'a>(&self, row: &'a [String], column: &str) -> Option<&'a str> {
+        let index = self.names.iter().position(|name| name == column)?;
+        row.get(index).map(|value| value.trim()).filter(|value| !value.is_empty())
+    }
+
+    fn text(&self, row: &[String], column: &str) -> Result<String, String> {
+        self.get(row, column)
+            .map(str::to_string)
+            .ok_or_else(|| format!("{} is required", column))
+    }
+
+    fn optional(&self, row: &[String], column: &str) -> Option<String> {
+        self.get(row, column).map(str::to_string)
+    }
+
+    fn number(&self, row: &[String], column: &str, default: f64) -> Result<f64, String> {
+        match self.get(row, column) {
+            Some(value) => value
+                .replace(',', "")
+                .parse()
+                .map_err(|_| format!("{} must be a number, got {}", column, value)),
+            None => Ok(default),
+        }
+    }
+
+    fn flag(&self, row: &[String], column: &str) -> Result<bool, String> {
+        match self.get(row, column).map(str::to_lowercase).as_deref() {
+            None | Some("1" | "true" | "yes" | "y" | "active") => Ok(true),
+            Some("0" | "false" | "no" | "n" | "inactive") => Ok(false),
+            Some(value) => Err(format!("{} must be yes or no, got {}", column, value)),
+        }
+    }
+}
+
+// =====================================================
+// Row Mapping
+// =====================================================
+
+fn customer_from_row(columns: &Columns, row: &[String]) -> Result<Customer, String> {
+    let gstin = columns.optional(row, "gstin").map(|gstin| gstin.to_uppercase());
+    let customer_type = match columns.optional(row, "customer_type") {
+        Some(customer_type) => customer_type.to_uppercase(),
+        None if gstin.is_some() => "B2B".to_string(),
+        None => "B2C".to_string(),
+    };
+    Ok(Customer {
+        id: None,
+        customer_name: columns.text(row, "customer_name")?,
+        gstin,
+        pan: columns.optional(row, "pan").map(|pan| pan.to_uppercase()),
+        customer_type,
+        address_line1: columns.text(row, "address_line1")?,
+        address_line2: columns.optional(row, "address_line2"),
+        city: columns.text(row, "city")?,
+        state: columns.text(row, "state")?,
+        pincode: columns.text(row, "pincode")?,
+        phone: columns.optional(row, "phone"),
+        email: columns.optional(row, "email"),
+        credit_limit: Money::from_rupees(columns.number(row, "credit_limit", 0.0)?),
+        credit_period_days: columns.number(row, "credit_period_days", 0.0)? as i32,
+        is_active: columns.flag(row, "is_active")?,
+        created_at: None,
+        updated_at: None,
+        invoice_template_id: None,
+    })
+}
+
+fn product_from_row(columns: &Columns, row: &[String]) -> Result<Product, String> {
+    let hsn_sac_code = columns.text(row, "hsn_sac_code")?;
+    let product_type = match columns.optional(row, "product_type") {
+        Some(product_type) => product_type.to_uppercase(),
+        None if hsn_sac_code.starts_with("99") => "SERVICES".to_string(),
+        None => "GOODS".to_string(),
+    };
+    Ok(Product {
+        id: None,
+        product_code: columns.text(row, "product_code")?,
+        product_name: columns.text(row, "product_name")?,
+        description: columns.optional(row, "description"),
+        hsn_sac_code,
+        product_type,
+        unit_of_measurement: columns.optional(row, "unit_of_measurement").unwrap_or_else(|| "NOS".to_string()),
+        rate: Money::from_rupees(columns.number(row, "rate", 0.0)?),
+        gst_rate: columns.number(row, "gst_rate", 0.0)?,
+        cess_rate: columns.number(row, "cess_rate", 0.0)?,
+        is_active: columns.flag(row, "is_active")?,
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+// =====================================================
+// Row Validation
+// =====================================================
+
+/// Everything wrong with a customer, so a row is fixed in one pass
+fn check_customer(customer: &Customer) -> Vec<String> {
+    let mut problems = Vec::new();
+    let export = customer.customer_type == "EXPORT";
+    if !matches!(customer.customer_type.as_str(), "B2B" | "B2C" | "EXPORT") {
+        problems.push(format!("customer_type must be B2B, B2C or EXPORT, got {}", customer.customer_type));
+    }
+
+    let state_code = gst::state_code(&customer.state);
+    if !export {
+        if state_code.is_none() {
+            problems.push(format!("state {} is not an Indian state or state code", customer.state));
+        }
+        if !is_valid_pincode(&customer.pincode) {
+            problems.push(format!("pincode {} must be 6 digits", customer.pincode));
+        }
+    }
+
+    match &customer.gstin {
+        Some(gstin) => match validate_gstin_structure(gstin) {
+            Ok(parts) => {
+                if let Some(code) = state_code.filter(|code| !export && *code != parts.state_code) {
+                    problems.push(format!(
+                        "gstin {} is registered in state {}, not {} ({})",
+                        gstin, parts.state_code, customer.state, code
+                    ));
+                }
+            }
+            Err(err) => problems.push(format!("gstin {}: {}", gstin, err)),
+        },
+        None if customer.customer_type == "B2B" => problems.push("gstin is required for B2B customers".to_string()),
+        None => {}
+    }
+    problems
+}
+
+fn check_product(product: &Product) -> Vec<String> {
+    let mut problems = Vec::new();
+    let code = &product.hsn_sac_code;
+    if !is_valid_hsn_sac(code) {
+        problems.push(format!("hsn_sac_code {} must be 4, 6 or 8 digits", code));
+    }
+    match product.product_type.as_str() {
+        "SERVICES" if !code.starts_with("99") => {
+            problems.push(format!("hsn_sac_code {} is not a SAC code; services use codes starting 99", code))
+        }
+        "GOODS" if code.starts_with("99") => {
+            problems.push(format!("hsn_sac_code {} is a SAC code; goods need an HSN code", code))
+        }
+        "GOODS" | "SERVICES" => {}
+        other => problems.push(format!("product_type must be GOODS or SERVICES, got {}", other)),
+    }
+    if !gst::is_valid_gst_rate(product.gst_rate) {
+        problems.push(format!("gst_rate {} must be from 0 to 100 with at most two decimals", product.gst_rate));
+    }
+    if !(0.0..=100.0).contains(&product.cess_rate) {
+        problems.push(format!("cess_rate {} must be between 0 and 100", product.cess_rate));
+    }
+    if product.rate < Money::ZERO {
+        problems.push("rate cannot be negative".to_string());
+    }
+    problems
+}
+
+/// Reads and validates a row, reporting either why it could not be read or every
+/// validation problem
+fn read_row<T>(
+    read: impl Fn(&Columns, &[String]) -> Result<T, String>,
+    check: impl Fn(&T) -> Vec<String>,
+    columns: &Columns,
+    row: &[String],
+) -> Result<T, String> {
+    let record = read(columns, row)?;
+    let problems = check(&record);
+    if problems.is_empty() {
+        Ok(record)
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+// =====================================================
+// Import
+// =====================================================
+
+/// Rows of a file, each with its line or row number
+type Records = Vec<(usize, Vec<String>)>;
+
+/// Records of a .csv or .xlsx file, with the worksheet names of a workbook
+fn read_records(path: &Path, sheet: Option<&str>) -> Result<(Vec<String>, Records), ImportError> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "xlsx" | "xlsm" => {
+            let data = std::fs::read(path)?;
+            Ok((xlsx::sheet_names(&data)?, xlsx::read_sheet(&data, sheet)?))
+        }
+        "csv" | "txt" | "" => Ok((Vec::new(), parse_csv(&std::fs::read_to_string(path)?)?)),
+        _ => Err(ImportError::UnsupportedFormat(extension)),
+    }
+}
+
+/// Normalizes a mapping before it is saved: the kind must exist, the name must not be
+/// blank and every column must go to a field of the kind or nowhere
+#[cfg(feature = "gui")]
+pub fn check_mapping(mapping: &ImportMapping) -> Result<ImportMapping, ImportError> {
+    let kind = ImportKind::parse(&mapping.kind)?;
+    let name = mapping.name.trim();
+    if name.is_empty() {
+        return Err(ImportError::MappingNameRequired);
+    }
+    let headers: Vec<String> = mapping.columns.keys().cloned().collect();
+    Columns::names(&headers, kind, Some(&mapping.columns))?;
+    Ok(ImportMapping {
+        name: name.to_string(),
+        kind: kind.as_str().to_string(),
+        columns: mapping
+            .columns
+            .iter()
+            .map(|(header, field)| (header.trim().to_string(), field_name(field)))
+            .collect(),
+        ..mapping.clone()
+    })
+}
+
+/// Columns given with the request, else those of the saved mapping
+fn resolve_mapping(db: &Database, options: &ImportOptions) -> Result<Option<BTreeMap<String, String>>, ImportError> {
+    if let Some(columns) = &options.columns {
+        return Ok(Some(columns.clone()));
+    }
+    match options.mapping_id {
+        Some(id) => {
+            let mapping = db.get_import_mapping(id)?.ok_or(ImportError::MappingNotFound(id))?;
+            Ok(Some(mapping.columns))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Shows how a file would be read under the options, without importing it
+#[cfg(feature = "gui")]
+pub fn preview_import(db: &Database, kind: ImportKind, path: &Path, options: &ImportOptions) -> Result<ImportPreview, ImportError> {
+    let (sheets, records) = read_records(path, options.sheet.as_deref())?;
+    let mapping = resolve_mapping(db, options)?;
+    let mut records = records.into_iter();
+    let (_, headers) = records.next().ok_or(ImportError::EmptyFile)?;
+    let names = Columns::names(&headers, kind, mapping.as_ref())?;
+    let rows: Vec<Vec<String>> = records.map(|(_, row)| row).collect();
+
+    Ok(ImportPreview {
+        kind,
+        sheets,
+        missing: Columns::missing(&names, kind),
+        fields: names
+            .into_iter()
+            .map(|name| Some(name).filter(|name| kind.fields().contains(&name.as_str())))
+            .collect(),
+        headers,
+        available_fields: kind.fields().iter().map(|field| field.to_string()).collect(),
+        total_rows: rows.len(),
+        rows: rows.into_iter().take(PREVIEW_ROWS).collect(),
+    })
+}
+
+/// Reads a .csv or .xlsx file of customers or products and saves every row that can be
+/// read and passes validation in one transaction, reporting the rest. A dry run
+/// reports the same without saving anything.
+pub fn import_file(db: &Database, kind: ImportKind, path: &Path, options: &ImportOptions) -> Result<ImportReport, ImportError> {
+    let (_, records) = read_records(path, options.sheet.as_deref())?;
+    let mapping = resolve_mapping(db, options)?;
+    let mut records = records.into_iter();
+    let (_, headers) = records.next().ok_or(ImportError::EmptyFile)?;
+    let columns = Columns::new(&headers, kind, mapping.as_ref())?;
+    let rows: Vec<(usize, Vec<String>)> = records.collect();
+
+    let mut errors = Vec::new();
+    let mut lines = Vec::new();
+    let failures = match kind {
+        ImportKind::Customers => {
+            let mut customers = Vec::new();
+            for (line, row) in &rows {
+                match read_row(customer_from_row, check_customer, &columns, row) {
+                    Ok(customer) => {
+                        lines.push(*line);
+                        customers.push(customer);
+                    }
+                    Err(message) => errors.push(ImportRowError { row: *line, message }),
+                }
+            }
+            db.import_customers(&customers, options.dry_run)?
+        }
+        ImportKind::Products => {
+            let mut products = Vec::new();
+            let mut codes: BTreeMap<String, usize> = BTreeMap::new();
+            for (line, row) in &rows {
+                match read_row(product_from_row, check_product, &columns, row) {
+                    Ok(product) => match codes.get(&product.product_code) {
+                        Some(first) => errors.push(ImportRowError {
+                            row: *line,
+                            message: format!("product_code {} repeats line {}", product.product_code, first),
+                        }),
+                        None => {
+                            codes.insert(product.product_code.clone(), *line);
+                            lines.push(*line);
+                            products.push(product);
+                        }
+                    },
+                    Err(message) => errors.push(ImportRowError { row: *line, message }),
+                }
+            }
+            db.import_products(&products, options.dry_run)?
+        }
+    };
+
+    let imported = lines.len() - failures.len();
+    errors.extend(failures.into_iter().map(|(index, message)| ImportRowError { row: lines[index], message }));
+    errors.sort_by_key(|error| error.row);
+    Ok(ImportReport { kind, dry_run: options.dry_run, total_rows: rows.len(), imported, errors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(records: &[(usize, Vec<String>)]) -> Vec<(usize, Vec<&str>)> {
+        records
+            .iter()
+            .map(|(line, record)| (*line, record.iter().map(String::as_str).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_quoted_csv() {
+        let text = "\u{feff}Name,Address\r\n\"Sharma, Traders\",\"12 \"\"A\"\" Wing\nMG Road\"\r\n\r\nPatel Stores,Station Road";
+        let records = parse_csv(text).unwrap();
+        assert_eq!(
+            fields(&records),
+            [
+                (1, vec!["Name", "Address"]),
+                (2, vec!["Sharma, Traders", "12 \"A\" Wing\nMG Road"]),
+                (5, vec!["Patel Stores", "Station Road"]),
+            ]
+        );
+        assert!(matches!(parse_csv("a,\"b\nc"), Err(ImportError::UnterminatedQuote { line: 1 })));
+    }
+
+    #[test]
+    fn maps_rows_by_column_name() {
+        let headers: Vec<String> = ["Customer Name", "GSTIN", "Address Line1", "City", "State", "Pincode", "Credit Limit"]
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+        let columns = Columns::new(&headers, ImportKind::Customers, None).unwrap();
+        let row: Vec<String> = ["Sharma Traders", "27aapfu0939f1zv", "MG Road", "Pune", "Maharashtra", "411001", "1,50,000"]
+            .iter()
+            .map(|value| value.to_string())
+            .collect();
+
+        let customer = customer_from_row(&columns, &row).unwrap();
+        assert_eq!(customer.customer_type, "B2B");
+        assert_eq!(customer.gstin.as_deref(), Some("27AAPFU0939F1ZV"));
+        assert_eq!(customer.credit_limit, Money::from_rupees(150_000.0));
+
+        let mut blank_city = row.clone();
+        blank_city[3] = " ".to_string();
+        assert_eq!(customer_from_row(&columns, &blank_city).unwrap_err(), "city is required");
+
+        assert!(matches!(
+            Columns::new(&headers[..2], ImportKind::Customers, None),
+            Err(ImportError::MissingColumns(missing)) if missing.len() == 4
+        ));
+    }
+
+    #[test]
+    fn validates_rows_through_a_mapping() {
+        let path = std::env::temp_dir().join(format!("payvlo-import-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "Item Code,Item,HSN,Price,Tax %,Kind,Notes\n\
+             P-1,Steel rod,7214,100,18,,ignored\n\
+             P-2,Consulting,998311,5000,18,,\n\
+             P-1,Steel rod again,7214,100,18,,\n\
+             P-3,Cement,25,300,150,,\n\
+             P-4,Repairs,8708,200,18,services,\n",
+        )
+        .unwrap();
+        let db = Database::new(Path::new(":memory:")).unwrap();
+        db.initialize_schema().unwrap();
+        let columns: BTreeMap<String, String> = [
+            ("item code", "product_code"),
+            ("Item", "Product Name"),
+            ("HSN", "hsn_sac_code"),
+            ("Price", "rate"),
+            ("Tax %", "gst_rate"),
+            ("Kind", "product_type"),
+            ("Notes", ""),
+        ]
+        .iter()
+        .map(|(header, field)| (header.to_string(), field.to_string()))
+        .collect();
+        let mut options = ImportOptions { columns: Some(columns), dry_run: true, ..Default::default() };
+
+        #[cfg(feature = "gui")]
+        {
+            let preview = preview_import(&db, ImportKind::Products, &path, &options).unwrap();
+            assert_eq!(preview.fields[1].as_deref(), Some("product_name"));
+            assert_eq!(preview.fields[6], None);
+            assert!(preview.missing.is_empty());
+            assert_eq!(preview.total_rows, 5);
+        }
+
+        let report = import_file(&db, ImportKind::Products, &path, &options).unwrap();
+        assert_eq!((report.total_rows, report.imported), (5, 2));
+        let messages: Vec<(usize, &str)> = report.errors.iter().map(|error| (error.row, error.message.as_str())).collect();
+        assert_eq!(
+            messages,
+            [
+                (4, "product_code P-1 repeats line 2"),
+                (5, "hsn_sac_code 25 must be 4, 6 or 8 digits; gst_rate 150 must be from 0 to 100 with at most two decimals"),
+                (6, "hsn_sac_code 8708 is not a SAC code; services use codes starting 99"),
+            ]
+        );
+        assert_eq!(db.count_records("products").unwrap(), 0);
+
+        options.dry_run = false;
+        assert_eq!(import_file(&db, ImportKind::Products, &path, &options).unwrap().imported, 2);
+        let again = import_file(&db, ImportKind::Products, &path, &options).unwrap();
+        assert_eq!(again.imported, 0);
+        assert_eq!(again.errors[0].message, "product_code P-1 already exists");
+        assert_eq!(db.count_records("products").unwrap(), 2);
+
+        options.columns.as_mut().unwrap().insert("Notes".to_string(), "colour".to_string());
+        assert!(matches!(
+            import_file(&db, ImportKind::Products, &path, &options),
+            Err(ImportError::UnknownField(field)) if field == "colour"
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn checks_customer_registration() {
+        let customer = |customer_type: &str, gstin: Option<&str>, state: &str, pincode: &str| Customer {
+            id: None,
+            customer_name: "Sharma Traders".to_string(),
+            gstin: gstin.map(str::to_string),
+            pan: None,
+            customer_type: customer_type.to_string(),
+            address_line1: "MG Road".to_string(),
+            address_line2: None,
+            city: "Pune".to_string(),
+            state: state.to_string(),
+            pincode: pincode.to_string(),
+            phone: None,
+            email: None,
+            credit_limit: Money::ZERO,
+            credit_period_days: 0,
+            is_active: true,
+            created_at: None,
+            updated_at: None,
+            invoice_template_id: None,
+        };
+
+        assert!(check_customer(&customer("B2B", Some("27AAPFU0939F1ZV"), "Maharashtra", "411001")).is_empty());
+        assert!(check_customer(&customer("EXPORT", None, "Dubai", "")).is_empty());
+        assert_eq!(
+            check_customer(&customer("B2B", Some("27AAPFU0939F1ZV"), "Karnataka", "41100")),
+            [
+                "pincode 41100 must be 6 digits",
+                "gstin 27AAPFU0939F1ZV is registered in state 27, not Karnataka (29)",
+            ]
+        );
+        assert_eq!(
+            check_customer(&customer("B2B", None, "Maharashtra", "411001")),
+            ["gstin is required for B2B customers"]
+        );
+    }
+}
@@ -1,9 +1,42 @@
 // Import our modules
+mod api_server;
+mod audit;
+mod backup;
+pub mod cli;
 mod database;
+mod migrations;
+mod money;
+#[cfg(feature = "gui")]
+mod einvoice;
+#[cfg(feature = "gui")]
+mod ewaybill;
+#[cfg(feature = "gui")]
+mod formatting;
+mod gst;
+mod gstr1;
+mod gstr3b;
+mod import;
+mod lifecycle;
+mod notes;
+mod numbering;
+mod operations;
+mod payments;
+#[cfg(feature = "gui")]
+mod pdf;
+mod rates;
+mod reports;
+mod tally;
+#[cfg(feature = "gui")]
+mod templates;
+mod validation;
+mod xlsx;
+#[cfg(feature = "gui")]
 mod commands;
 
+#[cfg(feature = "gui")]
 use commands::AppState;
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
@@ -13,6 +46,11 @@ pub fn run() {
       commands::initialize_database,
       commands::check_database_health,
       
+      // Audit trail
+      commands::set_audit_user,
+      commands::get_audit_user,
+      commands::get_audit_history,
+      
       // Company settings
       commands::get_company_settings,
       commands::save_company_settings,
@@ -31,6 +69,80 @@ pub fn run() {
       commands::delete_product,
       commands::search_products,
       
+      // GST rate master
+      commands::get_gst_rates,
+      commands::save_gst_rate,
+      commands::delete_gst_rate,
+      commands::lookup_gst_rate,
+      
+      // Invoice management
+      commands::list_invoices,
+      commands::get_invoice_by_id,
+      commands::save_invoice,
+      commands::delete_invoice,
+      
+      // Invoice status
+      commands::update_invoice_status,
+      commands::cancel_invoice,
+      commands::amend_invoice,
+      commands::get_invoice_status_history,
+      commands::mark_overdue_invoices,
+      
+      // Payments
+      commands::record_payment,
+      commands::list_payments,
+      commands::void_payment,
+      commands::get_invoice_balance,
+      
+      // Credit and debit notes
+      commands::save_note,
+      commands::get_note_references,
+      commands::list_invoice_notes,
+      
+      // GST returns
+      commands::export_gstr1,
+      commands::get_gstr3b,
+      commands::export_gstr3b,
+      
+      // Tally export
+      commands::get_tally_settings,
+      commands::save_tally_settings,
+      commands::export_tally,
+      
+      // Backups
+      commands::create_backup,
+      commands::restore_backup,
+      commands::list_backups,
+      commands::get_backup_settings,
+      commands::save_backup_settings,
+      
+      // E-invoice
+      commands::generate_e_invoice,
+      commands::save_e_invoice_registration,
+      
+      // E-way bill
+      commands::get_transport_details,
+      commands::save_transport_details,
+      commands::is_e_way_bill_required,
+      commands::generate_e_way_bills,
+
+      // Invoice PDFs
+      commands::generate_invoice_pdf,
+
+      // Invoice templates
+      commands::list_invoice_templates,
+      commands::get_invoice_template,
+      commands::save_invoice_template,
+      commands::delete_invoice_template,
+      commands::preview_invoice_template,
+      
+      // Bulk import
+      commands::import_records,
+      commands::preview_import,
+      commands::list_import_mappings,
+      commands::save_import_mapping,
+      commands::delete_import_mapping,
+      
       // Indian states
       commands::get_indian_states,
       commands::get_state_by_code,
@@ -38,10 +150,18 @@ pub fn run() {
       // Utilities
       commands::get_record_counts,
       commands::get_next_invoice_number,
+      commands::get_invoice_series,
+      commands::save_invoice_series,
+      commands::amount_in_words,
       
       // GST validation
       commands::validate_gstin,
       commands::validate_hsn_sac,
+      
+      // Local API server
+      commands::get_api_server_status,
+      commands::configure_api_server,
+      commands::regenerate_api_token,
     ])
     .setup(|app| {
       if cfg!(debug_assertions) {
@@ -51,6 +171,8 @@ pub fn run() {
             .build(),
         )?;
       }
+      commands::spawn_overdue_sweep(app.handle().clone());
+      commands::spawn_backup_schedule(app.handle().clone());
       Ok(())
     })
     .run(tauri::generate_context!())
@@ -0,0 +1,261 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Invoice Lifecycle
+// Status transitions of invoices and notes
+// =====================================================
+//
+// Allowed moves:
+//   DRAFT    -> SENT when issued, or CANCELLED
+//   SENT     -> PAID once settled, OVERDUE once past due, or CANCELLED
+//   OVERDUE  -> PAID or CANCELLED
+//   PAID     -> SENT or OVERDUE when a payment is voided or a debit note adds to it
+//   CANCELLED is final
+//
+// Only a draft may be edited or deleted. Users issue and cancel documents; PAID follows
+// from the payments recorded and OVERDUE from the due date, so neither can be set by
+// hand. An issued invoice that needs changing is amended: a revised draft is created
+// that points at it, and issuing the revision cancels the original. Database triggers
+// back these rules up so no code path can rewrite an issued invoice.
+
+use crate::notes::NoteError;
+use crate::numbering::NumberingError;
+use chrono::NaiveDate;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvoiceStatus {
+    Draft,
+    Sent,
+    Paid,
+    Overdue,
+    Cancelled,
+}
+
+impl InvoiceStatus {
+    pub fn parse(status: &str) -> Result<Self, StatusError> {
+        match status {
+            "DRAFT" => Ok(InvoiceStatus::Draft),
+            "SENT" => Ok(InvoiceStatus::Sent),
+            "PAID" => Ok(InvoiceStatus::Paid),
+            "OVERDUE" => Ok(InvoiceStatus::Overdue),
+            "CANCELLED" => Ok(InvoiceStatus::Cancelled),
+            _ => Err(StatusError::InvalidStatus(status.to_string())),
+        }
+    }
+
+    pub fn can_become(self, to: InvoiceStatus) -> bool {
+        use InvoiceStatus::*;
+        matches!(
+            (self, to),
+            (Draft, Sent)
+                | (Draft, Cancelled)
+                | (Sent, Paid)
+                | (Sent, Overdue)
+                | (Sent, Cancelled)
+                | (Overdue, Paid)
+                | (Overdue, Cancelled)
+                | (Paid, Sent)
+                | (Paid, Overdue)
+        )
+    }
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum StatusError {
+    InvalidStatus(String),
+    IllegalTransition { from: String, to: String },
+    NotSettable(String),
+    Locked { invoice_number: String, status: String },
+    NotDeletable { invoice_number: String, status: String },
+    MissingReason,
+    HasSettlements { invoice_number: String, settled_by: &'static str },
+    NotAmendable { invoice_number: String, problem: String },
+    InvoiceNotFound(i64),
+    Numbering(NumberingError),
+    /// A note that no longer fits the invoices it amends when it comes to be issued
+    Note(Box<NoteError>),
+    Sqlite(rusqlite::Error),
+}
+
+impl StatusError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            StatusError::InvalidStatus(_) => "InvalidStatus",
+            StatusError::IllegalTransition { .. } | StatusError::NotSettable(_) => "IllegalStatusTransition",
+            StatusError::Locked { .. } | StatusError::NotDeletable { .. } => "InvoiceLocked",
+            StatusError::MissingReason => "CancellationReasonRequired",
+            StatusError::HasSettlements { .. } => "InvoiceHasSettlements",
+            StatusError::NotAmendable { .. } => "InvoiceNotAmendable",
+            StatusError::InvoiceNotFound(_) => "NotFound",
+            StatusError::Numbering(_) => "InvalidNumberFormat",
+            StatusError::Note(err) => err.code(),
+            StatusError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatusError::InvalidStatus(status) => write!(
+                f,
+                "Unknown invoice status {}; use DRAFT, SENT, PAID, OVERDUE or CANCELLED",
+                status
+            ),
+            StatusError::IllegalTransition { from, to } => {
+                write!(f, "An invoice cannot move from {} to {}", from, to)
+            }
+            StatusError::NotSettable(status) if status == "PAID" => {
+                f.write_str("Invoices become PAID by recording payments against them")
+            }
+            StatusError::NotSettable(status) => {
+                write!(f, "{} is set from the due date and cannot be chosen", status)
+            }
+            StatusError::Locked { invoice_number, status } => write!(
+                f,
+                "Invoice {} is {} and can no longer be edited; only drafts can be changed",
+                invoice_number, status
+            ),
+            StatusError::NotDeletable { invoice_number, status } => write!(
+                f,
+                "Invoice {} is {}; issued invoices are kept for the record and can only be cancelled",
+                invoice_number, status
+            ),
+            StatusError::MissingReason => f.write_str("A reason is required to cancel an issued invoice"),
+            StatusError::HasSettlements { invoice_number, settled_by } => write!(
+                f,
+                "Invoice {} has {} against it; reverse them first",
+                invoice_number, settled_by
+            ),
+            StatusError::NotAmendable { invoice_number, problem } => {
+                write!(f, "Invoice {} cannot be amended: {}", invoice_number, problem)
+            }
+            StatusError::InvoiceNotFound(id) => write!(f, "Invoice {} does not exist", id),
+            StatusError::Numbering(err) => write!(f, "{}", err),
+            StatusError::Note(err) => write!(f, "{}", err),
+            StatusError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+impl From<NumberingError> for StatusError {
+    fn from(err: NumberingError) -> Self {
+        StatusError::Numbering(err)
+    }
+}
+
+impl From<NoteError> for StatusError {
+    fn from(err: NoteError) -> Self {
+        match err {
+            NoteError::Status(err) => err,
+            err => StatusError::Note(Box::new(err)),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StatusError {
+    fn from(err: rusqlite::Error) -> Self {
+        StatusError::Sqlite(err)
+    }
+}
+
+// =====================================================
+// Transition Rules
+// =====================================================
+
+/// Checks any move between two statuses, whether asked for or derived
+pub fn check_transition(from: &str, to: &str) -> Result<(), StatusError> {
+    if InvoiceStatus::parse(from)?.can_become(InvoiceStatus::parse(to)?) {
+        Ok(())
+    } else {
+        Err(StatusError::IllegalTransition { from: from.to_string(), to: to.to_string() })
+    }
+}
+
+/// Checks a status chosen by the user; only issuing and cancelling are theirs to make
+pub fn check_requested(from: &str, to: &str) -> Result<(), StatusError> {
+    match InvoiceStatus::parse(to)? {
+        InvoiceStatus::Paid | InvoiceStatus::Overdue => Err(StatusError::NotSettable(to.to_string())),
+        _ => check_transition(from, to),
+    }
+}
+
+/// Status a document may be created in: a draft, or issued straight away
+pub fn check_initial(status: &str) -> Result<(), StatusError> {
+    match InvoiceStatus::parse(status)? {
+        InvoiceStatus::Draft | InvoiceStatus::Sent => Ok(()),
+        _ => Err(StatusError::IllegalTransition { from: "a new invoice".to_string(), to: status.to_string() }),
+    }
+}
+
+/// Whether an issued, unpaid invoice is past its due date. The due date itself is the
+/// last day to pay, so an invoice falls overdue the day after.
+pub fn is_overdue(due_date: Option<&str>, today: NaiveDate) -> bool {
+    due_date
+        .and_then(|due| NaiveDate::parse_from_str(due, "%Y-%m-%d").ok())
+        .is_some_and(|due| due < today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_lifecycle() {
+        for (from, to) in [
+            ("DRAFT", "SENT"),
+            ("DRAFT", "CANCELLED"),
+            ("SENT", "PAID"),
+            ("SENT", "OVERDUE"),
+            ("OVERDUE", "PAID"),
+            ("OVERDUE", "CANCELLED"),
+            ("PAID", "SENT"),
+            ("PAID", "OVERDUE"),
+        ] {
+            assert!(check_transition(from, to).is_ok(), "{} -> {}", from, to);
+        }
+        for (from, to) in [
+            ("DRAFT", "PAID"),
+            ("DRAFT", "OVERDUE"),
+            ("SENT", "DRAFT"),
+            ("PAID", "CANCELLED"),
+            ("CANCELLED", "SENT"),
+            ("CANCELLED", "DRAFT"),
+            ("SENT", "SENT"),
+        ] {
+            assert!(
+                matches!(check_transition(from, to), Err(StatusError::IllegalTransition { .. })),
+                "{} -> {}",
+                from,
+                to
+            );
+        }
+        assert!(matches!(check_transition("SENT", "VOID"), Err(StatusError::InvalidStatus(_))));
+    }
+
+    #[test]
+    fn users_only_issue_and_cancel() {
+        assert!(check_requested("DRAFT", "SENT").is_ok());
+        assert!(check_requested("SENT", "CANCELLED").is_ok());
+        assert!(matches!(check_requested("SENT", "PAID"), Err(StatusError::NotSettable(_))));
+        assert!(matches!(check_requested("SENT", "OVERDUE"), Err(StatusError::NotSettable(_))));
+
+        assert!(check_initial("DRAFT").is_ok());
+        assert!(check_initial("SENT").is_ok());
+        assert!(check_initial("PAID").is_err());
+    }
+
+    #[test]
+    fn falls_overdue_after_the_due_date() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        assert!(is_overdue(Some("2026-09-30"), today));
+        assert!(!is_overdue(Some("2026-10-01"), today));
+        assert!(!is_overdue(None, today));
+        assert!(!is_overdue(Some("not a date"), today));
+    }
+}
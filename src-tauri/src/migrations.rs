@@ -0,0 +1,370 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Schema Migrations
+// Versioned, transactional upgrades keyed on PRAGMA user_version
+// =====================================================
+//
+// `schema.sql` is the frozen baseline (version 1). Every later schema change is a
+// new file in `src/lib/database/migrations/` appended to `MIGRATIONS` with the next
+// version number; released migrations are never edited.
+
+use rusqlite::Connection;
+use std::fmt;
+
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Baseline schema",
+        sql: include_str!("../../src/lib/database/schema.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "Invoice numbering series",
+        sql: include_str!("../../src/lib/database/migrations/0002_invoice_series.sql"),
+    },
+    Migration {
+        version: 3,
+        description: "Series reset rules",
+        sql: include_str!("../../src/lib/database/migrations/0003_series_reset_rules.sql"),
+    },
+    Migration {
+        version: 4,
+        description: "Payment receipts and voids",
+        sql: include_str!("../../src/lib/database/migrations/0004_payment_receipts.sql"),
+    },
+    Migration {
+        version: 5,
+        description: "E-invoice registration",
+        sql: include_str!("../../src/lib/database/migrations/0005_e_invoice_registration.sql"),
+    },
+    Migration {
+        version: 6,
+        description: "Invoice transport details",
+        sql: include_str!("../../src/lib/database/migrations/0006_invoice_transport.sql"),
+    },
+    Migration {
+        version: 7,
+        description: "Invoice layout templates",
+        sql: include_str!("../../src/lib/database/migrations/0007_invoice_templates.sql"),
+    },
+    Migration {
+        version: 8,
+        description: "Credit and debit notes",
+        sql: include_str!("../../src/lib/database/migrations/0008_credit_debit_notes.sql"),
+    },
+    Migration {
+        version: 9,
+        description: "Invoice status history",
+        sql: include_str!("../../src/lib/database/migrations/0009_invoice_status_history.sql"),
+    },
+    Migration {
+        version: 10,
+        description: "Issued invoice lock",
+        sql: include_str!("../../src/lib/database/migrations/0010_issued_invoice_lock.sql"),
+    },
+    Migration {
+        version: 11,
+        description: "Audit log",
+        sql: include_str!("../../src/lib/database/migrations/0011_audit_log.sql"),
+    },
+    Migration {
+        version: 12,
+        description: "GST rate master",
+        sql: include_str!("../../src/lib/database/migrations/0012_gst_rate_master.sql"),
+    },
+    Migration {
+        version: 13,
+        description: "Local API server",
+        sql: include_str!("../../src/lib/database/migrations/0013_api_server.sql"),
+    },
+    Migration {
+        version: 14,
+        description: "Import column mappings",
+        sql: include_str!("../../src/lib/database/migrations/0014_import_mappings.sql"),
+    },
+    Migration {
+        version: 15,
+        description: "Tally export",
+        sql: include_str!("../../src/lib/database/migrations/0015_tally_export.sql"),
+    },
+    Migration {
+        version: 16,
+        description: "Automatic backups",
+        sql: include_str!("../../src/lib/database/migrations/0016_backup_settings.sql"),
+    },
+    Migration {
+        version: 17,
+        description: "Numbering on issue",
+        sql: include_str!("../../src/lib/database/migrations/0017_draft_numbering.sql"),
+    },
+    Migration {
+        version: 18,
+        description: "Issued invoice status lock",
+        sql: include_str!("../../src/lib/database/migrations/0018_issued_invoice_status_lock.sql"),
+    },
+];
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The database was written by a newer build of the app
+    DatabaseTooNew { found: i32, supported: i32 },
+    /// A migration left rows violating foreign key constraints
+    ForeignKeyViolation { version: i32 },
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::DatabaseTooNew { found, supported } => write!(
+                f,
+                "Database schema version {} is newer than the latest supported version {}; please update Payvlo",
+                found, supported
+            ),
+            MigrationError::ForeignKeyViolation { version } => write!(
+                f,
+                "Migration to schema version {} failed the foreign key check",
+                version
+            ),
+            MigrationError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(err: rusqlite::Error) -> Self {
+        MigrationError::Sqlite(err)
+    }
+}
+
+// =====================================================
+// Migration Runner
+// =====================================================
+
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(connection: &Connection) -> rusqlite::Result<i32> {
+    connection.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Refuses to touch a database whose schema is newer than this binary understands
+pub fn check_compatible(connection: &Connection) -> Result<i32, MigrationError> {
+    let found = current_version(connection)?;
+    let supported = latest_version();
+    if found > supported {
+        return Err(MigrationError::DatabaseTooNew { found, supported });
+    }
+    Ok(found)
+}
+
+/// Applies all pending migrations in order, each in its own transaction.
+/// Returns the schema version the database is at afterwards.
+pub fn run_migrations(connection: &Connection) -> Result<i32, MigrationError> {
+    let current = check_compatible(connection)?;
+
+    // Table rebuilds need foreign keys off; the pragma is a no-op inside a transaction
+    connection.execute_batch("PRAGMA foreign_keys = OFF")?;
+    let result = apply_pending(connection, current);
+    connection.execute_batch("PRAGMA foreign_keys = ON")?;
+    result
+}
+
+fn apply_pending(connection: &Connection, current: i32) -> Result<i32, MigrationError> {
+    let mut version = current;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        log::info!(
+            "Applying schema migration {}: {}",
+            migration.version,
+            migration.description
+        );
+
+        let tx = connection.unchecked_transaction()?;
+        tx.execute_batch(migration.sql)?;
+
+        let violations: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM pragma_foreign_key_check",
+            [],
+            |row| row.get(0),
+        )?;
+        if violations > 0 {
+            return Err(MigrationError::ForeignKeyViolation { version: migration.version });
+        }
+
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        version = migration.version;
+    }
+
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database created by releases before versioning: baseline tables at user_version 0
+    fn baseline_fixture() -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(MIGRATIONS[0].sql).unwrap();
+        connection
+            .execute(
+                "INSERT INTO customers (customer_name, customer_type, address_line1, city, state, pincode)
+                 VALUES ('Sharma Traders', 'B2C', 'MG Road', 'Pune', 'Maharashtra', '411001')",
+                [],
+            )
+            .unwrap();
+        connection
+    }
+
+    #[test]
+    fn migrates_baseline_database_to_latest() {
+        let connection = baseline_fixture();
+        assert_eq!(current_version(&connection).unwrap(), 0);
+
+        assert_eq!(run_migrations(&connection).unwrap(), latest_version());
+        assert_eq!(current_version(&connection).unwrap(), latest_version());
+
+        let name: String = connection
+            .query_row("SELECT customer_name FROM customers", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(name, "Sharma Traders");
+    }
+
+    #[test]
+    fn migrations_are_idempotent() {
+        let connection = Connection::open_in_memory().unwrap();
+        run_migrations(&connection).unwrap();
+        assert_eq!(run_migrations(&connection).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn versions_are_strictly_increasing() {
+        for pair in MIGRATIONS.windows(2) {
+            assert_eq!(pair[1].version, pair[0].version + 1);
+        }
+    }
+
+    #[test]
+    fn issued_invoices_are_locked() {
+        let connection = baseline_fixture();
+        run_migrations(&connection).unwrap();
+        connection
+            .execute_batch(
+                "INSERT INTO products (product_code, product_name, hsn_sac_code, product_type, unit_of_measurement,
+                     rate, gst_rate)
+                 VALUES ('P-1', 'Steel rod', '7214', 'GOODS', 'PCS', 100, 18);
+                 INSERT INTO invoices (invoice_number, invoice_date, customer_id, place_of_supply, status)
+                 VALUES ('INV-1', '2026-09-01', 1, 'Maharashtra', 'DRAFT');
+                 INSERT INTO invoice_items (invoice_id, product_id, line_number, product_code, product_name,
+                     hsn_sac_code, quantity, unit_price, taxable_amount, gst_rate, line_total)
+                 VALUES (1, 1, 1, 'P-1', 'Steel rod', '7214', 1, 100, 100, 18, 118);
+                 UPDATE invoices SET final_amount = 118 WHERE id = 1;
+                 UPDATE invoices SET status = 'SENT' WHERE id = 1;",
+            )
+            .unwrap();
+
+        for sql in [
+            "UPDATE invoices SET final_amount = 1 WHERE id = 1",
+            "UPDATE invoice_items SET quantity = 2 WHERE invoice_id = 1",
+            "DELETE FROM invoice_items WHERE invoice_id = 1",
+            "INSERT INTO invoice_items (invoice_id, product_id, line_number, product_code, product_name,
+                 hsn_sac_code, quantity, unit_price, taxable_amount, gst_rate, line_total)
+             VALUES (1, 1, 2, 'P-1', 'Steel rod', '7214', 1, 100, 100, 18, 118)",
+            "DELETE FROM invoices WHERE id = 1",
+        ] {
+            assert!(connection.execute(sql, []).is_err(), "{}", sql);
+        }
+
+        connection
+            .execute_batch(
+                "UPDATE invoices SET pdf_path = '/tmp/INV-1.pdf' WHERE id = 1;
+                 UPDATE invoices SET status = 'CANCELLED', cancellation_reason = 'Wrong buyer' WHERE id = 1;",
+            )
+            .unwrap();
+        assert!(connection.execute("UPDATE invoices SET status = 'SENT' WHERE id = 1", []).is_err());
+    }
+
+    #[test]
+    fn issued_invoices_stay_issued() {
+        let connection = baseline_fixture();
+        run_migrations(&connection).unwrap();
+        connection
+            .execute_batch(
+                "INSERT INTO invoices (invoice_number, invoice_date, customer_id, place_of_supply, status)
+                 VALUES ('INV-1', '2026-09-01', 1, 'Maharashtra', 'SENT'),
+                        ('INV-2', '2026-09-02', 1, 'Maharashtra', 'PAID'),
+                        ('INV-3', '2026-09-03', 1, 'Maharashtra', 'OVERDUE'),
+                        ('INV-4', '2026-09-04', 1, 'Maharashtra', 'DRAFT');",
+            )
+            .unwrap();
+
+        for id in 1..=3 {
+            let sql = format!("UPDATE invoices SET status = 'DRAFT' WHERE id = {}", id);
+            assert!(connection.execute(&sql, []).is_err(), "{}", sql);
+        }
+        connection.execute("UPDATE invoices SET status = 'PAID' WHERE id = 1", []).unwrap();
+        connection.execute("UPDATE invoices SET status = 'DRAFT' WHERE id = 4", []).unwrap();
+    }
+
+    #[test]
+    fn audit_log_records_changes() {
+        let connection = baseline_fixture();
+        run_migrations(&connection).unwrap();
+        connection
+            .execute_batch(
+                "UPDATE audit_actor SET actor = 'priya' WHERE id = 1;
+                 UPDATE customers SET gstin = '27AAPFU0939F1ZV' WHERE id = 1;
+                 DELETE FROM customers WHERE id = 1;",
+            )
+            .unwrap();
+
+        let entries: Vec<(String, Option<String>, Option<String>, String)> = connection
+            .prepare("SELECT action, old_values, new_values, changed_by FROM audit_log WHERE table_name = 'customers'")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        // The timestamp trigger's own update of updated_at is not logged separately
+        assert_eq!(entries.len(), 2);
+        let (action, old, new, actor) = &entries[0];
+        assert_eq!((action.as_str(), actor.as_str()), ("UPDATE", "priya"));
+        assert!(old.as_deref().unwrap().contains(r#""gstin":null"#));
+        assert!(new.as_deref().unwrap().contains(r#""gstin":"27AAPFU0939F1ZV""#));
+        assert_eq!(entries[1].0, "DELETE");
+        assert!(entries[1].2.is_none());
+
+        assert!(connection.execute("DELETE FROM audit_log", []).is_err());
+        assert!(connection.execute("UPDATE audit_log SET changed_by = 'someone'", []).is_err());
+    }
+
+    #[test]
+    fn refuses_database_newer_than_binary() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        match run_migrations(&connection) {
+            Err(MigrationError::DatabaseTooNew { found, supported }) => {
+                assert_eq!(found, latest_version() + 1);
+                assert_eq!(supported, latest_version());
+            }
+            other => panic!("expected DatabaseTooNew, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Money Type
+// Fixed-point rupee amounts held as integer paise
+// =====================================================
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// A rupee amount held as a whole number of paise.
+///
+/// All arithmetic is integer arithmetic, so sums, tax splits and round-off are exact.
+/// Amounts cross the SQLite and JSON boundaries as rupees with two decimals and are
+/// rounded back to the nearest paisa on the way in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money::from_paise(0);
+
+    pub const fn from_paise(paise: i64) -> Self {
+        Money(paise)
+    }
+
+    /// Converts a rupee value to the nearest paisa
+    pub fn from_rupees(rupees: f64) -> Self {
+        Money::from_paise((rupees * 100.0).round() as i64)
+    }
+
+    #[cfg(feature = "gui")]
+    pub const fn paise(self) -> i64 {
+        self.0
+    }
+
+    pub fn to_rupees(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    /// Applies a percentage rate (e.g. 18.0 or 2.5), rounding half away from zero.
+    /// Rates are honoured to four decimal places.
+    pub fn percent(self, rate: f64) -> Self {
+        let basis_points = (rate * 10_000.0).round() as i128;
+        Money(div_round(self.0 as i128 * basis_points, 1_000_000))
+    }
+
+    /// Multiplies a unit price by a quantity, honoured to three decimal places
+    pub fn times(self, quantity: f64) -> Self {
+        let milli = (quantity * 1_000.0).round() as i128;
+        Money(div_round(self.0 as i128 * milli, 1_000))
+    }
+
+    /// Rounds to the nearest whole rupee, half away from zero
+    pub fn round_to_rupee(self) -> Self {
+        Money(div_round(self.0 as i128, 100) * 100)
+    }
+}
+
+fn div_round(numerator: i128, denominator: i128) -> i64 {
+    let half = denominator / 2;
+    let rounded = if numerator >= 0 {
+        (numerator + half) / denominator
+    } else {
+        (numerator - half) / denominator
+    };
+    rounded as i64
+}
+
+// =====================================================
+// Arithmetic
+// =====================================================
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let paise = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, paise / 100, paise % 100)
+    }
+}
+
+// =====================================================
+// SQLite Conversion
+// =====================================================
+
+impl ToSql for Money {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_rupees()))
+    }
+}
+
+impl FromSql for Money {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        // DECIMAL columns have NUMERIC affinity, so whole rupees come back as integers
+        match value {
+            ValueRef::Integer(rupees) => Ok(Money(rupees * 100)),
+            ValueRef::Real(rupees) => Ok(Money::from_rupees(rupees)),
+            ValueRef::Null => Ok(Money::ZERO),
+            ValueRef::Text(text) => std::str::from_utf8(text)
+                .ok()
+                .and_then(|text| text.trim().parse::<f64>().ok())
+                .map(Money::from_rupees)
+                .ok_or(FromSqlError::InvalidType),
+            ValueRef::Blob(_) => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+// =====================================================
+// Serde Representation
+// =====================================================
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_rupees())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+
+        impl Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a rupee amount as a number or decimal string")
+            }
+
+            fn visit_i64<E: de::Error>(self, rupees: i64) -> Result<Money, E> {
+                Ok(Money(rupees * 100))
+            }
+
+            fn visit_u64<E: de::Error>(self, rupees: u64) -> Result<Money, E> {
+                Ok(Money(rupees as i64 * 100))
+            }
+
+            fn visit_f64<E: de::Error>(self, rupees: f64) -> Result<Money, E> {
+                Ok(Money::from_rupees(rupees))
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<Money, E> {
+                text.trim()
+                    .parse::<f64>()
+                    .map(Money::from_rupees)
+                    .map_err(|_| E::custom(format!("invalid amount: {}", text)))
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_without_floating_point_drift() {
+        let total: Money = (0..10_000).map(|_| Money::from_rupees(0.1)).sum();
+        assert_eq!(total, Money::from_rupees(1000.0));
+    }
+
+    #[test]
+    fn applies_percentages_with_half_up_rounding() {
+        assert_eq!(Money::from_rupees(99.99).percent(9.0), Money::from_paise(900));
+        assert_eq!(Money::from_rupees(225.0).percent(2.5), Money::from_paise(563));
+        assert_eq!(Money::from_rupees(-225.0).percent(2.5), Money::from_paise(-563));
+    }
+
+    #[test]
+    fn multiplies_by_fractional_quantity() {
+        assert_eq!(Money::from_rupees(33.33).times(3.0), Money::from_rupees(99.99));
+        assert_eq!(Money::from_rupees(120.0).times(1.255), Money::from_rupees(150.6));
+    }
+
+    #[test]
+    fn rounds_to_nearest_rupee() {
+        assert_eq!(Money::from_rupees(354.25).round_to_rupee(), Money::from_rupees(354.0));
+        assert_eq!(Money::from_rupees(354.50).round_to_rupee(), Money::from_rupees(355.0));
+    }
+
+    #[test]
+    fn round_trips_through_json_and_sqlite() {
+        let amount = Money::from_paise(12_000_050);
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "120000.5");
+        assert_eq!(serde_json::from_str::<Money>("120000.5").unwrap(), amount);
+        assert_eq!(serde_json::from_str::<Money>("\"120000.50\"").unwrap(), amount);
+        assert_eq!(amount.to_string(), "120000.50");
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (amount DECIMAL(15,2))").unwrap();
+        conn.execute("INSERT INTO t VALUES (?1), (?2)", [amount, Money::from_rupees(118.0)])
+            .unwrap();
+        let mut stmt = conn.prepare("SELECT amount FROM t").unwrap();
+        let values: Vec<Money> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|v| v.unwrap())
+            .collect();
+        assert_eq!(values, vec![amount, Money::from_rupees(118.0)]);
+    }
+}
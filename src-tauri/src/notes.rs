@@ -0,0 +1,318 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Credit and Debit Notes
+// Rules for notes issued against earlier invoices
+// =====================================================
+//
+// A note amends one or more issued invoices of the same customer, carrying a share of
+// its value against each. Credit notes lower the value still due on an invoice and may
+// never take it below zero; debit notes raise it. Notes are numbered from their own
+// series and only count against an invoice once issued.
+
+use crate::database::{Invoice, NoteAllocation};
+use crate::lifecycle::StatusError;
+use crate::money::Money;
+use std::fmt;
+
+pub const NOTE_REASONS: [&str; 7] = [
+    "SALES_RETURN",
+    "POST_SALE_DISCOUNT",
+    "DEFICIENCY_IN_SERVICES",
+    "CORRECTION_IN_INVOICE",
+    "CHANGE_IN_POS",
+    "FINALISATION_OF_PROVISIONAL_ASSESSMENT",
+    "OTHERS",
+];
+
+pub const CREDIT_NOTE_FORMAT: &str = "CN-{YYYY}-{MM}-{####}";
+pub const DEBIT_NOTE_FORMAT: &str = "DN-{YYYY}-{MM}-{####}";
+
+pub fn is_note(invoice_type: &str) -> bool {
+    invoice_type == "CREDIT_NOTE" || invoice_type == "DEBIT_NOTE"
+}
+
+/// Numbering series used for a note type when the caller does not pick one
+pub fn default_format(invoice_type: &str) -> &'static str {
+    if invoice_type == "CREDIT_NOTE" { CREDIT_NOTE_FORMAT } else { DEBIT_NOTE_FORMAT }
+}
+
+/// Printable form of a reason code, as shown on the note
+#[cfg(feature = "gui")]
+pub fn reason_label(reason: &str) -> &str {
+    match reason {
+        "SALES_RETURN" => "Sales return",
+        "POST_SALE_DISCOUNT" => "Post-sale discount",
+        "DEFICIENCY_IN_SERVICES" => "Deficiency in services",
+        "CORRECTION_IN_INVOICE" => "Correction in invoice",
+        "CHANGE_IN_POS" => "Change in place of supply",
+        "FINALISATION_OF_PROVISIONAL_ASSESSMENT" => "Finalisation of provisional assessment",
+        "OTHERS" => "Others",
+        _ => reason,
+    }
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum NoteError {
+    NotANote(String),
+    SavedAsInvoice(String),
+    InvalidReason(Option<String>),
+    NoReferences,
+    DuplicateReference(i64),
+    InvalidAmount(Money),
+    AllocationMismatch { allocated: Money, note_value: Money },
+    InvoiceNotFound(i64),
+    InvalidOriginal { invoice_number: String, problem: &'static str },
+    ExceedsRemaining {
+        invoice_number: String,
+        remaining: Money,
+        amount: Money,
+    },
+    Status(StatusError),
+    Sqlite(rusqlite::Error),
+}
+
+impl NoteError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            NoteError::NotANote(_)
+            | NoteError::SavedAsInvoice(_)
+            | NoteError::InvalidReason(_)
+            | NoteError::NoReferences
+            | NoteError::DuplicateReference(_)
+            | NoteError::InvalidAmount(_)
+            | NoteError::AllocationMismatch { .. } => "InvalidNote",
+            NoteError::InvoiceNotFound(_) => "NotFound",
+            NoteError::InvalidOriginal { .. } => "InvalidOriginalInvoice",
+            NoteError::ExceedsRemaining { .. } => "CreditExceedsInvoice",
+            NoteError::Status(err) => err.code(),
+            NoteError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for NoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteError::NotANote(invoice_type) => {
+                write!(f, "Expected a CREDIT_NOTE or DEBIT_NOTE, got {}", invoice_type)
+            }
+            NoteError::SavedAsInvoice(invoice_type) => write!(
+                f,
+                "A {} is saved as a note, against the invoices it amends, not as an invoice",
+                invoice_type
+            ),
+            NoteError::InvalidReason(Some(reason)) => write!(
+                f,
+                "Unknown note reason {}; use one of {}",
+                reason,
+                NOTE_REASONS.join(", ")
+            ),
+            NoteError::InvalidReason(None) => f.write_str("A credit or debit note must state its reason"),
+            NoteError::NoReferences => f.write_str("A note must be issued against at least one invoice"),
+            NoteError::DuplicateReference(id) => {
+                write!(f, "Invoice {} appears more than once on the note", id)
+            }
+            NoteError::InvalidAmount(amount) => {
+                write!(f, "Amount against an invoice must be greater than 0, got {}", amount)
+            }
+            NoteError::AllocationMismatch { allocated, note_value } => write!(
+                f,
+                "Amounts against the invoices add up to {} but the note is for {}",
+                allocated, note_value
+            ),
+            NoteError::InvoiceNotFound(id) => write!(f, "Invoice {} does not exist", id),
+            NoteError::InvalidOriginal { invoice_number, problem } => {
+                write!(f, "Invoice {} {} and cannot be amended by this note", invoice_number, problem)
+            }
+            NoteError::ExceedsRemaining { invoice_number, remaining, amount } => write!(
+                f,
+                "Credit of {} exceeds the remaining {} on invoice {}",
+                amount, remaining, invoice_number
+            ),
+            NoteError::Status(err) => write!(f, "{}", err),
+            NoteError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for NoteError {}
+
+impl From<rusqlite::Error> for NoteError {
+    fn from(err: rusqlite::Error) -> Self {
+        NoteError::Sqlite(err)
+    }
+}
+
+impl From<StatusError> for NoteError {
+    fn from(err: StatusError) -> Self {
+        match err {
+            StatusError::Note(err) => *err,
+            err => NoteError::Status(err),
+        }
+    }
+}
+
+// =====================================================
+// Note Rules
+// =====================================================
+
+/// Checks the note itself and that its amounts against invoices add up to its value
+pub fn validate_note(note: &Invoice, allocations: &[NoteAllocation]) -> Result<(), NoteError> {
+    if !is_note(&note.invoice_type) {
+        return Err(NoteError::NotANote(note.invoice_type.clone()));
+    }
+    match note.note_reason.as_deref() {
+        Some(reason) if NOTE_REASONS.contains(&reason) => {}
+        reason => return Err(NoteError::InvalidReason(reason.map(str::to_string))),
+    }
+    if allocations.is_empty() {
+        return Err(NoteError::NoReferences);
+    }
+    for (index, allocation) in allocations.iter().enumerate() {
+        if allocation.amount <= Money::ZERO {
+            return Err(NoteError::InvalidAmount(allocation.amount));
+        }
+        if allocations[..index].iter().any(|earlier| earlier.invoice_id == allocation.invoice_id) {
+            return Err(NoteError::DuplicateReference(allocation.invoice_id));
+        }
+    }
+
+    let allocated: Money = allocations.iter().map(|allocation| allocation.amount).sum();
+    if allocated != note.final_amount {
+        return Err(NoteError::AllocationMismatch { allocated, note_value: note.final_amount });
+    }
+    Ok(())
+}
+
+/// Checks that `original` may be amended by `amount` of `note`. `adjustments` is the net
+/// of the other issued notes already against it, positive when debits outweigh credits.
+pub fn check_original(note: &Invoice, original: &Invoice, adjustments: Money, amount: Money) -> Result<(), NoteError> {
+    let problem = if is_note(&original.invoice_type) {
+        Some("is itself a credit or debit note")
+    } else if original.status == "DRAFT" {
+        Some("has not been issued")
+    } else if original.status == "CANCELLED" {
+        Some("is cancelled")
+    } else if original.customer_id != note.customer_id {
+        Some("was issued to a different customer")
+    } else if original.invoice_date > note.invoice_date {
+        Some("is dated after the note")
+    } else {
+        None
+    };
+    if let Some(problem) = problem {
+        return Err(NoteError::InvalidOriginal {
+            invoice_number: original.invoice_number.clone(),
+            problem,
+        });
+    }
+
+    let remaining = original.final_amount + adjustments;
+    if note.invoice_type == "CREDIT_NOTE" && amount > remaining {
+        return Err(NoteError::ExceedsRemaining {
+            invoice_number: original.invoice_number.clone(),
+            remaining,
+            amount,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reports::fixtures::{customer, document};
+
+    fn rupees(amount: f64) -> Money {
+        Money::from_rupees(amount)
+    }
+
+    fn credit_note(value: f64) -> Invoice {
+        let mut note = document("CN-1", "CREDIT_NOTE", "Maharashtra", value, customer(None, "B2C")).invoice;
+        note.invoice_date = "2026-09-20".to_string();
+        note.note_reason = Some("SALES_RETURN".to_string());
+        note
+    }
+
+    fn against(amounts: &[(i64, f64)]) -> Vec<NoteAllocation> {
+        amounts
+            .iter()
+            .map(|&(invoice_id, amount)| NoteAllocation { invoice_id, amount: rupees(amount) })
+            .collect()
+    }
+
+    fn original() -> Invoice {
+        document("INV-1", "REGULAR", "Maharashtra", 1000.0, customer(None, "B2C")).invoice
+    }
+
+    #[test]
+    fn rejects_invalid_notes() {
+        let note = credit_note(100.0);
+        assert_eq!(note.final_amount, rupees(118.0));
+        assert!(validate_note(&note, &against(&[(1, 18.0), (2, 100.0)])).is_ok());
+
+        assert!(matches!(
+            validate_note(&original(), &against(&[(1, 1180.0)])),
+            Err(NoteError::NotANote(_))
+        ));
+        let unexplained = Invoice { note_reason: None, ..note.clone() };
+        assert!(matches!(
+            validate_note(&unexplained, &against(&[(1, 118.0)])),
+            Err(NoteError::InvalidReason(None))
+        ));
+        assert!(matches!(validate_note(&note, &[]), Err(NoteError::NoReferences)));
+        assert!(matches!(
+            validate_note(&note, &against(&[(1, 118.0), (2, 0.0)])),
+            Err(NoteError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            validate_note(&note, &against(&[(1, 18.0), (1, 100.0)])),
+            Err(NoteError::DuplicateReference(1))
+        ));
+        assert!(matches!(
+            validate_note(&note, &against(&[(1, 100.0)])),
+            Err(NoteError::AllocationMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn credit_cannot_exceed_remaining_value() {
+        let note = credit_note(100.0);
+        let invoice = original();
+        assert_eq!(invoice.final_amount, rupees(1180.0));
+
+        assert!(check_original(&note, &invoice, Money::ZERO, rupees(1180.0)).is_ok());
+        assert!(matches!(
+            check_original(&note, &invoice, -rupees(1100.0), rupees(118.0)),
+            Err(NoteError::ExceedsRemaining { remaining, .. }) if remaining == rupees(80.0)
+        ));
+        // An earlier debit note raises what may be credited
+        assert!(check_original(&note, &invoice, rupees(100.0), rupees(1280.0)).is_ok());
+
+        let debit = Invoice { invoice_type: "DEBIT_NOTE".to_string(), ..note };
+        assert!(check_original(&debit, &invoice, -rupees(1180.0), rupees(500.0)).is_ok());
+    }
+
+    #[test]
+    fn only_issued_invoices_of_the_customer_can_be_amended() {
+        let note = credit_note(100.0);
+        let amount = rupees(118.0);
+
+        let problems = [
+            Invoice { status: "DRAFT".to_string(), ..original() },
+            Invoice { status: "CANCELLED".to_string(), ..original() },
+            Invoice { customer_id: 2, ..original() },
+            Invoice { invoice_date: "2026-09-21".to_string(), ..original() },
+            credit_note(50.0),
+        ];
+        for invoice in &problems {
+            assert!(matches!(
+                check_original(&note, invoice, Money::ZERO, amount),
+                Err(NoteError::InvalidOriginal { .. })
+            ));
+        }
+    }
+}
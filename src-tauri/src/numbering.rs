@@ -0,0 +1,412 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Invoice Numbering
+// Template parsing and rendering for numbering series
+// =====================================================
+//
+// A template such as `INV/{FY}/{####}` is made of literal text and tokens:
+//   {YYYY} {YY}  calendar year        {MM} {DD}  month and day
+//   {FY}         financial year, e.g. 2026-27
+//   {#...}       the running counter, zero-padded to the number of '#'
+// GST rule 46 limits invoice numbers to 16 characters of letters, digits, '-' and '/'.
+//
+// Each series may restart its counter per financial year, calendar year or month.
+// Numbers are drawn when a document is issued; until then a draft is saved as
+// `DRAFT-<id>`, so drafts that are deleted never leave a gap in the series.
+// Dates are taken on the Indian Standard Time clock.
+
+use chrono::{Datelike, FixedOffset, NaiveDate, Utc};
+use std::fmt;
+
+pub const DEFAULT_FORMAT: &str = "INV-{YYYY}-{MM}-{####}";
+pub const MAX_INVOICE_NUMBER_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(String),
+    Year,
+    ShortYear,
+    Month,
+    Day,
+    FinancialYear,
+    Counter(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberingError {
+    UnknownToken(String),
+    UnclosedToken,
+    MissingCounter,
+    MultipleCounters,
+    InvalidCharacter(char),
+    TooLong { length: usize },
+    NumberTooLong(String),
+    InvalidResetRule(String),
+    #[cfg(feature = "gui")]
+    ResetNeedsToken { rule: ResetRule, token: &'static str },
+}
+
+impl fmt::Display for NumberingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberingError::UnknownToken(token) => write!(f, "Unknown token {{{}}} in invoice number format", token),
+            NumberingError::UnclosedToken => f.write_str("Invoice number format has an unclosed '{'"),
+            NumberingError::MissingCounter => {
+                f.write_str("Invoice number format must contain a counter such as {####}")
+            }
+            NumberingError::MultipleCounters => {
+                f.write_str("Invoice number format may contain only one counter")
+            }
+            NumberingError::InvalidCharacter(c) => write!(
+                f,
+                "Invoice numbers may only contain letters, digits, '-' and '/', found '{}'",
+                c
+            ),
+            NumberingError::TooLong { length } => write!(
+                f,
+                "Invoice numbers from this format are {} characters; GST allows at most {}",
+                length, MAX_INVOICE_NUMBER_LENGTH
+            ),
+            NumberingError::NumberTooLong(number) => write!(
+                f,
+                "The next invoice number {} is {} characters; GST allows at most {}. Widen the counter or start a new series",
+                number,
+                number.len(),
+                MAX_INVOICE_NUMBER_LENGTH
+            ),
+            NumberingError::InvalidResetRule(rule) => write!(
+                f,
+                "Unknown reset rule {}; use NEVER, FINANCIAL_YEAR, CALENDAR_YEAR or MONTHLY",
+                rule
+            ),
+            #[cfg(feature = "gui")]
+            NumberingError::ResetNeedsToken { rule, token } => write!(
+                f,
+                "A series that resets {} must include {} in its format so numbers stay unique",
+                rule.describe(),
+                token
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NumberingError {}
+
+/// Placeholder number a draft is saved under until it is issued
+pub fn draft_number(invoice_id: i64) -> String {
+    format!("DRAFT-{}", invoice_id)
+}
+
+/// Financial year (April to March) containing `date`, rendered as `2026-27`
+pub fn financial_year(date: NaiveDate) -> String {
+    let start = if date.month() >= 4 { date.year() } else { date.year() - 1 };
+    format!("{}-{:02}", start, (start + 1) % 100)
+}
+
+/// Today's date in India (UTC+05:30), regardless of the machine's time zone
+pub fn today_ist() -> NaiveDate {
+    let ist = FixedOffset::east_opt(5 * 3600 + 30 * 60).expect("IST offset is in range");
+    Utc::now().with_timezone(&ist).date_naive()
+}
+
+// =====================================================
+// Reset Rules
+// =====================================================
+
+/// When a series restarts its counter at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetRule {
+    Never,
+    FinancialYear,
+    CalendarYear,
+    Monthly,
+}
+
+impl ResetRule {
+    pub fn parse(rule: &str) -> Result<Self, NumberingError> {
+        match rule {
+            "NEVER" => Ok(ResetRule::Never),
+            "FINANCIAL_YEAR" => Ok(ResetRule::FinancialYear),
+            "CALENDAR_YEAR" => Ok(ResetRule::CalendarYear),
+            "MONTHLY" => Ok(ResetRule::Monthly),
+            _ => Err(NumberingError::InvalidResetRule(rule.to_string())),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResetRule::Never => "NEVER",
+            ResetRule::FinancialYear => "FINANCIAL_YEAR",
+            ResetRule::CalendarYear => "CALENDAR_YEAR",
+            ResetRule::Monthly => "MONTHLY",
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    fn describe(self) -> &'static str {
+        match self {
+            ResetRule::Never => "never",
+            ResetRule::FinancialYear => "every 1 April",
+            ResetRule::CalendarYear => "every 1 January",
+            ResetRule::Monthly => "every month",
+        }
+    }
+
+    /// Key of the counter period containing `date`; series that never reset share one period
+    pub fn period(self, date: NaiveDate) -> String {
+        match self {
+            ResetRule::Never => String::new(),
+            ResetRule::FinancialYear => financial_year(date),
+            ResetRule::CalendarYear => format!("{:04}", date.year()),
+            ResetRule::Monthly => format!("{:04}-{:02}", date.year(), date.month()),
+        }
+    }
+
+    /// Whether a token's value is fixed within one counter period
+    fn fixes(self, token: &Token) -> bool {
+        match self {
+            ResetRule::Never => false,
+            ResetRule::FinancialYear => matches!(token, Token::FinancialYear),
+            ResetRule::CalendarYear => matches!(token, Token::Year | Token::ShortYear),
+            ResetRule::Monthly => matches!(token, Token::Year | Token::ShortYear | Token::Month),
+        }
+    }
+}
+
+/// A parsed invoice number template
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberFormat {
+    template: String,
+    tokens: Vec<Token>,
+}
+
+impl NumberFormat {
+    pub fn parse(template: &str) -> Result<Self, NumberingError> {
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.trim().chars();
+
+        while let Some(c) = chars.next() {
+            if c != '{' {
+                if !(c.is_ascii_alphanumeric() || c == '-' || c == '/') {
+                    return Err(NumberingError::InvalidCharacter(c));
+                }
+                literal.push(c);
+                continue;
+            }
+
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) => name.push(c),
+                    None => return Err(NumberingError::UnclosedToken),
+                }
+            }
+
+            if !literal.is_empty() {
+                tokens.push(Token::Literal(std::mem::take(&mut literal)));
+            }
+            tokens.push(match name.as_str() {
+                "YYYY" => Token::Year,
+                "YY" => Token::ShortYear,
+                "MM" => Token::Month,
+                "DD" => Token::Day,
+                "FY" => Token::FinancialYear,
+                hashes if !hashes.is_empty() && hashes.chars().all(|c| c == '#') => {
+                    Token::Counter(hashes.len())
+                }
+                _ => return Err(NumberingError::UnknownToken(name)),
+            });
+        }
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+
+        match tokens.iter().filter(|t| matches!(t, Token::Counter(_))).count() {
+            0 => return Err(NumberingError::MissingCounter),
+            1 => {}
+            _ => return Err(NumberingError::MultipleCounters),
+        }
+
+        let format = NumberFormat {
+            template: template.trim().to_string(),
+            tokens,
+        };
+        let length = format.render(NaiveDate::default(), 1).len();
+        if length > MAX_INVOICE_NUMBER_LENGTH {
+            return Err(NumberingError::TooLong { length });
+        }
+
+        Ok(format)
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    fn has(&self, wanted: &[Token]) -> bool {
+        self.tokens.iter().any(|token| wanted.contains(token))
+    }
+
+    /// Reset rule for a series created implicitly: templates carrying {FY} restart every April
+    pub fn default_reset_rule(&self) -> ResetRule {
+        if self.has(&[Token::FinancialYear]) {
+            ResetRule::FinancialYear
+        } else {
+            ResetRule::Never
+        }
+    }
+
+    /// Checks that numbers stay unique after the counter restarts under `rule`
+    #[cfg(feature = "gui")]
+    pub fn check_reset_rule(&self, rule: ResetRule) -> Result<(), NumberingError> {
+        let year = [Token::Year, Token::ShortYear];
+        let (supported, token) = match rule {
+            ResetRule::Never => (true, ""),
+            ResetRule::FinancialYear => (self.has(&[Token::FinancialYear]), "{FY}"),
+            ResetRule::CalendarYear => (self.has(&year), "{YYYY} or {YY}"),
+            ResetRule::Monthly => (
+                self.has(&[Token::Month]) && self.has(&year),
+                "{MM} with {YYYY} or {YY}",
+            ),
+        };
+        if supported {
+            Ok(())
+        } else {
+            Err(NumberingError::ResetNeedsToken { rule, token })
+        }
+    }
+
+    /// Renders the invoice number for a date and counter value
+    pub fn render(&self, date: NaiveDate, counter: i64) -> String {
+        self.tokens
+            .iter()
+            .map(|token| self.render_token(token, date, counter))
+            .collect()
+    }
+
+    /// Renders the number to issue for a counter value, refusing one that has outgrown
+    /// the rule 46 limit because the counter ran past its padding
+    pub fn render_checked(&self, date: NaiveDate, counter: i64) -> Result<String, NumberingError> {
+        let number = self.render(date, counter);
+        if number.len() > MAX_INVOICE_NUMBER_LENGTH {
+            return Err(NumberingError::NumberTooLong(number));
+        }
+        Ok(number)
+    }
+
+    fn render_token(&self, token: &Token, date: NaiveDate, counter: i64) -> String {
+        match token {
+            Token::Literal(text) => text.clone(),
+            Token::Year => format!("{:04}", date.year()),
+            Token::ShortYear => format!("{:02}", date.year() % 100),
+            Token::Month => format!("{:02}", date.month()),
+            Token::Day => format!("{:02}", date.day()),
+            Token::FinancialYear => financial_year(date),
+            Token::Counter(width) => format!("{:0width$}", counter, width = *width),
+        }
+    }
+
+    /// Regex that matches numbers rendered from this template in the counter period of
+    /// `date` under `rule`, capturing the counter
+    pub fn counter_pattern(&self, rule: ResetRule, date: NaiveDate) -> regex::Regex {
+        let pattern: String = self
+            .tokens
+            .iter()
+            .map(|token| match token {
+                Token::Literal(text) => regex::escape(text),
+                token if rule.fixes(token) => regex::escape(&self.render_token(token, date, 0)),
+                Token::Year => r"\d{4}".to_string(),
+                Token::ShortYear | Token::Month | Token::Day => r"\d{2}".to_string(),
+                Token::FinancialYear => r"\d{4}-\d{2}".to_string(),
+                Token::Counter(_) => r"(\d+)".to_string(),
+            })
+            .collect();
+        regex::Regex::new(&format!("^{}$", pattern)).expect("escaped template is a valid regex")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn renders_all_tokens() {
+        let format = NumberFormat::parse("A{YY}{MM}{DD}/{####}").unwrap();
+        assert_eq!(format.render(date(2026, 9, 5), 42), "A260905/0042");
+
+        let format = NumberFormat::parse(DEFAULT_FORMAT).unwrap();
+        assert_eq!(format.render(date(2026, 9, 5), 7), "INV-2026-09-0007");
+    }
+
+    #[test]
+    fn renders_financial_year() {
+        let format = NumberFormat::parse("{FY}/{###}").unwrap();
+        assert_eq!(format.render(date(2026, 4, 1), 1), "2026-27/001");
+        assert_eq!(format.render(date(2027, 3, 31), 12), "2026-27/012");
+        assert_eq!(format.render(date(2099, 12, 1), 1), "2099-00/001");
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert_eq!(NumberFormat::parse("INV-{YYYY}"), Err(NumberingError::MissingCounter));
+        assert_eq!(NumberFormat::parse("{##}{##}"), Err(NumberingError::MultipleCounters));
+        assert_eq!(
+            NumberFormat::parse("{Q}-{##}"),
+            Err(NumberingError::UnknownToken("Q".to_string()))
+        );
+        assert_eq!(NumberFormat::parse("INV {##}"), Err(NumberingError::InvalidCharacter(' ')));
+        assert_eq!(NumberFormat::parse("{##"), Err(NumberingError::UnclosedToken));
+        assert_eq!(
+            NumberFormat::parse("INVOICE-{YYYY}-{MM}-{####}"),
+            Err(NumberingError::TooLong { length: 20 })
+        );
+    }
+
+    #[test]
+    fn extracts_counter_from_rendered_numbers() {
+        let format = NumberFormat::parse("INV/{FY}/{####}").unwrap();
+        let pattern = format.counter_pattern(ResetRule::Never, date(2026, 9, 1));
+        let captures = pattern.captures("INV/2026-27/0153").unwrap();
+        assert_eq!(&captures[1], "0153");
+        assert!(pattern.is_match("INV/2025-26/0200"));
+        assert!(!pattern.is_match("INV-2026-09-0001"));
+    }
+
+    #[test]
+    fn scopes_counter_pattern_to_reset_period() {
+        let format = NumberFormat::parse("S/{FY}/{MM}/{###}").unwrap();
+        let pattern = format.counter_pattern(ResetRule::FinancialYear, date(2027, 2, 1));
+        assert!(pattern.is_match("S/2026-27/04/001"));
+        assert!(pattern.is_match("S/2026-27/03/099"));
+        assert!(!pattern.is_match("S/2025-26/03/300"));
+    }
+
+    #[test]
+    fn computes_reset_periods() {
+        let march = date(2027, 3, 31);
+        let april = date(2027, 4, 1);
+        assert_eq!(ResetRule::Never.period(march), ResetRule::Never.period(april));
+        assert_eq!(ResetRule::FinancialYear.period(march), "2026-27");
+        assert_eq!(ResetRule::FinancialYear.period(april), "2027-28");
+        assert_eq!(ResetRule::CalendarYear.period(march), "2027");
+        assert_eq!(ResetRule::Monthly.period(april), "2027-04");
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn requires_period_tokens_for_reset_rules() {
+        let format = NumberFormat::parse(DEFAULT_FORMAT).unwrap();
+        assert_eq!(format.default_reset_rule(), ResetRule::Never);
+        assert!(format.check_reset_rule(ResetRule::Monthly).is_ok());
+        assert!(format.check_reset_rule(ResetRule::FinancialYear).is_err());
+
+        let format = NumberFormat::parse("{FY}/{####}").unwrap();
+        assert_eq!(format.default_reset_rule(), ResetRule::FinancialYear);
+        assert!(format.check_reset_rule(ResetRule::CalendarYear).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Shared Operations
+// Error mapping and helpers shared by the app, the CLI and the API server
+// =====================================================
+//
+// Nothing here depends on tauri, so the headless CLI builds without the GUI feature.
+// The Tauri commands in commands.rs wrap these for the frontend.
+
+use crate::api_server::ServerError;
+use crate::audit::{self, AuditError};
+use crate::backup::BackupError;
+use crate::database::{Database, Invoice, InvoiceItem, NoteAllocation};
+#[cfg(feature = "gui")]
+use crate::einvoice::EInvoiceError;
+#[cfg(feature = "gui")]
+use crate::ewaybill::EWayBillError;
+use crate::gst::{self, GstError};
+use crate::gstr1::{self, Gstr1Return};
+use crate::gstr3b::{self, Gstr3bSummary};
+use crate::import::ImportError;
+use crate::lifecycle::StatusError;
+use crate::migrations::MigrationError;
+use crate::notes::{self, NoteError};
+use crate::numbering::{self, NumberFormat, NumberingError};
+use crate::payments::PaymentError;
+#[cfg(feature = "gui")]
+use crate::pdf::PdfError;
+use crate::rates::{self, RateError, RateMismatch};
+use crate::reports::{ReportError, ReturnPeriod};
+use crate::tally::TallyError;
+#[cfg(feature = "gui")]
+use crate::templates::TemplateError;
+use serde::{Deserialize, Serialize};
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiError {
+    pub error: String,
+    pub message: String,
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(err: rusqlite::Error) -> Self {
+        ApiError {
+            error: "DatabaseError".to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for ApiError {
+    fn from(err: Box<dyn std::error::Error>) -> Self {
+        ApiError {
+            error: "GeneralError".to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<MigrationError> for ApiError {
+    fn from(err: MigrationError) -> Self {
+        let error = match err {
+            MigrationError::DatabaseTooNew { .. } => "DatabaseTooNew",
+            MigrationError::ForeignKeyViolation { .. } => "MigrationFailed",
+            MigrationError::Sqlite(_) => "DatabaseError",
+        };
+        ApiError {
+            error: error.to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<NumberingError> for ApiError {
+    fn from(err: NumberingError) -> Self {
+        let error = match err {
+            NumberingError::InvalidResetRule(_) => "InvalidResetRule",
+            #[cfg(feature = "gui")]
+            NumberingError::ResetNeedsToken { .. } => "InvalidResetRule",
+            _ => "InvalidNumberFormat",
+        };
+        ApiError {
+            error: error.to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<GstError> for ApiError {
+    fn from(err: GstError) -> Self {
+        let error = match err {
+            GstError::InvalidGstRate(_) => "InvalidGstRate",
+            GstError::InvalidLineItem(_) | GstError::EmptyInvoice => "ValidationError",
+            GstError::TotalsMismatch { .. } => "TotalsMismatch",
+        };
+        ApiError {
+            error: error.to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<PaymentError> for ApiError {
+    fn from(err: PaymentError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<StatusError> for ApiError {
+    fn from(err: StatusError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<RateError> for ApiError {
+    fn from(err: RateError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ImportError> for ApiError {
+    fn from(err: ImportError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<TallyError> for ApiError {
+    fn from(err: TallyError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<BackupError> for ApiError {
+    fn from(err: BackupError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<AuditError> for ApiError {
+    fn from(err: AuditError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<NoteError> for ApiError {
+    fn from(err: NoteError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl From<EInvoiceError> for ApiError {
+    fn from(err: EInvoiceError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl From<EWayBillError> for ApiError {
+    fn from(err: EWayBillError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl From<PdfError> for ApiError {
+    fn from(err: PdfError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl From<TemplateError> for ApiError {
+    fn from(err: TemplateError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ServerError> for ApiError {
+    fn from(err: ServerError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ReportError> for ApiError {
+    fn from(err: ReportError) -> Self {
+        ApiError {
+            error: err.code().to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+pub type CommandResult<T> = Result<T, ApiError>;
+
+// =====================================================
+// Database
+// =====================================================
+
+/// Opens the database at `db_path` the way every front end does: migrated to the latest
+/// schema, changes attributed to the current account and overdue invoices marked
+pub fn open_database(db_path: &std::path::Path) -> CommandResult<Database> {
+    let db = Database::new(db_path).map_err(ApiError::from)?;
+    
+    // Bring the schema up to date; refuses databases written by a newer build
+    db.initialize_schema().map_err(ApiError::from)?;
+    
+    // Attribute changes to the operating system account until the user names themselves
+    db.set_audit_actor(&audit::default_actor()).map_err(ApiError::from)?;
+    
+    // Catch up on invoices that fell due while the app was closed
+    db.mark_overdue_invoices(numbering::today_ist()).map_err(ApiError::from)?;
+    
+    Ok(db)
+}
+
+// =====================================================
+// Invoices and Notes
+// =====================================================
+
+/// Saved invoice id with the lines whose rates differ from the GST rate master
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedInvoice {
+    pub id: i64,
+    pub rate_mismatches: Vec<RateMismatch>,
+}
+
+/// Saves an invoice as the app does, whichever front end it came from
+pub fn store_invoice(
+    db: &Database,
+    invoice: &Invoice,
+    items: &[InvoiceItem],
+    number_format: Option<&str>,
+) -> CommandResult<SavedInvoice> {
+    // Tax figures are recomputed from the supplier state so stored totals are authoritative
+    let company = db.get_company_settings().map_err(ApiError::from)?.ok_or_else(|| ApiError {
+        error: "CompanySettingsMissing".to_string(),
+        message: "Company settings must be saved before issuing invoices".to_string(),
+    })?;
+    let (mut invoice, items) = gst::recompute_invoice(&company.state, invoice, items)?;
+    if invoice.invoice_date.trim().is_empty() {
+        invoice.invoice_date = numbering::today_ist().format("%Y-%m-%d").to_string();
+    }
+    
+    // A draft with a blank invoice_number draws the next number from this series when issued
+    let number_format =
+        NumberFormat::parse(number_format.unwrap_or(numbering::DEFAULT_FORMAT))?;
+    
+    // Checked before saving so a refused date leaves nothing written
+    let invoice_date = rates::parse_date(&invoice.invoice_date)?;
+    
+    let id = db.save_invoice(&invoice, &items, &number_format, numbering::today_ist()).map_err(ApiError::from)?;
+    
+    // Rates are the user's call; disagreements with the rate master are flagged, not refused
+    let rate_mismatches = db.check_item_rates(invoice_date, &items).map_err(ApiError::from)?;
+    Ok(SavedInvoice { id, rate_mismatches })
+}
+
+/// Saves a credit or debit note as the app does, whichever front end it came from
+pub fn store_note(
+    db: &Database,
+    note: &Invoice,
+    items: &[InvoiceItem],
+    references: &[NoteAllocation],
+    number_format: Option<&str>,
+) -> CommandResult<i64> {
+    let company = db.get_company_settings().map_err(ApiError::from)?.ok_or_else(|| ApiError {
+        error: "CompanySettingsMissing".to_string(),
+        message: "Company settings must be saved before issuing notes".to_string(),
+    })?;
+    let (mut note, items) = gst::recompute_invoice(&company.state, note, items)?;
+    if note.invoice_date.trim().is_empty() {
+        note.invoice_date = numbering::today_ist().format("%Y-%m-%d").to_string();
+    }
+    
+    let number_format = NumberFormat::parse(
+        number_format.unwrap_or(notes::default_format(&note.invoice_type)),
+    )?;
+    
+    let id = db.save_note(&note, &items, references, &number_format, numbering::today_ist())?;
+    Ok(id)
+}
+
+// =====================================================
+// GST Returns
+// =====================================================
+
+pub fn compute_gstr1(db: &Database, return_period: &str) -> CommandResult<Gstr1Return> {
+    let period = ReturnPeriod::parse(return_period)?;
+    let company = db
+        .get_company_settings()
+        .map_err(ApiError::from)?
+        .ok_or(ReportError::CompanySettingsMissing)?;
+    let documents = db
+        .get_return_invoices(period.first_day(), period.last_day())
+        .map_err(ApiError::from)?;
+    
+    Ok(gstr1::build_gstr1(&company.gstin, &company.state, &period, &documents))
+}
+
+pub fn compute_gstr3b(db: &Database, return_period: &str) -> CommandResult<Gstr3bSummary> {
+    let period = ReturnPeriod::parse(return_period)?;
+    let company = db
+        .get_company_settings()
+        .map_err(ApiError::from)?
+        .ok_or(ReportError::CompanySettingsMissing)?;
+    let documents = db
+        .get_return_invoices(period.first_day(), period.last_day())
+        .map_err(ApiError::from)?;
+    
+    Ok(gstr3b::build_gstr3b(&company.gstin, &company.state, &period, &documents))
+}
@@ -0,0 +1,203 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Payments
+// Settlement rules for payments received against invoices
+// =====================================================
+//
+// One receipt may be split across several invoices and an invoice may be settled by
+// several partial payments. Voided payments are kept for the record but no longer
+// count towards the amount paid.
+
+use crate::lifecycle;
+use crate::money::Money;
+use chrono::NaiveDate;
+use std::fmt;
+
+pub const PAYMENT_METHODS: [&str; 6] = ["CASH", "CHEQUE", "BANK_TRANSFER", "UPI", "CARD", "OTHER"];
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum PaymentError {
+    NoAllocations,
+    InvalidAmount(Money),
+    InvalidMethod(String),
+    InvalidDate(String),
+    InvoiceNotFound(i64),
+    InvoiceCancelled(String),
+    NotIssued(String),
+    NotReceivable(String),
+    Overpayment {
+        invoice_number: String,
+        outstanding: Money,
+        amount: Money,
+    },
+    PaymentNotFound(i64),
+    AlreadyVoided(i64),
+    Sqlite(rusqlite::Error),
+}
+
+impl PaymentError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PaymentError::NoAllocations
+            | PaymentError::InvalidAmount(_)
+            | PaymentError::InvalidMethod(_)
+            | PaymentError::InvalidDate(_) => "InvalidPayment",
+            PaymentError::InvoiceNotFound(_) | PaymentError::PaymentNotFound(_) => "NotFound",
+            PaymentError::InvoiceCancelled(_) => "InvoiceCancelled",
+            PaymentError::NotIssued(_) => "InvoiceNotIssued",
+            PaymentError::NotReceivable(_) => "InvalidPayment",
+            PaymentError::Overpayment { .. } => "Overpayment",
+            PaymentError::AlreadyVoided(_) => "PaymentAlreadyVoided",
+            PaymentError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentError::NoAllocations => f.write_str("Payment must be allocated to at least one invoice"),
+            PaymentError::InvalidAmount(amount) => {
+                write!(f, "Payment amount must be greater than 0, got {}", amount)
+            }
+            PaymentError::InvalidMethod(method) => write!(
+                f,
+                "Unknown payment method {}; use one of {}",
+                method,
+                PAYMENT_METHODS.join(", ")
+            ),
+            PaymentError::InvalidDate(date) => write!(f, "Invalid payment date {}; use YYYY-MM-DD", date),
+            PaymentError::InvoiceNotFound(id) => write!(f, "Invoice {} does not exist", id),
+            PaymentError::InvoiceCancelled(number) => {
+                write!(f, "Invoice {} is cancelled and cannot receive payments", number)
+            }
+            PaymentError::NotIssued(number) => {
+                write!(f, "Invoice {} is still a draft; issue it before recording payments", number)
+            }
+            PaymentError::NotReceivable(number) => write!(
+                f,
+                "{} is a credit or debit note; record payments against the invoice it amends",
+                number
+            ),
+            PaymentError::Overpayment { invoice_number, outstanding, amount } => write!(
+                f,
+                "Payment of {} exceeds the outstanding {} on invoice {}",
+                amount, outstanding, invoice_number
+            ),
+            PaymentError::PaymentNotFound(id) => write!(f, "Payment {} does not exist", id),
+            PaymentError::AlreadyVoided(id) => write!(f, "Payment {} is already void", id),
+            PaymentError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for PaymentError {}
+
+impl From<rusqlite::Error> for PaymentError {
+    fn from(err: rusqlite::Error) -> Self {
+        PaymentError::Sqlite(err)
+    }
+}
+
+// =====================================================
+// Settlement Rules
+// =====================================================
+
+/// Checks the fields of a payment that do not depend on the invoices it settles
+pub fn validate_payment(payment_date: &str, payment_method: &str, amounts: &[Money]) -> Result<(), PaymentError> {
+    if amounts.is_empty() {
+        return Err(PaymentError::NoAllocations);
+    }
+    if let Some(amount) = amounts.iter().find(|amount| **amount <= Money::ZERO) {
+        return Err(PaymentError::InvalidAmount(*amount));
+    }
+    if !PAYMENT_METHODS.contains(&payment_method) {
+        return Err(PaymentError::InvalidMethod(payment_method.to_string()));
+    }
+    if NaiveDate::parse_from_str(payment_date, "%Y-%m-%d").is_err() {
+        return Err(PaymentError::InvalidDate(payment_date.to_string()));
+    }
+    Ok(())
+}
+
+/// Status an invoice should carry once `amount_paid` has been received against it.
+/// Fully settled invoices become PAID; a PAID invoice whose payment is voided reopens as
+/// OVERDUE when past its due date and SENT otherwise. DRAFT and CANCELLED are left alone.
+pub fn settled_status(
+    status: &str,
+    final_amount: Money,
+    amount_paid: Money,
+    due_date: Option<&str>,
+    today: NaiveDate,
+) -> String {
+    let settled = amount_paid >= final_amount;
+
+    match status {
+        "DRAFT" | "CANCELLED" => status.to_string(),
+        "PAID" if !settled => {
+            if lifecycle::is_overdue(due_date, today) { "OVERDUE" } else { "SENT" }.to_string()
+        }
+        _ if settled => "PAID".to_string(),
+        _ => status.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rupees(amount: f64) -> Money {
+        Money::from_rupees(amount)
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn marks_invoice_paid_once_settled() {
+        let today = date(2026, 10, 1);
+        assert_eq!(settled_status("SENT", rupees(1180.0), rupees(500.0), None, today), "SENT");
+        assert_eq!(settled_status("SENT", rupees(1180.0), rupees(1180.0), None, today), "PAID");
+        assert_eq!(settled_status("OVERDUE", rupees(1180.0), rupees(1180.0), None, today), "PAID");
+        assert_eq!(settled_status("CANCELLED", rupees(1180.0), rupees(1180.0), None, today), "CANCELLED");
+        assert_eq!(settled_status("DRAFT", rupees(1180.0), rupees(1180.0), None, today), "DRAFT");
+    }
+
+    #[test]
+    fn reopens_paid_invoice_after_void() {
+        let today = date(2026, 10, 1);
+        assert_eq!(
+            settled_status("PAID", rupees(1180.0), rupees(0.0), Some("2026-10-15"), today),
+            "SENT"
+        );
+        assert_eq!(
+            settled_status("PAID", rupees(1180.0), rupees(180.0), Some("2026-09-30"), today),
+            "OVERDUE"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_payments() {
+        assert!(matches!(
+            validate_payment("2026-10-01", "UPI", &[]),
+            Err(PaymentError::NoAllocations)
+        ));
+        assert!(matches!(
+            validate_payment("2026-10-01", "UPI", &[rupees(100.0), Money::ZERO]),
+            Err(PaymentError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            validate_payment("2026-10-01", "CRYPTO", &[rupees(100.0)]),
+            Err(PaymentError::InvalidMethod(_))
+        ));
+        assert!(matches!(
+            validate_payment("01/10/2026", "CASH", &[rupees(100.0)]),
+            Err(PaymentError::InvalidDate(_))
+        ));
+        assert!(validate_payment("2026-10-01", "BANK_TRANSFER", &[rupees(100.0)]).is_ok());
+    }
+}
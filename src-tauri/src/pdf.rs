@@ -0,0 +1,1184 @@
+// =====================================================
+// Payvlo GST Invoice Generator - PDF Rendering
+// GST tax invoice laid out on A4 with the standard PDF fonts
+// =====================================================
+//
+// Text is set in Helvetica with WinAnsi encoding, so no font files are embedded. The
+// rupee sign is outside that encoding and is printed as "Rs.". Which sections and item
+// columns appear, and in what order, comes from the invoice's layout template (see
+// templates.rs). Long tables continue on further pages with the column headings repeated.
+
+use crate::database::{CompanySettings, ReturnInvoice, ReturnLine};
+use crate::formatting;
+use crate::gst;
+use crate::money::Money;
+use crate::notes;
+use crate::templates::{ColumnField, ColumnSpec, Section, TemplateDefinition, TemplateStyle};
+use chrono::NaiveDate;
+use miniz_oxide::deflate::{compress_to_vec_zlib, CompressionLevel};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+pub const MARGIN: f32 = 36.0;
+pub const CONTENT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+
+/// Space kept free at the bottom of every page for the footer
+const FOOTER_HEIGHT: f32 = 24.0;
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum PdfError {
+    InvoiceNotFound(i64),
+    CompanySettingsMissing,
+    Io(std::io::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl PdfError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PdfError::InvoiceNotFound(_) => "NotFound",
+            PdfError::CompanySettingsMissing => "CompanySettingsMissing",
+            PdfError::Io(_) => "FileWriteError",
+            PdfError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for PdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PdfError::InvoiceNotFound(id) => write!(f, "Invoice {} does not exist", id),
+            PdfError::CompanySettingsMissing => {
+                f.write_str("Company settings must be saved before printing invoices")
+            }
+            PdfError::Io(err) => write!(f, "Could not write the PDF: {}", err),
+            PdfError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for PdfError {}
+
+impl From<rusqlite::Error> for PdfError {
+    fn from(err: rusqlite::Error) -> Self {
+        PdfError::Sqlite(err)
+    }
+}
+
+impl From<std::io::Error> for PdfError {
+    fn from(err: std::io::Error) -> Self {
+        PdfError::Io(err)
+    }
+}
+
+// =====================================================
+// Fonts
+// =====================================================
+
+/// Advance widths in 1/1000 em for characters 32..=126 of the standard fonts
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722,
+    722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722,
+    667, 944, 667, 667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556,
+    556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500,
+    500, 334, 260, 334, 584,
+];
+
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722,
+    722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722,
+    667, 944, 667, 667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611,
+    611, 278, 278, 556, 278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556,
+    500, 389, 280, 389, 584,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+        }
+    }
+
+    /// Width of `text` in points once encoded for the page
+    pub fn width(self, text: &str, size: f32) -> f32 {
+        let widths = match self {
+            Font::Regular => &HELVETICA_WIDTHS,
+            Font::Bold => &HELVETICA_BOLD_WIDTHS,
+        };
+        let units: u32 = encode(text)
+            .iter()
+            .map(|&byte| widths[(byte.clamp(32, 126) - 32) as usize] as u32)
+            .sum();
+        units as f32 * size / 1000.0
+    }
+}
+
+/// Encodes text for the standard fonts; anything beyond ASCII other than the rupee sign
+/// is replaced with '?'
+fn encode(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '₹' => bytes.extend_from_slice(b"Rs."),
+            ' '..='~' => bytes.push(c as u8),
+            '\t' | '\n' | '\r' => bytes.push(b' '),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
+
+/// Breaks text into lines no wider than `width`, splitting long words if they must
+pub fn wrap(text: &str, font: Font, size: f32, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if font.width(&candidate, size) <= width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            // A single word wider than the column is split by character
+            for c in word.chars() {
+                if !line.is_empty() && font.width(&format!("{}{}", line, c), size) > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(c);
+            }
+        }
+        lines.push(line);
+    }
+
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// =====================================================
+// Images
+// =====================================================
+
+/// A logo ready to embed: JPEGs are passed through, PNGs are decoded and re-compressed
+pub struct Image {
+    data: Vec<u8>,
+    filter: Filter,
+    width: u32,
+    height: u32,
+    components: u8,
+    alpha: Option<Vec<u8>>,
+}
+
+impl Image {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|err| err.to_string())?;
+        if data.starts_with(b"\x89PNG") {
+            Self::from_png(&data)
+        } else if data.starts_with(&[0xFF, 0xD8]) {
+            Self::from_jpeg(data)
+        } else {
+            Err("only PNG and JPEG logos are supported".to_string())
+        }
+    }
+
+    fn from_png(data: &[u8]) -> Result<Self, String> {
+        let mut decoder = png::Decoder::new(data);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(|err| err.to_string())?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut pixels).map_err(|err| err.to_string())?;
+        pixels.truncate(frame.buffer_size());
+
+        let (components, has_alpha) = match frame.color_type {
+            png::ColorType::Grayscale => (1, false),
+            png::ColorType::GrayscaleAlpha => (1, true),
+            png::ColorType::Rgb => (3, false),
+            png::ColorType::Rgba => (3, true),
+            png::ColorType::Indexed => return Err("indexed PNGs are not supported".to_string()),
+        };
+        let level = CompressionLevel::DefaultLevel as u8;
+        let (colour, alpha) = if has_alpha {
+            let stride = components + 1;
+            let colour: Vec<u8> = pixels
+                .chunks(stride)
+                .flat_map(|pixel| pixel[..components].to_vec())
+                .collect();
+            let alpha: Vec<u8> = pixels.chunks(stride).map(|pixel| pixel[components]).collect();
+            (colour, Some(compress_to_vec_zlib(&alpha, level)))
+        } else {
+            (pixels, None)
+        };
+
+        Ok(Image {
+            data: compress_to_vec_zlib(&colour, level),
+            filter: Filter::FlateDecode,
+            width: frame.width,
+            height: frame.height,
+            components: components as u8,
+            alpha,
+        })
+    }
+
+    /// Reads the frame size from the first start-of-frame marker
+    fn from_jpeg(data: Vec<u8>) -> Result<Self, String> {
+        let mut offset = 2;
+        while offset + 9 < data.len() {
+            if data[offset] != 0xFF {
+                return Err("malformed JPEG".to_string());
+            }
+            let marker = data[offset + 1];
+            let length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            let is_frame = matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+            if is_frame {
+                let height = u16::from_be_bytes([data[offset + 5], data[offset + 6]]) as u32;
+                let width = u16::from_be_bytes([data[offset + 7], data[offset + 8]]) as u32;
+                let components = data[offset + 9];
+                return Ok(Image {
+                    data,
+                    filter: Filter::DctDecode,
+                    width,
+                    height,
+                    components,
+                    alpha: None,
+                });
+            }
+            offset += 2 + length;
+        }
+        Err("JPEG has no frame header".to_string())
+    }
+}
+
+// =====================================================
+// Canvas
+// =====================================================
+
+fn draw_text(content: &mut Content, x: f32, top: f32, text: &str, font: Font, size: f32, align: Align) {
+    let x = match align {
+        Align::Left => x,
+        Align::Center => x - font.width(text, size) / 2.0,
+        Align::Right => x - font.width(text, size),
+    };
+    let baseline = PAGE_HEIGHT - top - size * 0.8;
+    let encoded = encode(text);
+    content
+        .begin_text()
+        .set_font(font.resource(), size)
+        .next_line(x, baseline)
+        .show(Str(&encoded))
+        .end_text();
+}
+
+/// Pages under construction. Positions are measured in points from the top-left corner
+/// of the page; `y` is the top of the next block to be placed.
+pub struct Canvas {
+    pages: Vec<Content>,
+    pub y: f32,
+    image: Option<Image>,
+}
+
+impl Canvas {
+    pub fn new(image: Option<Image>) -> Self {
+        Canvas {
+            pages: vec![Content::new()],
+            y: MARGIN,
+            image,
+        }
+    }
+
+    fn content(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("canvas always has a page")
+    }
+
+    pub fn new_page(&mut self) {
+        self.pages.push(Content::new());
+        self.y = MARGIN;
+    }
+
+    /// Space left above the footer on the current page
+    pub fn remaining(&self) -> f32 {
+        PAGE_HEIGHT - MARGIN - FOOTER_HEIGHT - self.y
+    }
+
+    /// Starts a new page unless `height` still fits on this one
+    pub fn ensure(&mut self, height: f32) -> bool {
+        if height > self.remaining() && self.y > MARGIN {
+            self.new_page();
+            return true;
+        }
+        false
+    }
+
+    /// Draws one line of text with its top at `top`
+    pub fn text(&mut self, x: f32, top: f32, text: &str, font: Font, size: f32, align: Align) {
+        draw_text(self.content(), x, top, text, font, size, align);
+    }
+
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32) {
+        self.content()
+            .set_line_width(width)
+            .move_to(x1, PAGE_HEIGHT - y1)
+            .line_to(x2, PAGE_HEIGHT - y2)
+            .stroke();
+    }
+
+    pub fn fill_rect(&mut self, x: f32, top: f32, width: f32, height: f32, gray: f32) {
+        self.content()
+            .save_state()
+            .set_fill_gray(gray)
+            .rect(x, PAGE_HEIGHT - top - height, width, height)
+            .fill_nonzero()
+            .restore_state();
+    }
+
+    /// Draws the image scaled to fit the box, keeping its aspect ratio. Returns the
+    /// width used, or 0 when there is no image.
+    pub fn image(&mut self, x: f32, top: f32, max_width: f32, max_height: f32) -> f32 {
+        let Some(image) = &self.image else { return 0.0 };
+        let scale = (max_width / image.width as f32).min(max_height / image.height as f32);
+        let (width, height) = (image.width as f32 * scale, image.height as f32 * scale);
+        self.content()
+            .save_state()
+            .transform([width, 0.0, 0.0, height, x, PAGE_HEIGHT - top - height])
+            .x_object(Name(b"Im1"))
+            .restore_state();
+        width
+    }
+
+    /// Writes the document, adding `footer` and page numbers to every page
+    pub fn finish(mut self, title: &str, footer: &str) -> Vec<u8> {
+        let page_count = self.pages.len();
+        let top = PAGE_HEIGHT - MARGIN - FOOTER_HEIGHT / 2.0;
+        for (index, content) in self.pages.iter_mut().enumerate() {
+            let page_label = format!("Page {} of {}", index + 1, page_count);
+            draw_text(content, MARGIN, top, footer, Font::Regular, 7.0, Align::Left);
+            draw_text(content, PAGE_WIDTH - MARGIN, top, &page_label, Font::Regular, 7.0, Align::Right);
+        }
+
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let info_id = Ref::new(3);
+        let regular_id = Ref::new(4);
+        let bold_id = Ref::new(5);
+        let image_id = Ref::new(6);
+        let alpha_id = Ref::new(7);
+        let first_page = 8;
+        let page_ids: Vec<Ref> = (0..page_count).map(|i| Ref::new(first_page + 2 * i as i32)).collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(page_count as i32);
+        pdf.document_info(info_id).title(TextStr(title)).producer(TextStr("Payvlo"));
+        pdf.type1_font(regular_id)
+            .base_font(Name(b"Helvetica"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.type1_font(bold_id)
+            .base_font(Name(b"Helvetica-Bold"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+        if let Some(image) = &self.image {
+            let mut xobject = pdf.image_xobject(image_id, &image.data);
+            xobject.filter(image.filter);
+            xobject.width(image.width as i32);
+            xobject.height(image.height as i32);
+            match image.components {
+                1 => xobject.color_space().device_gray(),
+                4 => xobject.color_space().device_cmyk(),
+                _ => xobject.color_space().device_rgb(),
+            };
+            xobject.bits_per_component(8);
+            if image.alpha.is_some() {
+                xobject.s_mask(alpha_id);
+            }
+            xobject.finish();
+
+            if let Some(alpha) = &image.alpha {
+                let mut mask = pdf.image_xobject(alpha_id, alpha);
+                mask.filter(Filter::FlateDecode);
+                mask.width(image.width as i32);
+                mask.height(image.height as i32);
+                mask.color_space().device_gray();
+                mask.bits_per_component(8);
+            }
+        }
+
+        for (index, content) in self.pages.into_iter().enumerate() {
+            let page_id = page_ids[index];
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            let mut resources = page.resources();
+            resources.fonts().pair(Font::Regular.resource(), regular_id).pair(Font::Bold.resource(), bold_id);
+            if self.image.is_some() {
+                resources.x_objects().pair(Name(b"Im1"), image_id);
+            }
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+
+        pdf.finish()
+    }
+}
+
+// =====================================================
+// Tables
+// =====================================================
+
+/// One column of a table: heading, width (0 takes the space left over) and alignment
+pub struct Column {
+    pub heading: String,
+    pub width: f32,
+    pub align: Align,
+}
+
+impl Column {
+    fn new(heading: &str, width: f32, align: Align) -> Self {
+        Column { heading: heading.to_string(), width, align }
+    }
+}
+
+/// Gives columns of width 0 an equal share of whatever `total` the others leave
+pub fn fit_columns(columns: &mut [Column], total: f32) {
+    let fixed: f32 = columns.iter().map(|column| column.width).sum();
+    let flexible = columns.iter().filter(|column| column.width == 0.0).count();
+    if flexible > 0 {
+        let share = ((total - fixed) / flexible as f32).max(20.0);
+        for column in columns.iter_mut().filter(|column| column.width == 0.0) {
+            column.width = share;
+        }
+    }
+}
+
+fn wrap_cells(columns: &[Column], row: &[String], font: Font, style: &TemplateStyle) -> Vec<Vec<String>> {
+    columns
+        .iter()
+        .zip(row)
+        .map(|(column, text)| wrap(text, font, style.table_font_size, column.width - 2.0 * style.cell_padding))
+        .collect()
+}
+
+fn row_height(cells: &[Vec<String>], style: &TemplateStyle) -> f32 {
+    let lines = cells.iter().map(Vec::len).max().unwrap_or(1).max(1);
+    lines as f32 * style.table_font_size * style.line_spacing + 2.0 * style.cell_padding
+}
+
+/// Draws a table row; each cell may hold several lines
+fn draw_row(canvas: &mut Canvas, columns: &[Column], cells: &[Vec<String>], font: Font, style: &TemplateStyle) {
+    let line_height = style.table_font_size * style.line_spacing;
+    let top = canvas.y;
+
+    let mut x = MARGIN;
+    for (column, cell) in columns.iter().zip(cells) {
+        let anchor = match column.align {
+            Align::Left => x + style.cell_padding,
+            Align::Center => x + column.width / 2.0,
+            Align::Right => x + column.width - style.cell_padding,
+        };
+        for (index, line) in cell.iter().enumerate() {
+            let line_top = top + style.cell_padding + index as f32 * line_height;
+            canvas.text(anchor, line_top, line, font, style.table_font_size, column.align);
+        }
+        x += column.width;
+    }
+
+    canvas.y += row_height(cells, style);
+}
+
+fn draw_heading_row(canvas: &mut Canvas, columns: &[Column], style: &TemplateStyle) {
+    let headings: Vec<String> = columns.iter().map(|column| column.heading.clone()).collect();
+    let cells = wrap_cells(columns, &headings, Font::Bold, style);
+    canvas.fill_rect(MARGIN, canvas.y, CONTENT_WIDTH, row_height(&cells, style), style.heading_shade);
+    draw_row(canvas, columns, &cells, Font::Bold, style);
+    canvas.line(MARGIN, canvas.y, MARGIN + CONTENT_WIDTH, canvas.y, 0.5);
+}
+
+/// Draws a table, repeating the headings on every page it runs onto
+pub fn draw_table(canvas: &mut Canvas, columns: &[Column], rows: &[Vec<String>], continued: &str, style: &TemplateStyle) {
+    canvas.ensure(60.0);
+    draw_heading_row(canvas, columns, style);
+
+    for row in rows {
+        let cells = wrap_cells(columns, row, Font::Regular, style);
+        if canvas.ensure(row_height(&cells, style)) {
+            canvas.text(MARGIN, canvas.y, continued, Font::Bold, style.font_size, Align::Left);
+            canvas.y += style.font_size * 2.0;
+            draw_heading_row(canvas, columns, style);
+        }
+        draw_row(canvas, columns, &cells, Font::Regular, style);
+        canvas.line(MARGIN, canvas.y, MARGIN + CONTENT_WIDTH, canvas.y, 0.25);
+    }
+}
+
+/// Draws lines of text downwards from `top` and returns the height used
+fn draw_lines(canvas: &mut Canvas, x: f32, top: f32, lines: &[(String, Font)], style: &TemplateStyle) -> f32 {
+    let line_height = style.font_size * style.line_spacing;
+    for (index, (text, font)) in lines.iter().enumerate() {
+        canvas.text(x, top + index as f32 * line_height, text, *font, style.font_size, Align::Left);
+    }
+    lines.len() as f32 * line_height
+}
+
+// =====================================================
+// Tax Invoice Layout
+// =====================================================
+
+fn money(amount: Money) -> String {
+    formatting::format_indian(amount)
+}
+
+fn rate(rate: f64) -> String {
+    format!("{}%", trim_number(rate, 2))
+}
+
+fn trim_number(value: f64, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value);
+    if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        formatted
+    }
+}
+
+fn display_date(date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| date.format("%d-%m-%Y").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// "Karnataka (29)"; places outside India are printed as stored
+fn state_with_code(state: &str) -> String {
+    match gst::state_code(state) {
+        Some(code) => {
+            let name = gst::STATE_CODES
+                .iter()
+                .find(|(c, _)| *c == code)
+                .map(|(_, name)| *name)
+                .unwrap_or(state);
+            format!("{} ({})", name, code)
+        }
+        None => state.trim().to_string(),
+    }
+}
+
+fn document_title<'a>(document: &ReturnInvoice, template: &'a TemplateDefinition) -> &'a str {
+    if let Some(title) = template.title.as_deref().filter(|title| !title.trim().is_empty()) {
+        return title;
+    }
+    match document.invoice.invoice_type.as_str() {
+        "CREDIT_NOTE" => "CREDIT NOTE",
+        "DEBIT_NOTE" => "DEBIT NOTE",
+        _ => "TAX INVOICE",
+    }
+}
+
+fn joined(parts: &[Option<&str>], separator: &str) -> String {
+    parts
+        .iter()
+        .flatten()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Which optional columns an invoice needs
+struct TaxColumns {
+    intra_state: bool,
+    inter_state: bool,
+    cess: bool,
+    discount: bool,
+}
+
+impl TaxColumns {
+    fn of(document: &ReturnInvoice) -> Self {
+        let lines = &document.lines;
+        let inter_state = lines.iter().any(|line| line.item.igst_amount != Money::ZERO);
+        let intra_state = lines.iter().any(|line| line.item.cgst_amount != Money::ZERO);
+        TaxColumns {
+            // Nil-rated invoices still show the split their place of supply implies
+            intra_state: intra_state || !inter_state,
+            inter_state,
+            cess: lines.iter().any(|line| line.item.cess_amount != Money::ZERO),
+            discount: lines.iter().any(|line| line.item.discount_amount != Money::ZERO),
+        }
+    }
+
+    fn shows(&self, field: ColumnField) -> bool {
+        match field {
+            ColumnField::Cgst | ColumnField::Sgst => self.intra_state,
+            ColumnField::Igst => self.inter_state,
+            ColumnField::Cess => self.cess,
+            ColumnField::Discount | ColumnField::DiscountPercent => self.discount,
+            _ => true,
+        }
+    }
+}
+
+fn item_columns(template: &TemplateDefinition, taxes: &TaxColumns) -> (Vec<ColumnField>, Vec<Column>) {
+    let specs: Vec<&ColumnSpec> = template.columns.iter().filter(|spec| taxes.shows(spec.field)).collect();
+    let fields = specs.iter().map(|spec| spec.field).collect();
+    let mut columns: Vec<Column> = specs
+        .iter()
+        .map(|spec| Column::new(spec.heading(), spec.width, spec.field.align()))
+        .collect();
+    fit_columns(&mut columns, CONTENT_WIDTH);
+    (fields, columns)
+}
+
+fn tax_cell(amount: Money, tax_rate: f64) -> String {
+    format!("{}\n@{}", money(amount), rate(tax_rate))
+}
+
+fn item_cell(field: ColumnField, line: &ReturnLine, template: &TemplateDefinition) -> String {
+    let item = &line.item;
+    match field {
+        ColumnField::Serial => item.line_number.to_string(),
+        ColumnField::ProductCode => item.product_code.clone(),
+        ColumnField::Description => {
+            let description = item.description.as_deref().map(str::trim).filter(|d| !d.is_empty());
+            match description {
+                Some(extra) if template.item_descriptions => format!("{}\n{}", item.product_name, extra),
+                _ => item.product_name.clone(),
+            }
+        }
+        ColumnField::HsnSac => item.hsn_sac_code.clone(),
+        ColumnField::Quantity => format!("{} {}", trim_number(item.quantity, 3), line.unit_of_measurement),
+        ColumnField::Rate => money(item.unit_price),
+        ColumnField::DiscountPercent => rate(item.discount_percent),
+        ColumnField::Discount => money(item.discount_amount),
+        ColumnField::Taxable => money(item.taxable_amount),
+        ColumnField::Cgst => tax_cell(item.cgst_amount, item.cgst_rate),
+        ColumnField::Sgst => tax_cell(item.sgst_amount, item.sgst_rate),
+        ColumnField::Igst => tax_cell(item.igst_amount, item.igst_rate),
+        ColumnField::Cess => tax_cell(item.cess_amount, item.cess_rate),
+        ColumnField::TotalTax => format!("{}\n@{}", money(item.total_tax), rate(item.gst_rate)),
+        ColumnField::Total => money(item.line_total),
+    }
+}
+
+/// Taxable value and tax per HSN/SAC code and rate, as required on invoices
+fn hsn_summary(document: &ReturnInvoice, taxes: &TaxColumns) -> (Vec<Column>, Vec<Vec<String>>) {
+    let mut columns = vec![
+        Column::new("HSN/SAC", 0.0, Align::Left),
+        Column::new("Rate", 40.0, Align::Right),
+        Column::new("Taxable Value", 70.0, Align::Right),
+    ];
+    if taxes.intra_state {
+        columns.push(Column::new("CGST", 62.0, Align::Right));
+        columns.push(Column::new("SGST", 62.0, Align::Right));
+    }
+    if taxes.inter_state {
+        columns.push(Column::new("IGST", 62.0, Align::Right));
+    }
+    if taxes.cess {
+        columns.push(Column::new("Cess", 56.0, Align::Right));
+    }
+    columns.push(Column::new("Total Tax", 70.0, Align::Right));
+    fit_columns(&mut columns, CONTENT_WIDTH);
+
+    // Keyed on code and rate in basis points so the order is stable
+    let mut groups: BTreeMap<(String, i64), [Money; 6]> = BTreeMap::new();
+    for line in &document.lines {
+        let item = &line.item;
+        let key = (item.hsn_sac_code.trim().to_string(), (item.gst_rate * 100.0).round() as i64);
+        let totals = groups.entry(key).or_insert([Money::ZERO; 6]);
+        totals[0] += item.taxable_amount;
+        totals[1] += item.cgst_amount;
+        totals[2] += item.sgst_amount;
+        totals[3] += item.igst_amount;
+        totals[4] += item.cess_amount;
+        totals[5] += item.total_tax;
+    }
+
+    let rows = groups
+        .into_iter()
+        .map(|((code, basis_points), totals)| {
+            let mut row = vec![code, rate(basis_points as f64 / 100.0), money(totals[0])];
+            if taxes.intra_state {
+                row.push(money(totals[1]));
+                row.push(money(totals[2]));
+            }
+            if taxes.inter_state {
+                row.push(money(totals[3]));
+            }
+            if taxes.cess {
+                row.push(money(totals[4]));
+            }
+            row.push(money(totals[5]));
+            row
+        })
+        .collect();
+    (columns, rows)
+}
+
+/// Everything a section needs to draw itself
+struct InvoiceLayout<'a> {
+    company: &'a CompanySettings,
+    document: &'a ReturnInvoice,
+    template: &'a TemplateDefinition,
+    taxes: TaxColumns,
+}
+
+impl InvoiceLayout<'_> {
+    fn style(&self) -> &TemplateStyle {
+        &self.template.style
+    }
+
+    fn continued(&self) -> String {
+        format!("{} (continued)", self.document.invoice.invoice_number)
+    }
+
+    fn draw(&self, canvas: &mut Canvas, section: Section) {
+        match section {
+            Section::Header => self.draw_header(canvas),
+            Section::Parties => self.draw_parties(canvas),
+            Section::Items => self.draw_items(canvas),
+            Section::Totals => self.draw_totals(canvas),
+            Section::AmountInWords => self.draw_amount_in_words(canvas),
+            Section::HsnSummary => {
+                let (columns, rows) = hsn_summary(self.document, &self.taxes);
+                draw_table(canvas, &columns, &rows, &self.continued(), self.style());
+                canvas.y += 10.0;
+            }
+            Section::BankDetails => self.draw_bank_details(canvas),
+            Section::Notes => self.draw_note(canvas, "Notes", self.document.invoice.notes.as_deref()),
+            Section::Terms => {
+                self.draw_note(canvas, "Terms & Conditions", self.document.invoice.terms_conditions.as_deref())
+            }
+            Section::Signature => self.draw_signature(canvas),
+        }
+    }
+
+    fn draw_header(&self, canvas: &mut Canvas) {
+        let (company, style) = (self.company, self.style());
+        if self.template.letterhead {
+            canvas.y += self.template.letterhead_space;
+        }
+
+        let top = canvas.y;
+        let title = document_title(self.document, self.template);
+        canvas.text(MARGIN + CONTENT_WIDTH / 2.0, top, title, Font::Bold, style.title_size, Align::Center);
+        canvas.text(MARGIN + CONTENT_WIDTH, top + 2.0, &self.template.copy_label, Font::Regular, 7.0, Align::Right);
+        canvas.y = top + style.title_size + 9.0;
+        if self.template.letterhead {
+            return;
+        }
+
+        // Supplier, with the logo to its left
+        let top = canvas.y;
+        let logo_width = canvas.image(MARGIN, top, 90.0, 54.0);
+        let x = if logo_width > 0.0 { MARGIN + logo_width + 10.0 } else { MARGIN };
+        let mut supplier = vec![
+            (company.company_name.trim().to_string(), Font::Bold),
+            (joined(&[Some(&company.address_line1), company.address_line2.as_deref()], ", "), Font::Regular),
+            (
+                format!("{}, {} - {}", company.city.trim(), state_with_code(&company.state), company.pincode.trim()),
+                Font::Regular,
+            ),
+            (
+                format!("GSTIN: {}   PAN: {}", company.gstin.trim().to_uppercase(), company.pan.trim()),
+                Font::Bold,
+            ),
+        ];
+        let contact = joined(
+            &[company.phone.as_deref(), company.email.as_deref(), company.website.as_deref()],
+            "  |  ",
+        );
+        if !contact.is_empty() {
+            supplier.push((contact, Font::Regular));
+        }
+        let height = draw_lines(canvas, x, top, &supplier, style).max(if logo_width > 0.0 { 54.0 } else { 0.0 });
+
+        let top = top + height + 8.0;
+        canvas.line(MARGIN, top, MARGIN + CONTENT_WIDTH, top, 0.75);
+        canvas.y = top + 6.0;
+    }
+
+    fn draw_parties(&self, canvas: &mut Canvas) {
+        let (document, style) = (self.document, self.style());
+        let invoice = &document.invoice;
+        let customer = &document.customer;
+        let top = canvas.y;
+
+        // Recipient on the left, document details on the right
+        let mut recipient = vec![
+            ("Bill To".to_string(), Font::Bold),
+            (customer.customer_name.trim().to_string(), Font::Bold),
+        ];
+        for part in [Some(customer.address_line1.as_str()), customer.address_line2.as_deref()].into_iter().flatten() {
+            if !part.trim().is_empty() {
+                recipient.push((part.trim().to_string(), Font::Regular));
+            }
+        }
+        recipient.push((
+            format!("{}, {} - {}", customer.city.trim(), state_with_code(&customer.state), customer.pincode.trim()),
+            Font::Regular,
+        ));
+        match customer.gstin.as_deref().map(str::trim).filter(|gstin| !gstin.is_empty()) {
+            Some(gstin) => recipient.push((format!("GSTIN: {}", gstin.to_uppercase()), Font::Bold)),
+            None => recipient.push(("Unregistered".to_string(), Font::Regular)),
+        }
+        let recipient_height = draw_lines(canvas, MARGIN, top, &recipient, style);
+
+        let is_note = notes::is_note(&invoice.invoice_type);
+        let mut details = vec![
+            (if is_note { "Note No" } else { "Invoice No" }, invoice.invoice_number.clone()),
+            (if is_note { "Note Date" } else { "Invoice Date" }, display_date(&invoice.invoice_date)),
+        ];
+        // Rule 53 requires a note to quote the invoices it amends
+        for original in &document.references {
+            details.push((
+                "Against Invoice",
+                format!("{} dt {}", original.invoice_number, display_date(&original.invoice_date)),
+            ));
+        }
+        if let Some(reason) = invoice.note_reason.as_deref().filter(|_| is_note) {
+            details.push(("Reason", notes::reason_label(reason).to_string()));
+        }
+        if let Some(due_date) = invoice.due_date.as_deref().filter(|date| !date.trim().is_empty()) {
+            details.push(("Due Date", display_date(due_date)));
+        }
+        details.push(("Place of Supply", state_with_code(&invoice.place_of_supply)));
+        details.push(("Reverse Charge", if invoice.reverse_charge { "Yes" } else { "No" }.to_string()));
+        if let Some(terms) = invoice.payment_terms.as_deref().filter(|terms| !terms.trim().is_empty()) {
+            details.push(("Payment Terms", terms.trim().to_string()));
+        }
+        let label_x = MARGIN + CONTENT_WIDTH * 0.58;
+        let value_x = label_x + style.font_size * 9.0;
+        let line_height = style.font_size * style.line_spacing;
+        for (index, (label, value)) in details.iter().enumerate() {
+            let line_top = top + index as f32 * line_height;
+            canvas.text(label_x, line_top, label, Font::Regular, style.font_size, Align::Left);
+            canvas.text(value_x, line_top, &format!(": {}", value), Font::Bold, style.font_size, Align::Left);
+        }
+        let details_height = details.len() as f32 * line_height;
+
+        canvas.y = top + recipient_height.max(details_height) + 6.0;
+        if let Some(irn) = invoice.irn.as_deref() {
+            canvas.text(MARGIN, canvas.y, &format!("IRN: {}", irn), Font::Regular, 7.0, Align::Left);
+            canvas.y += 7.0 * style.line_spacing;
+            if let Some(ack_number) = invoice.ack_number.as_deref() {
+                let ack = format!("Ack No: {}   Ack Date: {}", ack_number, invoice.ack_date.as_deref().unwrap_or_default());
+                canvas.text(MARGIN, canvas.y, &ack, Font::Regular, 7.0, Align::Left);
+                canvas.y += 7.0 * style.line_spacing;
+            }
+            canvas.y += 4.0;
+        }
+        if invoice.invoice_type == "EXPORT" {
+            let note = if invoice.igst_amount > Money::ZERO {
+                "Supply meant for export on payment of IGST"
+            } else {
+                "Supply meant for export under bond or LUT without payment of IGST"
+            };
+            canvas.text(MARGIN, canvas.y, note, Font::Bold, style.font_size, Align::Left);
+            canvas.y += style.font_size * 2.0;
+        }
+    }
+
+    fn draw_items(&self, canvas: &mut Canvas) {
+        let (fields, columns) = item_columns(self.template, &self.taxes);
+        let rows: Vec<Vec<String>> = self
+            .document
+            .lines
+            .iter()
+            .map(|line| fields.iter().map(|&field| item_cell(field, line, self.template)).collect())
+            .collect();
+        draw_table(canvas, &columns, &rows, &self.continued(), self.style());
+    }
+
+    fn draw_totals(&self, canvas: &mut Canvas) {
+        let (invoice, style) = (&self.document.invoice, self.style());
+        let mut rows = vec![("Taxable Value", invoice.taxable_amount)];
+        for (label, amount) in [
+            ("CGST", invoice.cgst_amount),
+            ("SGST", invoice.sgst_amount),
+            ("IGST", invoice.igst_amount),
+            ("Cess", invoice.cess_amount),
+            ("Round Off", invoice.round_off),
+        ] {
+            if amount != Money::ZERO {
+                rows.push((label, amount));
+            }
+        }
+
+        let line_height = style.font_size * 1.6;
+        canvas.ensure(line_height * (rows.len() + 1) as f32 + 12.0);
+        let label_x = MARGIN + CONTENT_WIDTH * 0.6;
+        let value_x = MARGIN + CONTENT_WIDTH - style.cell_padding;
+        canvas.y += 6.0;
+        for (label, amount) in rows {
+            canvas.text(label_x, canvas.y, label, Font::Regular, style.font_size, Align::Left);
+            canvas.text(value_x, canvas.y, &money(amount), Font::Regular, style.font_size, Align::Right);
+            canvas.y += line_height;
+        }
+        let shade_x = label_x - style.cell_padding;
+        canvas.fill_rect(shade_x, canvas.y - 3.0, MARGIN + CONTENT_WIDTH - shade_x, line_height + 2.0, style.heading_shade);
+        let total = formatting::format_indian_currency(invoice.final_amount);
+        canvas.text(label_x, canvas.y, "Invoice Total", Font::Bold, style.font_size + 1.0, Align::Left);
+        canvas.text(value_x, canvas.y, &total, Font::Bold, style.font_size + 1.0, Align::Right);
+        canvas.y += line_height + 6.0;
+    }
+
+    fn draw_amount_in_words(&self, canvas: &mut Canvas) {
+        let style = self.style();
+        let words = format!("Amount in words: {}", formatting::amount_in_words(self.document.invoice.final_amount));
+        let lines: Vec<(String, Font)> = wrap(&words, Font::Bold, style.font_size, CONTENT_WIDTH)
+            .into_iter()
+            .map(|line| (line, Font::Bold))
+            .collect();
+        canvas.ensure(lines.len() as f32 * style.font_size * style.line_spacing);
+        canvas.y += draw_lines(canvas, MARGIN, canvas.y, &lines, style) + 8.0;
+    }
+
+    fn draw_bank_details(&self, canvas: &mut Canvas) {
+        let company = self.company;
+        let mut lines = Vec::new();
+        for (label, value) in [
+            ("Bank", company.bank_name.as_deref()),
+            ("A/c No", company.account_number.as_deref()),
+            ("IFSC", company.ifsc_code.as_deref()),
+        ] {
+            if let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) {
+                lines.push((format!("{}: {}", label, value), Font::Regular));
+            }
+        }
+        if !lines.is_empty() {
+            lines.insert(0, ("Bank Details".to_string(), Font::Bold));
+            self.draw_block(canvas, &lines);
+        }
+    }
+
+    fn draw_note(&self, canvas: &mut Canvas, heading: &str, text: Option<&str>) {
+        let Some(text) = text.map(str::trim).filter(|t| !t.is_empty()) else { return };
+        let style = self.style();
+        let mut lines = vec![(heading.to_string(), Font::Bold)];
+        for line in wrap(text, Font::Regular, style.font_size, CONTENT_WIDTH * 0.6) {
+            lines.push((line, Font::Regular));
+        }
+        self.draw_block(canvas, &lines);
+    }
+
+    /// A block of text on the left, kept together on one page
+    fn draw_block(&self, canvas: &mut Canvas, lines: &[(String, Font)]) {
+        let style = self.style();
+        canvas.ensure(lines.len() as f32 * style.font_size * style.line_spacing);
+        canvas.y += draw_lines(canvas, MARGIN, canvas.y, lines, style) + 6.0;
+    }
+
+    fn draw_signature(&self, canvas: &mut Canvas) {
+        let style = self.style();
+        canvas.ensure(60.0);
+        let right = MARGIN + CONTENT_WIDTH;
+        let top = canvas.y + 4.0;
+        let company = format!("For {}", self.company.company_name.trim());
+        canvas.text(right, top, &company, Font::Bold, style.font_size, Align::Right);
+        canvas.text(right, top + 44.0, "Authorised Signatory", Font::Regular, style.font_size, Align::Right);
+        canvas.y = top + 44.0 + style.font_size * 2.0;
+    }
+}
+
+/// Lays out a GST tax invoice (or credit/debit note) with the given template and returns
+/// the PDF bytes. A logo that cannot be read is left out rather than failing the invoice.
+pub fn render_invoice(company: &CompanySettings, document: &ReturnInvoice, template: &TemplateDefinition) -> Vec<u8> {
+    let logo = if template.show_logo && !template.letterhead { company.logo_path.as_deref() } else { None }
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .and_then(|path| match Image::load(Path::new(path)) {
+            Ok(image) => Some(image),
+            Err(err) => {
+                log::warn!("Skipping logo {}: {}", path, err);
+                None
+            }
+        });
+
+    let layout = InvoiceLayout { company, document, template, taxes: TaxColumns::of(document) };
+    let mut canvas = Canvas::new(logo);
+    for &section in &template.sections {
+        layout.draw(&mut canvas, section);
+    }
+
+    let title = format!("{} {}", document_title(document, template), document.invoice.invoice_number);
+    canvas.finish(&title, &template.footer)
+}
+
+/// File name for an invoice's PDF, safe on every platform
+pub fn file_name(invoice_number: &str) -> String {
+    let name: String = invoice_number
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{}.pdf", name.trim_matches('-'))
+}
+
+/// Renders the invoice into `dir`, creating it if needed, and returns the file path
+pub fn write_invoice(
+    dir: &Path,
+    company: &CompanySettings,
+    document: &ReturnInvoice,
+    template: &TemplateDefinition,
+) -> Result<std::path::PathBuf, PdfError> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(file_name(&document.invoice.invoice_number));
+    std::fs::write(&path, render_invoice(company, document, template))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::NoteReference;
+    use crate::reports::fixtures::{customer, document};
+    use crate::templates::{sample_company, sample_invoice};
+
+    fn company() -> CompanySettings {
+        CompanySettings {
+            logo_path: Some("/nonexistent/logo.png".to_string()),
+            ..sample_company()
+        }
+    }
+
+    fn text_of(pdf: &[u8]) -> String {
+        String::from_utf8_lossy(pdf).to_string()
+    }
+
+    #[test]
+    fn renders_gst_invoice_fields() {
+        let buyer = customer(Some("29AAGCB7383J1Z4"), "B2B");
+        let document = document("INV/001", "REGULAR", "Karnataka", 120_000.0, buyer);
+        let pdf = render_invoice(&company(), &document, &TemplateDefinition::default());
+        let text = text_of(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-"));
+        for expected in [
+            "TAX INVOICE",
+            "GSTIN: 27AAPFU0939F1ZV",
+            "GSTIN: 29AAGCB7383J1Z4",
+            "Karnataka (29)",
+            "7214",
+            "(IGST)",
+            "21,600.00",
+            "Rs.1,41,600.00",
+            "Rupees One Lakh Forty One Thousand Six Hundred Only",
+            "IFSC: SBIN0000001",
+            "Page 1 of 1",
+        ] {
+            assert!(text.contains(expected), "missing {}", expected);
+        }
+        assert!(!text.contains("(CGST)"));
+        assert!(!text.contains("(Discount)"));
+    }
+
+    #[test]
+    fn quotes_the_original_on_notes() {
+        let buyer = customer(Some("29AAGCB7383J1Z4"), "B2B");
+        let mut note = document("CN-2026-09-0001", "CREDIT_NOTE", "Karnataka", 1000.0, buyer);
+        note.invoice.note_reason = Some("SALES_RETURN".to_string());
+        note.references.push(NoteReference {
+            note_id: 2,
+            note_number: "CN-2026-09-0001".to_string(),
+            note_type: "CREDIT_NOTE".to_string(),
+            note_date: "2026-09-10".to_string(),
+            note_status: "SENT".to_string(),
+            invoice_id: 1,
+            invoice_number: "INV-2026-08-0042".to_string(),
+            invoice_date: "2026-08-28".to_string(),
+            invoice_value: Money::from_rupees(1180.0),
+            invoice_place_of_supply: "Karnataka".to_string(),
+            amount: Money::from_rupees(1180.0),
+        });
+        let text = text_of(&render_invoice(&company(), &note, &TemplateDefinition::default()));
+
+        for expected in ["CREDIT NOTE", "Note No", "Against Invoice", "INV-2026-08-0042 dt 28-08-2026", "Sales return"] {
+            assert!(text.contains(expected), "missing {}", expected);
+        }
+    }
+
+    #[test]
+    fn continues_long_tables_on_new_pages() {
+        let mut document = document("INV/002", "REGULAR", "Maharashtra", 100.0, customer(None, "B2C"));
+        let line = document.lines[0].clone();
+        for number in 2..=80 {
+            let mut extra = line.clone();
+            extra.item.line_number = number;
+            document.lines.push(extra);
+        }
+
+        let text = text_of(&render_invoice(&company(), &document, &TemplateDefinition::default()));
+        assert!(text.contains("INV/002 (continued)"));
+        assert!(text.contains("Page 2 of"));
+        assert!(text.contains("(CGST)"));
+    }
+
+    #[test]
+    fn follows_the_template() {
+        let company = company();
+        let document = sample_invoice(&company);
+        let standard = text_of(&render_invoice(&company, &document, &TemplateDefinition::default()));
+        assert!(standard.contains("(Discount)"));
+        assert!(standard.contains("(Fe 500D, bundle of 10)"));
+        assert!(standard.contains("(Your Company Pvt Ltd)"));
+
+        let letterhead = TemplateDefinition {
+            title: Some("INVOICE".to_string()),
+            letterhead: true,
+            item_descriptions: false,
+            sections: vec![Section::Header, Section::Parties, Section::Items, Section::Totals],
+            columns: vec![
+                ColumnSpec::new(ColumnField::Description, 0.0),
+                ColumnSpec { heading: Some("Value".to_string()), ..ColumnSpec::new(ColumnField::Taxable, 60.0) },
+                ColumnSpec::new(ColumnField::TotalTax, 60.0),
+            ],
+            footer: "Thank you".to_string(),
+            ..TemplateDefinition::default()
+        };
+        let text = text_of(&render_invoice(&company, &document, &letterhead));
+        assert!(text.contains("(INVOICE)"));
+        assert!(text.contains("(Value)"));
+        assert!(text.contains("(Thank you)"));
+        for missing in ["(Your Company Pvt Ltd)", "(Discount)", "Fe 500D", "(HSN/SAC)", "Amount in words", "Authorised"] {
+            assert!(!text.contains(missing), "unexpected {}", missing);
+        }
+    }
+
+    #[test]
+    fn wraps_and_measures_text() {
+        assert_eq!(Font::Regular.width("0", 10.0), 5.56);
+        assert_eq!(wrap("Steel rod 12 mm TMT bars", Font::Regular, 8.0, 50.0), vec!["Steel rod 12", "mm TMT bars"]);
+        assert_eq!(wrap("", Font::Regular, 8.0, 50.0), vec![String::new()]);
+        assert_eq!(file_name("INV/2026-27/001"), "INV-2026-27-001.pdf");
+    }
+}
@@ -0,0 +1,292 @@
+// =====================================================
+// Payvlo GST Invoice Generator - GST Rate Master
+// Date-effective GST and cess rates by HSN/SAC code
+// =====================================================
+//
+// Each entry in `gst_rates` covers an HSN/SAC code or a prefix of one (a 4-digit
+// heading covers every 6 and 8-digit code under it) from `effective_from` until
+// `effective_to`, or indefinitely when that is empty. The rate for a code on a date is
+// the active entry in force that day with the longest matching prefix. Entries for the
+// same code may not overlap in time, so a rate change is recorded by closing the old
+// entry and adding a new one.
+
+use crate::database::{GstRate, InvoiceItem};
+#[cfg(feature = "gui")]
+use crate::gst;
+#[cfg(feature = "gui")]
+use crate::validation;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A line whose rate differs from the rate master on the invoice date
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RateMismatch {
+    pub line_number: i32,
+    pub hsn_sac_code: String,
+    pub gst_rate: f64,
+    pub cess_rate: f64,
+    pub master_gst_rate: f64,
+    pub master_cess_rate: f64,
+    pub rate_id: i64,
+}
+
+pub fn normalize_code(code: &str) -> String {
+    code.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// A code a product can carry (4, 6 or 8 digits), or a 2-digit chapter, which as a
+/// prefix sets the rate for every heading under it
+#[cfg(feature = "gui")]
+pub fn is_valid_code(code: &str) -> bool {
+    validation::is_valid_hsn_sac(code) || (code.len() == 2 && code.chars().all(|c| c.is_ascii_digit()))
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, RateError> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| RateError::InvalidDate(date.to_string()))
+}
+
+/// Checks an entry before it is saved and returns it with its code normalized
+#[cfg(feature = "gui")]
+pub fn validate_rate(rate: &GstRate) -> Result<GstRate, RateError> {
+    let hsn_sac_code = normalize_code(&rate.hsn_sac_code);
+    if !is_valid_code(&hsn_sac_code) {
+        return Err(RateError::InvalidCode(rate.hsn_sac_code.clone()));
+    }
+    if !gst::is_valid_gst_rate(rate.gst_rate) {
+        return Err(RateError::InvalidRate(rate.gst_rate));
+    }
+    if !(0.0..=100.0).contains(&rate.cess_rate) {
+        return Err(RateError::InvalidCess(rate.cess_rate));
+    }
+
+    let effective_from = parse_date(&rate.effective_from)?;
+    let effective_to = rate.effective_to.as_deref().map(str::trim).filter(|date| !date.is_empty());
+    if let Some(effective_to) = effective_to {
+        if parse_date(effective_to)? < effective_from {
+            return Err(RateError::InvalidPeriod {
+                effective_from: rate.effective_from.clone(),
+                effective_to: effective_to.to_string(),
+            });
+        }
+    }
+
+    Ok(GstRate {
+        hsn_sac_code,
+        effective_from: effective_from.to_string(),
+        effective_to: effective_to.map(str::to_string),
+        ..rate.clone()
+    })
+}
+
+/// Whether two entries are in force on a common day. Dates are ISO strings, which
+/// order the same way as the dates themselves.
+#[cfg(feature = "gui")]
+pub fn overlaps(a: &GstRate, b: &GstRate) -> bool {
+    let starts_before_b_ends = b.effective_to.as_deref().map_or(true, |to| a.effective_from.as_str() <= to);
+    let b_starts_before_a_ends = a.effective_to.as_deref().map_or(true, |to| b.effective_from.as_str() <= to);
+    starts_before_b_ends && b_starts_before_a_ends
+}
+
+pub fn is_in_force(rate: &GstRate, date: NaiveDate) -> bool {
+    let date = date.to_string();
+    rate.is_active
+        && rate.effective_from <= date
+        && rate.effective_to.as_deref().map_or(true, |to| date.as_str() <= to)
+}
+
+/// The entry that applies to `code` on `date`: the longest prefix in force that day,
+/// the latest starting one if entries were ever allowed to overlap
+pub fn resolve<'a>(rates: &'a [GstRate], code: &str, date: NaiveDate) -> Option<&'a GstRate> {
+    let code = normalize_code(code);
+    rates
+        .iter()
+        .filter(|rate| code.starts_with(rate.hsn_sac_code.as_str()) && is_in_force(rate, date))
+        .max_by(|a, b| {
+            a.hsn_sac_code
+                .len()
+                .cmp(&b.hsn_sac_code.len())
+                .then_with(|| a.effective_from.cmp(&b.effective_from))
+        })
+}
+
+/// Compares a line's rates with the master entry for its code
+pub fn check_line(item: &InvoiceItem, master: &GstRate) -> Option<RateMismatch> {
+    if item.gst_rate == master.gst_rate && item.cess_rate == master.cess_rate {
+        return None;
+    }
+    Some(RateMismatch {
+        line_number: item.line_number,
+        hsn_sac_code: item.hsn_sac_code.clone(),
+        gst_rate: item.gst_rate,
+        cess_rate: item.cess_rate,
+        master_gst_rate: master.gst_rate,
+        master_cess_rate: master.cess_rate,
+        rate_id: master.id.unwrap_or_default(),
+    })
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum RateError {
+    #[cfg(feature = "gui")]
+    InvalidCode(String),
+    #[cfg(feature = "gui")]
+    InvalidRate(f64),
+    #[cfg(feature = "gui")]
+    InvalidCess(f64),
+    InvalidDate(String),
+    #[cfg(feature = "gui")]
+    InvalidPeriod { effective_from: String, effective_to: String },
+    #[cfg(feature = "gui")]
+    Overlap { hsn_sac_code: String, effective_from: String },
+    #[cfg(feature = "gui")]
+    RateNotFound(i64),
+    Sqlite(rusqlite::Error),
+}
+
+impl RateError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "gui")]
+            RateError::InvalidCode(_) => "InvalidHsnSac",
+            #[cfg(feature = "gui")]
+            RateError::InvalidRate(_) => "InvalidGstRate",
+            #[cfg(feature = "gui")]
+            RateError::InvalidCess(_) => "InvalidCessRate",
+            RateError::InvalidDate(_) => "InvalidEffectiveDates",
+            #[cfg(feature = "gui")]
+            RateError::InvalidPeriod { .. } => "InvalidEffectiveDates",
+            #[cfg(feature = "gui")]
+            RateError::Overlap { .. } => "OverlappingGstRate",
+            #[cfg(feature = "gui")]
+            RateError::RateNotFound(_) => "NotFound",
+            RateError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for RateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "gui")]
+            RateError::InvalidCode(code) => {
+                write!(f, "HSN/SAC code {} must be a 2-digit chapter or 4, 6 or 8 digits", code)
+            }
+            #[cfg(feature = "gui")]
+            RateError::InvalidRate(rate) => write!(
+                f,
+                "Invalid GST rate: {}. Must be from 0 to 100 with at most two decimals",
+                rate
+            ),
+            #[cfg(feature = "gui")]
+            RateError::InvalidCess(rate) => {
+                write!(f, "Cess rate {} must be between 0 and 100", rate)
+            }
+            RateError::InvalidDate(date) => write!(f, "Invalid date {}; use YYYY-MM-DD", date),
+            #[cfg(feature = "gui")]
+            RateError::InvalidPeriod { effective_from, effective_to } => write!(
+                f,
+                "Rate cannot end on {} before it takes effect on {}",
+                effective_to, effective_from
+            ),
+            #[cfg(feature = "gui")]
+            RateError::Overlap { hsn_sac_code, effective_from } => write!(
+                f,
+                "HSN/SAC {} already has a rate in force from {} for part of this period; end that entry first",
+                hsn_sac_code, effective_from
+            ),
+            #[cfg(feature = "gui")]
+            RateError::RateNotFound(id) => write!(f, "GST rate {} does not exist", id),
+            RateError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for RateError {}
+
+impl From<rusqlite::Error> for RateError {
+    fn from(err: rusqlite::Error) -> Self {
+        RateError::Sqlite(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, code: &str, gst_rate: f64, from: &str, to: Option<&str>) -> GstRate {
+        GstRate {
+            id: Some(id),
+            hsn_sac_code: code.to_string(),
+            description: None,
+            gst_rate,
+            cess_rate: 0.0,
+            effective_from: from.to_string(),
+            effective_to: to.map(str::to_string),
+            is_active: true,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn on(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn resolves_longest_prefix_in_force() {
+        let rates = [
+            entry(1, "85", 18.0, "2017-07-01", None),
+            entry(2, "8517", 12.0, "2017-07-01", Some("2020-03-31")),
+            entry(3, "8517", 18.0, "2020-04-01", None),
+            entry(4, "851713", 5.0, "2017-07-01", None),
+            GstRate { is_active: false, ..entry(5, "85171200", 28.0, "2017-07-01", None) },
+        ];
+
+        assert_eq!(resolve(&rates, "85171200", on("2019-06-01")).unwrap().id, Some(2));
+        assert_eq!(resolve(&rates, "8517 1200", on("2020-04-01")).unwrap().id, Some(3));
+        assert_eq!(resolve(&rates, "85171300", on("2019-06-01")).unwrap().id, Some(4));
+        assert_eq!(resolve(&rates, "8501", on("2019-06-01")).unwrap().id, Some(1));
+        assert!(resolve(&rates, "8517", on("2017-06-30")).is_none());
+        assert!(resolve(&rates, "9983", on("2019-06-01")).is_none());
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn validates_entries() {
+        let rate = entry(1, " 8517 ", 18.0, "2017-07-01", Some(""));
+        let valid = validate_rate(&rate).unwrap();
+        assert_eq!(valid.hsn_sac_code, "8517");
+        assert_eq!(valid.effective_to, None);
+
+        assert!(matches!(validate_rate(&entry(1, "85A7", 18.0, "2017-07-01", None)), Err(RateError::InvalidCode(_))));
+        assert!(matches!(validate_rate(&entry(1, "851", 18.0, "2017-07-01", None)), Err(RateError::InvalidCode(_))));
+        assert!(matches!(validate_rate(&entry(1, "8517123", 18.0, "2017-07-01", None)), Err(RateError::InvalidCode(_))));
+        assert!(validate_rate(&entry(1, "85", 18.0, "2017-07-01", None)).is_ok());
+        assert!(validate_rate(&entry(1, "998314", 18.0, "2017-07-01", None)).is_ok());
+        assert!(validate_rate(&entry(1, "8703", 40.0, "2025-09-22", None)).is_ok());
+        assert!(validate_rate(&entry(1, "7108", 3.0, "2017-07-01", None)).is_ok());
+        assert!(validate_rate(&entry(1, "7102", 0.25, "2017-07-01", None)).is_ok());
+        assert!(matches!(validate_rate(&entry(1, "8517", 18.005, "2017-07-01", None)), Err(RateError::InvalidRate(_))));
+        assert!(matches!(validate_rate(&entry(1, "8517", 140.0, "2017-07-01", None)), Err(RateError::InvalidRate(_))));
+        assert!(matches!(validate_rate(&entry(1, "8517", 18.0, "01-07-2017", None)), Err(RateError::InvalidDate(_))));
+        assert!(matches!(
+            validate_rate(&entry(1, "8517", 18.0, "2017-07-01", Some("2017-06-30"))),
+            Err(RateError::InvalidPeriod { .. })
+        ));
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn detects_overlapping_periods() {
+        let closed = entry(1, "8517", 12.0, "2017-07-01", Some("2020-03-31"));
+        assert!(!overlaps(&closed, &entry(2, "8517", 18.0, "2020-04-01", None)));
+        assert!(overlaps(&closed, &entry(2, "8517", 18.0, "2020-03-31", None)));
+        assert!(overlaps(&entry(2, "8517", 18.0, "2016-01-01", None), &closed));
+        assert!(overlaps(&entry(2, "8517", 18.0, "2018-01-01", Some("2018-12-31")), &closed));
+    }
+}
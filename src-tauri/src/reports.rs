@@ -0,0 +1,346 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Return Reports
+// Shared helpers for GST return generation
+// =====================================================
+//
+// Returns are built from issued documents only: drafts are ignored and cancelled
+// invoices appear solely in the document summary.
+
+use crate::gst;
+use crate::money::Money;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// Place-of-supply code the GST portal uses for supplies outside India
+pub const FOREIGN_POS_CODE: &str = "96";
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum ReportError {
+    InvalidPeriod(String),
+    CompanySettingsMissing,
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl ReportError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ReportError::InvalidPeriod(_) => "InvalidReturnPeriod",
+            ReportError::CompanySettingsMissing => "CompanySettingsMissing",
+            ReportError::Io(_) => "FileWriteError",
+            ReportError::Serialization(_) => "SerializationError",
+            ReportError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::InvalidPeriod(period) => {
+                write!(f, "Invalid return period {}; use MMYYYY or YYYY-MM", period)
+            }
+            ReportError::CompanySettingsMissing => {
+                f.write_str("Company settings must be saved before generating returns")
+            }
+            ReportError::Io(err) => write!(f, "Could not write the return file: {}", err),
+            ReportError::Serialization(err) => write!(f, "Could not serialize the return: {}", err),
+            ReportError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReportError {}
+
+impl From<rusqlite::Error> for ReportError {
+    fn from(err: rusqlite::Error) -> Self {
+        ReportError::Sqlite(err)
+    }
+}
+
+impl From<std::io::Error> for ReportError {
+    fn from(err: std::io::Error) -> Self {
+        ReportError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ReportError {
+    fn from(err: serde_json::Error) -> Self {
+        ReportError::Serialization(err)
+    }
+}
+
+// =====================================================
+// Return Period
+// =====================================================
+
+/// A monthly tax period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReturnPeriod {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl ReturnPeriod {
+    /// Accepts the GSTN form `092026` or the ISO form `2026-09`
+    pub fn parse(period: &str) -> Result<Self, ReportError> {
+        let period = period.trim();
+        let invalid = || ReportError::InvalidPeriod(period.to_string());
+
+        let (month, year) = match period.split_once('-') {
+            Some((year, month)) => (month, year),
+            None if period.len() == 6 && period.is_ascii() => (&period[0..2], &period[2..6]),
+            None => return Err(invalid()),
+        };
+        let month: u32 = month.parse().map_err(|_| invalid())?;
+        let year: i32 = year.parse().map_err(|_| invalid())?;
+        if !(1..=12).contains(&month) || !(2017..=9999).contains(&year) {
+            return Err(invalid());
+        }
+
+        Ok(ReturnPeriod { year, month })
+    }
+
+    pub fn first_day(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year, self.month, 1).expect("validated period")
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        let (year, month) = if self.month == 12 {
+            (self.year + 1, 1)
+        } else {
+            (self.year, self.month + 1)
+        };
+        let next_month = NaiveDate::from_ymd_opt(year, month, 1).expect("validated period");
+        next_month.pred_opt().expect("not the first representable date")
+    }
+
+    /// The `fp` value of GSTN return files, e.g. `092026`
+    pub fn gstn(&self) -> String {
+        format!("{:02}{:04}", self.month, self.year)
+    }
+}
+
+// =====================================================
+// Shared Helpers
+// =====================================================
+
+/// Dates in GSTN return files are written `dd-mm-yyyy`
+pub fn gstn_date(date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| date.format("%d-%m-%Y").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// Two-digit place-of-supply code; exports and foreign places of supply use 96
+pub fn pos_code(place_of_supply: &str, invoice_type: &str) -> String {
+    if invoice_type == "EXPORT" {
+        return FOREIGN_POS_CODE.to_string();
+    }
+    gst::state_code(place_of_supply).unwrap_or(FOREIGN_POS_CODE).to_string()
+}
+
+/// Whether the recipient is a registered person, i.e. has a GSTIN on file
+pub fn is_registered(gstin: Option<&str>) -> bool {
+    gstin.is_some_and(|gstin| !gstin.trim().is_empty())
+}
+
+/// Invoice value above which an inter-state supply to an unregistered person is reported
+/// invoice-wise (B2CL). Lowered from ₹2.5 lakh to ₹1 lakh from 1 August 2024.
+pub fn b2cl_threshold(invoice_date: &str) -> Money {
+    let lowered_from = NaiveDate::from_ymd_opt(2024, 8, 1).expect("valid date");
+    match NaiveDate::parse_from_str(invoice_date, "%Y-%m-%d") {
+        Ok(date) if date < lowered_from => Money::from_rupees(250_000.0),
+        _ => Money::from_rupees(100_000.0),
+    }
+}
+
+/// Maps the product unit to a GST unit quantity code, falling back to OTH
+pub fn uqc(unit: &str) -> &'static str {
+    match unit.trim().to_uppercase().as_str() {
+        "PCS" | "PC" | "PIECE" | "PIECES" => "PCS",
+        "NOS" | "NO" | "NUMBER" | "NUMBERS" | "UNIT" | "UNITS" => "NOS",
+        "KG" | "KGS" | "KILOGRAM" | "KILOGRAMS" => "KGS",
+        "G" | "GM" | "GMS" | "GRAM" | "GRAMS" => "GMS",
+        "L" | "LTR" | "LITRE" | "LITRES" | "LITER" => "LTR",
+        "ML" => "MLT",
+        "M" | "MTR" | "METER" | "METRE" | "METERS" | "METRES" => "MTR",
+        "BOX" | "BOXES" => "BOX",
+        "SET" | "SETS" => "SET",
+        "DOZ" | "DOZEN" => "DOZ",
+        "PAC" | "PACK" | "PACKS" => "PAC",
+        "TON" | "TONNE" | "TONNES" | "MT" => "TON",
+        "BAG" | "BAGS" => "BAG",
+        _ => "OTH",
+    }
+}
+
+/// Writes a return as compact JSON, the form the GST offline tool imports
+pub fn write_json<T: Serialize>(path: &Path, report: &T) -> Result<(), ReportError> {
+    let json = serde_json::to_string(report)?;
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+/// Documents shared by the return tests
+#[cfg(test)]
+pub mod fixtures {
+    use crate::database::{Customer, Invoice, InvoiceItem, ReturnInvoice, ReturnLine};
+    use crate::gst;
+    use crate::money::Money;
+
+    fn rupees(amount: f64) -> Money {
+        Money::from_rupees(amount)
+    }
+
+    pub fn customer(gstin: Option<&str>, customer_type: &str) -> Customer {
+        Customer {
+            id: Some(1),
+            customer_name: "Customer".to_string(),
+            gstin: gstin.map(str::to_string),
+            pan: None,
+            customer_type: customer_type.to_string(),
+            address_line1: "MG Road".to_string(),
+            address_line2: None,
+            city: "Pune".to_string(),
+            state: "Maharashtra".to_string(),
+            pincode: "411001".to_string(),
+            phone: None,
+            email: None,
+            credit_limit: Money::ZERO,
+            credit_period_days: 0,
+            is_active: true,
+            created_at: None,
+            updated_at: None,
+            invoice_template_id: None,
+        }
+    }
+
+    /// A single-line document of `taxable` at 18%, taxed for the given place of supply
+    pub fn document(number: &str, kind: &str, place: &str, taxable: f64, customer: Customer) -> ReturnInvoice {
+        document_at_rate(number, kind, place, taxable, 18.0, customer)
+    }
+
+    pub fn document_at_rate(
+        number: &str,
+        kind: &str,
+        place: &str,
+        taxable: f64,
+        rate: f64,
+        customer: Customer,
+    ) -> ReturnInvoice {
+        let inter_state = gst::is_inter_state_transaction("Maharashtra", place, kind);
+        let line = gst::calculate_line_item(1.0, rupees(taxable), 0.0, rate, 0.0, inter_state).unwrap();
+        let gst = &line.gst_calculation;
+        let item = InvoiceItem {
+            id: Some(1),
+            invoice_id: 1,
+            product_id: 1,
+            line_number: 1,
+            product_code: "P1".to_string(),
+            product_name: "Steel rod".to_string(),
+            description: None,
+            hsn_sac_code: "7214".to_string(),
+            quantity: 1.0,
+            unit_price: rupees(taxable),
+            discount_percent: 0.0,
+            discount_amount: Money::ZERO,
+            taxable_amount: line.taxable_amount,
+            gst_rate: rate,
+            cgst_rate: gst.cgst_rate,
+            sgst_rate: gst.sgst_rate,
+            igst_rate: gst.igst_rate,
+            cess_rate: 0.0,
+            cgst_amount: gst.cgst_amount,
+            sgst_amount: gst.sgst_amount,
+            igst_amount: gst.igst_amount,
+            cess_amount: Money::ZERO,
+            total_tax: gst.total_tax,
+            line_total: line.line_total,
+            created_at: None,
+        };
+        let invoice = Invoice {
+            id: Some(1),
+            invoice_number: number.to_string(),
+            invoice_date: "2026-09-10".to_string(),
+            customer_id: 1,
+            invoice_type: kind.to_string(),
+            place_of_supply: place.to_string(),
+            reverse_charge: false,
+            subtotal: line.taxable_amount,
+            total_discount: Money::ZERO,
+            taxable_amount: line.taxable_amount,
+            cgst_amount: gst.cgst_amount,
+            sgst_amount: gst.sgst_amount,
+            igst_amount: gst.igst_amount,
+            cess_amount: Money::ZERO,
+            total_tax: gst.total_tax,
+            total_amount: line.line_total,
+            round_off: Money::ZERO,
+            final_amount: line.line_total,
+            payment_terms: None,
+            due_date: None,
+            status: "SENT".to_string(),
+            notes: None,
+            terms_conditions: None,
+            pdf_path: None,
+            created_at: None,
+            updated_at: None,
+            irn: None,
+            ack_number: None,
+            ack_date: None,
+            signed_qr_code: None,
+            note_reason: None,
+            cancelled_at: None,
+            cancellation_reason: None,
+            amends_invoice_id: None,
+        };
+        ReturnInvoice {
+            invoice,
+            customer,
+            lines: vec![ReturnLine {
+                item,
+                unit_of_measurement: "PCS".to_string(),
+                product_type: "GOODS".to_string(),
+            }],
+            references: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_return_periods() {
+        let period = ReturnPeriod::parse("092026").unwrap();
+        assert_eq!(period, ReturnPeriod::parse("2026-09").unwrap());
+        assert_eq!(period.gstn(), "092026");
+        assert_eq!(period.first_day().to_string(), "2026-09-01");
+        assert_eq!(period.last_day().to_string(), "2026-09-30");
+        assert_eq!(ReturnPeriod::parse("2027-02").unwrap().last_day().to_string(), "2027-02-28");
+        assert_eq!(ReturnPeriod::parse("122026").unwrap().last_day().to_string(), "2026-12-31");
+
+        for bad in ["132026", "2026-00", "2026", "ab2026", ""] {
+            assert!(ReturnPeriod::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn formats_gstn_fields() {
+        assert_eq!(gstn_date("2026-09-05"), "05-09-2026");
+        assert_eq!(pos_code("Karnataka", "REGULAR"), "29");
+        assert_eq!(pos_code("27", "REGULAR"), "27");
+        assert_eq!(pos_code("Maharashtra", "EXPORT"), "96");
+        assert_eq!(b2cl_threshold("2024-07-31"), Money::from_rupees(250_000.0));
+        assert_eq!(b2cl_threshold("2026-09-05"), Money::from_rupees(100_000.0));
+    }
+}
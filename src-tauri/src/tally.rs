@@ -0,0 +1,847 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Tally Export
+// Import XML for Tally Prime: party ledgers, sales, note and receipt vouchers
+// =====================================================
+//
+// Every voucher carries a REMOTEID derived from the Payvlo record, and each export is
+// logged. An incremental export sends only vouchers Tally has not seen, alters receipts
+// whose amount changed since (a split payment was voided) and cancels vouchers whose
+// invoice was cancelled or whose payments were all voided. Drafts are never exported.
+//
+// Tally signs amounts by side: debits are negative with ISDEEMEDPOSITIVE set, credits
+// positive. Vouchers are built the same way, credits positive, and always balance:
+// whatever the lines leave over is posted to the round-off ledger.
+
+use crate::database::{Customer, Database, Payment, ReturnInvoice, TallySettings};
+use crate::gst;
+use crate::money::Money;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+/// Replaced in ledger names by the rate of the lines posted to the ledger
+pub const RATE_PLACEHOLDER: &str = "{rate}";
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum TallyError {
+    #[cfg(feature = "gui")]
+    LedgerNameRequired(&'static str),
+    #[cfg(feature = "gui")]
+    DuplicatePartyLedger(String),
+    InvalidDate(String),
+    InvalidRange { from: String, to: String },
+    Io(std::io::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl TallyError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "gui")]
+            TallyError::LedgerNameRequired(_) | TallyError::DuplicatePartyLedger(_) => "InvalidTallySettings",
+            TallyError::InvalidDate(_) | TallyError::InvalidRange { .. } => "InvalidExportRange",
+            TallyError::Io(_) => "FileWriteError",
+            TallyError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for TallyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "gui")]
+            TallyError::LedgerNameRequired(field) => write!(f, "The Tally ledger name for {} is required", field),
+            #[cfg(feature = "gui")]
+            TallyError::DuplicatePartyLedger(name) => {
+                write!(f, "More than one customer is mapped to the Tally ledger {}", name)
+            }
+            TallyError::InvalidDate(date) => write!(f, "Invalid date {}; use YYYY-MM-DD", date),
+            TallyError::InvalidRange { from, to } => write!(f, "Export range ends on {} before it starts on {}", to, from),
+            TallyError::Io(err) => write!(f, "Could not write the Tally file: {}", err),
+            TallyError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for TallyError {}
+
+impl From<std::io::Error> for TallyError {
+    fn from(err: std::io::Error) -> Self {
+        TallyError::Io(err)
+    }
+}
+
+impl From<rusqlite::Error> for TallyError {
+    fn from(err: rusqlite::Error) -> Self {
+        TallyError::Sqlite(err)
+    }
+}
+
+// =====================================================
+// Export Options and Results
+// =====================================================
+
+/// Documents dated within the range, all of them when unset. An incremental export
+/// leaves out vouchers already exported unchanged.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TallyExportOptions {
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub incremental: bool,
+}
+
+/// A voucher as last sent to Tally
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ExportedVoucher {
+    pub remote_id: String,
+    pub voucher_type: String,
+    pub voucher_number: String,
+    pub amount: Money,
+    pub is_cancelled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TallyExportReport {
+    pub path: String,
+    pub incremental: bool,
+    pub ledgers: usize,
+    pub created: usize,
+    pub altered: usize,
+    pub cancelled: usize,
+    /// Voucher numbers by voucher type, for the vouchers in the file
+    pub vouchers: BTreeMap<String, Vec<String>>,
+}
+
+// =====================================================
+// Vouchers
+// =====================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Create,
+    Alter,
+    Cancel,
+}
+
+impl Action {
+    fn as_str(self) -> &'static str {
+        match self {
+            Action::Create => "Create",
+            Action::Alter => "Alter",
+            Action::Cancel => "Cancel",
+        }
+    }
+}
+
+/// What a ledger is for, which decides the group its master is created under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LedgerKind {
+    Party,
+    Sales,
+    Duty(&'static str),
+    RoundOff,
+    Cash,
+    Bank,
+}
+
+impl LedgerKind {
+    fn parent(self, settings: &TallySettings) -> &str {
+        match self {
+            LedgerKind::Party => &settings.party_group,
+            LedgerKind::Sales => "Sales Accounts",
+            LedgerKind::Duty(_) => "Duties & Taxes",
+            LedgerKind::RoundOff => "Indirect Expenses",
+            LedgerKind::Cash => "Cash-in-Hand",
+            LedgerKind::Bank => "Bank Accounts",
+        }
+    }
+}
+
+/// Bill-wise reference of a party entry: a new bill or a payment against one
+#[derive(Debug, Clone, PartialEq)]
+struct Bill {
+    name: String,
+    against: bool,
+    amount: Money,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    ledger: String,
+    kind: LedgerKind,
+    /// Credits positive, debits negative
+    amount: Money,
+    bills: Vec<Bill>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voucher {
+    remote_id: String,
+    voucher_type: &'static str,
+    number: String,
+    date: String,
+    party: String,
+    party_gstin: Option<String>,
+    place_of_supply: Option<String>,
+    narration: Option<String>,
+    entries: Vec<Entry>,
+    action: Action,
+    /// False once the document is cancelled or every payment in it voided
+    live: bool,
+}
+
+impl Voucher {
+    /// Total debited, the figure Tally shows as the voucher amount
+    fn amount(&self) -> Money {
+        -self.entries.iter().map(|entry| entry.amount).filter(|amount| *amount < Money::ZERO).sum::<Money>()
+    }
+
+    fn record(&self) -> ExportedVoucher {
+        ExportedVoucher {
+            remote_id: self.remote_id.clone(),
+            voucher_type: self.voucher_type.to_string(),
+            voucher_number: self.number.clone(),
+            amount: self.amount(),
+            is_cancelled: self.action == Action::Cancel,
+        }
+    }
+}
+
+/// Ledger name for lines at a rate, "Sales @ {rate}%" becoming "Sales @ 18%"
+fn ledger_name(template: &str, rate: f64) -> String {
+    template.replace(RATE_PLACEHOLDER, &rate.to_string())
+}
+
+/// Adds to the entry for a ledger, keeping ledgers in the order first posted
+fn post(entries: &mut Vec<Entry>, ledger: String, kind: LedgerKind, amount: Money) {
+    if amount == Money::ZERO {
+        return;
+    }
+    match entries.iter_mut().find(|entry| entry.ledger == ledger) {
+        Some(entry) => entry.amount += amount,
+        None => entries.push(Entry { ledger, kind, amount, bills: Vec::new() }),
+    }
+}
+
+/// State names as Tally lists them; codes are resolved to names
+fn state_name(state: &str) -> String {
+    gst::STATE_CODES
+        .iter()
+        .find(|(code, _)| *code == state.trim())
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| state.trim().to_string())
+}
+
+/// Sales, credit note or debit note voucher for an issued document
+pub fn document_voucher(document: &ReturnInvoice, party: &str, settings: &TallySettings) -> Voucher {
+    let invoice = &document.invoice;
+    let voucher_type = match invoice.invoice_type.as_str() {
+        "CREDIT_NOTE" => "Credit Note",
+        "DEBIT_NOTE" => "Debit Note",
+        _ => "Sales",
+    };
+    // A credit note reverses a sale: income and tax are debited and the party credited
+    let signed = |amount: Money| if voucher_type == "Credit Note" { -amount } else { amount };
+
+    let mut entries = Vec::new();
+    for line in &document.lines {
+        let item = &line.item;
+        post(&mut entries, ledger_name(&settings.sales_ledger, item.gst_rate), LedgerKind::Sales, signed(item.taxable_amount));
+    }
+    for line in &document.lines {
+        let item = &line.item;
+        let duties = [
+            (&settings.cgst_ledger, item.cgst_rate, item.cgst_amount, "Central Tax"),
+            (&settings.sgst_ledger, item.sgst_rate, item.sgst_amount, "State Tax"),
+            (&settings.igst_ledger, item.igst_rate, item.igst_amount, "Integrated Tax"),
+            (&settings.cess_ledger, item.cess_rate, item.cess_amount, "Cess"),
+        ];
+        for (template, rate, amount, head) in duties {
+            post(&mut entries, ledger_name(template, rate), LedgerKind::Duty(head), signed(amount));
+        }
+    }
+    let posted: Money = entries.iter().map(|entry| entry.amount).sum();
+    post(&mut entries, settings.round_off_ledger.clone(), LedgerKind::RoundOff, signed(invoice.final_amount) - posted);
+
+    // Notes settle against the invoices they amend; anything unallocated is a new bill
+    let mut bills: Vec<Bill> = document
+        .references
+        .iter()
+        .map(|reference| Bill { name: reference.invoice_number.clone(), against: true, amount: -signed(reference.amount) })
+        .collect();
+    let allocated: Money = document.references.iter().map(|reference| reference.amount).sum();
+    if allocated < invoice.final_amount {
+        bills.push(Bill {
+            name: invoice.invoice_number.clone(),
+            against: false,
+            amount: -signed(invoice.final_amount - allocated),
+        });
+    }
+    entries.insert(
+        0,
+        Entry { ledger: party.to_string(), kind: LedgerKind::Party, amount: -signed(invoice.final_amount), bills },
+    );
+
+    let narration = match (&invoice.note_reason, document.references.is_empty()) {
+        (Some(reason), false) => Some(format!(
+            "{} against {}",
+            reason,
+            document.references.iter().map(|r| r.invoice_number.as_str()).collect::<Vec<_>>().join(", ")
+        )),
+        (Some(reason), true) => Some(reason.clone()),
+        (None, _) => invoice.notes.clone(),
+    };
+
+    Voucher {
+        remote_id: format!("payvlo-invoice-{}", invoice.id.unwrap_or_default()),
+        voucher_type,
+        number: invoice.invoice_number.clone(),
+        date: invoice.invoice_date.clone(),
+        party: party.to_string(),
+        party_gstin: document.customer.gstin.clone(),
+        place_of_supply: Some(state_name(&invoice.place_of_supply)),
+        narration,
+        entries,
+        action: Action::Create,
+        live: invoice.status != "CANCELLED",
+    }
+}
+
+/// Receipt voucher for the rows of one receipt from one customer, each against the
+/// invoice it pays. Voided rows are left out.
+pub fn receipt_voucher(
+    receipt_number: &str,
+    customer: &Customer,
+    party: &str,
+    payments: &[(Payment, String)],
+    settings: &TallySettings,
+) -> Voucher {
+    let live: Vec<&(Payment, String)> = payments.iter().filter(|(payment, _)| payment.voided_at.is_none()).collect();
+    let first = &payments[0].0;
+    let total: Money = live.iter().map(|(payment, _)| payment.amount).sum();
+    let (ledger, kind) = match first.payment_method.as_str() {
+        "CASH" => (settings.cash_ledger.clone(), LedgerKind::Cash),
+        _ => (settings.bank_ledger.clone(), LedgerKind::Bank),
+    };
+    let bills = live
+        .iter()
+        .map(|(payment, invoice_number)| Bill { name: invoice_number.clone(), against: true, amount: payment.amount })
+        .collect();
+    let narration = [Some(first.payment_method.replace('_', " ")), first.reference_number.clone()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Voucher {
+        remote_id: format!("payvlo-receipt-{}-{}", receipt_number, customer.id.unwrap_or_default()),
+        voucher_type: "Receipt",
+        number: receipt_number.to_string(),
+        date: first.payment_date.clone(),
+        party: party.to_string(),
+        party_gstin: None,
+        place_of_supply: None,
+        narration: Some(narration),
+        entries: vec![
+            Entry { ledger, kind, amount: -total, bills: Vec::new() },
+            Entry { ledger: party.to_string(), kind: LedgerKind::Party, amount: total, bills },
+        ],
+        action: Action::Create,
+        live: !live.is_empty(),
+    }
+}
+
+/// Decides what to send for each voucher given what was exported before. Vouchers
+/// no longer live are cancelled if Tally has them and otherwise left out.
+pub fn plan(vouchers: Vec<Voucher>, exported: &BTreeMap<String, ExportedVoucher>, incremental: bool) -> Vec<Voucher> {
+    vouchers
+        .into_iter()
+        .filter_map(|mut voucher| {
+            let previous = exported.get(&voucher.remote_id);
+            voucher.action = match (voucher.live, previous) {
+                (false, Some(previous)) if !previous.is_cancelled => Action::Cancel,
+                (false, _) => return None,
+                (true, Some(previous)) if incremental && previous.is_cancelled => return None,
+                (true, Some(previous)) if incremental && previous.amount == voucher.amount() => return None,
+                (true, Some(_)) if incremental => Action::Alter,
+                (true, _) => Action::Create,
+            };
+            Some(voucher)
+        })
+        .collect()
+}
+
+// =====================================================
+// XML Writing
+// =====================================================
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// YYYY-MM-DD as Tally's YYYYMMDD
+fn tally_date(date: &str) -> String {
+    date.replace('-', "")
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "Yes"
+    } else {
+        "No"
+    }
+}
+
+fn element(xml: &mut String, indent: &str, name: &str, value: &str) {
+    xml.push_str(&format!("{}<{}>{}</{}>\n", indent, name, escape(value), name));
+}
+
+fn party_master(xml: &mut String, name: &str, customer: &Customer, settings: &TallySettings) {
+    let indent = "      ";
+    xml.push_str(&format!("     <LEDGER NAME=\"{}\">\n", escape(name)));
+    xml.push_str(&format!("{}<NAME.LIST TYPE=\"String\">\n", indent));
+    element(xml, "       ", "NAME", name);
+    xml.push_str(&format!("{}</NAME.LIST>\n", indent));
+    element(xml, indent, "PARENT", &settings.party_group);
+    element(xml, indent, "ISBILLWISEON", "Yes");
+    xml.push_str(&format!("{}<ADDRESS.LIST TYPE=\"String\">\n", indent));
+    let city = match customer.pincode.trim() {
+        "" => customer.city.clone(),
+        pincode => format!("{} - {}", customer.city, pincode),
+    };
+    for line in [Some(customer.address_line1.clone()), customer.address_line2.clone(), Some(city)].into_iter().flatten() {
+        element(xml, "       ", "ADDRESS", &line);
+    }
+    xml.push_str(&format!("{}</ADDRESS.LIST>\n", indent));
+    if customer.customer_type != "EXPORT" {
+        element(xml, indent, "LEDSTATENAME", &state_name(&customer.state));
+        element(xml, indent, "COUNTRYOFRESIDENCE", "India");
+        element(xml, indent, "PINCODE", &customer.pincode);
+    }
+    let registration = match (&customer.gstin, customer.customer_type.as_str()) {
+        (Some(_), _) => "Regular",
+        (None, "B2C") => "Consumer",
+        (None, _) => "Unregistered",
+    };
+    element(xml, indent, "GSTREGISTRATIONTYPE", registration);
+    if let Some(gstin) = &customer.gstin {
+        element(xml, indent, "PARTYGSTIN", gstin);
+    }
+    if let Some(pan) = &customer.pan {
+        element(xml, indent, "INCOMETAXNUMBER", pan);
+    }
+    if let Some(email) = &customer.email {
+        element(xml, indent, "EMAIL", email);
+    }
+    if let Some(phone) = &customer.phone {
+        element(xml, indent, "LEDGERPHONE", phone);
+    }
+    xml.push_str("     </LEDGER>\n");
+}
+
+fn account_master(xml: &mut String, name: &str, kind: LedgerKind, settings: &TallySettings) {
+    let indent = "      ";
+    xml.push_str(&format!("     <LEDGER NAME=\"{}\">\n", escape(name)));
+    xml.push_str(&format!("{}<NAME.LIST TYPE=\"String\">\n", indent));
+    element(xml, "       ", "NAME", name);
+    xml.push_str(&format!("{}</NAME.LIST>\n", indent));
+    element(xml, indent, "PARENT", kind.parent(settings));
+    if let LedgerKind::Duty(head) = kind {
+        element(xml, indent, "TAXTYPE", "GST");
+        element(xml, indent, "GSTDUTYHEAD", head);
+    }
+    xml.push_str("     </LEDGER>\n");
+}
+
+fn voucher_xml(xml: &mut String, voucher: &Voucher) {
+    let indent = "      ";
+    if voucher.action == Action::Cancel {
+        xml.push_str(&format!(
+            "     <VOUCHER REMOTEID=\"{}\" DATE=\"{}\" TAGNAME=\"Voucher Number\" TAGVALUE=\"{}\" VCHTYPE=\"{}\" ACTION=\"Cancel\">\n",
+            escape(&voucher.remote_id),
+            tally_date(&voucher.date),
+            escape(&voucher.number),
+            voucher.voucher_type
+        ));
+        element(xml, indent, "DATE", &tally_date(&voucher.date));
+        element(xml, indent, "VOUCHERTYPENAME", voucher.voucher_type);
+        element(xml, indent, "VOUCHERNUMBER", &voucher.number);
+        xml.push_str("     </VOUCHER>\n");
+        return;
+    }
+
+    xml.push_str(&format!(
+        "     <VOUCHER REMOTEID=\"{}\" VCHTYPE=\"{}\" ACTION=\"{}\" OBJVIEW=\"Accounting Voucher View\">\n",
+        escape(&voucher.remote_id),
+        voucher.voucher_type,
+        voucher.action.as_str()
+    ));
+    element(xml, indent, "DATE", &tally_date(&voucher.date));
+    element(xml, indent, "VOUCHERTYPENAME", voucher.voucher_type);
+    element(xml, indent, "VOUCHERNUMBER", &voucher.number);
+    element(xml, indent, "PARTYLEDGERNAME", &voucher.party);
+    if let Some(gstin) = &voucher.party_gstin {
+        element(xml, indent, "PARTYGSTIN", gstin);
+    }
+    if let Some(place) = &voucher.place_of_supply {
+        element(xml, indent, "PLACEOFSUPPLY", place);
+    }
+    if let Some(narration) = voucher.narration.as_deref().filter(|narration| !narration.is_empty()) {
+        element(xml, indent, "NARRATION", narration);
+    }
+    element(xml, indent, "PERSISTEDVIEW", "Accounting Voucher View");
+    element(xml, indent, "ISINVOICE", "No");
+
+    for entry in &voucher.entries {
+        let debit = entry.amount < Money::ZERO;
+        xml.push_str(&format!("{}<LEDGERENTRIES.LIST>\n", indent));
+        element(xml, "       ", "LEDGERNAME", &entry.ledger);
+        element(xml, "       ", "ISDEEMEDPOSITIVE", yes_no(debit));
+        element(xml, "       ", "ISPARTYLEDGER", yes_no(entry.kind == LedgerKind::Party));
+        element(xml, "       ", "AMOUNT", &entry.amount.to_string());
+        for bill in &entry.bills {
+            xml.push_str("       <BILLALLOCATIONS.LIST>\n");
+            element(xml, "        ", "NAME", &bill.name);
+            element(xml, "        ", "BILLTYPE", if bill.against { "Agst Ref" } else { "New Ref" });
+            element(xml, "        ", "AMOUNT", &bill.amount.to_string());
+            xml.push_str("       </BILLALLOCATIONS.LIST>\n");
+        }
+        xml.push_str(&format!("{}</LEDGERENTRIES.LIST>\n", indent));
+    }
+    xml.push_str("     </VOUCHER>\n");
+}
+
+/// The import file: masters for every ledger the vouchers post to, then the vouchers.
+/// Masters carry no action, so Tally's setting for existing masters decides whether
+/// they are skipped or updated.
+pub fn build_xml(settings: &TallySettings, parties: &BTreeMap<String, Customer>, vouchers: &[Voucher]) -> String {
+    let mut masters = String::new();
+    let mut seen = BTreeSet::new();
+    for voucher in vouchers.iter().filter(|voucher| voucher.action != Action::Cancel) {
+        for entry in &voucher.entries {
+            if !seen.insert(entry.ledger.to_lowercase()) {
+                continue;
+            }
+            masters.push_str("    <TALLYMESSAGE xmlns:UDF=\"TallyUDF\">\n");
+            match (entry.kind, parties.get(&entry.ledger)) {
+                (LedgerKind::Party, Some(customer)) => party_master(&mut masters, &entry.ledger, customer, settings),
+                (kind, _) => account_master(&mut masters, &entry.ledger, kind, settings),
+            }
+            masters.push_str("    </TALLYMESSAGE>\n");
+        }
+    }
+
+    let mut xml = String::from("<ENVELOPE>\n <HEADER>\n  <TALLYREQUEST>Import Data</TALLYREQUEST>\n </HEADER>\n <BODY>\n  <IMPORTDATA>\n");
+    xml.push_str("   <REQUESTDESC>\n    <REPORTNAME>Vouchers</REPORTNAME>\n");
+    if let Some(company) = settings.company_name.as_deref().filter(|company| !company.trim().is_empty()) {
+        xml.push_str("    <STATICVARIABLES>\n");
+        element(&mut xml, "     ", "SVCURRENTCOMPANY", company.trim());
+        xml.push_str("    </STATICVARIABLES>\n");
+    }
+    xml.push_str("   </REQUESTDESC>\n   <REQUESTDATA>\n");
+    xml.push_str(&masters);
+    for voucher in vouchers {
+        xml.push_str("    <TALLYMESSAGE xmlns:UDF=\"TallyUDF\">\n");
+        voucher_xml(&mut xml, voucher);
+        xml.push_str("    </TALLYMESSAGE>\n");
+    }
+    xml.push_str("   </REQUESTDATA>\n  </IMPORTDATA>\n </BODY>\n</ENVELOPE>\n");
+    xml
+}
+
+// =====================================================
+// Settings
+// =====================================================
+
+/// Trims the ledger names and rejects blank ones or two customers sharing a ledger
+#[cfg(feature = "gui")]
+pub fn check_settings(settings: &TallySettings) -> Result<TallySettings, TallyError> {
+    let required = |name: &str, field: &'static str| -> Result<String, TallyError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(TallyError::LedgerNameRequired(field));
+        }
+        Ok(name.to_string())
+    };
+
+    let mut party_ledgers = Vec::new();
+    let mut names = BTreeSet::new();
+    for party in &settings.party_ledgers {
+        let ledger_name = required(&party.ledger_name, "a customer")?;
+        if !names.insert(ledger_name.to_lowercase()) {
+            return Err(TallyError::DuplicatePartyLedger(ledger_name));
+        }
+        party_ledgers.push(crate::database::TallyPartyLedger { customer_id: party.customer_id, ledger_name });
+    }
+
+    Ok(TallySettings {
+        company_name: settings
+            .company_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string),
+        party_group: required(&settings.party_group, "the customer group")?,
+        sales_ledger: required(&settings.sales_ledger, "sales")?,
+        cgst_ledger: required(&settings.cgst_ledger, "CGST")?,
+        sgst_ledger: required(&settings.sgst_ledger, "SGST")?,
+        igst_ledger: required(&settings.igst_ledger, "IGST")?,
+        cess_ledger: required(&settings.cess_ledger, "cess")?,
+        round_off_ledger: required(&settings.round_off_ledger, "round off")?,
+        cash_ledger: required(&settings.cash_ledger, "cash receipts")?,
+        bank_ledger: required(&settings.bank_ledger, "bank receipts")?,
+        party_ledgers,
+        updated_at: settings.updated_at.clone(),
+    })
+}
+
+// =====================================================
+// Export
+// =====================================================
+
+fn parse_date(date: Option<&str>, default: NaiveDate) -> Result<NaiveDate, TallyError> {
+    match date.map(str::trim).filter(|date| !date.is_empty()) {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| TallyError::InvalidDate(date.to_string())),
+        None => Ok(default),
+    }
+}
+
+/// Writes the import file for the documents and receipts in range, then logs what it
+/// sent so the next incremental export starts from there
+pub fn export_to_file(db: &Database, path: &Path, options: &TallyExportOptions) -> Result<TallyExportReport, TallyError> {
+    let from = parse_date(options.date_from.as_deref(), NaiveDate::from_ymd_opt(1900, 1, 1).unwrap_or_default())?;
+    let to = parse_date(options.date_to.as_deref(), NaiveDate::from_ymd_opt(9999, 12, 31).unwrap_or_default())?;
+    if to < from {
+        return Err(TallyError::InvalidRange { from: from.to_string(), to: to.to_string() });
+    }
+
+    let settings = db.get_tally_settings()?;
+    let names = db.get_tally_party_names()?;
+    let party = |customer: &Customer| {
+        customer
+            .id
+            .and_then(|id| names.get(&id).cloned())
+            .unwrap_or_else(|| customer.customer_name.clone())
+    };
+    let mut parties = BTreeMap::new();
+    let mut vouchers = Vec::new();
+
+    for document in db.get_return_invoices(from, to)? {
+        let name = party(&document.customer);
+        vouchers.push(document_voucher(&document, &name, &settings));
+        parties.insert(name, document.customer);
+    }
+
+    // Rows of a receipt are grouped per customer, in case one receipt paid several
+    let mut receipts: BTreeMap<(String, i64), Vec<(Payment, String)>> = BTreeMap::new();
+    for (payment, invoice_number, customer_id) in db.get_tally_payments(from, to)? {
+        let receipt_number = payment
+            .receipt_number
+            .clone()
+            .unwrap_or_else(|| format!("RCT-{:06}", payment.id.unwrap_or_default()));
+        receipts.entry((receipt_number, customer_id)).or_default().push((payment, invoice_number));
+    }
+    for ((receipt_number, customer_id), payments) in &receipts {
+        let Some(customer) = db.get_customer_by_id(*customer_id)? else { continue };
+        let name = party(&customer);
+        vouchers.push(receipt_voucher(receipt_number, &customer, &name, payments, &settings));
+        parties.insert(name, customer);
+    }
+    vouchers.sort_by(|a, b| a.date.cmp(&b.date));
+
+    let exported = db.get_tally_exports()?;
+    let vouchers = plan(vouchers, &exported, options.incremental);
+    std::fs::write(path, build_xml(&settings, &parties, &vouchers))?;
+    db.record_tally_exports(&vouchers.iter().map(Voucher::record).collect::<Vec<_>>())?;
+
+    let mut report = TallyExportReport {
+        path: path.display().to_string(),
+        incremental: options.incremental,
+        ledgers: 0,
+        created: 0,
+        altered: 0,
+        cancelled: 0,
+        vouchers: BTreeMap::new(),
+    };
+    let mut ledgers = BTreeSet::new();
+    for voucher in &vouchers {
+        match voucher.action {
+            Action::Create => report.created += 1,
+            Action::Alter => report.altered += 1,
+            Action::Cancel => report.cancelled += 1,
+        }
+        if voucher.action != Action::Cancel {
+            ledgers.extend(voucher.entries.iter().map(|entry| entry.ledger.to_lowercase()));
+        }
+        report.vouchers.entry(voucher.voucher_type.to_string()).or_default().push(voucher.number.clone());
+    }
+    report.ledgers = ledgers.len();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::NoteReference;
+    use crate::reports::fixtures::{customer, document};
+
+    fn settings() -> TallySettings {
+        let db = Database::new(Path::new(":memory:")).unwrap();
+        db.initialize_schema().unwrap();
+        db.get_tally_settings().unwrap()
+    }
+
+    fn payment(id: i64, amount: f64, voided: bool) -> (Payment, String) {
+        let payment = Payment {
+            id: Some(id),
+            invoice_id: id,
+            payment_date: "2026-09-20".to_string(),
+            amount: Money::from_rupees(amount),
+            payment_method: "BANK_TRANSFER".to_string(),
+            reference_number: Some("UTR123".to_string()),
+            notes: None,
+            receipt_number: Some("RCT-000001".to_string()),
+            voided_at: voided.then(|| "2026-09-21 10:00:00".to_string()),
+            void_reason: None,
+            created_at: None,
+        };
+        (payment, format!("INV-{}", id))
+    }
+
+    fn entries(voucher: &Voucher) -> Vec<(&str, Money)> {
+        voucher.entries.iter().map(|entry| (entry.ledger.as_str(), entry.amount)).collect()
+    }
+
+    fn rupees(amount: f64) -> Money {
+        Money::from_rupees(amount)
+    }
+
+    #[test]
+    fn builds_balanced_vouchers() {
+        let settings = settings();
+        let buyer = customer(Some("27AAPFU0939F1ZV"), "B2B");
+
+        let mut sale = document("INV-1", "REGULAR", "Maharashtra", 1000.0, buyer.clone());
+        sale.invoice.final_amount = rupees(1181.0);
+        let voucher = document_voucher(&sale, "Sharma Traders", &settings);
+        assert_eq!(voucher.voucher_type, "Sales");
+        assert_eq!(
+            entries(&voucher),
+            [
+                ("Sharma Traders", rupees(-1181.0)),
+                ("Sales @ 18%", rupees(1000.0)),
+                ("Output CGST @ 9%", rupees(90.0)),
+                ("Output SGST @ 9%", rupees(90.0)),
+                ("Round Off", rupees(1.0)),
+            ]
+        );
+        assert_eq!(voucher.entries[0].bills, [Bill { name: "INV-1".to_string(), against: false, amount: rupees(-1181.0) }]);
+
+        let mut note = document("CN-1", "CREDIT_NOTE", "Karnataka", 500.0, buyer.clone());
+        note.references = vec![NoteReference {
+            note_id: 2,
+            note_number: "CN-1".to_string(),
+            note_type: "CREDIT_NOTE".to_string(),
+            note_date: "2026-09-15".to_string(),
+            note_status: "SENT".to_string(),
+            invoice_id: 1,
+            invoice_number: "INV-1".to_string(),
+            invoice_date: "2026-09-10".to_string(),
+            invoice_value: rupees(1181.0),
+            invoice_place_of_supply: "Maharashtra".to_string(),
+            amount: rupees(400.0),
+        }];
+        let voucher = document_voucher(&note, "Sharma Traders", &settings);
+        assert_eq!(voucher.voucher_type, "Credit Note");
+        assert_eq!(
+            entries(&voucher),
+            [("Sharma Traders", rupees(590.0)), ("Sales @ 18%", rupees(-500.0)), ("Output IGST @ 18%", rupees(-90.0))]
+        );
+        assert_eq!(
+            voucher.entries[0].bills,
+            [
+                Bill { name: "INV-1".to_string(), against: true, amount: rupees(400.0) },
+                Bill { name: "CN-1".to_string(), against: false, amount: rupees(190.0) },
+            ]
+        );
+
+        let receipt = receipt_voucher(
+            "RCT-000001",
+            &buyer,
+            "Sharma Traders",
+            &[payment(1, 1000.0, false), payment(2, 300.0, true)],
+            &settings,
+        );
+        assert_eq!(entries(&receipt), [("Bank Account", rupees(-1000.0)), ("Sharma Traders", rupees(1000.0))]);
+        assert_eq!(receipt.narration.as_deref(), Some("BANK TRANSFER UTR123"));
+        assert!(receipt.live);
+
+        for voucher in [&voucher, &receipt] {
+            assert_eq!(voucher.entries.iter().map(|entry| entry.amount).sum::<Money>(), Money::ZERO);
+        }
+    }
+
+    #[test]
+    fn exports_only_what_changed() {
+        let settings = settings();
+        let buyer = customer(Some("27AAPFU0939F1ZV"), "B2B");
+        let sale = document_voucher(&document("INV-1", "REGULAR", "Maharashtra", 1000.0, buyer.clone()), "Sharma & Co", &settings);
+        let mut cancelled = document("INV-2", "REGULAR", "Maharashtra", 200.0, buyer.clone());
+        cancelled.invoice.id = Some(2);
+        cancelled.invoice.status = "CANCELLED".to_string();
+        let cancelled = document_voucher(&cancelled, "Sharma & Co", &settings);
+        let receipt = |voided| {
+            receipt_voucher("RCT-000001", &buyer, "Sharma & Co", &[payment(1, 700.0, false), payment(2, 300.0, voided)], &settings)
+        };
+
+        // Nothing exported yet: the cancelled invoice never reached Tally, so it is left out
+        let first = plan(vec![sale.clone(), cancelled.clone(), receipt(false)], &BTreeMap::new(), true);
+        assert_eq!(first.iter().map(|voucher| voucher.action).collect::<Vec<_>>(), [Action::Create, Action::Create]);
+        let mut exported: BTreeMap<String, ExportedVoucher> =
+            first.iter().map(|voucher| (voucher.remote_id.clone(), voucher.record())).collect();
+
+        // Later one payment of the receipt is voided and the invoice cancelled
+        let mut sale_cancelled = sale.clone();
+        sale_cancelled.live = false;
+        let second = plan(vec![sale_cancelled, cancelled.clone(), receipt(true)], &exported, true);
+        assert_eq!(
+            second.iter().map(|voucher| (voucher.number.as_str(), voucher.action)).collect::<Vec<_>>(),
+            [("INV-1", Action::Cancel), ("RCT-000001", Action::Alter)]
+        );
+        assert_eq!(second[1].amount(), rupees(700.0));
+        exported.extend(second.iter().map(|voucher| (voucher.remote_id.clone(), voucher.record())));
+
+        assert!(plan(vec![receipt(true)], &exported, true).is_empty());
+        assert_eq!(plan(vec![sale.clone(), receipt(true)], &exported, false).len(), 2);
+
+        let mut parties = BTreeMap::new();
+        parties.insert("Sharma & Co".to_string(), buyer);
+        let xml = build_xml(&settings, &parties, &second);
+        assert!(xml.contains("<VOUCHER REMOTEID=\"payvlo-invoice-1\" DATE=\"20260910\" TAGNAME=\"Voucher Number\" TAGVALUE=\"INV-1\" VCHTYPE=\"Sales\" ACTION=\"Cancel\">"));
+        assert!(xml.contains("<VOUCHER REMOTEID=\"payvlo-receipt-RCT-000001-1\" VCHTYPE=\"Receipt\" ACTION=\"Alter\""));
+        assert!(xml.contains("<LEDGER NAME=\"Sharma &amp; Co\">"));
+        assert!(xml.contains("<PARENT>Sundry Debtors</PARENT>"));
+        assert!(xml.contains("<PARENT>Bank Accounts</PARENT>"));
+        assert!(xml.contains("<BILLTYPE>Agst Ref</BILLTYPE>\n        <AMOUNT>700.00</AMOUNT>"));
+        // Ledgers of a cancelled voucher are not needed
+        assert!(!xml.contains("Sales @ 18%"));
+    }
+}
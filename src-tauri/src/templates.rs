@@ -0,0 +1,531 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Invoice Layout Templates
+// Declarative layouts stored as JSON in invoice_templates
+// =====================================================
+//
+// A template lists the sections of the document in print order, the columns of the
+// item table and a few style settings. Every field has a default, so a stored
+// definition only needs what differs from the standard layout. Tax columns follow the
+// supply: CGST/SGST print on intra-state invoices and IGST on inter-state ones, and the
+// discount and cess columns are left out when no line has any.
+
+use crate::database::{
+    CompanySettings, Customer, Invoice, InvoiceItem, InvoiceTemplate, ReturnInvoice, ReturnLine,
+};
+use crate::gst;
+use crate::money::Money;
+use crate::pdf::{Align, CONTENT_WIDTH};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+// =====================================================
+// Template Definition
+// =====================================================
+
+const MIN_FLEXIBLE_WIDTH: f32 = 40.0;
+
+/// Blocks of the document, drawn in the order the template lists them
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    /// Document title, copy label and the supplier block (or letterhead space)
+    Header,
+    /// Bill-to block beside the invoice number, dates and place of supply
+    Parties,
+    Items,
+    Totals,
+    AmountInWords,
+    HsnSummary,
+    BankDetails,
+    Notes,
+    Terms,
+    Signature,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnField {
+    Serial,
+    ProductCode,
+    Description,
+    HsnSac,
+    Quantity,
+    Rate,
+    DiscountPercent,
+    Discount,
+    Taxable,
+    Cgst,
+    Sgst,
+    Igst,
+    Cess,
+    TotalTax,
+    Total,
+}
+
+impl ColumnField {
+    pub fn default_heading(self) -> &'static str {
+        match self {
+            ColumnField::Serial => "#",
+            ColumnField::ProductCode => "Code",
+            ColumnField::Description => "Description",
+            ColumnField::HsnSac => "HSN/SAC",
+            ColumnField::Quantity => "Qty",
+            ColumnField::Rate => "Rate",
+            ColumnField::DiscountPercent => "Disc. %",
+            ColumnField::Discount => "Discount",
+            ColumnField::Taxable => "Taxable Value",
+            ColumnField::Cgst => "CGST",
+            ColumnField::Sgst => "SGST",
+            ColumnField::Igst => "IGST",
+            ColumnField::Cess => "Cess",
+            ColumnField::TotalTax => "Tax",
+            ColumnField::Total => "Total",
+        }
+    }
+
+    pub fn align(self) -> Align {
+        match self {
+            ColumnField::Serial => Align::Center,
+            ColumnField::ProductCode | ColumnField::Description | ColumnField::HsnSac => Align::Left,
+            _ => Align::Right,
+        }
+    }
+}
+
+/// A column of the item table; a width of 0 shares out the space the others leave
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ColumnSpec {
+    pub field: ColumnField,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+    #[serde(default)]
+    pub width: f32,
+}
+
+impl ColumnSpec {
+    pub fn new(field: ColumnField, width: f32) -> Self {
+        ColumnSpec { field, heading: None, width }
+    }
+
+    pub fn heading(&self) -> &str {
+        self.heading.as_deref().unwrap_or(self.field.default_heading())
+    }
+}
+
+/// Font sizes and spacing, in points
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TemplateStyle {
+    pub font_size: f32,
+    pub table_font_size: f32,
+    pub title_size: f32,
+    pub line_spacing: f32,
+    pub cell_padding: f32,
+    /// Gray level of table heading rows, 0 black to 1 white
+    pub heading_shade: f32,
+}
+
+impl Default for TemplateStyle {
+    fn default() -> Self {
+        TemplateStyle {
+            font_size: 8.0,
+            table_font_size: 7.0,
+            title_size: 13.0,
+            line_spacing: 1.25,
+            cell_padding: 3.0,
+            heading_shade: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TemplateDefinition {
+    /// Replaces "TAX INVOICE", "CREDIT NOTE" or "DEBIT NOTE"
+    pub title: Option<String>,
+    pub copy_label: String,
+    /// Leaves `letterhead_space` blank at the top of the first page instead of printing
+    /// the supplier block, for stationery with a printed letterhead
+    pub letterhead: bool,
+    pub letterhead_space: f32,
+    pub show_logo: bool,
+    /// Prints item descriptions under the product name
+    pub item_descriptions: bool,
+    pub sections: Vec<Section>,
+    pub columns: Vec<ColumnSpec>,
+    pub style: TemplateStyle,
+    pub footer: String,
+}
+
+impl Default for TemplateDefinition {
+    /// The standard layout, also used when no template has been chosen
+    fn default() -> Self {
+        TemplateDefinition {
+            title: None,
+            copy_label: "Original for Recipient".to_string(),
+            letterhead: false,
+            letterhead_space: 110.0,
+            show_logo: true,
+            item_descriptions: true,
+            sections: vec![
+                Section::Header,
+                Section::Parties,
+                Section::Items,
+                Section::Totals,
+                Section::AmountInWords,
+                Section::HsnSummary,
+                Section::BankDetails,
+                Section::Notes,
+                Section::Terms,
+                Section::Signature,
+            ],
+            columns: vec![
+                ColumnSpec::new(ColumnField::Serial, 16.0),
+                ColumnSpec::new(ColumnField::Description, 0.0),
+                ColumnSpec::new(ColumnField::HsnSac, 40.0),
+                ColumnSpec::new(ColumnField::Quantity, 38.0),
+                ColumnSpec::new(ColumnField::Rate, 46.0),
+                ColumnSpec::new(ColumnField::Discount, 40.0),
+                ColumnSpec::new(ColumnField::Taxable, 54.0),
+                ColumnSpec::new(ColumnField::Cgst, 46.0),
+                ColumnSpec::new(ColumnField::Sgst, 46.0),
+                ColumnSpec::new(ColumnField::Igst, 48.0),
+                ColumnSpec::new(ColumnField::Cess, 40.0),
+                ColumnSpec::new(ColumnField::Total, 54.0),
+            ],
+            style: TemplateStyle::default(),
+            footer: "This is a computer generated invoice.".to_string(),
+        }
+    }
+}
+
+impl TemplateDefinition {
+    pub fn has(&self, section: Section) -> bool {
+        self.sections.contains(&section)
+    }
+
+    /// Problems that would make the layout unusable; empty when it can be rendered
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if !self.has(Section::Items) {
+            problems.push("Template must include the items section".to_string());
+        }
+        let mut seen = HashSet::new();
+        for section in &self.sections {
+            if !seen.insert(section) {
+                problems.push(format!("Section {:?} is listed more than once", section));
+            }
+        }
+
+        if !self.columns.iter().any(|column| column.field == ColumnField::Description) {
+            problems.push("Item table must have a description column".to_string());
+        }
+        let mut fields = HashSet::new();
+        for column in &self.columns {
+            if !fields.insert(column.field) {
+                problems.push(format!("Column {} is listed more than once", column.field.default_heading()));
+            }
+            if column.width < 0.0 {
+                problems.push(format!("Column {} has a negative width", column.heading()));
+            }
+        }
+        // Each flexible column needs room for at least a short word
+        let fixed: f32 = self.columns.iter().map(|column| column.width).sum();
+        let flexible = self.columns.iter().filter(|column| column.width == 0.0).count();
+        let needed = fixed + MIN_FLEXIBLE_WIDTH * flexible as f32;
+        if needed > CONTENT_WIDTH {
+            problems.push(format!(
+                "Columns need {:.0}pt but the page only has {:.0}pt",
+                needed, CONTENT_WIDTH
+            ));
+        }
+
+        let style = &self.style;
+        for (name, size) in [
+            ("Font size", style.font_size),
+            ("Table font size", style.table_font_size),
+            ("Title size", style.title_size),
+        ] {
+            if !(5.0..=24.0).contains(&size) {
+                problems.push(format!("{} must be between 5 and 24 points", name));
+            }
+        }
+        if !(1.0..=2.0).contains(&style.line_spacing) {
+            problems.push("Line spacing must be between 1 and 2".to_string());
+        }
+        if !(0.0..=10.0).contains(&style.cell_padding) {
+            problems.push("Cell padding must be between 0 and 10 points".to_string());
+        }
+        if !(0.0..=1.0).contains(&style.heading_shade) {
+            problems.push("Heading shade must be between 0 and 1".to_string());
+        }
+        if self.letterhead && !(0.0..=300.0).contains(&self.letterhead_space) {
+            problems.push("Letterhead space must be between 0 and 300 points".to_string());
+        }
+
+        problems
+    }
+}
+
+// =====================================================
+// Error Handling
+// =====================================================
+
+#[derive(Debug)]
+pub enum TemplateError {
+    Invalid(Vec<String>),
+    NotFound(i64),
+    BuiltIn(String),
+    Sqlite(rusqlite::Error),
+}
+
+impl TemplateError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TemplateError::Invalid(_) => "TemplateValidationError",
+            TemplateError::NotFound(_) => "NotFound",
+            TemplateError::BuiltIn(_) => "BuiltInTemplate",
+            TemplateError::Sqlite(_) => "DatabaseError",
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Invalid(problems) => write!(f, "Invalid template: {}", problems.join("; ")),
+            TemplateError::NotFound(id) => write!(f, "Invoice template {} does not exist", id),
+            TemplateError::BuiltIn(name) => {
+                write!(f, "The built-in template \"{}\" cannot be changed; save a copy instead", name)
+            }
+            TemplateError::Sqlite(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<rusqlite::Error> for TemplateError {
+    fn from(err: rusqlite::Error) -> Self {
+        TemplateError::Sqlite(err)
+    }
+}
+
+/// Trims the name and rejects templates that cannot be rendered
+pub fn validate_template(template: &InvoiceTemplate) -> Result<(), TemplateError> {
+    let mut problems = Vec::new();
+    if template.name.trim().is_empty() {
+        problems.push("Template name is required".to_string());
+    }
+    problems.extend(template.definition.validate());
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(TemplateError::Invalid(problems))
+    }
+}
+
+// =====================================================
+// Sample Invoice for Previews
+// =====================================================
+
+/// Stand-in supplier for previews made before company settings are saved
+pub fn sample_company() -> CompanySettings {
+    CompanySettings {
+        id: None,
+        company_name: "Your Company Pvt Ltd".to_string(),
+        gstin: "27AAPFU0939F1ZV".to_string(),
+        pan: "AAPFU0939F".to_string(),
+        address_line1: "101 Business Park, FC Road".to_string(),
+        address_line2: None,
+        city: "Pune".to_string(),
+        state: "Maharashtra".to_string(),
+        pincode: "411004".to_string(),
+        phone: Some("+91 20 1234 5678".to_string()),
+        email: Some("accounts@example.com".to_string()),
+        website: None,
+        bank_name: Some("State Bank of India".to_string()),
+        account_number: Some("12345678901".to_string()),
+        ifsc_code: Some("SBIN0000001".to_string()),
+        logo_path: None,
+        created_at: None,
+        updated_at: None,
+        invoice_template_id: None,
+    }
+}
+
+/// An intra-state invoice from `company` with a discount and mixed rates, so every
+/// column a template can show has something in it
+pub fn sample_invoice(company: &CompanySettings) -> ReturnInvoice {
+    let lines = [
+        ("Steel TMT bar 12 mm", Some("Fe 500D, bundle of 10"), "7214", "GOODS", "KG", 250.0, 62.0, 0.0, 18.0),
+        ("Portland cement 53 grade", None, "2523", "GOODS", "BAG", 40.0, 380.0, 5.0, 28.0),
+        ("Site delivery and unloading", None, "996511", "SERVICES", "NOS", 1.0, 1500.0, 0.0, 5.0),
+    ];
+
+    let mut items = Vec::new();
+    let mut calculations = Vec::new();
+    for (index, (name, description, hsn, product_type, unit, quantity, price, discount, rate)) in
+        lines.into_iter().enumerate()
+    {
+        let line = gst::calculate_line_item(quantity, Money::from_rupees(price), discount, rate, 0.0, false)
+            .expect("sample lines are valid");
+        let tax = &line.gst_calculation;
+        let item = InvoiceItem {
+            id: None,
+            invoice_id: 0,
+            product_id: 0,
+            line_number: index as i32 + 1,
+            product_code: format!("SKU-{:03}", index + 1),
+            product_name: name.to_string(),
+            description: description.map(str::to_string),
+            hsn_sac_code: hsn.to_string(),
+            quantity,
+            unit_price: line.unit_price,
+            discount_percent: discount,
+            discount_amount: line.discount_amount,
+            taxable_amount: line.taxable_amount,
+            gst_rate: rate,
+            cgst_rate: tax.cgst_rate,
+            sgst_rate: tax.sgst_rate,
+            igst_rate: tax.igst_rate,
+            cess_rate: 0.0,
+            cgst_amount: tax.cgst_amount,
+            sgst_amount: tax.sgst_amount,
+            igst_amount: tax.igst_amount,
+            cess_amount: tax.cess_amount,
+            total_tax: tax.total_tax,
+            line_total: line.line_total,
+            created_at: None,
+        };
+        items.push(ReturnLine {
+            item,
+            unit_of_measurement: unit.to_string(),
+            product_type: product_type.to_string(),
+        });
+        calculations.push(line);
+    }
+    let totals = gst::calculate_invoice_totals(&calculations).expect("sample has lines");
+
+    let invoice = Invoice {
+        id: None,
+        invoice_number: "SAMPLE/001".to_string(),
+        invoice_date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        customer_id: 0,
+        invoice_type: "REGULAR".to_string(),
+        place_of_supply: company.state.clone(),
+        reverse_charge: false,
+        subtotal: totals.subtotal,
+        total_discount: totals.total_discount,
+        taxable_amount: totals.taxable_amount,
+        cgst_amount: totals.cgst_total,
+        sgst_amount: totals.sgst_total,
+        igst_amount: totals.igst_total,
+        cess_amount: totals.cess_total,
+        total_tax: totals.total_tax,
+        total_amount: totals.total_amount,
+        round_off: totals.round_off,
+        final_amount: totals.final_amount,
+        payment_terms: Some("Net 30".to_string()),
+        due_date: None,
+        status: "DRAFT".to_string(),
+        notes: Some("Thank you for your business.".to_string()),
+        terms_conditions: Some("Goods once sold will not be taken back. Interest at 18% p.a. on overdue bills.".to_string()),
+        pdf_path: None,
+        created_at: None,
+        updated_at: None,
+        irn: None,
+        ack_number: None,
+        ack_date: None,
+        signed_qr_code: None,
+        note_reason: None,
+        cancelled_at: None,
+        cancellation_reason: None,
+        amends_invoice_id: None,
+    };
+    let customer = Customer {
+        id: None,
+        customer_name: "Sample Customer Traders".to_string(),
+        gstin: Some(format!("{}AAGCB7383J1Z4", gst::state_code(&company.state).unwrap_or("27"))),
+        pan: None,
+        customer_type: "B2B".to_string(),
+        address_line1: "14 Market Yard".to_string(),
+        address_line2: None,
+        city: company.city.clone(),
+        state: company.state.clone(),
+        pincode: company.pincode.clone(),
+        phone: None,
+        email: None,
+        credit_limit: Money::ZERO,
+        credit_period_days: 30,
+        is_active: true,
+        created_at: None,
+        updated_at: None,
+        invoice_template_id: None,
+    };
+
+    ReturnInvoice { invoice, customer, lines: items, references: Vec::new() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations;
+    use rusqlite::Connection;
+
+    #[test]
+    fn builtin_templates_are_valid() {
+        let connection = Connection::open_in_memory().unwrap();
+        migrations::run_migrations(&connection).unwrap();
+        let mut stmt = connection
+            .prepare("SELECT name, definition FROM invoice_templates WHERE is_builtin = 1")
+            .unwrap();
+        let templates: Vec<(String, String)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(templates.len(), 4);
+        for (name, json) in templates {
+            let definition: TemplateDefinition = serde_json::from_str(&json).unwrap();
+            assert_eq!(definition.validate(), Vec::<String>::new(), "{}", name);
+            if name == "Standard" {
+                assert_eq!(definition, TemplateDefinition::default());
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_unusable_layouts() {
+        let partial: TemplateDefinition =
+            serde_json::from_str(r#"{"sections": ["header", "header"], "style": {"font_size": 40}}"#).unwrap();
+        assert_eq!(partial.columns, TemplateDefinition::default().columns);
+        assert_eq!(partial.style.table_font_size, 7.0);
+
+        let problems = partial.validate();
+        assert!(problems.iter().any(|p| p.contains("items section")));
+        assert!(problems.iter().any(|p| p.contains("more than once")));
+        assert!(problems.iter().any(|p| p.starts_with("Font size")));
+
+        let mut wide = TemplateDefinition::default();
+        wide.columns.retain(|column| column.field != ColumnField::Description);
+        wide.columns.push(ColumnSpec::new(ColumnField::ProductCode, 400.0));
+        let problems = wide.validate();
+        assert!(problems.iter().any(|p| p.contains("description column")));
+        assert!(problems.iter().any(|p| p.contains("page only has")));
+    }
+
+    #[test]
+    fn sample_invoice_adds_up() {
+        let document = sample_invoice(&sample_company());
+        let invoice = &document.invoice;
+        assert_eq!(document.lines.len(), 3);
+        assert!(invoice.total_discount > Money::ZERO);
+        assert_eq!(invoice.taxable_amount + invoice.total_tax, invoice.total_amount);
+        assert_eq!(invoice.total_amount + invoice.round_off, invoice.final_amount);
+        assert!(document.customer.gstin.as_deref().unwrap().starts_with("27"));
+    }
+}
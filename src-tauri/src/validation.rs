@@ -0,0 +1,258 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Validation
+// Structural checks for GST identifiers and addresses
+// =====================================================
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const GSTIN_CODE_POINTS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Components of a structurally valid GSTIN
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GstinParts {
+    pub state_code: String,
+    pub pan_number: String,
+    pub entity_number: String,
+    pub check_digit: String,
+}
+
+/// Why a GSTIN was rejected; each variant maps to a stable error code for the forms
+#[derive(Debug, Clone, PartialEq)]
+pub enum GstinError {
+    Required,
+    InvalidLength,
+    InvalidCharacters,
+    InvalidStateCode,
+    #[cfg(feature = "gui")]
+    UnknownStateCode(String),
+    InvalidPan,
+    InvalidEntityNumber,
+    InvalidDefaultCharacter,
+    InvalidChecksum { expected: char },
+}
+
+#[cfg(feature = "gui")]
+impl GstinError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            GstinError::Required => "GSTIN_REQUIRED",
+            GstinError::InvalidLength => "GSTIN_INVALID_LENGTH",
+            GstinError::InvalidCharacters => "GSTIN_INVALID_CHARACTERS",
+            GstinError::InvalidStateCode => "GSTIN_INVALID_STATE_CODE",
+            #[cfg(feature = "gui")]
+            GstinError::UnknownStateCode(_) => "GSTIN_UNKNOWN_STATE_CODE",
+            GstinError::InvalidPan => "GSTIN_INVALID_PAN",
+            GstinError::InvalidEntityNumber => "GSTIN_INVALID_ENTITY_NUMBER",
+            GstinError::InvalidDefaultCharacter => "GSTIN_INVALID_DEFAULT_CHARACTER",
+            GstinError::InvalidChecksum { .. } => "GSTIN_INVALID_CHECKSUM",
+        }
+    }
+}
+
+impl fmt::Display for GstinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GstinError::Required => f.write_str("GSTIN is required"),
+            GstinError::InvalidLength => f.write_str("GSTIN must be exactly 15 characters"),
+            GstinError::InvalidCharacters => {
+                f.write_str("GSTIN may only contain digits and capital letters")
+            }
+            GstinError::InvalidStateCode => f.write_str("Characters 1-2 must be a numeric state code"),
+            #[cfg(feature = "gui")]
+            GstinError::UnknownStateCode(code) => write!(f, "State code {} does not exist", code),
+            GstinError::InvalidPan => {
+                f.write_str("Characters 3-12 must be a valid PAN (5 letters, 4 digits, 1 letter)")
+            }
+            GstinError::InvalidEntityNumber => {
+                f.write_str("Character 13 must be an entity number from 1-9 or A-Z")
+            }
+            GstinError::InvalidDefaultCharacter => f.write_str("Character 14 must be 'Z'"),
+            GstinError::InvalidChecksum { expected } => {
+                write!(f, "Invalid check digit; expected '{}'", expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GstinError {}
+
+/// Computes the mod-36 check character over the first 14 characters of a GSTIN
+pub fn gstin_check_digit(first_fourteen: &str) -> Option<char> {
+    let modulus = 36;
+    let mut factor = 2;
+    let mut sum = 0;
+
+    for c in first_fourteen.chars().rev() {
+        let code_point = GSTIN_CODE_POINTS.find(c)? as u32;
+        let product = factor * code_point;
+        factor = if factor == 2 { 1 } else { 2 };
+        sum += product / modulus + product % modulus;
+    }
+
+    let check = ((modulus - sum % modulus) % modulus) as usize;
+    GSTIN_CODE_POINTS.chars().nth(check)
+}
+
+/// Validates GSTIN structure and check digit. Whether the state code exists is left to
+/// the caller, which can consult the `indian_states` table.
+pub fn validate_gstin_structure(gstin: &str) -> Result<GstinParts, GstinError> {
+    let gstin: String = gstin
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase();
+
+    if gstin.is_empty() {
+        return Err(GstinError::Required);
+    }
+    if !gstin.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(GstinError::InvalidCharacters);
+    }
+    if gstin.len() != 15 {
+        return Err(GstinError::InvalidLength);
+    }
+
+    let chars: Vec<char> = gstin.chars().collect();
+
+    if !chars[0..2].iter().all(|c| c.is_ascii_digit()) {
+        return Err(GstinError::InvalidStateCode);
+    }
+
+    let pan = &chars[2..12];
+    let pan_valid = pan[0..5].iter().all(|c| c.is_ascii_uppercase())
+        && pan[5..9].iter().all(|c| c.is_ascii_digit())
+        && pan[9].is_ascii_uppercase();
+    if !pan_valid {
+        return Err(GstinError::InvalidPan);
+    }
+
+    if chars[12] == '0' {
+        return Err(GstinError::InvalidEntityNumber);
+    }
+    if chars[13] != 'Z' {
+        return Err(GstinError::InvalidDefaultCharacter);
+    }
+
+    let expected = gstin_check_digit(&gstin[0..14]).ok_or(GstinError::InvalidCharacters)?;
+    if chars[14] != expected {
+        return Err(GstinError::InvalidChecksum { expected });
+    }
+
+    Ok(GstinParts {
+        state_code: gstin[0..2].to_string(),
+        pan_number: gstin[2..12].to_string(),
+        entity_number: gstin[12..13].to_string(),
+        check_digit: gstin[14..15].to_string(),
+    })
+}
+
+/// HSN codes for goods are 4, 6 or 8 digits; SAC codes for services are 6 digits from 99
+pub fn is_valid_hsn_sac(code: &str) -> bool {
+    let code = code.trim();
+    code.chars().all(|c| c.is_ascii_digit()) && matches!(code.len(), 4 | 6 | 8)
+}
+
+/// Indian postal codes are six digits and never start with 0
+pub fn is_valid_pincode(pincode: &str) -> bool {
+    let pincode = pincode.trim();
+    pincode.len() == 6 && pincode.chars().all(|c| c.is_ascii_digit()) && !pincode.starts_with('0')
+}
+
+/// State codes served by each two-digit postal prefix. Circles that straddle a border
+/// list every state they deliver to, so this catches wrong states rather than proving a match.
+#[cfg(feature = "gui")]
+const PINCODE_STATES: &[(u32, u32, &[&str])] = &[
+    (11, 11, &["07"]),
+    (12, 13, &["06"]),
+    (14, 15, &["03"]),
+    (16, 16, &["03", "04"]),
+    (17, 17, &["02"]),
+    (18, 19, &["01", "38"]),
+    (20, 28, &["09", "05"]),
+    (30, 34, &["08"]),
+    (36, 39, &["24", "25", "26"]),
+    (40, 44, &["27", "30"]),
+    (45, 48, &["23"]),
+    (49, 49, &["22"]),
+    (50, 53, &["36", "37", "28"]),
+    (56, 59, &["29"]),
+    (60, 66, &["33", "34"]),
+    (67, 69, &["32", "31", "34"]),
+    (70, 74, &["19", "11", "35"]),
+    (75, 77, &["21"]),
+    (78, 79, &["18", "12", "13", "14", "15", "16", "17"]),
+    (80, 85, &["10", "20"]),
+];
+
+/// Whether a pincode lies in a postal circle that serves the given GST state code
+#[cfg(feature = "gui")]
+pub fn pincode_matches_state(pincode: &str, state_code: &str) -> bool {
+    if !is_valid_pincode(pincode) {
+        return false;
+    }
+    let prefix: u32 = pincode.trim()[0..2].parse().unwrap_or(0);
+    PINCODE_STATES
+        .iter()
+        .find(|(from, to, _)| (*from..=*to).contains(&prefix))
+        .is_some_and(|(_, _, states)| states.contains(&state_code.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_gstins() {
+        for gstin in ["27AAPFU0939F1ZV", "29AAGCB7383J1Z4", "24aaacc1206d1zm"] {
+            assert!(validate_gstin_structure(gstin).is_ok(), "{}", gstin);
+        }
+
+        let parts = validate_gstin_structure("27AAPFU0939F1ZV").unwrap();
+        assert_eq!(parts.state_code, "27");
+        assert_eq!(parts.pan_number, "AAPFU0939F");
+        assert_eq!(parts.entity_number, "1");
+        assert_eq!(parts.check_digit, "V");
+    }
+
+    #[test]
+    fn reports_each_structural_failure() {
+        let cases = [
+            ("", GstinError::Required),
+            ("27AAPFU0939F1Z", GstinError::InvalidLength),
+            ("27AAPFU0939F1Z-", GstinError::InvalidCharacters),
+            ("2AAAPFU0939F1ZV", GstinError::InvalidStateCode),
+            ("27AAPF10939F1ZV", GstinError::InvalidPan),
+            ("27AAPFU0939F0ZV", GstinError::InvalidEntityNumber),
+            ("27AAPFU0939F1YV", GstinError::InvalidDefaultCharacter),
+            ("27AAPFU0939F1ZA", GstinError::InvalidChecksum { expected: 'V' }),
+        ];
+        for (gstin, expected) in cases {
+            assert_eq!(validate_gstin_structure(gstin), Err(expected), "{}", gstin);
+        }
+    }
+
+    #[test]
+    fn checks_hsn_codes_and_pincodes() {
+        for code in ["7214", "721420", "72142090", "998314"] {
+            assert!(is_valid_hsn_sac(code), "{}", code);
+        }
+        for code in ["72", "72142", "7214A0", ""] {
+            assert!(!is_valid_hsn_sac(code), "{}", code);
+        }
+        assert!(is_valid_pincode("411001"));
+        assert!(!is_valid_pincode("011001"));
+        assert!(!is_valid_pincode("41100"));
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn matches_pincodes_to_states() {
+        assert!(pincode_matches_state("411001", "27"));
+        assert!(pincode_matches_state("560001", "29"));
+        assert!(pincode_matches_state("403001", "30"));
+        assert!(!pincode_matches_state("411001", "29"));
+        assert!(!pincode_matches_state("911001", "27"));
+        assert!(!pincode_matches_state("4110", "27"));
+    }
+}
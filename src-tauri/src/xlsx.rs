@@ -0,0 +1,490 @@
+// =====================================================
+// Payvlo GST Invoice Generator - Excel Workbooks
+// Reads one worksheet of an .xlsx file as rows of text
+// =====================================================
+//
+// An .xlsx file is a zip archive of XML parts. Only what an import needs is read: the
+// sheet list, the shared string table and the cells of one worksheet, each cell as the
+// text Excel stores for it. Numbers come back unformatted ("150000", not "1,50,000")
+// and booleans as 1 or 0. Formulas give their last calculated value.
+
+use crate::import::ImportError;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+
+/// Largest part that will be unpacked, so a malicious archive cannot exhaust memory
+const MAX_PART_BYTES: usize = 64 * 1024 * 1024;
+
+/// Rows of the named worksheet, or of the first one, each with its row number.
+/// Rows without a value are left out.
+pub fn read_sheet(data: &[u8], sheet: Option<&str>) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+    let archive = Archive::open(data)?;
+    let path = worksheet_path(&archive, sheet)?;
+    let shared_strings = match archive.find("xl/sharedStrings.xml") {
+        Some(entry) => read_shared_strings(&archive.text(entry)?),
+        None => Vec::new(),
+    };
+    let entry = archive.find(&path).ok_or_else(|| invalid(format!("{} is missing", path)))?;
+    read_rows(&archive.text(entry)?, &shared_strings)
+}
+
+/// Names of the worksheets in workbook order
+pub fn sheet_names(data: &[u8]) -> Result<Vec<String>, ImportError> {
+    let archive = Archive::open(data)?;
+    Ok(sheets(&archive)?.into_iter().map(|(name, _)| name).collect())
+}
+
+fn invalid(problem: String) -> ImportError {
+    ImportError::InvalidWorkbook(problem)
+}
+
+// =====================================================
+// Zip Archive
+// =====================================================
+
+struct Entry<'a> {
+    name: String,
+    method: u16,
+    data: &'a [u8],
+}
+
+struct Archive<'a> {
+    entries: Vec<Entry<'a>>,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<usize> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize)
+}
+
+impl<'a> Archive<'a> {
+    /// Lists the entries from the central directory at the end of the archive
+    fn open(data: &'a [u8]) -> Result<Self, ImportError> {
+        let corrupt = || invalid("the file is not a valid .xlsx workbook".to_string());
+
+        // The end record is the last 22 bytes, unless a comment of up to 64 KB follows it
+        let earliest = data.len().saturating_sub(22 + 0xFFFF);
+        let end = (earliest..=data.len().saturating_sub(22))
+            .rev()
+            .find(|&offset| data[offset..].starts_with(b"PK\x05\x06"))
+            .ok_or_else(corrupt)?;
+        let count = u16_at(data, end + 10).ok_or_else(corrupt)?;
+        let mut offset = u32_at(data, end + 16).ok_or_else(corrupt)?;
+
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            if !data.get(offset..).is_some_and(|rest| rest.starts_with(b"PK\x01\x02")) {
+                return Err(corrupt());
+            }
+            let method = u16_at(data, offset + 10).ok_or_else(corrupt)?;
+            let compressed_size = u32_at(data, offset + 20).ok_or_else(corrupt)?;
+            let name_length = u16_at(data, offset + 28).ok_or_else(corrupt)? as usize;
+            let extra_length = u16_at(data, offset + 30).ok_or_else(corrupt)? as usize;
+            let comment_length = u16_at(data, offset + 32).ok_or_else(corrupt)? as usize;
+            let local = u32_at(data, offset + 42).ok_or_else(corrupt)?;
+            let name = data.get(offset + 46..offset + 46 + name_length).ok_or_else(corrupt)?;
+
+            // The local header repeats the name and may carry a different extra field
+            if !data.get(local..).is_some_and(|rest| rest.starts_with(b"PK\x03\x04")) {
+                return Err(corrupt());
+            }
+            let start = local
+                + 30
+                + u16_at(data, local + 26).ok_or_else(corrupt)? as usize
+                + u16_at(data, local + 28).ok_or_else(corrupt)? as usize;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method,
+                data: data.get(start..start + compressed_size).ok_or_else(corrupt)?,
+            });
+            offset += 46 + name_length + extra_length + comment_length;
+        }
+        Ok(Archive { entries })
+    }
+
+    fn find(&self, name: &str) -> Option<&Entry<'a>> {
+        let name = name.trim_start_matches('/');
+        self.entries.iter().find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    fn text(&self, entry: &Entry) -> Result<String, ImportError> {
+        let bytes = match entry.method {
+            0 => entry.data.to_vec(),
+            8 => decompress_to_vec_with_limit(entry.data, MAX_PART_BYTES)
+                .map_err(|_| invalid(format!("{} is damaged or too large", entry.name)))?,
+            method => return Err(invalid(format!("{} uses unsupported compression {}", entry.name, method))),
+        };
+        String::from_utf8(bytes).map_err(|_| invalid(format!("{} is not UTF-8", entry.name)))
+    }
+}
+
+// =====================================================
+// XML Scanning
+// =====================================================
+
+enum Token<'a> {
+    Open { name: &'a str, attributes: &'a str, empty: bool },
+    Close(&'a str),
+    Text(&'a str),
+}
+
+/// Splits XML into tags and text. Declarations, comments and processing instructions
+/// are skipped; element names lose any namespace prefix.
+struct Tokens<'a> {
+    xml: &'a str,
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        loop {
+            if self.xml.is_empty() {
+                return None;
+            }
+            if !self.xml.starts_with('<') {
+                let end = self.xml.find('<').unwrap_or(self.xml.len());
+                let (text, rest) = self.xml.split_at(end);
+                self.xml = rest;
+                return Some(Token::Text(text));
+            }
+            if let Some(rest) = self.xml.strip_prefix("<![CDATA[") {
+                let end = rest.find("]]>").unwrap_or(rest.len());
+                self.xml = rest.get(end + 3..).unwrap_or("");
+                return Some(Token::Text(&rest[..end]));
+            }
+            let (terminator, skip) = if self.xml.starts_with("<!--") {
+                ("-->", true)
+            } else if self.xml.starts_with("<?") || self.xml.starts_with("<!") {
+                (">", true)
+            } else {
+                (">", false)
+            };
+            let end = self.xml.find(terminator).map_or(self.xml.len(), |end| end + terminator.len());
+            let tag = &self.xml[..end];
+            self.xml = &self.xml[end..];
+            if skip {
+                continue;
+            }
+
+            let inner = tag.trim_start_matches('<').trim_end_matches('>');
+            if let Some(name) = inner.strip_prefix('/') {
+                return Some(Token::Close(local_name(name.trim())));
+            }
+            let empty = inner.ends_with('/');
+            let inner = inner.trim_end_matches('/');
+            let (name, attributes) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+            return Some(Token::Open { name: local_name(name), attributes, empty });
+        }
+    }
+}
+
+/// Value of an attribute, matched on its name without namespace prefix
+fn attribute(attributes: &str, wanted: &str) -> Option<String> {
+    let mut rest = attributes;
+    loop {
+        rest = rest.trim_start();
+        let (name, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let quote = after.chars().next()?;
+        let value_and_rest = after.get(1..)?;
+        let end = value_and_rest.find(quote)?;
+        if local_name(name.trim()) == wanted {
+            return Some(unescape(&value_and_rest[..end]));
+        }
+        rest = &value_and_rest[end + 1..];
+    }
+}
+
+/// Replaces the five named entities and numeric character references
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else { break };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+// =====================================================
+// Workbook Parts
+// =====================================================
+
+/// Sheet names with the path of their part, in workbook order
+fn sheets(archive: &Archive) -> Result<Vec<(String, String)>, ImportError> {
+    let workbook = archive
+        .find("xl/workbook.xml")
+        .ok_or_else(|| invalid("the file is not an .xlsx workbook".to_string()))?;
+    let relationships = match archive.find("xl/_rels/workbook.xml.rels") {
+        Some(entry) => archive.text(entry)?,
+        None => String::new(),
+    };
+    let targets: Vec<(String, String)> = Tokens { xml: &relationships }
+        .filter_map(|token| match token {
+            Token::Open { name: "Relationship", attributes, .. } => {
+                Some((attribute(attributes, "Id")?, attribute(attributes, "Target")?))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let xml = archive.text(workbook)?;
+    let mut sheets = Vec::new();
+    for token in (Tokens { xml: &xml }) {
+        if let Token::Open { name: "sheet", attributes, .. } = token {
+            let name = attribute(attributes, "name").unwrap_or_default();
+            let target = attribute(attributes, "id")
+                .and_then(|id| targets.iter().find(|(target_id, _)| *target_id == id))
+                .map(|(_, target)| target.clone())
+                .unwrap_or_else(|| format!("worksheets/sheet{}.xml", sheets.len() + 1));
+            // Targets are relative to xl/ unless they start from the archive root
+            let path = match target.strip_prefix('/') {
+                Some(path) => path.to_string(),
+                None => format!("xl/{}", target),
+            };
+            sheets.push((name, path));
+        }
+    }
+    Ok(sheets)
+}
+
+fn worksheet_path(archive: &Archive, sheet: Option<&str>) -> Result<String, ImportError> {
+    let sheets = sheets(archive)?;
+    let found = match sheet {
+        Some(wanted) => sheets.iter().find(|(name, _)| name.trim().eq_ignore_ascii_case(wanted.trim())),
+        None => sheets.first(),
+    };
+    match (found, sheet) {
+        (Some((_, path)), _) => Ok(path.clone()),
+        (None, Some(wanted)) => Err(invalid(format!(
+            "there is no sheet named {}; the workbook has {}",
+            wanted,
+            sheets.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", ")
+        ))),
+        (None, None) => Err(invalid("the workbook has no sheets".to_string())),
+    }
+}
+
+/// Text of each string in the shared table. Rich text runs are joined; phonetic
+/// guides are left out.
+fn read_shared_strings(xml: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+    let mut in_phonetic = false;
+    for token in (Tokens { xml }) {
+        match token {
+            Token::Open { name: "si", empty, .. } => {
+                current.clear();
+                if empty {
+                    strings.push(String::new());
+                }
+            }
+            Token::Open { name: "rPh", empty: false, .. } => in_phonetic = true,
+            Token::Close("rPh") => in_phonetic = false,
+            Token::Open { name: "t", empty: false, .. } => in_text = !in_phonetic,
+            Token::Close("t") => in_text = false,
+            Token::Text(text) if in_text => current.push_str(&unescape(text)),
+            Token::Close("si") => strings.push(std::mem::take(&mut current)),
+            _ => {}
+        }
+    }
+    strings
+}
+
+/// Zero-based column of a cell reference such as "AB12"
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: Vec<u8> = reference.bytes().take_while(u8::is_ascii_alphabetic).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    letters
+        .iter()
+        .try_fold(0usize, |index, letter| {
+            index.checked_mul(26)?.checked_add((letter.to_ascii_uppercase() - b'A') as usize + 1)
+        })
+        .map(|index| index - 1)
+}
+
+fn read_rows(xml: &str, shared_strings: &[String]) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+    let mut rows = Vec::new();
+    let mut row_number = 0;
+    let mut cells: Vec<String> = Vec::new();
+    let mut column = 0;
+    let mut cell_type = String::new();
+    let mut value = String::new();
+    let mut in_value = false;
+
+    for token in (Tokens { xml }) {
+        match token {
+            Token::Open { name: "row", attributes, empty } => {
+                row_number = attribute(attributes, "r")
+                    .and_then(|r| r.parse().ok())
+                    .unwrap_or(row_number + 1);
+                cells.clear();
+                column = 0;
+                if empty {
+                    continue;
+                }
+            }
+            Token::Open { name: "c", attributes, empty } => {
+                column = attribute(attributes, "r").and_then(|r| column_index(&r)).unwrap_or(column);
+                cell_type = attribute(attributes, "t").unwrap_or_default();
+                value.clear();
+                if empty {
+                    column += 1;
+                }
+            }
+            Token::Open { name: "v" | "t", empty: false, .. } => in_value = true,
+            Token::Close("v" | "t") => in_value = false,
+            Token::Text(text) if in_value => value.push_str(&unescape(text)),
+            Token::Close("c") => {
+                let text = match cell_type.as_str() {
+                    "s" => {
+                        let index: usize = value
+                            .trim()
+                            .parse()
+                            .map_err(|_| invalid(format!("row {} refers to a missing string", row_number)))?;
+                        shared_strings
+                            .get(index)
+                            .cloned()
+                            .ok_or_else(|| invalid(format!("row {} refers to a missing string", row_number)))?
+                    }
+                    _ => std::mem::take(&mut value),
+                };
+                if cells.len() <= column {
+                    cells.resize(column + 1, String::new());
+                }
+                cells[column] = text;
+                column += 1;
+            }
+            Token::Close("row") if cells.iter().any(|cell| !cell.trim().is_empty()) => {
+                rows.push((row_number, std::mem::take(&mut cells)));
+            }
+            _ => {}
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stored (uncompressed) zip archive of the given parts
+    pub fn zip(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut directory = Vec::new();
+        for (name, content) in parts {
+            let offset = data.len() as u32;
+            let header = |signature: &[u8], data: &mut Vec<u8>| {
+                data.extend_from_slice(signature);
+                data.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+                data.extend_from_slice(&(content.len() as u32).to_le_bytes());
+                data.extend_from_slice(&(content.len() as u32).to_le_bytes());
+                data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                data.extend_from_slice(&[0, 0]);
+            };
+            header(b"PK\x03\x04", &mut data);
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(content.as_bytes());
+
+            directory.extend_from_slice(b"PK\x01\x02\x14\x00");
+            header(b"", &mut directory);
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = data.len() as u32;
+        data.extend_from_slice(&directory);
+        data.extend_from_slice(b"PK\x05\x06\x00\x00\x00\x00");
+        data.extend_from_slice(&(parts.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(parts.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        data.extend_from_slice(&directory_offset.to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data
+    }
+
+    #[test]
+    fn reads_cells_of_the_chosen_sheet() {
+        let workbook = zip(&[
+            (
+                "xl/workbook.xml",
+                r#"<?xml version="1.0"?><workbook xmlns:r="r"><sheets>
+                    <sheet name="Notes" sheetId="1" r:id="rId2"/><sheet name="Customers" sheetId="2" r:id="rId1"/>
+                </sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/>
+                    <Relationship Id="rId2" Target="/xl/worksheets/sheet2.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>Customer Name</t></si><si><r><t>Sharma </t></r><r><t>&amp; Sons</t></r><rPh><t>x</t></rPh></si></sst>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><sheetData>
+                    <row r="1"><c r="A1" t="s"><v>0</v></c><c r="C1" t="inlineStr"><is><t>Pincode</t></is></c></row>
+                    <row r="2"/>
+                    <row r="4"><c r="A4" t="s"><v>1</v></c><c r="B4" t="b"><v>1</v></c><c r="C4"><v>411001</v></c></row>
+                </sheetData></worksheet>"#,
+            ),
+            ("xl/worksheets/sheet2.xml", r#"<worksheet><sheetData><row r="1"><c r="A1" t="str"><v>notes</v></c></row></sheetData></worksheet>"#),
+        ]);
+
+        assert_eq!(sheet_names(&workbook).unwrap(), ["Notes", "Customers"]);
+        assert_eq!(read_sheet(&workbook, None).unwrap(), [(1, vec!["notes".to_string()])]);
+        assert_eq!(
+            read_sheet(&workbook, Some("customers")).unwrap(),
+            [
+                (1, vec!["Customer Name".to_string(), String::new(), "Pincode".to_string()]),
+                (4, vec!["Sharma & Sons".to_string(), "1".to_string(), "411001".to_string()]),
+            ]
+        );
+        assert!(matches!(read_sheet(&workbook, Some("Products")), Err(ImportError::InvalidWorkbook(_))));
+        assert!(matches!(read_sheet(b"not a zip", None), Err(ImportError::InvalidWorkbook(_))));
+
+        assert_eq!(column_index("A1"), Some(0));
+        assert_eq!(column_index("AB12"), Some(27));
+        assert_eq!(unescape("&lt;a&gt; &#65;&#x42; & co"), "<a> AB & co");
+    }
+}
@@ -9,9 +9,30 @@ import type {
 	Customer,
 	Product,
 	IndianState,
+	Invoice,
+	InvoiceItem,
+	InvoiceFilter,
 	CreateCompanySettings,
 	CreateCustomer,
-	CreateProduct
+	CreateProduct,
+	CreateInvoice,
+	CreateInvoiceItem,
+	Payment,
+	PaymentFilter,
+	CreatePayment,
+	InvoiceBalance,
+	NoteAllocation,
+	NoteReference,
+	StatusChange,
+	InvoiceStatus,
+	GstRate,
+	CreateGstRate,
+	RateMismatch,
+	AuditEntry,
+	AuditedTable,
+	TransportDetails,
+	InvoiceTemplate,
+	TemplateDefinition
 } from '../types/database';
 
 // =====================================================
@@ -65,6 +86,34 @@ export const databaseApi = {
 	}
 };
 
+// =====================================================
+// Audit Trail API
+// =====================================================
+
+export const auditApi = {
+	/**
+	 * Set the name recorded against changes from now on; defaults to the
+	 * operating system account each time the database is opened
+	 */
+	async setUser(name: string): Promise<string> {
+		return tauriInvoke<string>('set_audit_user', { name });
+	},
+
+	/**
+	 * Get the name changes are currently recorded under
+	 */
+	async getUser(): Promise<string> {
+		return tauriInvoke<string>('get_audit_user');
+	},
+
+	/**
+	 * Get every recorded change to one record, oldest first
+	 */
+	async getHistory(tableName: AuditedTable, recordId: number): Promise<AuditEntry[]> {
+		return tauriInvoke<AuditEntry[]>('get_audit_history', { table_name: tableName, record_id: recordId });
+	}
+};
+
 // =====================================================
 // Company Settings API
 // =====================================================
@@ -183,6 +232,645 @@ export const productApi = {
 	}
 };
 
+// =====================================================
+// GST Rate Master API
+// =====================================================
+
+export const gstRateApi = {
+	/**
+	 * List rate entries, optionally only those applying to an HSN/SAC code
+	 */
+	async list(hsnSacCode?: string, includeInactive = false): Promise<GstRate[]> {
+		return tauriInvoke<GstRate[]>('get_gst_rates', {
+			hsn_sac_code: hsnSacCode,
+			include_inactive: includeInactive
+		});
+	},
+
+	/**
+	 * Create or update a rate entry; entries for the same code may not overlap in time
+	 */
+	async save(rate: CreateGstRate | GstRate): Promise<number> {
+		return tauriInvoke<number>('save_gst_rate', { rate });
+	},
+
+	/**
+	 * Soft delete a rate entry (set inactive)
+	 */
+	async delete(id: number): Promise<boolean> {
+		return tauriInvoke<boolean>('delete_gst_rate', { id });
+	},
+
+	/**
+	 * Get the entry in force for an HSN/SAC code on a date (YYYY-MM-DD), today by default,
+	 * matching the longest code prefix in the master
+	 */
+	async lookup(hsnSacCode: string, date?: string): Promise<GstRate | null> {
+		return tauriInvoke<GstRate | null>('lookup_gst_rate', { hsn_sac_code: hsnSacCode, date });
+	}
+};
+
+// =====================================================
+// Invoice Management API
+// =====================================================
+
+export interface InvoiceListOptions extends InvoiceFilter {
+	limit?: number;
+	offset?: number;
+}
+
+export type InvoiceWithItems = Invoice & { items: InvoiceItem[] };
+
+export interface SavedInvoice {
+	id: number;
+	rate_mismatches: RateMismatch[]; // Lines whose rates differ from the rate master
+}
+
+export const invoiceApi = {
+	/**
+	 * List invoices filtered by date range, customer, status and type
+	 */
+	async list(options?: InvoiceListOptions): Promise<Invoice[]> {
+		return tauriInvoke<Invoice[]>('list_invoices', { filter: options });
+	},
+
+	/**
+	 * Get invoice with its line items by ID
+	 */
+	async getById(id: number): Promise<InvoiceWithItems | null> {
+		return tauriInvoke<InvoiceWithItems | null>('get_invoice_by_id', { id });
+	},
+
+	/**
+	 * Save invoice header and line items atomically (create or update).
+	 * An invoice with an empty invoice_number is saved as DRAFT-<id> and numbered from the
+	 * series for numberFormat when issued, so deleted drafts leave no gaps.
+	 * Only drafts can be edited; saving a draft as SENT issues it.
+	 * Credit and debit notes are refused here; save them with notes.save.
+	 * Lines whose rates disagree with the GST rate master are saved and flagged.
+	 */
+	async save(
+		invoice: CreateInvoice | Invoice,
+		items: Array<CreateInvoiceItem | InvoiceItem>,
+		numberFormat?: string
+	): Promise<SavedInvoice> {
+		return tauriInvoke<SavedInvoice>('save_invoice', { invoice, items, number_format: numberFormat });
+	},
+
+	/**
+	 * Delete a draft invoice and its line items; issued invoices can only be cancelled
+	 */
+	async delete(id: number): Promise<boolean> {
+		return tauriInvoke<boolean>('delete_invoice', { id });
+	},
+
+	/**
+	 * Render the tax invoice PDF into the app data directory; returns the file path,
+	 * which is also stored as the invoice's pdf_path
+	 */
+	async generatePdf(invoiceId: number): Promise<string> {
+		return tauriInvoke<string>('generate_invoice_pdf', { invoice_id: invoiceId });
+	},
+
+	/**
+	 * Issue (SENT) or cancel (CANCELLED) an invoice; PAID and OVERDUE are set by the backend
+	 */
+	async updateStatus(invoiceId: number, status: InvoiceStatus, reason?: string): Promise<StatusChange> {
+		return tauriInvoke<StatusChange>('update_invoice_status', { invoice_id: invoiceId, status, reason });
+	},
+
+	/**
+	 * Get every status change of an invoice, oldest first
+	 */
+	async getStatusHistory(invoiceId: number): Promise<StatusChange[]> {
+		return tauriInvoke<StatusChange[]>('get_invoice_status_history', { invoice_id: invoiceId });
+	},
+
+	/**
+	 * Mark invoices past their due date OVERDUE; returns the ids that changed.
+	 * The backend also does this when the database opens and hourly while running.
+	 */
+	async markOverdue(): Promise<number[]> {
+		return tauriInvoke<number[]>('mark_overdue_invoices');
+	},
+
+	/**
+	 * Cancel an invoice or note, keeping it and its number on record. A reason is
+	 * required once it has been issued, and payments or notes against it must be
+	 * reversed first.
+	 */
+	async cancel(invoiceId: number, reason: string): Promise<StatusChange> {
+		return tauriInvoke<StatusChange>('cancel_invoice', { invoice_id: invoiceId, reason });
+	},
+
+	/**
+	 * Start a revision of an issued invoice; returns the id of the new draft.
+	 * Issuing the revision cancels the original.
+	 */
+	async amend(invoiceId: number, numberFormat?: string): Promise<number> {
+		return tauriInvoke<number>('amend_invoice', { invoice_id: invoiceId, number_format: numberFormat });
+	}
+};
+
+// =====================================================
+// Credit and Debit Notes API
+// =====================================================
+
+export const noteApi = {
+	/**
+	 * Save a credit or debit note against one or more invoices; the references must add up
+	 * to the note's value and a credit may not exceed what remains on an invoice.
+	 * Notes with an empty number are numbered when issued, from the CN- or DN- series by default.
+	 */
+	async save(
+		note: CreateInvoice | Invoice,
+		items: Array<CreateInvoiceItem | InvoiceItem>,
+		references: NoteAllocation[],
+		numberFormat?: string
+	): Promise<number> {
+		return tauriInvoke<number>('save_note', { note, items, references, number_format: numberFormat });
+	},
+
+	/**
+	 * Get the invoices a note amends
+	 */
+	async getReferences(noteId: number): Promise<NoteReference[]> {
+		return tauriInvoke<NoteReference[]>('get_note_references', { note_id: noteId });
+	},
+
+	/**
+	 * List the notes issued against an invoice
+	 */
+	async listForInvoice(invoiceId: number): Promise<NoteReference[]> {
+		return tauriInvoke<NoteReference[]>('list_invoice_notes', { invoice_id: invoiceId });
+	}
+};
+
+// =====================================================
+// Payments API
+// =====================================================
+
+export const paymentApi = {
+	/**
+	 * Record a receipt against one or more invoices; settled invoices move to PAID
+	 */
+	async record(payment: CreatePayment): Promise<number[]> {
+		return tauriInvoke<number[]>('record_payment', { payment });
+	},
+
+	/**
+	 * List payments, excluding voided ones unless include_voided is set
+	 */
+	async list(filter?: PaymentFilter): Promise<Payment[]> {
+		return tauriInvoke<Payment[]>('list_payments', { filter });
+	},
+
+	/**
+	 * Void a payment and reopen its invoice if no longer settled
+	 */
+	async void(id: number, reason?: string): Promise<InvoiceBalance> {
+		return tauriInvoke<InvoiceBalance>('void_payment', { id, reason });
+	},
+
+	/**
+	 * Get amount paid and outstanding for an invoice
+	 */
+	async getInvoiceBalance(invoiceId: number): Promise<InvoiceBalance | null> {
+		return tauriInvoke<InvoiceBalance | null>('get_invoice_balance', { invoice_id: invoiceId });
+	}
+};
+
+// =====================================================
+// GST Returns API
+// =====================================================
+
+/**
+ * GSTR-1 in the GSTN offline tool format; sections are omitted when empty
+ */
+export interface Gstr1Return {
+	gstin: string;
+	fp: string; // Return period as MMYYYY
+	b2b?: unknown[];
+	b2cl?: unknown[];
+	b2cs?: unknown[];
+	cdnr?: unknown[];
+	cdnur?: unknown[];
+	exp?: unknown[];
+	hsn: { hsn_b2b: unknown[]; hsn_b2c: unknown[] };
+	doc_issue: { doc_det: unknown[] };
+}
+
+export interface Gstr3bTaxValues {
+	txval: number;
+	iamt: number;
+	camt: number;
+	samt: number;
+	csamt: number;
+}
+
+export interface Gstr3bPlaceOfSupply {
+	pos: string;
+	txval: number;
+	iamt: number;
+}
+
+/**
+ * GSTR-3B tables 3.1 (sup_details) and 3.2 (inter_sup) in the GSTN JSON layout
+ */
+export interface Gstr3bSummary {
+	gstin: string;
+	ret_period: string;
+	sup_details: {
+		osup_det: Gstr3bTaxValues;
+		osup_zero: Gstr3bTaxValues;
+		osup_nil_exmp: Gstr3bTaxValues;
+		isup_rev: Gstr3bTaxValues;
+		osup_nongst: Gstr3bTaxValues;
+	};
+	inter_sup: {
+		unreg_details: Gstr3bPlaceOfSupply[];
+		comp_details: Gstr3bPlaceOfSupply[];
+		uin_details: Gstr3bPlaceOfSupply[];
+	};
+}
+
+export const returnsApi = {
+	/**
+	 * Build GSTR-1 for a period (MMYYYY or YYYY-MM) and write the JSON file to path
+	 */
+	async exportGstr1(returnPeriod: string, path: string): Promise<Gstr1Return> {
+		return tauriInvoke<Gstr1Return>('export_gstr1', { return_period: returnPeriod, path });
+	},
+
+	/**
+	 * Compute GSTR-3B tables 3.1 and 3.2 for a period
+	 */
+	async getGstr3b(returnPeriod: string): Promise<Gstr3bSummary> {
+		return tauriInvoke<Gstr3bSummary>('get_gstr3b', { return_period: returnPeriod });
+	},
+
+	/**
+	 * Compute GSTR-3B for a period and write the JSON file to path
+	 */
+	async exportGstr3b(returnPeriod: string, path: string): Promise<Gstr3bSummary> {
+		return tauriInvoke<Gstr3bSummary>('export_gstr3b', { return_period: returnPeriod, path });
+	}
+};
+
+// =====================================================
+// Tally Export API
+// =====================================================
+
+export interface TallyPartyLedger {
+	customer_id: number;
+	ledger_name: string;
+}
+
+// Ledger names in Tally; sales and tax names may contain {rate}, e.g. "Sales @ {rate}%"
+export interface TallySettings {
+	company_name?: string; // Tally company to import into; the open company when blank
+	party_group: string;
+	sales_ledger: string;
+	cgst_ledger: string;
+	sgst_ledger: string;
+	igst_ledger: string;
+	cess_ledger: string;
+	round_off_ledger: string;
+	cash_ledger: string;
+	bank_ledger: string; // Receipts by any method other than cash
+	party_ledgers: TallyPartyLedger[]; // Customers whose ledger is not named after them
+	updated_at?: string;
+}
+
+export interface TallyExportOptions {
+	date_from?: string; // YYYY-MM-DD
+	date_to?: string;
+	incremental?: boolean; // Only vouchers not yet exported, or changed since
+}
+
+export interface TallyExportReport {
+	path: string;
+	incremental: boolean;
+	ledgers: number;
+	created: number;
+	altered: number;
+	cancelled: number;
+	vouchers: Record<string, string[]>; // Voucher numbers by voucher type
+}
+
+export const tallyApi = {
+	/**
+	 * Get the ledger names used when exporting to Tally
+	 */
+	async getSettings(): Promise<TallySettings> {
+		return tauriInvoke<TallySettings>('get_tally_settings');
+	},
+
+	/**
+	 * Save the ledger names, replacing every customer's ledger name
+	 */
+	async saveSettings(settings: TallySettings): Promise<TallySettings> {
+		return tauriInvoke<TallySettings>('save_tally_settings', { settings });
+	},
+
+	/**
+	 * Write Tally import XML with party ledgers, sales, note and receipt vouchers to path
+	 */
+	async export(path: string, options?: TallyExportOptions): Promise<TallyExportReport> {
+		return tauriInvoke<TallyExportReport>('export_tally', { path, options });
+	}
+};
+
+// =====================================================
+// Backup API
+// =====================================================
+
+export interface BackupInfo {
+	path: string;
+	size_bytes: number;
+	encrypted: boolean;
+	schema_version: number;
+}
+
+export interface BackupFile {
+	path: string;
+	file_name: string;
+	size_bytes: number;
+	encrypted: boolean;
+	automatic: boolean; // Taken by the schedule; the oldest are removed beyond keep_count
+	modified_at?: string;
+}
+
+export interface RestoreReport {
+	restored_from: string;
+	backup_version: number; // Schema version of the backup
+	schema_version: number; // Schema version after migrating it
+	previous_copy?: string; // Where the replaced database was kept
+}
+
+// The password automatic backups are encrypted with is never returned
+export interface BackupSettings {
+	enabled: boolean;
+	directory?: string; // A backups folder beside the database when blank
+	interval_hours: number; // 1-720
+	keep_count: number; // 1-365
+	encrypted: boolean;
+	last_backup_at?: string; // UTC
+	last_error?: string; // Why the last automatic backup failed
+	updated_at?: string;
+}
+
+export const backupApi = {
+	/**
+	 * Copy the database to path while it stays in use, encrypted when a password is given
+	 */
+	async create(path: string, password?: string): Promise<BackupInfo> {
+		return tauriInvoke<BackupInfo>('create_backup', { path, password });
+	},
+
+	/**
+	 * Replace the database with a backup once it passes the integrity and schema checks
+	 */
+	async restore(path: string, password?: string): Promise<RestoreReport> {
+		return tauriInvoke<RestoreReport>('restore_backup', { path, password });
+	},
+
+	/**
+	 * List the backups in the automatic backup folder, newest first
+	 */
+	async list(): Promise<BackupFile[]> {
+		return tauriInvoke<BackupFile[]>('list_backups');
+	},
+
+	/**
+	 * Get the automatic backup schedule and how the last backup went
+	 */
+	async getSettings(): Promise<BackupSettings> {
+		return tauriInvoke<BackupSettings>('get_backup_settings');
+	},
+
+	/**
+	 * Save the schedule; a password replaces the one automatic backups are encrypted with
+	 */
+	async saveSettings(settings: BackupSettings, password?: string): Promise<BackupSettings> {
+		return tauriInvoke<BackupSettings>('save_backup_settings', { settings, password });
+	}
+};
+
+// =====================================================
+// E-invoice API
+// =====================================================
+
+/**
+ * INV-01 payload in the NIC e-invoice schema 1.1 (PascalCase keys as sent to the IRP)
+ */
+export interface EInvoice {
+	Version: string;
+	TranDtls: { TaxSch: string; SupTyp: string; RegRev: string; IgstOnIntra: string };
+	DocDtls: { Typ: string; No: string; Dt: string };
+	SellerDtls: Record<string, unknown>;
+	BuyerDtls: Record<string, unknown>;
+	ItemList: Record<string, unknown>[];
+	ValDtls: Record<string, number>;
+}
+
+export interface EInvoiceRegistration {
+	irn: string;
+	ack_number: string;
+	ack_date: string;
+	signed_qr_code: string;
+}
+
+export const einvoiceApi = {
+	/**
+	 * Build and validate the e-invoice payload, optionally writing the JSON file to path
+	 */
+	async generate(invoiceId: number, path?: string): Promise<EInvoice> {
+		return tauriInvoke<EInvoice>('generate_e_invoice', { invoice_id: invoiceId, path });
+	},
+
+	/**
+	 * Store the IRN, acknowledgement and signed QR code returned by the IRP
+	 */
+	async saveRegistration(invoiceId: number, registration: EInvoiceRegistration): Promise<Invoice> {
+		return tauriInvoke<Invoice>('save_e_invoice_registration', {
+			invoice_id: invoiceId,
+			registration
+		});
+	}
+};
+
+// =====================================================
+// E-way Bill API
+// =====================================================
+
+/**
+ * Bulk-upload file for the e-way bill portal (camelCase keys as the portal expects)
+ */
+export interface EWayBillBulk {
+	version: string;
+	billLists: Record<string, unknown>[];
+}
+
+export const ewayBillApi = {
+	/**
+	 * Get the transport details stored for an invoice
+	 */
+	async getTransportDetails(invoiceId: number): Promise<TransportDetails | null> {
+		return tauriInvoke<TransportDetails | null>('get_transport_details', { invoice_id: invoiceId });
+	},
+
+	/**
+	 * Validate and store transport details, replacing any saved before
+	 */
+	async saveTransportDetails(details: TransportDetails): Promise<TransportDetails> {
+		return tauriInvoke<TransportDetails>('save_transport_details', { details });
+	},
+
+	/**
+	 * Whether the goods on an invoice are worth more than the ₹50,000 threshold
+	 */
+	async isRequired(invoiceId: number): Promise<boolean> {
+		return tauriInvoke<boolean>('is_e_way_bill_required', { invoice_id: invoiceId });
+	},
+
+	/**
+	 * Build and validate e-way bills for the invoices, optionally writing the JSON file to path
+	 */
+	async generate(invoiceIds: number[], path?: string): Promise<EWayBillBulk> {
+		return tauriInvoke<EWayBillBulk>('generate_e_way_bills', { invoice_ids: invoiceIds, path });
+	}
+};
+
+// =====================================================
+// Invoice Templates API
+// =====================================================
+
+export const templateApi = {
+	/**
+	 * List built-in and saved invoice layouts
+	 */
+	async list(): Promise<InvoiceTemplate[]> {
+		return tauriInvoke<InvoiceTemplate[]>('list_invoice_templates');
+	},
+
+	/**
+	 * Get an invoice layout by ID
+	 */
+	async getById(id: number): Promise<InvoiceTemplate | null> {
+		return tauriInvoke<InvoiceTemplate | null>('get_invoice_template', { id });
+	},
+
+	/**
+	 * Create or update a layout; built-in layouts must be saved under a new name
+	 */
+	async save(template: InvoiceTemplate): Promise<InvoiceTemplate> {
+		return tauriInvoke<InvoiceTemplate>('save_invoice_template', { template });
+	},
+
+	/**
+	 * Delete a saved layout; companies and customers using it revert to the default
+	 */
+	async delete(id: number): Promise<boolean> {
+		return tauriInvoke<boolean>('delete_invoice_template', { id });
+	},
+
+	/**
+	 * Render a layout against an invoice, or a sample invoice when none is given,
+	 * and return the path of the preview PDF
+	 */
+	async preview(definition: TemplateDefinition, invoiceId?: number): Promise<string> {
+		return tauriInvoke<string>('preview_invoice_template', { definition, invoice_id: invoiceId });
+	}
+};
+
+// =====================================================
+// Bulk Import API
+// =====================================================
+
+export type ImportKind = 'customers' | 'products';
+
+// File column header to field name; an empty field leaves the column out
+export type ImportColumns = Record<string, string>;
+
+export interface ImportMapping {
+	id?: number;
+	name: string;
+	kind: ImportKind;
+	columns: ImportColumns;
+	created_at?: string;
+	updated_at?: string;
+}
+
+export interface ImportOptions {
+	columns?: ImportColumns; // Takes precedence over mapping_id
+	mapping_id?: number;
+	sheet?: string; // Worksheet of an .xlsx file; the first one when absent
+	dry_run?: boolean;
+}
+
+export interface ImportRowError {
+	row: number; // Line of a CSV file or row of the worksheet
+	message: string; // Every problem with the row, separated by "; "
+}
+
+export interface ImportReport {
+	kind: ImportKind;
+	dry_run: boolean;
+	total_rows: number;
+	imported: number; // On a dry run, the rows that would be imported
+	errors: ImportRowError[];
+}
+
+export interface ImportPreview {
+	kind: ImportKind;
+	sheets: string[]; // Empty for CSV files
+	headers: string[];
+	fields: (string | null)[]; // Field each header fills, null when ignored
+	missing: string[]; // Required fields no column fills yet
+	available_fields: string[];
+	rows: string[][]; // The first few rows
+	total_rows: number;
+}
+
+export const importApi = {
+	/**
+	 * Show how a .csv or .xlsx file would be read, to map its columns before importing
+	 */
+	async preview(kind: ImportKind, path: string, options?: ImportOptions): Promise<ImportPreview> {
+		return tauriInvoke<ImportPreview>('preview_import', { kind, path, options });
+	},
+
+	/**
+	 * Import every valid row in one transaction and report the rows that failed;
+	 * with dry_run nothing is saved
+	 */
+	async run(kind: ImportKind, path: string, options?: ImportOptions): Promise<ImportReport> {
+		return tauriInvoke<ImportReport>('import_records', { kind, path, options });
+	},
+
+	/**
+	 * List saved column mappings for a kind of record
+	 */
+	async listMappings(kind: ImportKind): Promise<ImportMapping[]> {
+		return tauriInvoke<ImportMapping[]>('list_import_mappings', { kind });
+	},
+
+	/**
+	 * Save a column mapping; a mapping with the same name and kind is replaced
+	 */
+	async saveMapping(mapping: ImportMapping): Promise<ImportMapping> {
+		return tauriInvoke<ImportMapping>('save_import_mapping', { mapping });
+	},
+
+	/**
+	 * Delete a saved column mapping
+	 */
+	async deleteMapping(id: number): Promise<void> {
+		return tauriInvoke<void>('delete_import_mapping', { id });
+	}
+};
+
 // =====================================================
 // Indian States API
 // =====================================================
@@ -213,6 +901,20 @@ export interface RecordCounts {
 	invoices: number;
 }
 
+export type SeriesResetRule = 'NEVER' | 'FINANCIAL_YEAR' | 'CALENDAR_YEAR' | 'MONTHLY';
+
+export type WordsLanguage = 'english' | 'hindi' | 'marathi';
+export type WordsStyle = 'invoice' | 'sentence';
+
+export interface InvoiceSeries {
+	id: number;
+	format: string;
+	reset_rule: SeriesResetRule;
+	is_active: boolean;
+	created_at: string;
+	updated_at?: string;
+}
+
 export const utilityApi = {
 	/**
 	 * Get record counts for dashboard
@@ -222,10 +924,31 @@ export const utilityApi = {
 	},
 
 	/**
-	 * Generate next invoice number based on format
+	 * Preview the next invoice number for a format without consuming it
 	 */
 	async getNextInvoiceNumber(format?: string): Promise<string> {
 		return tauriInvoke<string>('get_next_invoice_number', { format });
+	},
+
+	/**
+	 * Get all invoice numbering series with their reset rules
+	 */
+	async getInvoiceSeries(): Promise<InvoiceSeries[]> {
+		return tauriInvoke<InvoiceSeries[]>('get_invoice_series');
+	},
+
+	/**
+	 * Create a numbering series or change its reset rule ({FY} formats default to FINANCIAL_YEAR)
+	 */
+	async saveInvoiceSeries(format: string, resetRule?: SeriesResetRule): Promise<number> {
+		return tauriInvoke<number>('save_invoice_series', { format, reset_rule: resetRule });
+	},
+
+	/**
+	 * Write an amount in words; 'invoice' style gives "Rupees ... Only", 'sentence' matches amountToWords
+	 */
+	async amountInWords(amount: number, language?: WordsLanguage, style?: WordsStyle): Promise<string> {
+		return tauriInvoke<string>('amount_in_words', { amount, language, style });
 	}
 };
 
@@ -240,8 +963,20 @@ export interface GstinValidationResult {
 	entity_number?: string;
 	check_digit?: string;
 	error?: string;
+	error_code?: GstinErrorCode;
 }
 
+export type GstinErrorCode =
+	| 'GSTIN_REQUIRED'
+	| 'GSTIN_INVALID_LENGTH'
+	| 'GSTIN_INVALID_CHARACTERS'
+	| 'GSTIN_INVALID_STATE_CODE'
+	| 'GSTIN_UNKNOWN_STATE_CODE'
+	| 'GSTIN_INVALID_PAN'
+	| 'GSTIN_INVALID_ENTITY_NUMBER'
+	| 'GSTIN_INVALID_DEFAULT_CHARACTER'
+	| 'GSTIN_INVALID_CHECKSUM';
+
 export interface HsnSacValidationResult {
 	is_valid: boolean;
 	validation_type?: 'HSN' | 'SAC';
@@ -259,25 +994,73 @@ export const validationApi = {
 	},
 
 	/**
-	 * Validate HSN/SAC code format
+	 * Validate HSN/SAC code format and suggest today's rate from the rate master
 	 */
 	async validateHsnSac(code: string): Promise<HsnSacValidationResult> {
 		return tauriInvoke<HsnSacValidationResult>('validate_hsn_sac', { code });
 	}
 };
 
+// =====================================================
+// Local API Server API
+// =====================================================
+
+export interface ApiServerStatus {
+	enabled: boolean;
+	running: boolean; // False while enabled when the port was taken at startup
+	port: number;
+	url: string; // e.g. http://127.0.0.1:8787/api/v1; the OpenAPI description is at url + '/openapi.json'
+	token: string; // Sent by other programs as `Authorization: Bearer <token>`
+}
+
+export const apiServerApi = {
+	/**
+	 * Get whether the local HTTP API is enabled and running, its address and token
+	 */
+	async getStatus(): Promise<ApiServerStatus> {
+		return tauriInvoke<ApiServerStatus>('get_api_server_status');
+	},
+
+	/**
+	 * Turn the local HTTP API on or off and choose its port (1024-65535)
+	 */
+	async configure(enabled: boolean, port: number): Promise<ApiServerStatus> {
+		return tauriInvoke<ApiServerStatus>('configure_api_server', { enabled, port });
+	},
+
+	/**
+	 * Replace the API token; programs using the old one are refused from then on
+	 */
+	async regenerateToken(): Promise<ApiServerStatus> {
+		return tauriInvoke<ApiServerStatus>('regenerate_api_token');
+	}
+};
+
 // =====================================================
 // Combined API Object
 // =====================================================
 
 export const api = {
 	database: databaseApi,
+	audit: auditApi,
 	company: companyApi,
 	customers: customerApi,
 	products: productApi,
+	gstRates: gstRateApi,
+	invoices: invoiceApi,
+	notes: noteApi,
+	payments: paymentApi,
+	returns: returnsApi,
+	tally: tallyApi,
+	backups: backupApi,
+	einvoice: einvoiceApi,
+	ewayBill: ewayBillApi,
+	templates: templateApi,
+	import: importApi,
 	states: statesApi,
 	utility: utilityApi,
-	validation: validationApi
+	validation: validationApi,
+	apiServer: apiServerApi
 };
 
 export default api;
@@ -0,0 +1,20 @@
+-- =====================================================
+-- Migration 2: Invoice Numbering Series
+-- Persistent counter per invoice number format
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS invoice_series (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    format TEXT UNIQUE NOT NULL, -- e.g. INV-{YYYY}-{MM}-{####}
+    next_number INTEGER NOT NULL DEFAULT 1,
+    is_active BOOLEAN DEFAULT 1,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER IF NOT EXISTS update_invoice_series_timestamp 
+AFTER UPDATE ON invoice_series
+FOR EACH ROW
+BEGIN
+    UPDATE invoice_series SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
@@ -0,0 +1,21 @@
+-- =====================================================
+-- Migration 3: Series Reset Rules
+-- Per-series reset rule and one counter per reset period
+-- =====================================================
+
+ALTER TABLE invoice_series ADD COLUMN reset_rule TEXT NOT NULL DEFAULT 'NEVER'
+    CHECK (reset_rule IN ('NEVER', 'FINANCIAL_YEAR', 'CALENDAR_YEAR', 'MONTHLY'));
+
+CREATE TABLE IF NOT EXISTS invoice_series_counters (
+    series_id INTEGER NOT NULL,
+    period TEXT NOT NULL DEFAULT '', -- '' for NEVER, else e.g. 2026-27, 2026 or 2026-09
+    next_number INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (series_id, period),
+    FOREIGN KEY (series_id) REFERENCES invoice_series(id) ON DELETE CASCADE
+);
+
+-- Existing series never reset, so their counter moves to the single '' period
+INSERT INTO invoice_series_counters (series_id, period, next_number)
+SELECT id, '', next_number FROM invoice_series;
+
+ALTER TABLE invoice_series DROP COLUMN next_number;
@@ -0,0 +1,11 @@
+-- =====================================================
+-- Migration 4: Payment Receipts and Voids
+-- Groups split payments by receipt and keeps voided payments on record
+-- =====================================================
+
+-- Rows recorded from one receipt split across several invoices share a receipt number
+ALTER TABLE payments ADD COLUMN receipt_number TEXT;
+ALTER TABLE payments ADD COLUMN voided_at DATETIME;
+ALTER TABLE payments ADD COLUMN void_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_payments_receipt ON payments(receipt_number);
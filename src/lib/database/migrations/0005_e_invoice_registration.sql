@@ -0,0 +1,11 @@
+-- =====================================================
+-- Migration 5: E-invoice Registration
+-- IRN, acknowledgement and signed QR returned by the IRP
+-- =====================================================
+
+ALTER TABLE invoices ADD COLUMN irn TEXT; -- 64-character invoice reference number
+ALTER TABLE invoices ADD COLUMN ack_number TEXT;
+ALTER TABLE invoices ADD COLUMN ack_date DATETIME;
+ALTER TABLE invoices ADD COLUMN signed_qr_code TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_invoices_irn ON invoices(irn) WHERE irn IS NOT NULL;
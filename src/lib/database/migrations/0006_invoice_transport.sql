@@ -0,0 +1,21 @@
+-- =====================================================
+-- Migration 6: Invoice Transport Details
+-- Part-B details for e-way bills on goods invoices
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS invoice_transport (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    invoice_id INTEGER UNIQUE NOT NULL,
+    transporter_id TEXT, -- GSTIN or TRANSIN of the transporter
+    transporter_name TEXT,
+    transport_mode TEXT CHECK (transport_mode IN ('ROAD', 'RAIL', 'AIR', 'SHIP')) NOT NULL DEFAULT 'ROAD',
+    distance_km INTEGER NOT NULL DEFAULT 0, -- 0 lets the portal compute it from the pincodes
+    vehicle_number TEXT,
+    vehicle_type TEXT CHECK (vehicle_type IN ('REGULAR', 'OVER_DIMENSIONAL')) NOT NULL DEFAULT 'REGULAR',
+    transport_doc_number TEXT, -- Railway receipt, airway bill or bill of lading
+    transport_doc_date DATE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE CASCADE
+);
@@ -0,0 +1,105 @@
+-- =====================================================
+-- Migration 7: Invoice Layout Templates
+-- JSON layout definitions, chosen per company or per customer
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS invoice_templates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT UNIQUE NOT NULL,
+    description TEXT,
+    definition TEXT NOT NULL, -- JSON; fields left out take the standard layout's values
+    is_builtin BOOLEAN NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO invoice_templates (name, description, definition, is_builtin) VALUES
+(
+    'Standard',
+    'Supplier header with logo, full item table, HSN summary and bank details',
+    '{
+        "title": null,
+        "copy_label": "Original for Recipient",
+        "letterhead": false,
+        "letterhead_space": 110,
+        "show_logo": true,
+        "item_descriptions": true,
+        "sections": ["header", "parties", "items", "totals", "amount_in_words", "hsn_summary", "bank_details", "notes", "terms", "signature"],
+        "columns": [
+            {"field": "serial", "width": 16},
+            {"field": "description", "width": 0},
+            {"field": "hsn_sac", "width": 40},
+            {"field": "quantity", "width": 38},
+            {"field": "rate", "width": 46},
+            {"field": "discount", "width": 40},
+            {"field": "taxable", "width": 54},
+            {"field": "cgst", "width": 46},
+            {"field": "sgst", "width": 46},
+            {"field": "igst", "width": 48},
+            {"field": "cess", "width": 40},
+            {"field": "total", "width": 54}
+        ],
+        "style": {"font_size": 8, "table_font_size": 7, "title_size": 13, "line_spacing": 1.25, "cell_padding": 3, "heading_shade": 0.9},
+        "footer": "This is a computer generated invoice."
+    }',
+    1
+),
+(
+    'Compact',
+    'Smaller type without logo, discount column, HSN summary or notes',
+    '{
+        "show_logo": false,
+        "item_descriptions": false,
+        "sections": ["header", "parties", "items", "totals", "amount_in_words", "bank_details", "signature"],
+        "columns": [
+            {"field": "serial", "width": 14},
+            {"field": "description", "width": 0},
+            {"field": "hsn_sac", "width": 40},
+            {"field": "quantity", "width": 38},
+            {"field": "rate", "width": 46},
+            {"field": "taxable", "width": 54},
+            {"field": "cgst", "width": 46},
+            {"field": "sgst", "width": 46},
+            {"field": "igst", "width": 48},
+            {"field": "total", "width": 54}
+        ],
+        "style": {"font_size": 7, "table_font_size": 6.5, "title_size": 11, "line_spacing": 1.15, "cell_padding": 2}
+    }',
+    1
+),
+(
+    'Detailed',
+    'Product codes, discount percentages and the HSN summary',
+    '{
+        "columns": [
+            {"field": "serial", "width": 14},
+            {"field": "product_code", "width": 38},
+            {"field": "description", "width": 0},
+            {"field": "hsn_sac", "width": 38},
+            {"field": "quantity", "width": 34},
+            {"field": "rate", "width": 42},
+            {"field": "discount_percent", "heading": "Disc. %", "width": 30},
+            {"field": "discount", "width": 38},
+            {"field": "taxable", "width": 48},
+            {"field": "cgst", "width": 42},
+            {"field": "sgst", "width": 42},
+            {"field": "igst", "width": 44},
+            {"field": "total", "width": 50}
+        ],
+        "style": {"table_font_size": 6.5}
+    }',
+    1
+),
+(
+    'Letterhead',
+    'Leaves room for pre-printed letterhead stationery instead of printing the supplier block',
+    '{
+        "letterhead": true,
+        "letterhead_space": 110,
+        "show_logo": false
+    }',
+    1
+);
+
+ALTER TABLE company_settings ADD COLUMN invoice_template_id INTEGER REFERENCES invoice_templates(id) ON DELETE SET NULL;
+ALTER TABLE customers ADD COLUMN invoice_template_id INTEGER REFERENCES invoice_templates(id) ON DELETE SET NULL;
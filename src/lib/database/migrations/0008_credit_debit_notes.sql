@@ -0,0 +1,23 @@
+-- =====================================================
+-- Migration 8: Credit and Debit Notes
+-- Links each note to the invoices it amends and records why it was issued
+-- =====================================================
+
+-- SALES_RETURN, POST_SALE_DISCOUNT, DEFICIENCY_IN_SERVICES, CORRECTION_IN_INVOICE,
+-- CHANGE_IN_POS, FINALISATION_OF_PROVISIONAL_ASSESSMENT or OTHERS; NULL on invoices
+ALTER TABLE invoices ADD COLUMN note_reason TEXT;
+
+-- A note may amend several invoices; each row carries the share of the note's value
+CREATE TABLE IF NOT EXISTS invoice_note_references (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    note_id INTEGER NOT NULL,
+    invoice_id INTEGER NOT NULL,
+    amount DECIMAL(15,2) NOT NULL CHECK (amount > 0),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    UNIQUE (note_id, invoice_id),
+    FOREIGN KEY (note_id) REFERENCES invoices(id) ON DELETE CASCADE,
+    FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE RESTRICT
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_references_invoice ON invoice_note_references(invoice_id);
@@ -0,0 +1,22 @@
+-- =====================================================
+-- Migration 9: Invoice Status History
+-- Every status change of an invoice or note, with when and why it happened
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS invoice_status_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    invoice_id INTEGER NOT NULL,
+    from_status TEXT, -- NULL when the document was created
+    to_status TEXT NOT NULL,
+    reason TEXT,
+    changed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (invoice_id) REFERENCES invoices(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_status_history_invoice ON invoice_status_history(invoice_id);
+
+-- Existing documents start their history in the status they already have
+INSERT INTO invoice_status_history (invoice_id, from_status, to_status, reason, changed_at)
+SELECT id, NULL, status, 'Status before history was kept', COALESCE(updated_at, created_at, CURRENT_TIMESTAMP)
+FROM invoices;
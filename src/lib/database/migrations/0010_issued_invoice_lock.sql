@@ -0,0 +1,84 @@
+-- =====================================================
+-- Migration 10: Issued Invoice Lock
+-- Issued invoices are kept as issued: cancelled or amended, never edited or deleted
+-- =====================================================
+
+ALTER TABLE invoices ADD COLUMN cancelled_at DATETIME;
+ALTER TABLE invoices ADD COLUMN cancellation_reason TEXT;
+-- A revised invoice points at the one it replaces; issuing it cancels the original
+ALTER TABLE invoices ADD COLUMN amends_invoice_id INTEGER REFERENCES invoices(id);
+
+CREATE INDEX IF NOT EXISTS idx_invoices_amends ON invoices(amends_invoice_id);
+
+-- Status, PDF path, e-invoice registration and cancellation details may still change
+CREATE TRIGGER IF NOT EXISTS lock_issued_invoices
+BEFORE UPDATE ON invoices
+FOR EACH ROW
+WHEN OLD.status != 'DRAFT' AND (
+    NEW.invoice_number IS NOT OLD.invoice_number OR
+    NEW.invoice_date IS NOT OLD.invoice_date OR
+    NEW.customer_id IS NOT OLD.customer_id OR
+    NEW.invoice_type IS NOT OLD.invoice_type OR
+    NEW.place_of_supply IS NOT OLD.place_of_supply OR
+    NEW.reverse_charge IS NOT OLD.reverse_charge OR
+    NEW.subtotal IS NOT OLD.subtotal OR
+    NEW.total_discount IS NOT OLD.total_discount OR
+    NEW.taxable_amount IS NOT OLD.taxable_amount OR
+    NEW.cgst_amount IS NOT OLD.cgst_amount OR
+    NEW.sgst_amount IS NOT OLD.sgst_amount OR
+    NEW.igst_amount IS NOT OLD.igst_amount OR
+    NEW.cess_amount IS NOT OLD.cess_amount OR
+    NEW.total_tax IS NOT OLD.total_tax OR
+    NEW.total_amount IS NOT OLD.total_amount OR
+    NEW.round_off IS NOT OLD.round_off OR
+    NEW.final_amount IS NOT OLD.final_amount OR
+    NEW.payment_terms IS NOT OLD.payment_terms OR
+    NEW.due_date IS NOT OLD.due_date OR
+    NEW.notes IS NOT OLD.notes OR
+    NEW.terms_conditions IS NOT OLD.terms_conditions OR
+    NEW.note_reason IS NOT OLD.note_reason OR
+    NEW.amends_invoice_id IS NOT OLD.amends_invoice_id
+)
+BEGIN
+    SELECT RAISE(ABORT, 'Issued invoices cannot be edited; cancel or amend them instead');
+END;
+
+CREATE TRIGGER IF NOT EXISTS keep_cancelled_invoices
+BEFORE UPDATE OF status ON invoices
+FOR EACH ROW
+WHEN OLD.status = 'CANCELLED' AND NEW.status != 'CANCELLED'
+BEGIN
+    SELECT RAISE(ABORT, 'Cancelled invoices cannot be reinstated');
+END;
+
+CREATE TRIGGER IF NOT EXISTS keep_issued_invoices
+BEFORE DELETE ON invoices
+FOR EACH ROW
+WHEN OLD.status != 'DRAFT'
+BEGIN
+    SELECT RAISE(ABORT, 'Issued invoices cannot be deleted; cancel them instead');
+END;
+
+CREATE TRIGGER IF NOT EXISTS lock_issued_invoice_items_insert
+BEFORE INSERT ON invoice_items
+FOR EACH ROW
+WHEN (SELECT status FROM invoices WHERE id = NEW.invoice_id) != 'DRAFT'
+BEGIN
+    SELECT RAISE(ABORT, 'Lines of issued invoices cannot be changed');
+END;
+
+CREATE TRIGGER IF NOT EXISTS lock_issued_invoice_items_update
+BEFORE UPDATE ON invoice_items
+FOR EACH ROW
+WHEN (SELECT status FROM invoices WHERE id = OLD.invoice_id) != 'DRAFT'
+BEGIN
+    SELECT RAISE(ABORT, 'Lines of issued invoices cannot be changed');
+END;
+
+CREATE TRIGGER IF NOT EXISTS lock_issued_invoice_items_delete
+BEFORE DELETE ON invoice_items
+FOR EACH ROW
+WHEN (SELECT status FROM invoices WHERE id = OLD.invoice_id) != 'DRAFT'
+BEGIN
+    SELECT RAISE(ABORT, 'Lines of issued invoices cannot be changed');
+END;
@@ -0,0 +1,411 @@
+-- =====================================================
+-- Migration 11: Audit Log
+-- Every insert, update and delete on the accounting tables, with the row before and
+-- after as JSON, when it happened and who was using the app
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    table_name TEXT NOT NULL,
+    record_id INTEGER NOT NULL,
+    action TEXT CHECK (action IN ('INSERT', 'UPDATE', 'DELETE')) NOT NULL,
+    old_values TEXT, -- JSON of the row before the change; NULL for inserts
+    new_values TEXT, -- JSON of the row after the change; NULL for deletes
+    changed_by TEXT NOT NULL,
+    changed_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_record ON audit_log(table_name, record_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_changed_at ON audit_log(changed_at);
+
+-- Who the triggers record as making changes; the app sets it when the database opens
+CREATE TABLE IF NOT EXISTS audit_actor (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    actor TEXT NOT NULL
+);
+
+INSERT OR IGNORE INTO audit_actor (id, actor) VALUES (1, 'system');
+
+-- Updates are logged when a column other than the timestamps is written, so the
+-- bump of updated_at by the timestamp triggers is not logged as a change of its own
+
+-- The trail itself may never be edited or removed
+CREATE TRIGGER IF NOT EXISTS keep_audit_log_updates
+BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'The audit log cannot be changed');
+END;
+
+CREATE TRIGGER IF NOT EXISTS keep_audit_log_deletes
+BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'The audit log cannot be changed');
+END;
+
+-- =====================================================
+-- Company Settings
+-- =====================================================
+
+CREATE TRIGGER IF NOT EXISTS audit_company_settings_insert
+AFTER INSERT ON company_settings
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, new_values, changed_by)
+    SELECT 'company_settings', NEW.id, 'INSERT', json_object(
+        'id', NEW.id, 'company_name', NEW.company_name, 'gstin', NEW.gstin, 'pan', NEW.pan,
+        'address_line1', NEW.address_line1, 'address_line2', NEW.address_line2, 'city', NEW.city, 'state', NEW.state,
+        'pincode', NEW.pincode, 'phone', NEW.phone, 'email', NEW.email, 'website', NEW.website,
+        'bank_name', NEW.bank_name, 'account_number', NEW.account_number, 'ifsc_code', NEW.ifsc_code, 'logo_path', NEW.logo_path,
+        'created_at', NEW.created_at, 'updated_at', NEW.updated_at, 'invoice_template_id', NEW.invoice_template_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_company_settings_update
+AFTER UPDATE OF
+    company_name, gstin, pan, address_line1, address_line2, city,
+    state, pincode, phone, email, website, bank_name,
+    account_number, ifsc_code, logo_path, invoice_template_id
+ON company_settings
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, new_values, changed_by)
+    SELECT 'company_settings', NEW.id, 'UPDATE', json_object(
+        'id', OLD.id, 'company_name', OLD.company_name, 'gstin', OLD.gstin, 'pan', OLD.pan,
+        'address_line1', OLD.address_line1, 'address_line2', OLD.address_line2, 'city', OLD.city, 'state', OLD.state,
+        'pincode', OLD.pincode, 'phone', OLD.phone, 'email', OLD.email, 'website', OLD.website,
+        'bank_name', OLD.bank_name, 'account_number', OLD.account_number, 'ifsc_code', OLD.ifsc_code, 'logo_path', OLD.logo_path,
+        'created_at', OLD.created_at, 'updated_at', OLD.updated_at, 'invoice_template_id', OLD.invoice_template_id
+    ), json_object(
+        'id', NEW.id, 'company_name', NEW.company_name, 'gstin', NEW.gstin, 'pan', NEW.pan,
+        'address_line1', NEW.address_line1, 'address_line2', NEW.address_line2, 'city', NEW.city, 'state', NEW.state,
+        'pincode', NEW.pincode, 'phone', NEW.phone, 'email', NEW.email, 'website', NEW.website,
+        'bank_name', NEW.bank_name, 'account_number', NEW.account_number, 'ifsc_code', NEW.ifsc_code, 'logo_path', NEW.logo_path,
+        'created_at', NEW.created_at, 'updated_at', NEW.updated_at, 'invoice_template_id', NEW.invoice_template_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_company_settings_delete
+AFTER DELETE ON company_settings
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, changed_by)
+    SELECT 'company_settings', OLD.id, 'DELETE', json_object(
+        'id', OLD.id, 'company_name', OLD.company_name, 'gstin', OLD.gstin, 'pan', OLD.pan,
+        'address_line1', OLD.address_line1, 'address_line2', OLD.address_line2, 'city', OLD.city, 'state', OLD.state,
+        'pincode', OLD.pincode, 'phone', OLD.phone, 'email', OLD.email, 'website', OLD.website,
+        'bank_name', OLD.bank_name, 'account_number', OLD.account_number, 'ifsc_code', OLD.ifsc_code, 'logo_path', OLD.logo_path,
+        'created_at', OLD.created_at, 'updated_at', OLD.updated_at, 'invoice_template_id', OLD.invoice_template_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+-- =====================================================
+-- Customers
+-- =====================================================
+
+CREATE TRIGGER IF NOT EXISTS audit_customers_insert
+AFTER INSERT ON customers
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, new_values, changed_by)
+    SELECT 'customers', NEW.id, 'INSERT', json_object(
+        'id', NEW.id, 'customer_name', NEW.customer_name, 'gstin', NEW.gstin, 'pan', NEW.pan,
+        'customer_type', NEW.customer_type, 'address_line1', NEW.address_line1, 'address_line2', NEW.address_line2, 'city', NEW.city,
+        'state', NEW.state, 'pincode', NEW.pincode, 'phone', NEW.phone, 'email', NEW.email,
+        'credit_limit', NEW.credit_limit, 'credit_period_days', NEW.credit_period_days, 'is_active', NEW.is_active, 'created_at', NEW.created_at,
+        'updated_at', NEW.updated_at, 'invoice_template_id', NEW.invoice_template_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_customers_update
+AFTER UPDATE OF
+    customer_name, gstin, pan, customer_type, address_line1, address_line2,
+    city, state, pincode, phone, email, credit_limit,
+    credit_period_days, is_active, invoice_template_id
+ON customers
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, new_values, changed_by)
+    SELECT 'customers', NEW.id, 'UPDATE', json_object(
+        'id', OLD.id, 'customer_name', OLD.customer_name, 'gstin', OLD.gstin, 'pan', OLD.pan,
+        'customer_type', OLD.customer_type, 'address_line1', OLD.address_line1, 'address_line2', OLD.address_line2, 'city', OLD.city,
+        'state', OLD.state, 'pincode', OLD.pincode, 'phone', OLD.phone, 'email', OLD.email,
+        'credit_limit', OLD.credit_limit, 'credit_period_days', OLD.credit_period_days, 'is_active', OLD.is_active, 'created_at', OLD.created_at,
+        'updated_at', OLD.updated_at, 'invoice_template_id', OLD.invoice_template_id
+    ), json_object(
+        'id', NEW.id, 'customer_name', NEW.customer_name, 'gstin', NEW.gstin, 'pan', NEW.pan,
+        'customer_type', NEW.customer_type, 'address_line1', NEW.address_line1, 'address_line2', NEW.address_line2, 'city', NEW.city,
+        'state', NEW.state, 'pincode', NEW.pincode, 'phone', NEW.phone, 'email', NEW.email,
+        'credit_limit', NEW.credit_limit, 'credit_period_days', NEW.credit_period_days, 'is_active', NEW.is_active, 'created_at', NEW.created_at,
+        'updated_at', NEW.updated_at, 'invoice_template_id', NEW.invoice_template_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_customers_delete
+AFTER DELETE ON customers
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, changed_by)
+    SELECT 'customers', OLD.id, 'DELETE', json_object(
+        'id', OLD.id, 'customer_name', OLD.customer_name, 'gstin', OLD.gstin, 'pan', OLD.pan,
+        'customer_type', OLD.customer_type, 'address_line1', OLD.address_line1, 'address_line2', OLD.address_line2, 'city', OLD.city,
+        'state', OLD.state, 'pincode', OLD.pincode, 'phone', OLD.phone, 'email', OLD.email,
+        'credit_limit', OLD.credit_limit, 'credit_period_days', OLD.credit_period_days, 'is_active', OLD.is_active, 'created_at', OLD.created_at,
+        'updated_at', OLD.updated_at, 'invoice_template_id', OLD.invoice_template_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+-- =====================================================
+-- Products
+-- =====================================================
+
+CREATE TRIGGER IF NOT EXISTS audit_products_insert
+AFTER INSERT ON products
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, new_values, changed_by)
+    SELECT 'products', NEW.id, 'INSERT', json_object(
+        'id', NEW.id, 'product_code', NEW.product_code, 'product_name', NEW.product_name, 'description', NEW.description,
+        'hsn_sac_code', NEW.hsn_sac_code, 'product_type', NEW.product_type, 'unit_of_measurement', NEW.unit_of_measurement, 'rate', NEW.rate,
+        'gst_rate', NEW.gst_rate, 'cess_rate', NEW.cess_rate, 'is_active', NEW.is_active, 'created_at', NEW.created_at,
+        'updated_at', NEW.updated_at
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_products_update
+AFTER UPDATE OF
+    product_code, product_name, description, hsn_sac_code, product_type, unit_of_measurement,
+    rate, gst_rate, cess_rate, is_active
+ON products
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, new_values, changed_by)
+    SELECT 'products', NEW.id, 'UPDATE', json_object(
+        'id', OLD.id, 'product_code', OLD.product_code, 'product_name', OLD.product_name, 'description', OLD.description,
+        'hsn_sac_code', OLD.hsn_sac_code, 'product_type', OLD.product_type, 'unit_of_measurement', OLD.unit_of_measurement, 'rate', OLD.rate,
+        'gst_rate', OLD.gst_rate, 'cess_rate', OLD.cess_rate, 'is_active', OLD.is_active, 'created_at', OLD.created_at,
+        'updated_at', OLD.updated_at
+    ), json_object(
+        'id', NEW.id, 'product_code', NEW.product_code, 'product_name', NEW.product_name, 'description', NEW.description,
+        'hsn_sac_code', NEW.hsn_sac_code, 'product_type', NEW.product_type, 'unit_of_measurement', NEW.unit_of_measurement, 'rate', NEW.rate,
+        'gst_rate', NEW.gst_rate, 'cess_rate', NEW.cess_rate, 'is_active', NEW.is_active, 'created_at', NEW.created_at,
+        'updated_at', NEW.updated_at
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_products_delete
+AFTER DELETE ON products
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, changed_by)
+    SELECT 'products', OLD.id, 'DELETE', json_object(
+        'id', OLD.id, 'product_code', OLD.product_code, 'product_name', OLD.product_name, 'description', OLD.description,
+        'hsn_sac_code', OLD.hsn_sac_code, 'product_type', OLD.product_type, 'unit_of_measurement', OLD.unit_of_measurement, 'rate', OLD.rate,
+        'gst_rate', OLD.gst_rate, 'cess_rate', OLD.cess_rate, 'is_active', OLD.is_active, 'created_at', OLD.created_at,
+        'updated_at', OLD.updated_at
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+-- =====================================================
+-- Invoices
+-- =====================================================
+
+CREATE TRIGGER IF NOT EXISTS audit_invoices_insert
+AFTER INSERT ON invoices
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, new_values, changed_by)
+    SELECT 'invoices', NEW.id, 'INSERT', json_object(
+        'id', NEW.id, 'invoice_number', NEW.invoice_number, 'invoice_date', NEW.invoice_date, 'customer_id', NEW.customer_id,
+        'invoice_type', NEW.invoice_type, 'place_of_supply', NEW.place_of_supply, 'reverse_charge', NEW.reverse_charge, 'subtotal', NEW.subtotal,
+        'total_discount', NEW.total_discount, 'taxable_amount', NEW.taxable_amount, 'cgst_amount', NEW.cgst_amount, 'sgst_amount', NEW.sgst_amount,
+        'igst_amount', NEW.igst_amount, 'cess_amount', NEW.cess_amount, 'total_tax', NEW.total_tax, 'total_amount', NEW.total_amount,
+        'round_off', NEW.round_off, 'final_amount', NEW.final_amount, 'payment_terms', NEW.payment_terms, 'due_date', NEW.due_date,
+        'status', NEW.status, 'notes', NEW.notes, 'terms_conditions', NEW.terms_conditions, 'pdf_path', NEW.pdf_path,
+        'created_at', NEW.created_at, 'updated_at', NEW.updated_at, 'irn', NEW.irn, 'ack_number', NEW.ack_number,
+        'ack_date', NEW.ack_date, 'signed_qr_code', NEW.signed_qr_code, 'note_reason', NEW.note_reason, 'cancelled_at', NEW.cancelled_at,
+        'cancellation_reason', NEW.cancellation_reason, 'amends_invoice_id', NEW.amends_invoice_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_invoices_update
+AFTER UPDATE OF
+    invoice_number, invoice_date, customer_id, invoice_type, place_of_supply, reverse_charge,
+    subtotal, total_discount, taxable_amount, cgst_amount, sgst_amount, igst_amount,
+    cess_amount, total_tax, total_amount, round_off, final_amount, payment_terms,
+    due_date, status, notes, terms_conditions, pdf_path, irn,
+    ack_number, ack_date, signed_qr_code, note_reason, cancelled_at, cancellation_reason,
+    amends_invoice_id
+ON invoices
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, new_values, changed_by)
+    SELECT 'invoices', NEW.id, 'UPDATE', json_object(
+        'id', OLD.id, 'invoice_number', OLD.invoice_number, 'invoice_date', OLD.invoice_date, 'customer_id', OLD.customer_id,
+        'invoice_type', OLD.invoice_type, 'place_of_supply', OLD.place_of_supply, 'reverse_charge', OLD.reverse_charge, 'subtotal', OLD.subtotal,
+        'total_discount', OLD.total_discount, 'taxable_amount', OLD.taxable_amount, 'cgst_amount', OLD.cgst_amount, 'sgst_amount', OLD.sgst_amount,
+        'igst_amount', OLD.igst_amount, 'cess_amount', OLD.cess_amount, 'total_tax', OLD.total_tax, 'total_amount', OLD.total_amount,
+        'round_off', OLD.round_off, 'final_amount', OLD.final_amount, 'payment_terms', OLD.payment_terms, 'due_date', OLD.due_date,
+        'status', OLD.status, 'notes', OLD.notes, 'terms_conditions', OLD.terms_conditions, 'pdf_path', OLD.pdf_path,
+        'created_at', OLD.created_at, 'updated_at', OLD.updated_at, 'irn', OLD.irn, 'ack_number', OLD.ack_number,
+        'ack_date', OLD.ack_date, 'signed_qr_code', OLD.signed_qr_code, 'note_reason', OLD.note_reason, 'cancelled_at', OLD.cancelled_at,
+        'cancellation_reason', OLD.cancellation_reason, 'amends_invoice_id', OLD.amends_invoice_id
+    ), json_object(
+        'id', NEW.id, 'invoice_number', NEW.invoice_number, 'invoice_date', NEW.invoice_date, 'customer_id', NEW.customer_id,
+        'invoice_type', NEW.invoice_type, 'place_of_supply', NEW.place_of_supply, 'reverse_charge', NEW.reverse_charge, 'subtotal', NEW.subtotal,
+        'total_discount', NEW.total_discount, 'taxable_amount', NEW.taxable_amount, 'cgst_amount', NEW.cgst_amount, 'sgst_amount', NEW.sgst_amount,
+        'igst_amount', NEW.igst_amount, 'cess_amount', NEW.cess_amount, 'total_tax', NEW.total_tax, 'total_amount', NEW.total_amount,
+        'round_off', NEW.round_off, 'final_amount', NEW.final_amount, 'payment_terms', NEW.payment_terms, 'due_date', NEW.due_date,
+        'status', NEW.status, 'notes', NEW.notes, 'terms_conditions', NEW.terms_conditions, 'pdf_path', NEW.pdf_path,
+        'created_at', NEW.created_at, 'updated_at', NEW.updated_at, 'irn', NEW.irn, 'ack_number', NEW.ack_number,
+        'ack_date', NEW.ack_date, 'signed_qr_code', NEW.signed_qr_code, 'note_reason', NEW.note_reason, 'cancelled_at', NEW.cancelled_at,
+        'cancellation_reason', NEW.cancellation_reason, 'amends_invoice_id', NEW.amends_invoice_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_invoices_delete
+AFTER DELETE ON invoices
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, changed_by)
+    SELECT 'invoices', OLD.id, 'DELETE', json_object(
+        'id', OLD.id, 'invoice_number', OLD.invoice_number, 'invoice_date', OLD.invoice_date, 'customer_id', OLD.customer_id,
+        'invoice_type', OLD.invoice_type, 'place_of_supply', OLD.place_of_supply, 'reverse_charge', OLD.reverse_charge, 'subtotal', OLD.subtotal,
+        'total_discount', OLD.total_discount, 'taxable_amount', OLD.taxable_amount, 'cgst_amount', OLD.cgst_amount, 'sgst_amount', OLD.sgst_amount,
+        'igst_amount', OLD.igst_amount, 'cess_amount', OLD.cess_amount, 'total_tax', OLD.total_tax, 'total_amount', OLD.total_amount,
+        'round_off', OLD.round_off, 'final_amount', OLD.final_amount, 'payment_terms', OLD.payment_terms, 'due_date', OLD.due_date,
+        'status', OLD.status, 'notes', OLD.notes, 'terms_conditions', OLD.terms_conditions, 'pdf_path', OLD.pdf_path,
+        'created_at', OLD.created_at, 'updated_at', OLD.updated_at, 'irn', OLD.irn, 'ack_number', OLD.ack_number,
+        'ack_date', OLD.ack_date, 'signed_qr_code', OLD.signed_qr_code, 'note_reason', OLD.note_reason, 'cancelled_at', OLD.cancelled_at,
+        'cancellation_reason', OLD.cancellation_reason, 'amends_invoice_id', OLD.amends_invoice_id
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+-- =====================================================
+-- Invoice Items
+-- =====================================================
+
+CREATE TRIGGER IF NOT EXISTS audit_invoice_items_insert
+AFTER INSERT ON invoice_items
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, new_values, changed_by)
+    SELECT 'invoice_items', NEW.id, 'INSERT', json_object(
+        'id', NEW.id, 'invoice_id', NEW.invoice_id, 'product_id', NEW.product_id, 'line_number', NEW.line_number,
+        'product_code', NEW.product_code, 'product_name', NEW.product_name, 'description', NEW.description, 'hsn_sac_code', NEW.hsn_sac_code,
+        'quantity', NEW.quantity, 'unit_price', NEW.unit_price, 'discount_percent', NEW.discount_percent, 'discount_amount', NEW.discount_amount,
+        'taxable_amount', NEW.taxable_amount, 'gst_rate', NEW.gst_rate, 'cgst_rate', NEW.cgst_rate, 'sgst_rate', NEW.sgst_rate,
+        'igst_rate', NEW.igst_rate, 'cess_rate', NEW.cess_rate, 'cgst_amount', NEW.cgst_amount, 'sgst_amount', NEW.sgst_amount,
+        'igst_amount', NEW.igst_amount, 'cess_amount', NEW.cess_amount, 'total_tax', NEW.total_tax, 'line_total', NEW.line_total,
+        'created_at', NEW.created_at
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_invoice_items_update
+AFTER UPDATE OF
+    invoice_id, product_id, line_number, product_code, product_name, description,
+    hsn_sac_code, quantity, unit_price, discount_percent, discount_amount, taxable_amount,
+    gst_rate, cgst_rate, sgst_rate, igst_rate, cess_rate, cgst_amount,
+    sgst_amount, igst_amount, cess_amount, total_tax, line_total
+ON invoice_items
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, new_values, changed_by)
+    SELECT 'invoice_items', NEW.id, 'UPDATE', json_object(
+        'id', OLD.id, 'invoice_id', OLD.invoice_id, 'product_id', OLD.product_id, 'line_number', OLD.line_number,
+        'product_code', OLD.product_code, 'product_name', OLD.product_name, 'description', OLD.description, 'hsn_sac_code', OLD.hsn_sac_code,
+        'quantity', OLD.quantity, 'unit_price', OLD.unit_price, 'discount_percent', OLD.discount_percent, 'discount_amount', OLD.discount_amount,
+        'taxable_amount', OLD.taxable_amount, 'gst_rate', OLD.gst_rate, 'cgst_rate', OLD.cgst_rate, 'sgst_rate', OLD.sgst_rate,
+        'igst_rate', OLD.igst_rate, 'cess_rate', OLD.cess_rate, 'cgst_amount', OLD.cgst_amount, 'sgst_amount', OLD.sgst_amount,
+        'igst_amount', OLD.igst_amount, 'cess_amount', OLD.cess_amount, 'total_tax', OLD.total_tax, 'line_total', OLD.line_total,
+        'created_at', OLD.created_at
+    ), json_object(
+        'id', NEW.id, 'invoice_id', NEW.invoice_id, 'product_id', NEW.product_id, 'line_number', NEW.line_number,
+        'product_code', NEW.product_code, 'product_name', NEW.product_name, 'description', NEW.description, 'hsn_sac_code', NEW.hsn_sac_code,
+        'quantity', NEW.quantity, 'unit_price', NEW.unit_price, 'discount_percent', NEW.discount_percent, 'discount_amount', NEW.discount_amount,
+        'taxable_amount', NEW.taxable_amount, 'gst_rate', NEW.gst_rate, 'cgst_rate', NEW.cgst_rate, 'sgst_rate', NEW.sgst_rate,
+        'igst_rate', NEW.igst_rate, 'cess_rate', NEW.cess_rate, 'cgst_amount', NEW.cgst_amount, 'sgst_amount', NEW.sgst_amount,
+        'igst_amount', NEW.igst_amount, 'cess_amount', NEW.cess_amount, 'total_tax', NEW.total_tax, 'line_total', NEW.line_total,
+        'created_at', NEW.created_at
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_invoice_items_delete
+AFTER DELETE ON invoice_items
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, changed_by)
+    SELECT 'invoice_items', OLD.id, 'DELETE', json_object(
+        'id', OLD.id, 'invoice_id', OLD.invoice_id, 'product_id', OLD.product_id, 'line_number', OLD.line_number,
+        'product_code', OLD.product_code, 'product_name', OLD.product_name, 'description', OLD.description, 'hsn_sac_code', OLD.hsn_sac_code,
+        'quantity', OLD.quantity, 'unit_price', OLD.unit_price, 'discount_percent', OLD.discount_percent, 'discount_amount', OLD.discount_amount,
+        'taxable_amount', OLD.taxable_amount, 'gst_rate', OLD.gst_rate, 'cgst_rate', OLD.cgst_rate, 'sgst_rate', OLD.sgst_rate,
+        'igst_rate', OLD.igst_rate, 'cess_rate', OLD.cess_rate, 'cgst_amount', OLD.cgst_amount, 'sgst_amount', OLD.sgst_amount,
+        'igst_amount', OLD.igst_amount, 'cess_amount', OLD.cess_amount, 'total_tax', OLD.total_tax, 'line_total', OLD.line_total,
+        'created_at', OLD.created_at
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+-- =====================================================
+-- Payments
+-- =====================================================
+
+CREATE TRIGGER IF NOT EXISTS audit_payments_insert
+AFTER INSERT ON payments
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, new_values, changed_by)
+    SELECT 'payments', NEW.id, 'INSERT', json_object(
+        'id', NEW.id, 'invoice_id', NEW.invoice_id, 'payment_date', NEW.payment_date, 'amount', NEW.amount,
+        'payment_method', NEW.payment_method, 'reference_number', NEW.reference_number, 'notes', NEW.notes, 'created_at', NEW.created_at,
+        'receipt_number', NEW.receipt_number, 'voided_at', NEW.voided_at, 'void_reason', NEW.void_reason
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_payments_update
+AFTER UPDATE OF
+    invoice_id, payment_date, amount, payment_method, reference_number, notes,
+    receipt_number, voided_at, void_reason
+ON payments
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, new_values, changed_by)
+    SELECT 'payments', NEW.id, 'UPDATE', json_object(
+        'id', OLD.id, 'invoice_id', OLD.invoice_id, 'payment_date', OLD.payment_date, 'amount', OLD.amount,
+        'payment_method', OLD.payment_method, 'reference_number', OLD.reference_number, 'notes', OLD.notes, 'created_at', OLD.created_at,
+        'receipt_number', OLD.receipt_number, 'voided_at', OLD.voided_at, 'void_reason', OLD.void_reason
+    ), json_object(
+        'id', NEW.id, 'invoice_id', NEW.invoice_id, 'payment_date', NEW.payment_date, 'amount', NEW.amount,
+        'payment_method', NEW.payment_method, 'reference_number', NEW.reference_number, 'notes', NEW.notes, 'created_at', NEW.created_at,
+        'receipt_number', NEW.receipt_number, 'voided_at', NEW.voided_at, 'void_reason', NEW.void_reason
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audit_payments_delete
+AFTER DELETE ON payments
+FOR EACH ROW
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, action, old_values, changed_by)
+    SELECT 'payments', OLD.id, 'DELETE', json_object(
+        'id', OLD.id, 'invoice_id', OLD.invoice_id, 'payment_date', OLD.payment_date, 'amount', OLD.amount,
+        'payment_method', OLD.payment_method, 'reference_number', OLD.reference_number, 'notes', OLD.notes, 'created_at', OLD.created_at,
+        'receipt_number', OLD.receipt_number, 'voided_at', OLD.voided_at, 'void_reason', OLD.void_reason
+    ), actor
+    FROM audit_actor WHERE id = 1;
+END;
@@ -0,0 +1,17 @@
+-- =====================================================
+-- Migration 12: GST Rate Master
+-- Maintained rate table used to resolve rates by HSN/SAC and invoice date
+-- =====================================================
+
+ALTER TABLE gst_rates ADD COLUMN updated_at DATETIME;
+UPDATE gst_rates SET updated_at = created_at;
+
+-- Lookups match the code's prefixes and then pick the entry in force on the date
+CREATE INDEX IF NOT EXISTS idx_gst_rates_lookup ON gst_rates(hsn_sac_code, effective_from);
+
+CREATE TRIGGER IF NOT EXISTS update_gst_rates_timestamp
+AFTER UPDATE ON gst_rates
+FOR EACH ROW
+BEGIN
+    UPDATE gst_rates SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
@@ -0,0 +1,23 @@
+-- =====================================================
+-- Migration 13: Local API Server
+-- Whether the HTTP API starts with the app, the port it listens on and the token
+-- other programs present to use it
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS api_server_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled BOOLEAN DEFAULT 0,
+    port INTEGER CHECK (port BETWEEN 1024 AND 65535) NOT NULL DEFAULT 8787,
+    token TEXT, -- Generated on first use; regenerating revokes the old one
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO api_server_settings (id) VALUES (1);
+
+CREATE TRIGGER IF NOT EXISTS update_api_server_settings_timestamp
+AFTER UPDATE ON api_server_settings
+FOR EACH ROW
+BEGIN
+    UPDATE api_server_settings SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
@@ -0,0 +1,22 @@
+-- =====================================================
+-- Migration 14: Import Column Mappings
+-- Saved choices of which spreadsheet column fills which customer or product field,
+-- so files exported from another program can be imported again without remapping
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS import_mappings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    kind TEXT CHECK (kind IN ('customers', 'products')) NOT NULL,
+    columns TEXT NOT NULL, -- JSON object of file column to field; an empty field skips the column
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (kind, name)
+);
+
+CREATE TRIGGER IF NOT EXISTS update_import_mappings_timestamp
+AFTER UPDATE ON import_mappings
+FOR EACH ROW
+BEGIN
+    UPDATE import_mappings SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
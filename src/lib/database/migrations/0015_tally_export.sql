@@ -0,0 +1,47 @@
+-- =====================================================
+-- Migration 15: Tally Export
+-- Ledger names used when exporting to Tally and the vouchers already exported, so
+-- later exports send only what is new or has changed since
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS tally_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    company_name TEXT, -- Tally company to import into; the open company when blank
+    party_group TEXT NOT NULL DEFAULT 'Sundry Debtors',
+    -- Ledger names may contain {rate}, replaced by the GST rate of the lines posted to them
+    sales_ledger TEXT NOT NULL DEFAULT 'Sales @ {rate}%',
+    cgst_ledger TEXT NOT NULL DEFAULT 'Output CGST @ {rate}%',
+    sgst_ledger TEXT NOT NULL DEFAULT 'Output SGST @ {rate}%',
+    igst_ledger TEXT NOT NULL DEFAULT 'Output IGST @ {rate}%',
+    cess_ledger TEXT NOT NULL DEFAULT 'Output Cess',
+    round_off_ledger TEXT NOT NULL DEFAULT 'Round Off',
+    cash_ledger TEXT NOT NULL DEFAULT 'Cash',
+    bank_ledger TEXT NOT NULL DEFAULT 'Bank Account',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO tally_settings (id) VALUES (1);
+
+-- Customers whose ledger in Tally is not named after them
+CREATE TABLE IF NOT EXISTS tally_party_ledgers (
+    customer_id INTEGER PRIMARY KEY REFERENCES customers(id) ON DELETE CASCADE,
+    ledger_name TEXT NOT NULL UNIQUE
+);
+
+-- One row per voucher sent to Tally; amount and cancellation tell whether it changed
+CREATE TABLE IF NOT EXISTS tally_exports (
+    remote_id TEXT PRIMARY KEY,
+    voucher_type TEXT NOT NULL,
+    voucher_number TEXT NOT NULL,
+    amount DECIMAL(15,2) NOT NULL,
+    is_cancelled BOOLEAN NOT NULL DEFAULT 0,
+    exported_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER IF NOT EXISTS update_tally_settings_timestamp
+AFTER UPDATE ON tally_settings
+FOR EACH ROW
+BEGIN
+    UPDATE tally_settings SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
@@ -0,0 +1,29 @@
+-- =====================================================
+-- Migration 16: Automatic Backups
+-- How often the database is backed up, where to and how many backups are kept, and
+-- how the last scheduled backup went
+-- =====================================================
+
+CREATE TABLE IF NOT EXISTS backup_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled BOOLEAN DEFAULT 0,
+    directory TEXT, -- A backups folder beside the database when blank
+    interval_hours INTEGER CHECK (interval_hours BETWEEN 1 AND 720) NOT NULL DEFAULT 24,
+    keep_count INTEGER CHECK (keep_count BETWEEN 1 AND 365) NOT NULL DEFAULT 14,
+    -- Encrypts automatic backups when set. Kept here so backups run unattended; it
+    -- protects the copies, which may leave the machine, not the live database.
+    password TEXT,
+    last_backup_at DATETIME, -- UTC, like CURRENT_TIMESTAMP
+    last_error TEXT, -- Why the last scheduled backup failed; cleared by the next success
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO backup_settings (id) VALUES (1);
+
+CREATE TRIGGER IF NOT EXISTS update_backup_settings_timestamp
+AFTER UPDATE ON backup_settings
+FOR EACH ROW
+BEGIN
+    UPDATE backup_settings SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
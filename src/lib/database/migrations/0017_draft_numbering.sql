@@ -0,0 +1,9 @@
+-- =====================================================
+-- Migration 17: Numbering on Issue
+-- Drafts hold a DRAFT-<id> placeholder and take the next number of their series only
+-- when issued, so deleting a draft leaves no gap in the series
+-- =====================================================
+
+-- Template of the series to number the draft from; cleared once numbered, and never set
+-- on documents numbered by hand
+ALTER TABLE invoices ADD COLUMN number_format TEXT;
@@ -0,0 +1,13 @@
+-- =====================================================
+-- Migration 18: Issued Invoice Status Lock
+-- An issued invoice cannot be put back to DRAFT, which would lift the edit and delete
+-- locks of migration 10
+-- =====================================================
+
+CREATE TRIGGER IF NOT EXISTS keep_issued_invoices_issued
+BEFORE UPDATE OF status ON invoices
+FOR EACH ROW
+WHEN OLD.status != 'DRAFT' AND NEW.status = 'DRAFT'
+BEGIN
+    SELECT RAISE(ABORT, 'Issued invoices cannot be returned to draft');
+END;
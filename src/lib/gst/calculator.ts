@@ -16,9 +16,18 @@ import type {
 // GST Rate Configuration
 // =====================================================
 
-export const GST_RATES = [0, 5, 12, 18, 28] as const;
+// Common slabs offered in rate pickers; any rate from 0 to 100 with two decimals is valid
+export const GST_RATES = [0, 5, 12, 18, 28, 40] as const;
 export type GstRate = (typeof GST_RATES)[number];
 
+/**
+ * Whether a GST rate is usable: 0 to 100 with at most two decimals, covering special
+ * rates such as 0.25 (rough diamonds), 1.5 (cut diamonds) and 3 (gold)
+ */
+export function isValidGstRate(rate: number): boolean {
+	return rate >= 0 && rate <= 100 && Math.abs(Math.round(rate * 100) - rate * 100) < 1e-6;
+}
+
 // State codes for GSTIN validation and IGST determination
 export const STATE_CODES = {
 	'01': 'Jammu and Kashmir',
@@ -101,7 +110,7 @@ export function isInterStateTransaction(
 /**
  * Calculates GST amounts for a given taxable amount and rate
  * @param taxableAmount - Amount on which GST is calculated
- * @param gstRate - GST rate (0, 5, 12, 18, 28)
+ * @param gstRate - GST rate in percent, e.g. 18 or 0.25
  * @param cessRate - Cess rate (optional, default 0)
  * @param isInterState - Whether transaction is inter-state
  * @returns GstCalculation object with all tax amounts
@@ -113,8 +122,8 @@ export function calculateGst(
 	isInterState: boolean = false
 ): GstCalculation {
 	// Validate GST rate
-	if (!GST_RATES.includes(gstRate as GstRate)) {
-		throw new Error(`Invalid GST rate: ${gstRate}. Must be one of: ${GST_RATES.join(', ')}`);
+	if (!isValidGstRate(gstRate)) {
+		throw new Error(`Invalid GST rate: ${gstRate}. Must be from 0 to 100 with at most two decimals`);
 	}
 
 	// Calculate total GST amount
@@ -158,7 +167,7 @@ export function calculateGst(
  * @param quantity - Quantity of items
  * @param unitPrice - Price per unit
  * @param discountPercent - Discount percentage (0-100)
- * @param gstRate - GST rate (0, 5, 12, 18, 28)
+ * @param gstRate - GST rate in percent, e.g. 18 or 0.25
  * @param cessRate - Cess rate (optional)
  * @param isInterState - Whether transaction is inter-state
  * @returns LineItemCalculation with all amounts
@@ -27,6 +27,7 @@ export interface CompanySettings extends BaseEntity {
 	account_number?: string;
 	ifsc_code?: string;
 	logo_path?: string;
+	invoice_template_id?: number | null; // Default layout; the standard one when unset
 }
 
 // Customer Management
@@ -47,6 +48,7 @@ export interface Customer extends BaseEntity {
 	credit_limit: number;
 	credit_period_days: number;
 	is_active: boolean;
+	invoice_template_id?: number | null; // Overrides the company's layout
 }
 
 // Product/Service Catalog
@@ -60,14 +62,46 @@ export interface Product extends BaseEntity {
 	product_type: ProductType;
 	unit_of_measurement: string;
 	rate: number;
-	gst_rate: number; // 0, 5, 12, 18, 28
+	gst_rate: number; // e.g. 5, 18 or 0.25
 	cess_rate: number;
 	is_active: boolean;
 }
 
+// GST rate master entry for an HSN/SAC code or a prefix of one
+export interface GstRate extends BaseEntity {
+	hsn_sac_code: string;
+	description?: string;
+	gst_rate: number; // e.g. 5, 18 or 0.25
+	cess_rate: number;
+	effective_from: string;
+	effective_to?: string; // Absent while still in force
+	is_active: boolean;
+}
+
+export type CreateGstRate = Omit<GstRate, 'id' | 'created_at' | 'updated_at'>;
+
+// Invoice line whose rates differ from the rate master on the invoice date
+export interface RateMismatch {
+	line_number: number;
+	hsn_sac_code: string;
+	gst_rate: number;
+	cess_rate: number;
+	master_gst_rate: number;
+	master_cess_rate: number;
+	rate_id: number;
+}
+
 // Invoice Management
 export type InvoiceType = 'REGULAR' | 'EXPORT' | 'DEBIT_NOTE' | 'CREDIT_NOTE';
 export type InvoiceStatus = 'DRAFT' | 'SENT' | 'PAID' | 'OVERDUE' | 'CANCELLED';
+export type NoteReason =
+	| 'SALES_RETURN'
+	| 'POST_SALE_DISCOUNT'
+	| 'DEFICIENCY_IN_SERVICES'
+	| 'CORRECTION_IN_INVOICE'
+	| 'CHANGE_IN_POS'
+	| 'FINALISATION_OF_PROVISIONAL_ASSESSMENT'
+	| 'OTHERS';
 
 export interface Invoice extends BaseEntity {
 	invoice_number: string;
@@ -104,6 +138,22 @@ export interface Invoice extends BaseEntity {
 	terms_conditions?: string;
 	pdf_path?: string;
 
+	// E-invoice registration returned by the IRP
+	irn?: string;
+	ack_number?: string;
+	ack_date?: string;
+	signed_qr_code?: string;
+
+	// Why a credit or debit note was issued
+	note_reason?: NoteReason;
+
+	// Cancellation keeps the invoice and its number; set only by cancelling
+	cancelled_at?: string;
+	cancellation_reason?: string;
+
+	// Issued invoice this revised draft replaces; issuing the revision cancels it
+	amends_invoice_id?: number;
+
 	// Relations (populated via joins)
 	customer?: Customer;
 	items?: InvoiceItem[];
@@ -173,6 +223,94 @@ export interface IndianState {
 	is_active: boolean;
 }
 
+// Transport details for e-way bills
+export type TransportMode = 'ROAD' | 'RAIL' | 'AIR' | 'SHIP';
+export type VehicleType = 'REGULAR' | 'OVER_DIMENSIONAL';
+
+export interface TransportDetails {
+	id?: number;
+	invoice_id: number;
+	transporter_id?: string; // GSTIN or TRANSIN
+	transporter_name?: string;
+	transport_mode: TransportMode;
+	distance_km: number; // 0 lets the portal compute it from the pincodes
+	vehicle_number?: string;
+	vehicle_type: VehicleType;
+	transport_doc_number?: string; // Railway receipt, airway bill or bill of lading
+	transport_doc_date?: string; // ISO date string
+	created_at?: string;
+	updated_at?: string;
+}
+
+// Invoice layout templates
+export type TemplateSection =
+	| 'header'
+	| 'parties'
+	| 'items'
+	| 'totals'
+	| 'amount_in_words'
+	| 'hsn_summary'
+	| 'bank_details'
+	| 'notes'
+	| 'terms'
+	| 'signature';
+
+export type TemplateColumnField =
+	| 'serial'
+	| 'product_code'
+	| 'description'
+	| 'hsn_sac'
+	| 'quantity'
+	| 'rate'
+	| 'discount_percent'
+	| 'discount'
+	| 'taxable'
+	| 'cgst'
+	| 'sgst'
+	| 'igst'
+	| 'cess'
+	| 'total_tax'
+	| 'total';
+
+export interface TemplateColumn {
+	field: TemplateColumnField;
+	heading?: string;
+	width: number; // Points; 0 shares the remaining width
+}
+
+export interface TemplateStyle {
+	font_size: number;
+	table_font_size: number;
+	title_size: number;
+	line_spacing: number;
+	cell_padding: number;
+	heading_shade: number; // Gray level, 0 black to 1 white
+}
+
+// Fields left out take the standard layout's values
+export interface TemplateDefinition {
+	title?: string | null;
+	copy_label?: string;
+	letterhead?: boolean;
+	letterhead_space?: number;
+	show_logo?: boolean;
+	item_descriptions?: boolean;
+	sections?: TemplateSection[];
+	columns?: TemplateColumn[];
+	style?: Partial<TemplateStyle>;
+	footer?: string;
+}
+
+export interface InvoiceTemplate {
+	id?: number;
+	name: string;
+	description?: string;
+	definition: TemplateDefinition;
+	is_builtin?: boolean; // Built-in templates are read-only
+	created_at?: string;
+	updated_at?: string;
+}
+
 // Payment Records
 export type PaymentMethod = 'CASH' | 'CHEQUE' | 'BANK_TRANSFER' | 'UPI' | 'CARD' | 'OTHER';
 
@@ -184,12 +322,92 @@ export interface Payment {
 	payment_method: PaymentMethod;
 	reference_number?: string;
 	notes?: string;
+	receipt_number?: string; // Shared by the rows of one receipt split across invoices
+	voided_at?: string;
+	void_reason?: string;
 	created_at: string;
 
 	// Relations
 	invoice?: Invoice;
 }
 
+export interface PaymentAllocation {
+	invoice_id: number;
+	amount: number;
+}
+
+export interface CreatePayment {
+	payment_date: string; // ISO date string
+	payment_method: PaymentMethod;
+	reference_number?: string;
+	notes?: string;
+	receipt_number?: string;
+	allocations: PaymentAllocation[];
+}
+
+export interface InvoiceBalance {
+	invoice_id: number;
+	invoice_number: string;
+	final_amount: number;
+	adjustments: number; // Net of issued debit (+) and credit (-) notes
+	amount_paid: number;
+	outstanding: number;
+	status: InvoiceStatus;
+}
+
+// One status change of an invoice or note
+export interface StatusChange {
+	id: number;
+	invoice_id: number;
+	from_status?: InvoiceStatus; // Absent when the document was created
+	to_status: InvoiceStatus;
+	reason?: string;
+	changed_at: string;
+}
+
+// Tables whose changes are recorded in the audit trail
+export type AuditedTable =
+	| 'company_settings'
+	| 'customers'
+	| 'products'
+	| 'invoices'
+	| 'invoice_items'
+	| 'payments';
+
+// One insert, update or delete of an audited record
+export interface AuditEntry {
+	id: number;
+	table_name: AuditedTable;
+	record_id: number;
+	action: 'INSERT' | 'UPDATE' | 'DELETE';
+	old_values?: Record<string, unknown>; // Absent for inserts
+	new_values?: Record<string, unknown>; // Absent for deletes
+	changed_fields: string[];
+	changed_by: string;
+	changed_at: string;
+}
+
+// Share of a credit or debit note's value applied to one invoice
+export interface NoteAllocation {
+	invoice_id: number;
+	amount: number;
+}
+
+// A note together with one invoice it amends
+export interface NoteReference {
+	note_id: number;
+	note_number: string;
+	note_type: 'CREDIT_NOTE' | 'DEBIT_NOTE';
+	note_date: string;
+	note_status: InvoiceStatus;
+	invoice_id: number;
+	invoice_number: string;
+	invoice_date: string;
+	invoice_value: number;
+	invoice_place_of_supply: string;
+	amount: number;
+}
+
 // =====================================================
 // GST Calculation Types
 // =====================================================
@@ -282,9 +500,13 @@ export interface InvoiceFilter {
 
 export interface PaymentFilter {
 	invoice_id?: number;
+	customer_id?: number;
 	payment_method?: PaymentMethod;
 	date_from?: string;
 	date_to?: string;
+	include_voided?: boolean;
+	limit?: number;
+	offset?: number;
 }
 
 // =====================================================